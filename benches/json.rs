@@ -0,0 +1,62 @@
+//! Parsing and writing 10 MB JSON documents, one of many small records and one nested a thousand levels deep.
+//! Run with `cargo bench --features text`
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use simple_serde::text::TextRepr;
+
+
+/// A document of roughly the given size, made of records that nest a few levels deep
+fn document(size: usize) -> String {
+	let mut out = String::from("[");
+	let mut i = 0;
+	while out.len() < size {
+		if i > 0 {
+			out.push_str(", ");
+		}
+		out.push_str(&format!(
+			"{{id: {i}, name: \"record {i}\", score: {}.5, tags: [\"a\", \"b\", [1, 2, [3, 4]]], owner: {{id: {}, active: true, address: {{city: \"c{i}\", zip: {i}}}}}}}",
+			i % 100,
+			i * 7
+		));
+		i += 1;
+	}
+	out.push(']');
+	out
+}
+
+
+/// A document of roughly the given size, made of tables nested as deep as the parser allows,
+/// which used to be copied once per level
+fn nested_document(size: usize) -> String {
+	let depth = simple_serde::text::MAX_DEPTH - 1;
+	let padding = vec!["1"; size / depth / 3].join(", ");
+	let mut out = String::new();
+	for _ in 0..depth {
+		out.push_str(&format!("{{values: [{padding}], next: "));
+	}
+	out.push('0');
+	out.push_str(&"}".repeat(depth));
+	out
+}
+
+
+fn json(c: &mut Criterion) {
+	let data = document(10 * 1024 * 1024);
+	let parsed = TextRepr::from_json(data.clone()).unwrap();
+
+	let mut group = c.benchmark_group("json");
+	group.sample_size(10);
+	group.throughput(Throughput::Bytes(data.len() as u64));
+	group.bench_function("from_json", |b| b.iter(|| TextRepr::from_json(data.clone()).unwrap()));
+	group.bench_function("to_json", |b| b.iter(|| parsed.clone().to_json()));
+
+	let data = nested_document(10 * 1024 * 1024);
+	let parsed = TextRepr::from_json(data.clone()).unwrap();
+	group.throughput(Throughput::Bytes(data.len() as u64));
+	group.bench_function("from_json_nested", |b| b.iter(|| TextRepr::from_json(data.clone()).unwrap()));
+	group.bench_function("to_json_nested", |b| b.iter(|| parsed.clone().to_json()));
+	group.finish();
+}
+
+
+criterion_group!(benches, json);
+criterion_main!(benches);
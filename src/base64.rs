@@ -0,0 +1,43 @@
+//! Standard padded base64, for formats that need to store bytes as text
+use crate::{DeserializationError, DeserializationErrorKind};
+
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+
+#[cfg_attr(not(feature = "bin"), allow(dead_code))]
+pub fn encode(bytes: &[u8]) -> String {
+	let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+	for chunk in bytes.chunks(3) {
+		let n = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+		for i in 0..4 {
+			if i <= chunk.len() {
+				out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+			} else {
+				out.push('=');
+			}
+		}
+	}
+	out
+}
+
+
+pub fn decode(data: &str) -> Result<Vec<u8>, DeserializationError> {
+	let invalid = || DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: "invalid base64".into() });
+	let data = data.trim_end_matches('=').as_bytes();
+	if data.len() % 4 == 1 {
+		return Err(invalid())
+	}
+	let mut out = Vec::with_capacity(data.len() * 3 / 4);
+	for chunk in data.chunks(4) {
+		let mut n = 0u32;
+		for (i, c) in chunk.iter().enumerate() {
+			let value = ALPHABET.iter().position(|x| x == c).ok_or_else(invalid)? as u32;
+			n |= value << (18 - 6 * i);
+		}
+		for i in 0..(chunk.len() - 1) {
+			out.push((n >> (16 - 8 * i)) as u8);
+		}
+	}
+	Ok(out)
+}
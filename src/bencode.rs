@@ -0,0 +1,386 @@
+//! Bencode, the format of torrent files, with its dictionaries, lists, integers and byte strings.
+//!
+//! Strings and bytes are both written as byte strings, booleans as the integers 0 and 1, and floats as byte strings
+//! of their decimal digits, since bencode has none of these. Dictionaries are written with their keys sorted, as the
+//! format requires. There is no null either, so keys holding None are left out of their dictionary, and None in a
+//! list is written as an empty list, which reads back as None
+use std::borrow::Borrow;
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+#[cfg(feature = "text")]
+use crate::text::TextRepr;
+use crate::{DeserializationError, DeserializationErrorKind, Deserialize, DeserializeSeed, Number, NumberType, PrimitiveSerializer, Serialize, Serializer};
+
+
+/// How deeply lists and dictionaries can be nested when reading
+const MAX_DEPTH: usize = 256;
+
+
+/// A value of a document, before it is written or after it is read
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+	Null,
+	Int(i128),
+	Bytes(Vec<u8>),
+	List(Vec<Value>),
+	Dict(Vec<(String, Value)>)
+}
+
+
+impl Value {
+	fn type_name(&self) -> &'static str {
+		match self {
+			Value::Null => "null",
+			Value::Int(_) => "integer",
+			Value::Bytes(_) => "byte string",
+			Value::List(_) => "list",
+			Value::Dict(_) => "dictionary"
+		}
+	}
+
+	fn invalid_type(&self, expected: &'static str) -> DeserializationError {
+		DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected, actual: self.type_name() })
+	}
+
+	/// Byte strings that are valid UTF-8 become strings, and the rest become arrays of their bytes
+	#[cfg(feature = "text")]
+	fn into_text(self) -> TextRepr {
+		match self {
+			Value::Null => TextRepr::Null,
+			Value::Int(x) => TextRepr::Integer(x as i64),
+			Value::Bytes(x) => match String::from_utf8(x) {
+				Ok(x) => TextRepr::String(x),
+				Err(e) => TextRepr::Array(e.into_bytes().into_iter().map(|x| TextRepr::Integer(x as i64)).collect())
+			},
+			Value::List(x) => TextRepr::Array(x.into_iter().map(Value::into_text).collect()),
+			Value::Dict(x) => TextRepr::Table(x.into_iter().map(|(key, value)| (key.into(), value.into_text())).collect())
+		}
+	}
+
+	fn write(self, out: &mut Vec<u8>) {
+		match self {
+			Value::Null => out.extend_from_slice(b"le"),
+			Value::Int(x) => out.extend_from_slice(format!("i{}e", x).as_bytes()),
+			Value::Bytes(x) => {
+				out.extend_from_slice(format!("{}:", x.len()).as_bytes());
+				out.extend(x);
+			}
+			Value::List(x) => {
+				out.push(b'l');
+				for value in x {
+					value.write(out);
+				}
+				out.push(b'e');
+			}
+			Value::Dict(mut x) => {
+				x.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+				out.push(b'd');
+				for (key, value) in x {
+					if value == Value::Null {
+						continue
+					}
+					Value::Bytes(key.into_bytes()).write(out);
+					value.write(out);
+				}
+				out.push(b'e');
+			}
+		}
+	}
+}
+
+
+struct Decoder<'a> {
+	buf: &'a [u8],
+	pos: usize
+}
+
+
+impl Decoder<'_> {
+	fn peek(&self) -> Result<u8, DeserializationError> {
+		self.buf.get(self.pos).copied().ok_or(DeserializationError::EOF)
+	}
+
+	/// Read the digits up to the given terminator, which is consumed
+	fn digits(&mut self, end: u8) -> Result<&str, DeserializationError> {
+		let len = self.buf[self.pos..].iter().position(|x| *x == end).ok_or(DeserializationError::EOF)?;
+		let digits = std::str::from_utf8(&self.buf[self.pos..self.pos + len])
+			.map_err(|_| DeserializationError::invalid_format("digits are not ASCII"))?;
+		self.pos += len + 1;
+		Ok(digits)
+	}
+
+	fn integer(&mut self) -> Result<i128, DeserializationError> {
+		self.pos += 1;
+		let digits = self.digits(b'e')?;
+		let unsigned = digits.strip_prefix('-').unwrap_or(digits);
+		if unsigned.is_empty() || !unsigned.bytes().all(|x| x.is_ascii_digit()) {
+			return Err(DeserializationError::invalid_format(format!("{:?} is not an integer", digits)))
+		}
+		if (unsigned.starts_with('0') && unsigned.len() > 1) || digits == "-0" {
+			return Err(DeserializationError::invalid_format(format!("integer {} is not in its canonical form", digits)))
+		}
+		digits.parse().map_err(|_| DeserializationError::invalid_format(format!("integer {} is too large", digits)))
+	}
+
+	fn bytes(&mut self) -> Result<Vec<u8>, DeserializationError> {
+		let digits = self.digits(b':')?;
+		let len: usize = digits.parse()
+			.map_err(|_| DeserializationError::invalid_format(format!("{:?} is not the length of a byte string", digits)))?;
+		let bytes = self.buf.get(self.pos..self.pos.saturating_add(len)).ok_or(DeserializationError::EOF)?;
+		self.pos += len;
+		Ok(bytes.to_vec())
+	}
+
+	/// Consume the end of a list or dictionary if it is next
+	fn at_end(&mut self) -> Result<bool, DeserializationError> {
+		if self.peek()? == b'e' {
+			self.pos += 1;
+			return Ok(true)
+		}
+		Ok(false)
+	}
+
+	fn read(&mut self, depth: usize) -> Result<Value, DeserializationError> {
+		if depth > MAX_DEPTH {
+			return Err(DeserializationError::invalid_format(format!("document is nested deeper than {} levels", MAX_DEPTH)))
+		}
+		Ok(match self.peek()? {
+			b'i' => Value::Int(self.integer()?),
+			b'0'..=b'9' => Value::Bytes(self.bytes()?),
+			b'l' => {
+				self.pos += 1;
+				let mut items = Vec::new();
+				while !self.at_end()? {
+					items.push(self.read(depth + 1)?);
+				}
+				Value::List(items)
+			}
+			b'd' => {
+				self.pos += 1;
+				let mut entries = Vec::new();
+				while !self.at_end()? {
+					if !self.peek()?.is_ascii_digit() {
+						return Err(DeserializationError::invalid_format("dictionary key is not a byte string"))
+					}
+					let key = String::from_utf8(self.bytes()?)
+						.map_err(|e| DeserializationError::new_kind(DeserializationErrorKind::from(e)))?;
+					entries.push((key, self.read(depth + 1)?));
+				}
+				Value::Dict(entries)
+			}
+			x => return Err(DeserializationError::invalid_format(format!("unexpected byte {:?}", x as char)))
+		})
+	}
+
+	fn root(mut self) -> Result<Value, DeserializationError> {
+		let value = self.read(0)?;
+		if self.pos < self.buf.len() {
+			return Err(DeserializationError::invalid_format(format!("{} bytes after the end of the document", self.buf.len() - self.pos)))
+		}
+		Ok(value)
+	}
+}
+
+
+/// A value being written or read.
+/// Values that are not keyed are kept in order, and keyed values are kept in a dictionary
+#[derive(Debug, Default)]
+struct Document {
+	values: VecDeque<Value>,
+	dict: Option<Vec<(String, Value)>>
+}
+
+
+impl Document {
+	fn from_value(value: Value) -> Self {
+		match value {
+			Value::List(x) => Self { values: x.into(), dict: None },
+			Value::Dict(x) => Self { values: VecDeque::new(), dict: Some(x) },
+			Value::Null => Self::default(),
+			x => Self { values: VecDeque::from([x]), dict: None }
+		}
+	}
+
+	fn into_value(mut self) -> Value {
+		if let Some(x) = self.dict {
+			return Value::Dict(x)
+		}
+		match self.values.len() {
+			0 => Value::Null,
+			1 => self.values.pop_front().unwrap(),
+			_ => Value::List(self.values.into())
+		}
+	}
+
+	fn next_value(&mut self) -> Result<Value, DeserializationError> {
+		self.values.pop_front().ok_or(DeserializationError::EOF)
+	}
+
+	fn key_deserialize<T, F>(&mut self, key: &str, f: F) -> Result<T, DeserializationError>
+		where
+			F: FnOnce(&mut Document) -> Result<T, DeserializationError>
+	{
+		let dict = self.dict.as_mut().ok_or_else(|| DeserializationError::missing_field(key))?;
+		let idx = dict.iter().position(|x| x.0 == key).ok_or_else(|| DeserializationError::missing_field(key))?;
+		let value = dict.remove(idx).1;
+		f(&mut Document::from_value(value)).map_err(|e| e.nest().set_field(key))
+	}
+}
+
+
+impl PrimitiveSerializer for Document {
+	fn serialize_bool(&mut self, boolean: bool) {
+		self.values.push_back(Value::Int(boolean as i128));
+	}
+
+	fn deserialize_bool(&mut self) -> Result<bool, DeserializationError> {
+		match self.next_value()? {
+			Value::Int(0) => Ok(false),
+			Value::Int(1) => Ok(true),
+			Value::Int(x) => Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: format!("{} is not a boolean", x) })),
+			x => Err(x.invalid_type("integer"))
+		}
+	}
+
+	fn serialize_num<T: NumberType>(&mut self, num: T) {
+		self.values.push_back(match num.to_number() {
+			Number::Unsigned(x) => Value::Int(x as i128),
+			Number::Signed(x) => Value::Int(x as i128),
+			Number::F32(x) => Value::Bytes(x.to_string().into_bytes()),
+			Number::F64(x) => Value::Bytes(x.to_string().into_bytes())
+		});
+	}
+
+	fn deserialize_num<T: NumberType>(&mut self) -> Result<T, DeserializationError> {
+		let number = match self.next_value()? {
+			Value::Int(x) => match u64::try_from(x) {
+				Ok(x) => Some(Number::Unsigned(x)),
+				Err(_) => i64::try_from(x).ok().map(Number::Signed)
+			},
+			Value::Bytes(x) => Some(Number::F64(
+				std::str::from_utf8(&x).ok().and_then(|x| x.parse().ok())
+					.ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: "byte string is not a float".into() }))?
+			)),
+			x => return Err(x.invalid_type("integer"))
+		};
+		number.and_then(T::from_number)
+			.ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: "number does not fit its type".into() }))
+	}
+
+	fn serialize_string<T: Into<String>>(&mut self, string: T) {
+		self.values.push_back(Value::Bytes(string.into().into_bytes()));
+	}
+
+	fn deserialize_string(&mut self) -> Result<String, DeserializationError> {
+		match self.next_value()? {
+			Value::Bytes(x) => String::from_utf8(x).map_err(|e| DeserializationError::new_kind(DeserializationErrorKind::from(e))),
+			x => Err(x.invalid_type("byte string"))
+		}
+	}
+
+	fn serialize_bytes<T: Into<VecDeque<u8>>>(&mut self, bytes: T) {
+		self.values.push_back(Value::Bytes(bytes.into().into()));
+	}
+
+	fn deserialize_bytes<T: FromIterator<u8>>(&mut self) -> Result<T, DeserializationError> {
+		match self.next_value()? {
+			Value::Bytes(x) => Ok(x.into_iter().collect()),
+			x => Err(x.invalid_type("byte string"))
+		}
+	}
+}
+
+
+impl Serializer for Document {
+	fn serialize<P, T: Serialize<P>>(&mut self, item: T) {
+		let mut document = Document::default();
+		item.serialize(&mut document);
+		self.values.push_back(document.into_value());
+	}
+
+	/// Always writes a list, even with one or no items
+	fn serialize_iter<P, T: Serialize<P>, I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		let mut document = Document::default();
+		for item in iter {
+			document.serialize(item);
+		}
+		self.values.push_back(Value::List(document.values.into()));
+	}
+
+	fn serialize_key<P, T: Serialize<P>, K: Borrow<str>>(&mut self, key: K, item: T) {
+		let mut document = Document::default();
+		item.serialize(&mut document);
+		self.dict.get_or_insert_with(Vec::new).push((key.borrow().into(), document.into_value()));
+	}
+
+	fn deserialize<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError> {
+		let value = self.next_value()?;
+		T::deserialize(&mut Document::from_value(value))
+	}
+
+	fn deserialize_key_internal<P, T: Deserialize<P>>(&mut self, key: &str) -> Result<T, DeserializationError> {
+		self.key_deserialize(key, |x| T::deserialize(x))
+	}
+
+	fn deserialize_seed<P, S: DeserializeSeed<P>>(&mut self, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		let value = self.next_value()?;
+		seed.deserialize(&mut Document::from_value(value))
+	}
+
+	fn deserialize_key_seed<P, S: DeserializeSeed<P>, K: Borrow<str>>(&mut self, key: K, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		self.key_deserialize(key.borrow(), |x| seed.deserialize(x))
+	}
+
+	fn serialize_option<P, T: Serialize<P>>(&mut self, item: Option<T>) {
+		match item {
+			Some(x) => x.serialize(self),
+			None => self.values.push_back(Value::Null)
+		}
+	}
+
+	/// An empty list is read as None, as is a missing key
+	fn deserialize_option<P, T: Deserialize<P>>(&mut self) -> Result<Option<T>, DeserializationError> {
+		if self.values.is_empty() && self.dict.is_none() {
+			return Ok(None)
+		}
+		T::deserialize(self).map(Some)
+	}
+
+	fn try_get_key<K: FromStr>(&mut self) -> Option<K> {
+		self.dict.as_ref()?.first().and_then(|x| K::from_str(&x.0).ok())
+	}
+
+	fn is_human_readable(&self) -> bool {
+		false
+	}
+
+	fn remaining_keys(&self) -> Vec<String> {
+		self.dict.iter().flatten().map(|x| x.0.clone()).collect()
+	}
+
+	#[cfg(feature = "text")]
+	fn deserialize_text(&mut self) -> Result<TextRepr, DeserializationError> {
+		if let Some(x) = self.dict.take() {
+			return Ok(Value::Dict(x).into_text())
+		}
+		self.next_value().map(Value::into_text)
+	}
+}
+
+
+/// Serialize any value as a bencoded document
+pub fn to_vec<P, T: Serialize<P>>(value: T) -> Vec<u8> {
+	let mut document = Document::default();
+	value.serialize(&mut document);
+	let mut out = Vec::new();
+	document.into_value().write(&mut out);
+	out
+}
+
+
+/// Deserialize any value from a single bencoded document
+pub fn from_slice<P, T: Deserialize<P>>(data: &[u8]) -> Result<T, DeserializationError> {
+	let root = Decoder { buf: data, pos: 0 }.root()?;
+	T::deserialize(&mut Document::from_value(root))
+}
@@ -1,6 +1,11 @@
 use std::collections::VecDeque;
+use std::io::{ErrorKind, Read, Write};
+use std::marker::PhantomData;
 use std::str::FromStr;
 
+#[cfg(feature = "text")]
+use crate::text::Table;
+
 use super::*;
 
 pub mod prelude {
@@ -13,13 +18,78 @@ pub mod prelude {
 type Binary = VecDeque<u8>;
 
 
+/// Serialize any value to binary, without implementing BinSerialize for it
+pub fn to_vec<P, T: Serialize<P>>(value: T) -> Vec<u8> {
+	let mut out = Binary::new();
+	value.serialize(&mut out);
+	out.into()
+}
+
+
+/// Deserialize any value from binary, without implementing BinDeserialize for it
+pub fn from_slice<P, T: Deserialize<P>>(data: &[u8]) -> Result<T, DeserializationError> {
+	T::deserialize(&mut Binary::from(data.to_vec()))
+}
+
+
+/// Deserialize any value from the front of data, returning the bytes after it
+pub fn from_slice_partial<P, T: Deserialize<P>>(data: &[u8]) -> Result<(T, Vec<u8>), DeserializationError> {
+	let mut bytes = Binary::from(data.to_vec());
+	let value = T::deserialize(&mut bytes)?;
+	Ok((value, bytes.into()))
+}
+
+
+/// Write bytes as lowercase hex, two digits per byte
+fn encode_hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+
+fn decode_hex(data: &str) -> Result<Vec<u8>, DeserializationError> {
+	let invalid = || DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: "invalid hex".into() });
+	let data = data.trim().as_bytes();
+	if data.len() % 2 == 1 {
+		return Err(invalid())
+	}
+	data.chunks(2)
+		.map(|x| std::str::from_utf8(x).ok().and_then(|x| u8::from_str_radix(x, 16).ok()).ok_or_else(invalid))
+		.collect()
+}
+
+
 pub trait BinSerialize<P = NaturalProfile> {
 	fn serialize_bin(self) -> Vec<u8>;
+
+	/// Serialize to binary, written as a string of lowercase hex
+	fn serialize_hex(self) -> String where Self: Sized {
+		encode_hex(&self.serialize_bin())
+	}
+
+	/// Serialize to binary, written as standard padded base64 so it can be stored as text
+	#[cfg(feature = "base64")]
+	fn serialize_bin_base64(self) -> String where Self: Sized {
+		crate::base64::encode(&self.serialize_bin())
+	}
 }
 
 
 pub trait BinDeserialize<P = NaturalProfile>: Sized {
 	fn deserialize_bin(data: Vec<u8>) -> Result<Self, DeserializationError>;
+	/// Deserialize a value from the front of data, returning the bytes after it,
+	/// so that values concatenated in one buffer can be read one after another
+	fn deserialize_bin_partial(data: Vec<u8>) -> Result<(Self, Vec<u8>), DeserializationError>;
+
+	/// Deserialize from binary written as a string of hex, in either case
+	fn deserialize_hex(data: String) -> Result<Self, DeserializationError> {
+		Self::deserialize_bin(decode_hex(&data)?)
+	}
+
+	/// Deserialize from binary written as standard padded base64
+	#[cfg(feature = "base64")]
+	fn deserialize_bin_base64(data: String) -> Result<Self, DeserializationError> {
+		Self::deserialize_bin(crate::base64::decode(data.trim())?)
+	}
 }
 
 
@@ -73,9 +143,7 @@ macro_rules! impl_bin_ser {
     ($name: ty, $profile: ty) => {
 		impl BinSerialize<$profile> for $name {
 			fn serialize_bin(self) -> Vec<u8> {
-				let mut out = std::collections::VecDeque::<u8>::new();
-				Serialize::<$profile>::serialize(self, &mut out);
-				out.into()
+				$crate::bin::to_vec::<$profile, _>(self)
 			}
 		}
 	};
@@ -101,6 +169,9 @@ macro_rules! impl_bin_deser {
 			fn deserialize_bin(data: Vec<u8>) -> Result<Self, DeserializationError> {
 				Deserialize::<$profile>::deserialize(&mut Into::<std::collections::VecDeque<u8>>::into(data))
 			}
+			fn deserialize_bin_partial(data: Vec<u8>) -> Result<(Self, Vec<u8>), DeserializationError> {
+				$crate::bin::from_slice_partial::<$profile, _>(&data)
+			}
 		}
 	};
     // ($name: ty, $profile: ty, $marshall: ty) => {
@@ -185,14 +256,15 @@ fn find_key<K: Borrow<str>>(bytes: &[u8], key: K) -> Option<usize> {
 // 	}
 // }
 
-/// Deserialize a type, using the given fn, at the given key
+/// Deserialize a type, using the given fn, at the given key.
+/// The key is consumed along with the value, so only the bytes of other values are left
 fn key_deserialize<T, F>(bytes: &mut Binary, key: &str, f: F) -> Result<T, DeserializationError>
 	where
-		F: Fn(&mut Binary) -> Result<T, DeserializationError>
+		F: FnOnce(&mut Binary) -> Result<T, DeserializationError>
 {
-	let key = key.to_string();
-	let idx = find_key(bytes.make_contiguous(), key.clone()).ok_or_else(|| DeserializationError::missing_field(key.clone()))?;
+	let idx = find_key(bytes.make_contiguous(), key).ok_or_else(|| DeserializationError::missing_field(key))?;
 	let mut last = bytes.drain(idx..).collect();
+	bytes.truncate(idx - key.len());
 	let item = (f)(&mut last).map_err(|e| { DeserializationError::nest(e).set_field(key) })?;
 	bytes.append(&mut last);
 	Ok(item)
@@ -252,8 +324,13 @@ impl Serializer for Binary {
 	}
 
 	fn serialize_key<P, T: Serialize<P>, K: Borrow<str>>(&mut self, key: K, item: T) {
-		self.append(&mut key.borrow().to_string().as_bytes().to_vec().into());
-		item.serialize(self);
+		let key = key.borrow();
+		observe::field(key, |_| {
+			let start = self.len();
+			self.append(&mut key.to_string().as_bytes().to_vec().into());
+			item.serialize(self);
+			self.len() - start
+		});
 	}
 
 	fn deserialize<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError> {
@@ -264,7 +341,363 @@ impl Serializer for Binary {
 		key_deserialize(self, key, |x| { T::deserialize::<Self>(x) })
 	}
 
+	fn deserialize_seed<P, S: DeserializeSeed<P>>(&mut self, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		seed.deserialize(self)
+	}
+
+	fn deserialize_key_seed<P, S: DeserializeSeed<P>, K: Borrow<str>>(&mut self, key: K, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		key_deserialize(self, key.borrow(), |x| { seed.deserialize(x) })
+	}
+
 	fn try_get_key<K: FromStr>(&mut self) -> Option<K> {
-		self.deserialize_string().ok().map(|x| K::from_str(x.as_str()).ok()).flatten()
+		self.deserialize_string().ok().and_then(|x| K::from_str(x.as_str()).ok())
+	}
+
+	fn is_human_readable(&self) -> bool {
+		false
+	}
+
+	fn serialize_variant<P, T: Serialize<P>>(&mut self, _name: &str, index: u8, item: T) {
+		self.push_back(index);
+		item.serialize(self);
+	}
+
+	fn deserialize_variant_index(&mut self, variants: &[(&str, u8)]) -> Result<u8, DeserializationError> {
+		let index = self.pop_front().ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::UnexpectedEOF))?;
+		if !variants.iter().any(|x| x.1 == index) {
+			return Err(DeserializationError::new_kind(DeserializationErrorKind::NoMatch { actual: index.to_string() }))
+		}
+		Ok(index)
+	}
+
+	fn deserialize_variant<P, T: Deserialize<P>>(&mut self, _name: &str) -> Result<T, DeserializationError> {
+		T::deserialize::<Self>(self)
+	}
+
+	/// Binary is not self describing, so each value is prefixed with a single byte tag
+	#[cfg(feature = "text")]
+	fn serialize_text(&mut self, text: TextRepr) {
+		match text {
+			TextRepr::Empty => self.push_back(0),
+			TextRepr::String(x) => {
+				self.push_back(1);
+				self.serialize_string(x);
+			}
+			TextRepr::Integer(x) => {
+				self.push_back(2);
+				self.serialize_num(x);
+			}
+			TextRepr::Float(x) => {
+				self.push_back(3);
+				self.serialize_num(x);
+			}
+			TextRepr::Boolean(x) => {
+				self.push_back(4);
+				self.serialize_bool(x);
+			}
+			TextRepr::Table(x) => {
+				self.push_back(5);
+				self.serialize_num(x.len() as u32);
+				for (key, value) in x {
+					self.serialize_string(&*key);
+					self.serialize_text(value);
+				}
+			}
+			TextRepr::Array(x) => {
+				self.push_back(6);
+				self.serialize_num(x.len() as u32);
+				for value in x {
+					self.serialize_text(value);
+				}
+			}
+			TextRepr::Null => self.push_back(7),
+		}
+	}
+
+	#[cfg(feature = "text")]
+	fn deserialize_text(&mut self) -> Result<TextRepr, DeserializationError> {
+		Ok(match self.pop_front().ok_or(DeserializationError::EOF)? {
+			0 => TextRepr::Empty,
+			1 => TextRepr::String(self.deserialize_string()?),
+			2 => TextRepr::Integer(self.deserialize_num()?),
+			3 => TextRepr::Float(self.deserialize_num()?),
+			4 => TextRepr::Boolean(self.deserialize_bool()?),
+			5 => {
+				let len = self.deserialize_num::<u32>()? as usize;
+				let mut table = Table::with_capacity_and_hasher(len, Default::default());
+				for _ in 0..len {
+					let key = self.deserialize_string()?;
+					let value = self.deserialize_text().set_field(&key)?;
+					table.insert(key.into(), value);
+				}
+				TextRepr::Table(table)
+			}
+			6 => {
+				let len = self.deserialize_num::<u32>()? as usize;
+				let mut arr = VecDeque::with_capacity(len);
+				for _ in 0..len {
+					arr.push_back(self.deserialize_text()?);
+				}
+				TextRepr::Array(arr)
+			}
+			7 => TextRepr::Null,
+			x => return Err(DeserializationError::new_kind(DeserializationErrorKind::NoMatch { actual: x.to_string() }))
+		})
+	}
+}
+
+
+/// Read into buf until it is full or the reader runs out, returning the number of bytes read
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+	let mut read = 0;
+	while read < buf.len() {
+		match reader.read(&mut buf[read..]) {
+			Ok(0) => break,
+			Ok(n) => read += n,
+			Err(e) if e.kind() == ErrorKind::Interrupted => {}
+			Err(e) => return Err(e)
+		}
+	}
+	Ok(read)
+}
+
+
+/// Read a record of the given length, growing the buffer as bytes arrive rather than trusting the length up front.
+/// The record is shorter than len if the reader runs out
+fn read_record_bytes<R: Read>(reader: &mut R, len: usize) -> std::io::Result<Vec<u8>> {
+	let mut record = Vec::new();
+	reader.take(len as u64).read_to_end(&mut record)?;
+	Ok(record)
+}
+
+
+/// Writes values one after another as records, each prefixed with its length as a big endian u32.
+/// Read them back with a RecordReader
+pub struct RecordWriter<W, P = NaturalProfile> {
+	writer: W,
+	_profile: PhantomData<P>
+}
+
+
+impl<W: Write, P> RecordWriter<W, P> {
+	pub fn new(writer: W) -> Self {
+		Self { writer, _profile: PhantomData }
+	}
+
+	pub fn write<T: Serialize<P>>(&mut self, item: T) -> std::io::Result<()> {
+		let record = to_vec::<P, T>(item);
+		self.writer.write_all(&(record.len() as u32).to_be_bytes())?;
+		self.writer.write_all(&record)
+	}
+
+	pub fn flush(&mut self) -> std::io::Result<()> {
+		self.writer.flush()
+	}
+
+	pub fn into_inner(self) -> W {
+		self.writer
+	}
+}
+
+
+/// Reads the records written by a RecordWriter, yielding each value until the reader runs out.
+/// A record that is cut short yields an UnexpectedEOF error, after which nothing more is read
+pub struct RecordReader<R, T, P = NaturalProfile> {
+	reader: R,
+	done: bool,
+	_item: PhantomData<(T, P)>
+}
+
+
+impl<R: Read, T: Deserialize<P>, P> RecordReader<R, T, P> {
+	pub fn new(reader: R) -> Self {
+		Self { reader, done: false, _item: PhantomData }
+	}
+
+	pub fn into_inner(self) -> R {
+		self.reader
+	}
+
+	fn read_record(&mut self) -> Result<Option<T>, DeserializationError> {
+		let mut len = [0u8; 4];
+		match read_full(&mut self.reader, &mut len).map_err(DeserializationError::custom)? {
+			0 => return Ok(None),
+			4 => {}
+			_ => return Err(DeserializationError::EOF)
+		}
+		let len = u32::from_be_bytes(len) as usize;
+		let record = read_record_bytes(&mut self.reader, len).map_err(DeserializationError::custom)?;
+		if record.len() < len {
+			return Err(DeserializationError::EOF)
+		}
+		T::deserialize(&mut Binary::from(record)).map(Some)
+	}
+}
+
+
+impl<R: Read, T: Deserialize<P>, P> Iterator for RecordReader<R, T, P> {
+	type Item = Result<T, DeserializationError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None
+		}
+		let out = self.read_record().transpose();
+		self.done = !matches!(out, Some(Ok(_)));
+		out
+	}
+}
+
+
+/// The CRC-32 (IEEE) checksum of the given bytes
+fn crc32(bytes: &[u8]) -> u32 {
+	let mut crc = !0u32;
+	for byte in bytes {
+		crc ^= *byte as u32;
+		for _ in 0..8 {
+			crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB88320 } else { crc >> 1 };
+		}
+	}
+	!crc
+}
+
+
+/// Appends values to an append only log. Each record is prefixed with its length and its CRC-32 checksum,
+/// both as big endian u32s, so that a LogReader can tell a record cut short by a crash from a valid one
+pub struct LogWriter<W, P = NaturalProfile> {
+	writer: W,
+	_profile: PhantomData<P>
+}
+
+
+impl<W: Write, P> LogWriter<W, P> {
+	pub fn new(writer: W) -> Self {
+		Self { writer, _profile: PhantomData }
+	}
+
+	pub fn append<T: Serialize<P>>(&mut self, item: T) -> std::io::Result<()> {
+		let record = to_vec::<P, T>(item);
+		let mut header = [0u8; 8];
+		header[..4].copy_from_slice(&(record.len() as u32).to_be_bytes());
+		header[4..].copy_from_slice(&crc32(&record).to_be_bytes());
+		self.writer.write_all(&header)?;
+		self.writer.write_all(&record)
+	}
+
+	pub fn flush(&mut self) -> std::io::Result<()> {
+		self.writer.flush()
+	}
+
+	pub fn into_inner(self) -> W {
+		self.writer
+	}
+}
+
+
+/// Reads the records of a log written by a LogWriter.
+///
+/// If the last record is cut short, such as by a crash while it was being appended, it is skipped
+/// and `is_torn` returns true once the reader is exhausted. `valid_len` can then be used to truncate the log
+/// before appending to it again. A complete record whose checksum does not match yields an error,
+/// after which nothing more is read
+pub struct LogReader<R, T, P = NaturalProfile> {
+	reader: R,
+	done: bool,
+	torn: bool,
+	valid_len: u64,
+	_item: PhantomData<(T, P)>
+}
+
+
+impl<R: Read, T: Deserialize<P>, P> LogReader<R, T, P> {
+	pub fn new(reader: R) -> Self {
+		Self { reader, done: false, torn: false, valid_len: 0, _item: PhantomData }
+	}
+
+	/// Whether the last record was cut short and skipped
+	pub fn is_torn(&self) -> bool {
+		self.torn
+	}
+
+	/// The number of bytes taken up by the valid records read so far
+	pub fn valid_len(&self) -> u64 {
+		self.valid_len
+	}
+
+	pub fn into_inner(self) -> R {
+		self.reader
+	}
+
+	fn read_record(&mut self) -> Result<Option<T>, DeserializationError> {
+		let mut header = [0u8; 8];
+		match read_full(&mut self.reader, &mut header).map_err(DeserializationError::custom)? {
+			0 => return Ok(None),
+			8 => {}
+			_ => {
+				self.torn = true;
+				return Ok(None)
+			}
+		}
+		let len = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+		let checksum = u32::from_be_bytes(header[4..].try_into().unwrap());
+		let record = read_record_bytes(&mut self.reader, len).map_err(DeserializationError::custom)?;
+		if record.len() < len {
+			self.torn = true;
+			return Ok(None)
+		}
+		if crc32(&record) != checksum {
+			return Err(DeserializationError::invalid_format("record checksum does not match"))
+		}
+		let item = T::deserialize(&mut Binary::from(record))?;
+		self.valid_len += 8 + len as u64;
+		Ok(Some(item))
+	}
+}
+
+
+impl<R: Read, T: Deserialize<P>, P> Iterator for LogReader<R, T, P> {
+	type Item = Result<T, DeserializationError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None
+		}
+		let out = self.read_record().transpose();
+		self.done = !matches!(out, Some(Ok(_)));
+		out
+	}
+}
+
+
+/// A compact delta from one byte image to another, for state that is serialized repeatedly with small changes.
+/// It stores the lengths of the prefix and suffix the images share, as big endian u32s, followed by the bytes between them
+pub fn delta(base: &[u8], new: &[u8]) -> Vec<u8> {
+	let prefix = base.iter().zip(new).take_while(|(x, y)| x == y).count();
+	let suffix = base[prefix..].iter().rev()
+		.zip(new[prefix..].iter().rev())
+		.take_while(|(x, y)| x == y)
+		.count();
+	let mut out = Vec::with_capacity(8 + new.len() - prefix - suffix);
+	out.extend_from_slice(&(prefix as u32).to_be_bytes());
+	out.extend_from_slice(&(suffix as u32).to_be_bytes());
+	out.extend_from_slice(&new[prefix..(new.len() - suffix)]);
+	out
+}
+
+
+/// Rebuild the new byte image from the base image and a delta made by `delta`
+pub fn apply_delta(base: &[u8], delta: &[u8]) -> Result<Vec<u8>, DeserializationError> {
+	if delta.len() < 8 {
+		return Err(DeserializationError::EOF)
+	}
+	let prefix = u32::from_be_bytes(delta[..4].try_into().unwrap()) as usize;
+	let suffix = u32::from_be_bytes(delta[4..8].try_into().unwrap()) as usize;
+	if prefix + suffix > base.len() {
+		return Err(DeserializationError::invalid_format("delta does not fit the base image"))
 	}
+	let mut out = Vec::with_capacity(prefix + delta.len() - 8 + suffix);
+	out.extend_from_slice(&base[..prefix]);
+	out.extend_from_slice(&delta[8..]);
+	out.extend_from_slice(&base[(base.len() - suffix)..]);
+	Ok(out)
 }
@@ -0,0 +1,434 @@
+//! A layout packed at bit granularity, for network packets with tight size budgets.
+//!
+//! Like postcard, keyed values are written in order and without their keys. Bools are a single bit,
+//! numbers take the full width of their type, and fields using the codec `BitWidth<N>` take N bits,
+//! such as `impl_key_serde!(Player, NaturalProfile, health with BitWidth<7>)`. Strings, bytes and sequences
+//! are prefixed with their length as a varint of 8 bit groups, and variants with their index in 8 bits.
+//! Several values can be packed into the same packet with [BitWriter] and read back with [BitReader]
+use std::borrow::Borrow;
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+#[cfg(feature = "text")]
+use crate::text::TextRepr;
+use crate::{Codec, DeserializationError, DeserializationErrorKind, DeserializationResult, Deserialize, DeserializeSeed, Number, NumberType, PrimitiveSerializer, Serialize, Serializer};
+use crate::primitives::{is_float, is_signed};
+
+
+/// A queue of bits, most significant bit first within each byte
+#[derive(Debug, Default)]
+struct BitQueue {
+	bytes: VecDeque<u8>,
+	/// The bits already read from the front byte
+	head: u32,
+	len: usize
+}
+
+
+impl BitQueue {
+	fn push(&mut self, bit: bool) {
+		let end = self.head as usize + self.len;
+		if end.is_multiple_of(8) {
+			self.bytes.push_back(0);
+		}
+		if bit {
+			*self.bytes.back_mut().unwrap() |= 0x80 >> (end % 8);
+		}
+		self.len += 1;
+	}
+
+	fn pop(&mut self) -> Option<bool> {
+		if self.len == 0 {
+			return None
+		}
+		let bit = self.bytes[0] & (0x80 >> self.head) != 0;
+		self.head += 1;
+		self.len -= 1;
+		if self.head == 8 {
+			self.bytes.pop_front();
+			self.head = 0;
+		}
+		Some(bit)
+	}
+
+	/// Push the lowest bits of value, most significant first
+	fn write(&mut self, value: u64, bits: u32) {
+		for i in (0..bits.min(64)).rev() {
+			self.push((value >> i) & 1 == 1);
+		}
+	}
+
+	fn read(&mut self, bits: u32) -> Result<u64, DeserializationError> {
+		let bits = bits.min(64);
+		if self.len < bits as usize {
+			return Err(DeserializationError::EOF)
+		}
+		let mut value = 0;
+		for _ in 0..bits {
+			value = (value << 1) | self.pop().unwrap() as u64;
+		}
+		Ok(value)
+	}
+
+	fn append(&mut self, other: &mut BitQueue) {
+		while let Some(bit) = other.pop() {
+			self.push(bit);
+		}
+	}
+
+	fn write_varint(&mut self, mut value: u64) {
+		loop {
+			let group = value & 0x7F;
+			value >>= 7;
+			self.push(value != 0);
+			self.write(group, 7);
+			if value == 0 {
+				return
+			}
+		}
+	}
+
+	fn read_varint(&mut self) -> Result<u64, DeserializationError> {
+		let mut value = 0u64;
+		for shift in (0..64).step_by(7) {
+			let more = self.pop().ok_or(DeserializationError::EOF)?;
+			value |= self.read(7)? << shift;
+			if !more {
+				return Ok(value)
+			}
+		}
+		Err(DeserializationError::invalid_format("varint is longer than 10 groups"))
+	}
+
+	/// Read the length of a string or bytes, checking that there are enough bytes left for it
+	fn read_len(&mut self) -> Result<usize, DeserializationError> {
+		let len = self.read_varint()? as usize;
+		if len > self.len / 8 {
+			return Err(DeserializationError::EOF)
+		}
+		Ok(len)
+	}
+
+	fn into_bytes(self) -> Vec<u8> {
+		debug_assert_eq!(self.head, 0);
+		self.bytes.into()
+	}
+}
+
+
+/// The width of T in bits, with half precision floats written as f32s
+fn width<T: NumberType>() -> u32 {
+	if is_float::<T>() && std::mem::size_of::<T>() <= 4 {
+		32
+	} else {
+		(std::mem::size_of::<T>() as u32 * 8).min(64)
+	}
+}
+
+
+fn sign_extend(value: u64, bits: u32) -> i64 {
+	if bits == 0 {
+		return 0
+	}
+	let shift = 64 - bits.min(64);
+	((value << shift) as i64) >> shift
+}
+
+
+fn from_number<T: NumberType>(number: Number) -> Result<T, DeserializationError> {
+	T::from_number(number).ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: "number does not fit its type".into() }))
+}
+
+
+/// Nest an UnexpectedEOF from a sequence item, so it is not mistaken for the end of the sequence
+fn cut_short(e: DeserializationError) -> DeserializationError {
+	match e.kind {
+		DeserializationErrorKind::UnexpectedEOF => e.nest(),
+		_ => e
+	}
+}
+
+
+/// A view of the bits of a value. Sequences read their length on the first item, so `items` is None until then
+#[derive(Debug)]
+struct Bits<'a> {
+	bits: &'a mut BitQueue,
+	items: Option<usize>
+}
+
+
+impl<'a> Bits<'a> {
+	fn new(bits: &'a mut BitQueue) -> Self {
+		Self { bits, items: None }
+	}
+
+	fn child(&mut self) -> Bits<'_> {
+		Bits::new(self.bits)
+	}
+
+	/// Count off the next item of a sequence, returning UnexpectedEOF once there are none left
+	fn next_item(&mut self) -> Result<(), DeserializationError> {
+		let items = match self.items {
+			Some(x) => x,
+			None => self.bits.read_varint()? as usize
+		};
+		if items == 0 {
+			self.items = Some(0);
+			return Err(DeserializationError::EOF)
+		}
+		self.items = Some(items - 1);
+		Ok(())
+	}
+}
+
+
+impl PrimitiveSerializer for Bits<'_> {
+	fn serialize_bool(&mut self, boolean: bool) {
+		self.bits.push(boolean);
+	}
+
+	fn deserialize_bool(&mut self) -> Result<bool, DeserializationError> {
+		self.bits.pop().ok_or(DeserializationError::EOF)
+	}
+
+	fn serialize_num<T: NumberType>(&mut self, num: T) {
+		match num.to_number() {
+			Number::Unsigned(x) => self.bits.write(x, width::<T>()),
+			Number::Signed(x) => self.bits.write(x as u64, width::<T>()),
+			Number::F32(x) => self.bits.write(x.to_bits() as u64, 32),
+			Number::F64(x) => self.bits.write(x.to_bits(), 64)
+		}
+	}
+
+	fn deserialize_num<T: NumberType>(&mut self) -> Result<T, DeserializationError> {
+		let bits = width::<T>();
+		let x = self.bits.read(bits)?;
+		let number = if is_float::<T>() {
+			if bits == 32 { Number::F32(f32::from_bits(x as u32)) } else { Number::F64(f64::from_bits(x)) }
+		} else if is_signed::<T>() {
+			Number::Signed(sign_extend(x, bits))
+		} else {
+			Number::Unsigned(x)
+		};
+		from_number(number)
+	}
+
+	fn serialize_string<T: Into<String>>(&mut self, string: T) {
+		self.serialize_bytes(string.into().into_bytes());
+	}
+
+	fn deserialize_string(&mut self) -> Result<String, DeserializationError> {
+		String::from_utf8(self.deserialize_bytes()?).map_err(DeserializationErrorKind::from).no_field()
+	}
+
+	fn serialize_bytes<T: Into<VecDeque<u8>>>(&mut self, bytes: T) {
+		let bytes = bytes.into();
+		self.bits.write_varint(bytes.len() as u64);
+		for byte in bytes {
+			self.bits.write(byte as u64, 8);
+		}
+	}
+
+	fn deserialize_bytes<T: FromIterator<u8>>(&mut self) -> Result<T, DeserializationError> {
+		let len = self.bits.read_len()?;
+		(0..len).map(|_| self.bits.read(8).map(|x| x as u8)).collect()
+	}
+}
+
+
+impl Serializer for Bits<'_> {
+	fn serialize<P, T: Serialize<P>>(&mut self, item: T) {
+		item.serialize(&mut self.child());
+	}
+
+	/// Prefixes the items with their count
+	fn serialize_iter<P, T: Serialize<P>, I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		let mut items = BitQueue::default();
+		let mut count = 0;
+		for item in iter {
+			item.serialize(&mut Bits::new(&mut items));
+			count += 1;
+		}
+		self.bits.write_varint(count);
+		self.bits.append(&mut items);
+	}
+
+	/// Integers are clamped to the range that fits in the given bits. Floats are written in full
+	fn serialize_bits<T: NumberType>(&mut self, num: T, bits: u32) {
+		let bits = bits.min(64);
+		match num.to_number() {
+			Number::F32(x) => self.bits.write(x.to_bits() as u64, 32),
+			Number::F64(x) => self.bits.write(x.to_bits(), 64),
+			_ if bits == 0 => {}
+			Number::Unsigned(x) => self.bits.write(x.min(u64::MAX >> (64 - bits)), bits),
+			Number::Signed(x) => {
+				let max = i64::MAX >> (64 - bits);
+				self.bits.write(x.clamp(-max - 1, max) as u64, bits);
+			}
+		}
+	}
+
+	fn deserialize_bits<T: NumberType>(&mut self, bits: u32) -> Result<T, DeserializationError> {
+		if is_float::<T>() {
+			return self.deserialize_num()
+		}
+		let bits = bits.min(64);
+		let x = self.bits.read(bits)?;
+		if is_signed::<T>() {
+			from_number(Number::Signed(sign_extend(x, bits)))
+		} else {
+			from_number(Number::Unsigned(x))
+		}
+	}
+
+	fn serialize_key<P, T: Serialize<P>, K: Borrow<str>>(&mut self, _key: K, item: T) {
+		item.serialize(&mut self.child());
+	}
+
+	fn deserialize<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError> {
+		self.next_item()?;
+		T::deserialize(&mut self.child()).map_err(cut_short)
+	}
+
+	fn deserialize_key_internal<P, T: Deserialize<P>>(&mut self, key: &str) -> Result<T, DeserializationError> {
+		T::deserialize(&mut self.child()).map_err(|e| e.nest().set_field(key))
+	}
+
+	fn deserialize_seed<P, S: DeserializeSeed<P>>(&mut self, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		self.next_item()?;
+		seed.deserialize(&mut self.child()).map_err(cut_short)
+	}
+
+	fn deserialize_key_seed<P, S: DeserializeSeed<P>, K: Borrow<str>>(&mut self, key: K, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		seed.deserialize(&mut self.child()).map_err(|e| e.nest().set_field(key.borrow()))
+	}
+
+	/// Tuples have a fixed length, so their elements are not prefixed with a count
+	fn deserialize_tuple<R, F: FnOnce(&mut Self) -> Result<R, DeserializationError>>(&mut self, f: F) -> Result<R, DeserializationError> {
+		let items = self.items.replace(usize::MAX);
+		let out = f(self);
+		self.items = items;
+		out
+	}
+
+	fn try_get_key<K: FromStr>(&mut self) -> Option<K> {
+		None
+	}
+
+	fn is_human_readable(&self) -> bool {
+		false
+	}
+
+	fn serialize_variant<P, T: Serialize<P>>(&mut self, _name: &str, index: u8, item: T) {
+		self.bits.write(index as u64, 8);
+		item.serialize(&mut self.child());
+	}
+
+	fn deserialize_variant_index(&mut self, variants: &[(&str, u8)]) -> Result<u8, DeserializationError> {
+		let index = self.bits.read(8)?;
+		variants.iter()
+			.find(|x| x.1 as u64 == index)
+			.map(|x| x.1)
+			.ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::NoMatch { actual: index.to_string() }))
+	}
+
+	fn deserialize_variant<P, T: Deserialize<P>>(&mut self, _name: &str) -> Result<T, DeserializationError> {
+		T::deserialize(&mut self.child())
+	}
+
+	#[cfg(feature = "text")]
+	fn deserialize_text(&mut self) -> Result<TextRepr, DeserializationError> {
+		Err(DeserializationError::invalid_format("bit packed data is not self describing"))
+	}
+}
+
+
+/// A codec that writes integers in N bits in the bit packed layout, clamping them to the range that fits.
+/// Other formats write the integer as is
+pub struct BitWidth<const N: u32>;
+
+
+impl<V: NumberType, const N: u32> Codec<V> for BitWidth<N> {
+	fn serialize<T: Serializer>(value: V, data: &mut T) {
+		data.serialize_bits(value, N);
+	}
+
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<V, DeserializationError> {
+		data.deserialize_bits(N)
+	}
+}
+
+
+/// Packs values one after another at bit granularity
+#[derive(Debug, Default)]
+pub struct BitWriter {
+	bits: BitQueue
+}
+
+
+impl BitWriter {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn write<P, T: Serialize<P>>(&mut self, value: T) {
+		value.serialize(&mut Bits::new(&mut self.bits));
+	}
+
+	/// Write the lowest bits of value as is
+	pub fn write_bits(&mut self, value: u64, bits: u32) {
+		self.bits.write(value, bits);
+	}
+
+	/// The number of bits written so far
+	pub fn bit_len(&self) -> usize {
+		self.bits.len
+	}
+
+	/// The written bits, with the last byte padded with zeros
+	pub fn into_bytes(self) -> Vec<u8> {
+		self.bits.into_bytes()
+	}
+}
+
+
+/// Reads values that were packed by a [BitWriter], in the same order
+#[derive(Debug)]
+pub struct BitReader {
+	bits: BitQueue
+}
+
+
+impl BitReader {
+	pub fn new(bytes: &[u8]) -> Self {
+		Self { bits: BitQueue { bytes: bytes.iter().copied().collect(), head: 0, len: bytes.len() * 8 } }
+	}
+
+	pub fn read<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError> {
+		T::deserialize(&mut Bits::new(&mut self.bits))
+	}
+
+	/// Read the given number of bits as is
+	pub fn read_bits(&mut self, bits: u32) -> Result<u64, DeserializationError> {
+		self.bits.read(bits)
+	}
+
+	/// The number of bits left, including the padding of the last byte
+	pub fn remaining_bits(&self) -> usize {
+		self.bits.len
+	}
+}
+
+
+/// Serialize any value in the bit packed layout
+pub fn to_vec<P, T: Serialize<P>>(value: T) -> Vec<u8> {
+	let mut writer = BitWriter::new();
+	writer.write(value);
+	writer.into_bytes()
+}
+
+
+/// Deserialize any value from the bit packed layout
+pub fn from_slice<P, T: Deserialize<P>>(data: &[u8]) -> Result<T, DeserializationError> {
+	BitReader::new(data).read()
+}
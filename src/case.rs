@@ -0,0 +1,46 @@
+//! Case conventions that field names can be converted to with `rename_all`
+
+
+/// A case convention for keys. Field names are expected to be in snake_case
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Case {
+	/// camelCase
+	Camel,
+	/// PascalCase
+	Pascal,
+	/// snake_case
+	Snake,
+	/// SCREAMING_SNAKE_CASE
+	ScreamingSnake,
+	/// kebab-case
+	Kebab,
+	/// SCREAMING-KEBAB-CASE
+	ScreamingKebab
+}
+
+
+fn capitalize(word: &str) -> String {
+	let mut chars = word.chars();
+	match chars.next() {
+		Some(c) => c.to_uppercase().chain(chars).collect(),
+		None => String::new()
+	}
+}
+
+
+impl Case {
+	/// Convert a snake_case name into this case
+	pub fn apply(self, name: &str) -> String {
+		let words = name.split('_').filter(|x| !x.is_empty());
+		match self {
+			Case::Camel => words.enumerate()
+				.map(|(i, x)| if i == 0 { x.to_string() } else { capitalize(x) })
+				.collect(),
+			Case::Pascal => words.map(capitalize).collect(),
+			Case::Snake => words.collect::<Vec<_>>().join("_"),
+			Case::ScreamingSnake => words.collect::<Vec<_>>().join("_").to_uppercase(),
+			Case::Kebab => words.collect::<Vec<_>>().join("-"),
+			Case::ScreamingKebab => words.collect::<Vec<_>>().join("-").to_uppercase()
+		}
+	}
+}
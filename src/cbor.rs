@@ -0,0 +1,564 @@
+//! CBOR (RFC 8949), a self-describing binary format that many other languages can read and write.
+//!
+//! Integers, lengths and floats are written in their shortest form, keyed values become maps with text keys,
+//! sequences become arrays and bytes become byte strings. [to_vec] writes arrays and maps with definite lengths,
+//! while [to_vec_indefinite] writes them with indefinite lengths, as streaming encoders do.
+//! Reading accepts both, along with chunked strings, half precision floats, integer map keys and tagged values,
+//! whose tags are skipped
+use std::borrow::Borrow;
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+#[cfg(feature = "text")]
+use crate::text::TextRepr;
+use crate::{DeserializationError, DeserializationErrorKind, Deserialize, DeserializeSeed, Number, NumberType, PrimitiveSerializer, Serialize, Serializer};
+
+
+const UNSIGNED: u8 = 0;
+const NEGATIVE: u8 = 1;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+const TAG: u8 = 6;
+const SIMPLE: u8 = 7;
+
+const FALSE: u8 = 20;
+const TRUE: u8 = 21;
+const NULL: u8 = 22;
+const UNDEFINED: u8 = 23;
+const HALF: u8 = 25;
+const SINGLE: u8 = 26;
+const DOUBLE: u8 = 27;
+/// The additional information of an indefinite length, which is also the break that ends one
+const INDEFINITE: u8 = 31;
+const BREAK: u8 = SIMPLE << 5 | INDEFINITE;
+
+/// How deeply arrays, maps and tags can be nested when reading
+const MAX_DEPTH: usize = 256;
+
+
+/// A value of a document, before it is written or after it is read
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+	Null,
+	Int(i64),
+	UInt(u64),
+	Float(f64),
+	Bool(bool),
+	String(String),
+	Bytes(Vec<u8>),
+	Array(Vec<Value>),
+	Map(Vec<(String, Value)>)
+}
+
+
+impl Value {
+	fn type_name(&self) -> &'static str {
+		match self {
+			Value::Null => "null",
+			Value::Int(_) | Value::UInt(_) | Value::Float(_) => "number",
+			Value::Bool(_) => "bool",
+			Value::String(_) => "string",
+			Value::Bytes(_) => "bytes",
+			Value::Array(_) => "array",
+			Value::Map(_) => "map"
+		}
+	}
+
+	fn invalid_type(&self, expected: &'static str) -> DeserializationError {
+		DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected, actual: self.type_name() })
+	}
+
+	#[cfg(feature = "text")]
+	fn into_text(self) -> TextRepr {
+		match self {
+			Value::Null => TextRepr::Null,
+			Value::Int(x) => TextRepr::Integer(x),
+			Value::UInt(x) => TextRepr::Integer(x as i64),
+			Value::Float(x) => TextRepr::Float(x),
+			Value::Bool(x) => TextRepr::Boolean(x),
+			Value::String(x) => TextRepr::String(x),
+			Value::Bytes(x) => TextRepr::Array(x.into_iter().map(|x| TextRepr::Integer(x as i64)).collect()),
+			Value::Array(x) => TextRepr::Array(x.into_iter().map(Value::into_text).collect()),
+			Value::Map(x) => TextRepr::Table(x.into_iter().map(|(key, value)| (key.into(), value.into_text())).collect())
+		}
+	}
+}
+
+
+struct Encoder {
+	buf: Vec<u8>,
+	indefinite: bool
+}
+
+
+impl Encoder {
+	/// Write the major type with its argument in the fewest bytes
+	fn head(&mut self, major: u8, argument: u64) {
+		let major = major << 5;
+		if argument < 24 {
+			self.buf.push(major | argument as u8);
+		} else if let Ok(x) = u8::try_from(argument) {
+			self.buf.extend_from_slice(&[major | 24, x]);
+		} else if let Ok(x) = u16::try_from(argument) {
+			self.buf.push(major | 25);
+			self.buf.extend_from_slice(&x.to_be_bytes());
+		} else if let Ok(x) = u32::try_from(argument) {
+			self.buf.push(major | 26);
+			self.buf.extend_from_slice(&x.to_be_bytes());
+		} else {
+			self.buf.push(major | 27);
+			self.buf.extend_from_slice(&argument.to_be_bytes());
+		}
+	}
+
+	/// Write the length of an array or map, or mark it as indefinite
+	fn length(&mut self, major: u8, len: usize) {
+		if self.indefinite {
+			self.buf.push(major << 5 | INDEFINITE);
+		} else {
+			self.head(major, len as u64);
+		}
+	}
+
+	fn end(&mut self) {
+		if self.indefinite {
+			self.buf.push(BREAK);
+		}
+	}
+
+	fn write(&mut self, value: Value) {
+		match value {
+			Value::Null => self.buf.push(SIMPLE << 5 | NULL),
+			Value::Bool(x) => self.buf.push(SIMPLE << 5 | if x { TRUE } else { FALSE }),
+			Value::UInt(x) => self.head(UNSIGNED, x),
+			Value::Int(x) if x >= 0 => self.head(UNSIGNED, x as u64),
+			Value::Int(x) => self.head(NEGATIVE, !x as u64),
+			// Single precision is enough for any float that survives the round trip through it
+			Value::Float(x) if x as f32 as f64 == x || x.is_nan() => {
+				self.buf.push(SIMPLE << 5 | SINGLE);
+				self.buf.extend_from_slice(&(x as f32).to_be_bytes());
+			}
+			Value::Float(x) => {
+				self.buf.push(SIMPLE << 5 | DOUBLE);
+				self.buf.extend_from_slice(&x.to_be_bytes());
+			}
+			Value::String(x) => {
+				self.head(TEXT, x.len() as u64);
+				self.buf.extend_from_slice(x.as_bytes());
+			}
+			Value::Bytes(x) => {
+				self.head(BYTES, x.len() as u64);
+				self.buf.extend(x);
+			}
+			Value::Array(x) => {
+				self.length(ARRAY, x.len());
+				for value in x {
+					self.write(value);
+				}
+				self.end();
+			}
+			Value::Map(x) => {
+				self.length(MAP, x.len());
+				for (key, value) in x {
+					self.write(Value::String(key));
+					self.write(value);
+				}
+				self.end();
+			}
+		}
+	}
+}
+
+
+/// Widen the bits of a half precision float
+fn half_to_f64(bits: u16) -> f64 {
+	let exponent = (bits >> 10 & 0x1F) as i32;
+	let mantissa = (bits & 0x3FF) as f64;
+	let magnitude = match exponent {
+		0 => mantissa * 2f64.powi(-24),
+		31 if mantissa == 0.0 => f64::INFINITY,
+		31 => f64::NAN,
+		_ => (mantissa + 1024.0) * 2f64.powi(exponent - 25)
+	};
+	if bits >> 15 == 1 { -magnitude } else { magnitude }
+}
+
+
+fn utf8_error(e: std::string::FromUtf8Error) -> DeserializationError {
+	DeserializationError::new_kind(DeserializationErrorKind::from(e))
+}
+
+
+struct Decoder<'a> {
+	buf: &'a [u8],
+	pos: usize
+}
+
+
+impl<'a> Decoder<'a> {
+	fn byte(&mut self) -> Result<u8, DeserializationError> {
+		let byte = *self.buf.get(self.pos).ok_or(DeserializationError::EOF)?;
+		self.pos += 1;
+		Ok(byte)
+	}
+
+	fn bytes(&mut self, len: u64) -> Result<&'a [u8], DeserializationError> {
+		let end = usize::try_from(len).ok()
+			.and_then(|x| self.pos.checked_add(x))
+			.filter(|x| *x <= self.buf.len())
+			.ok_or(DeserializationError::EOF)?;
+		let bytes = &self.buf[self.pos..end];
+		self.pos = end;
+		Ok(bytes)
+	}
+
+	/// Read the argument that follows the initial byte, which is None for an indefinite length
+	fn argument(&mut self, info: u8) -> Result<Option<u64>, DeserializationError> {
+		Ok(Some(match info {
+			0..=23 => info as u64,
+			24 => self.byte()? as u64,
+			25 => u16::from_be_bytes(self.bytes(2)?.try_into().unwrap()) as u64,
+			26 => u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()) as u64,
+			27 => u64::from_be_bytes(self.bytes(8)?.try_into().unwrap()),
+			INDEFINITE => return Ok(None),
+			x => return Err(DeserializationError::invalid_format(format!("reserved additional information {}", x)))
+		}))
+	}
+
+	/// Consume a break if it is next, which ends an indefinite length
+	fn at_break(&mut self) -> Result<bool, DeserializationError> {
+		if *self.buf.get(self.pos).ok_or(DeserializationError::EOF)? == BREAK {
+			self.pos += 1;
+			return Ok(true)
+		}
+		Ok(false)
+	}
+
+	/// Read a byte or text string, joining the chunks of an indefinite length one
+	fn string(&mut self, major: u8, len: Option<u64>) -> Result<Vec<u8>, DeserializationError> {
+		if let Some(len) = len {
+			return Ok(self.bytes(len)?.to_vec())
+		}
+		let mut out = Vec::new();
+		while !self.at_break()? {
+			let initial = self.byte()?;
+			if initial >> 5 != major {
+				return Err(DeserializationError::invalid_format("chunk of an indefinite length string has a different type"))
+			}
+			let len = self.argument(initial & 0x1F)?
+				.ok_or_else(|| DeserializationError::invalid_format("chunk of an indefinite length string is indefinite"))?;
+			out.extend_from_slice(self.bytes(len)?);
+		}
+		Ok(out)
+	}
+
+	/// Read the key of a map, which is written as its decimal digits if it is an integer
+	fn key(&mut self, depth: usize) -> Result<String, DeserializationError> {
+		match self.read(depth)? {
+			Value::String(x) => Ok(x),
+			Value::Int(x) => Ok(x.to_string()),
+			Value::UInt(x) => Ok(x.to_string()),
+			x => Err(DeserializationError::invalid_format(format!("map key is a {}, not a string", x.type_name())))
+		}
+	}
+
+	/// Call f for each item of an array or map until its length is reached, or until the break if it has none
+	fn items<F>(&mut self, len: Option<u64>, mut f: F) -> Result<(), DeserializationError>
+		where
+			F: FnMut(&mut Self) -> Result<(), DeserializationError>
+	{
+		match len {
+			Some(len) => {
+				// Every item takes at least one byte, so longer lengths cannot be satisfied
+				if len > (self.buf.len() - self.pos) as u64 {
+					return Err(DeserializationError::EOF)
+				}
+				for _ in 0..len {
+					f(self)?;
+				}
+			}
+			None => while !self.at_break()? {
+				f(self)?;
+			}
+		}
+		Ok(())
+	}
+
+	fn read(&mut self, depth: usize) -> Result<Value, DeserializationError> {
+		if depth > MAX_DEPTH {
+			return Err(DeserializationError::invalid_format(format!("document is nested deeper than {} levels", MAX_DEPTH)))
+		}
+		let initial = self.byte()?;
+		let major = initial >> 5;
+		let info = initial & 0x1F;
+
+		if major == SIMPLE {
+			return Ok(match info {
+				FALSE => Value::Bool(false),
+				TRUE => Value::Bool(true),
+				NULL | UNDEFINED => Value::Null,
+				HALF => Value::Float(half_to_f64(u16::from_be_bytes(self.bytes(2)?.try_into().unwrap()))),
+				SINGLE => Value::Float(f32::from_be_bytes(self.bytes(4)?.try_into().unwrap()) as f64),
+				DOUBLE => Value::Float(f64::from_be_bytes(self.bytes(8)?.try_into().unwrap())),
+				INDEFINITE => return Err(DeserializationError::invalid_format("unexpected break")),
+				x => return Err(DeserializationError::invalid_format(format!("unsupported simple value {}", x)))
+			})
+		}
+
+		let argument = self.argument(info)?;
+		let definite = || argument.ok_or_else(|| DeserializationError::invalid_format(format!("major type {} cannot have an indefinite length", major)));
+		Ok(match major {
+			UNSIGNED => Value::UInt(definite()?),
+			NEGATIVE => {
+				let x = definite()?;
+				Value::Int(i64::try_from(x).map(|x| -1 - x).map_err(|_| DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: "negative integer is too small".into() }))?)
+			}
+			BYTES => Value::Bytes(self.string(BYTES, argument)?),
+			TEXT => Value::String(String::from_utf8(self.string(TEXT, argument)?).map_err(utf8_error)?),
+			ARRAY => {
+				let mut items = Vec::new();
+				self.items(argument, |x| {
+					items.push(x.read(depth + 1)?);
+					Ok(())
+				})?;
+				Value::Array(items)
+			}
+			MAP => {
+				let mut entries = Vec::new();
+				self.items(argument, |x| {
+					let key = x.key(depth + 1)?;
+					entries.push((key, x.read(depth + 1)?));
+					Ok(())
+				})?;
+				Value::Map(entries)
+			}
+			TAG => {
+				definite()?;
+				self.read(depth + 1)?
+			}
+			_ => unreachable!()
+		})
+	}
+
+	fn root(mut self) -> Result<Value, DeserializationError> {
+		let value = self.read(0)?;
+		if self.pos < self.buf.len() {
+			return Err(DeserializationError::invalid_format(format!("{} bytes after the end of the document", self.buf.len() - self.pos)))
+		}
+		Ok(value)
+	}
+}
+
+
+/// A value being written or read.
+/// Values that are not keyed are kept in order, and keyed values are kept in a map
+#[derive(Debug, Default)]
+struct Document {
+	values: VecDeque<Value>,
+	map: Option<Vec<(String, Value)>>
+}
+
+
+impl Document {
+	fn from_value(value: Value) -> Self {
+		match value {
+			Value::Array(x) => Self { values: x.into(), map: None },
+			Value::Map(x) => Self { values: VecDeque::new(), map: Some(x) },
+			Value::Null => Self::default(),
+			x => Self { values: VecDeque::from([x]), map: None }
+		}
+	}
+
+	fn into_value(mut self) -> Value {
+		if let Some(x) = self.map {
+			return Value::Map(x)
+		}
+		match self.values.len() {
+			0 => Value::Null,
+			1 => self.values.pop_front().unwrap(),
+			_ => Value::Array(self.values.into())
+		}
+	}
+
+	fn next_value(&mut self) -> Result<Value, DeserializationError> {
+		self.values.pop_front().ok_or(DeserializationError::EOF)
+	}
+
+	/// Remove the value at the given key and deserialize it with the given fn.
+	/// A null value is only missing if the fn fails for lack of values
+	fn key_deserialize<T, F>(&mut self, key: &str, f: F) -> Result<T, DeserializationError>
+		where
+			F: FnOnce(&mut Document) -> Result<T, DeserializationError>
+	{
+		let map = self.map.as_mut().ok_or_else(|| DeserializationError::missing_field(key))?;
+		let idx = map.iter().position(|x| x.0 == key).ok_or_else(|| DeserializationError::missing_field(key))?;
+		let value = map.remove(idx).1;
+		let null = value == Value::Null;
+		f(&mut Document::from_value(value)).map_err(|e| match e.kind {
+			DeserializationErrorKind::UnexpectedEOF if null => DeserializationError::missing_field(key),
+			_ => e.nest().set_field(key)
+		})
+	}
+}
+
+
+impl PrimitiveSerializer for Document {
+	fn serialize_bool(&mut self, boolean: bool) {
+		self.values.push_back(Value::Bool(boolean));
+	}
+
+	fn deserialize_bool(&mut self) -> Result<bool, DeserializationError> {
+		match self.next_value()? {
+			Value::Bool(x) => Ok(x),
+			x => Err(x.invalid_type("bool"))
+		}
+	}
+
+	fn serialize_num<T: NumberType>(&mut self, num: T) {
+		self.values.push_back(match num.to_number() {
+			Number::Unsigned(x) => Value::UInt(x),
+			Number::Signed(x) => Value::Int(x),
+			Number::F32(x) => Value::Float(x as f64),
+			Number::F64(x) => Value::Float(x)
+		});
+	}
+
+	fn deserialize_num<T: NumberType>(&mut self) -> Result<T, DeserializationError> {
+		let number = match self.next_value()? {
+			Value::Int(x) => Number::Signed(x),
+			Value::UInt(x) => Number::Unsigned(x),
+			Value::Float(x) => Number::F64(x),
+			x => return Err(x.invalid_type("number"))
+		};
+		T::from_number(number).ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: "number does not fit its type".into() }))
+	}
+
+	fn serialize_string<T: Into<String>>(&mut self, string: T) {
+		self.values.push_back(Value::String(string.into()));
+	}
+
+	fn deserialize_string(&mut self) -> Result<String, DeserializationError> {
+		match self.next_value()? {
+			Value::String(x) => Ok(x),
+			x => Err(x.invalid_type("string"))
+		}
+	}
+
+	fn serialize_bytes<T: Into<VecDeque<u8>>>(&mut self, bytes: T) {
+		self.values.push_back(Value::Bytes(bytes.into().into()));
+	}
+
+	fn deserialize_bytes<T: FromIterator<u8>>(&mut self) -> Result<T, DeserializationError> {
+		match self.next_value()? {
+			Value::Bytes(x) => Ok(x.into_iter().collect()),
+			x => Err(x.invalid_type("bytes"))
+		}
+	}
+}
+
+
+impl Serializer for Document {
+	fn serialize<P, T: Serialize<P>>(&mut self, item: T) {
+		let mut document = Document::default();
+		item.serialize(&mut document);
+		self.values.push_back(document.into_value());
+	}
+
+	/// Always writes an array, even with one or no items
+	fn serialize_iter<P, T: Serialize<P>, I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		let mut document = Document::default();
+		for item in iter {
+			document.serialize(item);
+		}
+		self.values.push_back(Value::Array(document.values.into()));
+	}
+
+	fn serialize_key<P, T: Serialize<P>, K: Borrow<str>>(&mut self, key: K, item: T) {
+		let mut document = Document::default();
+		item.serialize(&mut document);
+		self.map.get_or_insert_with(Vec::new).push((key.borrow().into(), document.into_value()));
+	}
+
+	fn deserialize<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError> {
+		let value = self.next_value()?;
+		T::deserialize(&mut Document::from_value(value))
+	}
+
+	fn deserialize_key_internal<P, T: Deserialize<P>>(&mut self, key: &str) -> Result<T, DeserializationError> {
+		self.key_deserialize(key, |x| T::deserialize(x))
+	}
+
+	fn deserialize_seed<P, S: DeserializeSeed<P>>(&mut self, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		let value = self.next_value()?;
+		seed.deserialize(&mut Document::from_value(value))
+	}
+
+	fn deserialize_key_seed<P, S: DeserializeSeed<P>, K: Borrow<str>>(&mut self, key: K, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		self.key_deserialize(key.borrow(), |x| seed.deserialize(x))
+	}
+
+	fn serialize_option<P, T: Serialize<P>>(&mut self, item: Option<T>) {
+		match item {
+			Some(x) => x.serialize(self),
+			None => self.values.push_back(Value::Null)
+		}
+	}
+
+	/// A null or undefined is read as a document with nothing in it
+	fn deserialize_option<P, T: Deserialize<P>>(&mut self) -> Result<Option<T>, DeserializationError> {
+		if self.values.is_empty() && self.map.is_none() {
+			return Ok(None)
+		}
+		T::deserialize(self).map(Some)
+	}
+
+	fn try_get_key<K: FromStr>(&mut self) -> Option<K> {
+		self.map.as_ref()?.first().and_then(|x| K::from_str(&x.0).ok())
+	}
+
+	fn is_human_readable(&self) -> bool {
+		false
+	}
+
+	fn remaining_keys(&self) -> Vec<String> {
+		self.map.iter().flatten().map(|x| x.0.clone()).collect()
+	}
+
+	#[cfg(feature = "text")]
+	fn deserialize_text(&mut self) -> Result<TextRepr, DeserializationError> {
+		if let Some(x) = self.map.take() {
+			return Ok(Value::Map(x).into_text())
+		}
+		self.next_value().map(Value::into_text)
+	}
+}
+
+
+fn encode<P, T: Serialize<P>>(value: T, indefinite: bool) -> Vec<u8> {
+	let mut document = Document::default();
+	value.serialize(&mut document);
+	let mut encoder = Encoder { buf: Vec::new(), indefinite };
+	encoder.write(document.into_value());
+	encoder.buf
+}
+
+
+/// Serialize any value as a CBOR document, with the length of every array and map written up front
+pub fn to_vec<P, T: Serialize<P>>(value: T) -> Vec<u8> {
+	encode(value, false)
+}
+
+
+/// Serialize any value as a CBOR document, with every array and map ended by a break instead of a length
+pub fn to_vec_indefinite<P, T: Serialize<P>>(value: T) -> Vec<u8> {
+	encode(value, true)
+}
+
+
+/// Deserialize any value from a single CBOR document
+pub fn from_slice<P, T: Deserialize<P>>(data: &[u8]) -> Result<T, DeserializationError> {
+	let root = Decoder { buf: data, pos: 0 }.root()?;
+	T::deserialize(&mut Document::from_value(root))
+}
@@ -0,0 +1,93 @@
+//! Checks that can be attached to fields in impl_key_deser!, such as
+//! `impl_key_deser!(Config, ReadableProfile, port: check::range(1..=65535), host: check::non_empty())`.
+//! A failed check produces an InvalidValue error on that field
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
+use std::ops::RangeBounds;
+
+
+/// Anything with a length that the length checks can inspect
+pub trait Length {
+	fn length(&self) -> usize;
+}
+
+
+impl Length for String {
+	fn length(&self) -> usize {
+		self.chars().count()
+	}
+}
+
+
+impl<T> Length for Vec<T> {
+	fn length(&self) -> usize {
+		self.len()
+	}
+}
+
+
+impl<T> Length for VecDeque<T> {
+	fn length(&self) -> usize {
+		self.len()
+	}
+}
+
+
+impl<K, V, S> Length for HashMap<K, V, S> {
+	fn length(&self) -> usize {
+		self.len()
+	}
+}
+
+
+impl<T, S> Length for HashSet<T, S> {
+	fn length(&self) -> usize {
+		self.len()
+	}
+}
+
+
+/// The value must be inside the given range
+pub fn range<T: PartialOrd + Debug, R: RangeBounds<T> + Debug>(range: R) -> impl Fn(&T) -> Result<(), String> {
+	move |x| if range.contains(x) {
+		Ok(())
+	} else {
+		Err(format!("{:?} is outside of {:?}", x, range))
+	}
+}
+
+
+/// The length of the value must be inside the given range
+pub fn length<T: Length, R: RangeBounds<usize> + Debug>(range: R) -> impl Fn(&T) -> Result<(), String> {
+	move |x| if range.contains(&x.length()) {
+		Ok(())
+	} else {
+		Err(format!("length of {} is outside of {:?}", x.length(), range))
+	}
+}
+
+
+/// The value must not be empty
+pub fn non_empty<T: Length>() -> impl Fn(&T) -> Result<(), String> {
+	|x| if x.length() == 0 {
+		Err("value is empty".into())
+	} else {
+		Ok(())
+	}
+}
+
+
+/// The whole value must match the given regex.
+///
+/// Panics if the pattern is not a valid regex, as that is a mistake in the code rather than in the data
+#[cfg(feature = "regex")]
+pub fn regex(pattern: &str) -> impl Fn(&String) -> Result<(), String> {
+	let regex = regex::Regex::new(&format!("^(?:{})$", pattern))
+		.unwrap_or_else(|e| panic!("invalid regex {} in field check: {}", pattern, e));
+	let pattern = pattern.to_string();
+	move |x| if regex.is_match(x) {
+		Ok(())
+	} else {
+		Err(format!("{:?} does not match {}", x, pattern))
+	}
+}
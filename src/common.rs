@@ -1,13 +1,14 @@
-use std::collections::{HashMap, HashSet};
-use std::hash::Hash;
+use std::collections::{BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
+use std::hash::{BuildHasher, Hash};
 use std::str::FromStr;
 
 use super::*;
 
-impl<P, K, V> Serialize<P> for HashMap<K, V>
+impl<P, K, V, S> Serialize<P> for HashMap<K, V, S>
 	where
 		K: Borrow<str> + Eq + Hash,
-		V: Serialize<P>
+		V: Serialize<P>,
+		S: BuildHasher
 {
 	fn serialize<T: Serializer>(self, data: &mut T) {
 		for (key, val) in self {
@@ -17,14 +18,15 @@ impl<P, K, V> Serialize<P> for HashMap<K, V>
 }
 
 
-impl<P, K, V, E> Deserialize<P> for HashMap<K, V>
+impl<P, K, V, E, S> Deserialize<P> for HashMap<K, V, S>
 	where
 		E: Debug,
 		K: Eq + Hash + FromStr<Err=E>,
-		V: Deserialize<P>
+		V: Deserialize<P>,
+		S: BuildHasher + Default
 {
 	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
-		let mut out = Self::new();
+		let mut out = Self::default();
 		while let Some(key) = data.try_get_key::<String>() {
 			let deser = data.deserialize_key(key.as_str()).set_field(key.clone())?;
 			out.insert(K::from_str(key.as_str()).map_err(|e| DeserializationError::new(key, DeserializationErrorKind::from_str_err(e)))?, deser);
@@ -35,11 +37,21 @@ impl<P, K, V, E> Deserialize<P> for HashMap<K, V>
 }
 
 
+/// Serializes the items of an iterator lazily, the same way a Vec of those items would be serialized.
+/// Useful for fields whose items are generated on demand
+pub struct IterSerialize<I>(pub I);
+
+
+impl<P, V: Serialize<P>, I: IntoIterator<Item=V>> Serialize<P> for IterSerialize<I> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_iter(self.0);
+	}
+}
+
+
 impl<P, V: Serialize<P>> Serialize<P> for Vec<V> {
 	fn serialize<T: Serializer>(self, data: &mut T) {
-		for item in self {
-			data.serialize(item);
-		}
+		data.serialize_iter(self);
 	}
 }
 
@@ -62,19 +74,99 @@ impl<P, V: Deserialize<P>> Deserialize<P> for Vec<V> {
 }
 
 
-impl<P, V: Serialize<P> + Eq + Hash> Serialize<P> for HashSet<V> {
+impl<P, V: Serialize<P>> Serialize<P> for VecDeque<V> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_iter(self);
+	}
+}
+
+
+impl<P, V: Deserialize<P>> Deserialize<P> for VecDeque<V> {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		Vec::<V>::deserialize(data).map(Self::from)
+	}
+}
+
+
+impl<P, V: Serialize<P>> Serialize<P> for LinkedList<V> {
 	fn serialize<T: Serializer>(self, data: &mut T) {
-		for item in self {
-			data.serialize(item);
+		data.serialize_iter(self);
+	}
+}
+
+
+impl<P, V: Deserialize<P>> Deserialize<P> for LinkedList<V> {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		Vec::<V>::deserialize(data).map(|x| x.into_iter().collect())
+	}
+}
+
+
+/// Serialized in ascending order, so equal heaps are always serialized the same way
+impl<P, V: Serialize<P> + Ord> Serialize<P> for BinaryHeap<V> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_iter(self.into_sorted_vec());
+	}
+}
+
+
+impl<P, V: Deserialize<P> + Ord> Deserialize<P> for BinaryHeap<V> {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		Vec::<V>::deserialize(data).map(Self::from)
+	}
+}
+
+
+impl<P, V: Serialize<P>, const N: usize> Serialize<P> for [V; N] {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_iter(self);
+	}
+}
+
+
+/// Reads the whole sequence, so a sequence of any other length is a WrongLength error
+impl<P, V: Deserialize<P>, const N: usize> Deserialize<P> for [V; N] {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		let items: Vec<V> = Deserialize::<P>::deserialize(data)?;
+		items.try_into().map_err(|x: Vec<V>| DeserializationError::new_kind(DeserializationErrorKind::WrongLength { expected: N, actual: x.len() }))
+	}
+}
+
+
+/// A seed that deserializes a Vec, using the inner seed for each element
+pub struct SeqSeed<S>(pub S);
+
+
+impl<P, S: DeserializeSeed<P>> DeserializeSeed<P> for SeqSeed<S> {
+	type Value = Vec<S::Value>;
+
+	fn deserialize<T: Serializer>(&mut self, data: &mut T) -> Result<Self::Value, DeserializationError> {
+		let mut out = Vec::new();
+		loop {
+			match data.deserialize_seed(&mut self.0) {
+				Ok(x) => out.push(x),
+				Err(e) => match &e.kind {
+					DeserializationErrorKind::UnexpectedEOF => break,
+					_ => return Err(e)
+				}
+			}
 		}
+		Ok(out)
 	}
 }
 
 
-impl<P, V: Deserialize<P> + Eq + Hash> Deserialize<P> for HashSet<V> {
+impl<P, V: Serialize<P> + Eq + Hash, S: BuildHasher> Serialize<P> for HashSet<V, S> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_set(self);
+	}
+}
+
+
+impl<P, V: Deserialize<P> + Eq + Hash, S: BuildHasher + Default> Deserialize<P> for HashSet<V, S> {
 	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
 		let data_ref = data.borrow_mut();
-		let mut out = Self::new();
+		let mut out = Self::default();
 		loop {
 			match data_ref.deserialize() {
 				Ok(x) => out.insert(x),
@@ -87,3 +179,119 @@ impl<P, V: Deserialize<P> + Eq + Hash> Deserialize<P> for HashSet<V> {
 		Ok(out)
 	}
 }
+
+
+/// Entries are serialized in insertion order, which is kept when deserializing
+#[cfg(feature = "indexmap")]
+impl<P, K, V, S> Serialize<P> for indexmap::IndexMap<K, V, S>
+	where
+		K: Borrow<str> + Eq + Hash,
+		V: Serialize<P>,
+		S: BuildHasher
+{
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		for (key, val) in self {
+			data.serialize_key(key.borrow(), val);
+		}
+	}
+}
+
+
+#[cfg(feature = "indexmap")]
+impl<P, K, V, E, S> Deserialize<P> for indexmap::IndexMap<K, V, S>
+	where
+		E: Debug,
+		K: Eq + Hash + FromStr<Err=E>,
+		V: Deserialize<P>,
+		S: BuildHasher + Default
+{
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		let mut out = Self::default();
+		while let Some(key) = data.try_get_key::<String>() {
+			let deser = data.deserialize_key(key.as_str()).set_field(key.clone())?;
+			out.insert(K::from_str(key.as_str()).map_err(|e| DeserializationError::new(key, DeserializationErrorKind::from_str_err(e)))?, deser);
+		}
+
+		Ok(out)
+	}
+}
+
+
+/// Unlike HashSet, items are serialized as an ordinary sequence so that their order is kept
+#[cfg(feature = "indexmap")]
+impl<P, V: Serialize<P> + Eq + Hash, S: BuildHasher> Serialize<P> for indexmap::IndexSet<V, S> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_iter(self);
+	}
+}
+
+
+#[cfg(feature = "indexmap")]
+impl<P, V: Deserialize<P> + Eq + Hash, S: BuildHasher + Default> Deserialize<P> for indexmap::IndexSet<V, S> {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		Vec::<V>::deserialize(data).map(|x| x.into_iter().collect())
+	}
+}
+
+
+#[cfg(feature = "smallvec")]
+impl<P, A: smallvec::Array> Serialize<P> for smallvec::SmallVec<A> where A::Item: Serialize<P> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_iter(self);
+	}
+}
+
+
+/// Items are pushed straight into the SmallVec, so short sequences are deserialized without allocating
+#[cfg(feature = "smallvec")]
+impl<P, A: smallvec::Array> Deserialize<P> for smallvec::SmallVec<A> where A::Item: Deserialize<P> {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		let data_ref = data.borrow_mut();
+		let mut out = Self::new();
+		loop {
+			match data_ref.deserialize() {
+				Ok(x) => out.push(x),
+				Err(e) => match &e.kind {
+					DeserializationErrorKind::UnexpectedEOF => break,
+					_ => return Err(e)
+				}
+			}
+		}
+		Ok(out)
+	}
+}
+
+
+macro_rules! impl_tuple_serde {
+	($($name: ident $var: ident),+) => {
+		impl<P, $($name: Serialize<P>),+> Serialize<P> for ($($name,)+) {
+			fn serialize<T: Serializer>(self, data: &mut T) {
+				let ($($var,)+) = self;
+				data.serialize_tuple(|data| {
+					$(data.serialize::<P, _>($var);)+
+				});
+			}
+		}
+
+
+		impl<P, $($name: Deserialize<P>),+> Deserialize<P> for ($($name,)+) {
+			fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+				data.deserialize_tuple(|data| Ok(($(data.deserialize::<P, $name>()?,)+)))
+			}
+		}
+	};
+}
+
+
+impl_tuple_serde!(A a);
+impl_tuple_serde!(A a, B b);
+impl_tuple_serde!(A a, B b, C c);
+impl_tuple_serde!(A a, B b, C c, D d);
+impl_tuple_serde!(A a, B b, C c, D d, E e);
+impl_tuple_serde!(A a, B b, C c, D d, E e, F f);
+impl_tuple_serde!(A a, B b, C c, D d, E e, F f, G g);
+impl_tuple_serde!(A a, B b, C c, D d, E e, F f, G g, H h);
+impl_tuple_serde!(A a, B b, C c, D d, E e, F f, G g, H h, I i);
+impl_tuple_serde!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j);
+impl_tuple_serde!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k);
+impl_tuple_serde!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l);
@@ -0,0 +1,433 @@
+//! Durations and points in time.
+//!
+//! Under the ReadableProfile, a Duration is written as a string of seconds such as `"1.5s"`, and a SystemTime as an
+//! RFC 3339 timestamp in UTC such as `"2024-02-29T12:00:00.5Z"`. Under the EfficientProfile, both are written as a
+//! tuple of whole seconds and nanoseconds, where the seconds of a SystemTime are since the unix epoch and may be negative.
+//!
+//! A readable Duration may also be given in `ns`, `us`, `ms`, `m` or `h`, and a readable SystemTime
+//! may have any UTC offset, such as `"2024-02-29T14:00:00+02:00"`.
+//!
+//! With the `chrono` feature, `DateTime<Utc>`, `NaiveDate` and `NaiveDateTime` are written as RFC 3339
+//! timestamps, dates and local date times in human readable formats, and as i64 nanoseconds since the
+//! unix epoch in other formats. Naive values are taken to be in UTC, and dates to be at midnight.
+//! Nanoseconds only span the years 1677 to 2262, so times outside of that are clamped to it.
+//!
+//! The `time` feature does the same for `OffsetDateTime` and `Date`, where an OffsetDateTime is written in UTC.
+//! A `Time` of day is written as `"12:30:00.5"`, or as nanoseconds since midnight
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{DeserializationError, DeserializationErrorKind, Deserialize, EfficientProfile, ReadableProfile, Serialize, Serializer};
+
+
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+
+fn not_a(string: &str, expected: &str) -> DeserializationError {
+	DeserializationError::new_kind(DeserializationErrorKind::FromStrError(format!("{:?} is not {}", string, expected)))
+}
+
+
+/// Write seconds and nanoseconds as a decimal without trailing zeros, such as `1.5`
+fn write_seconds(secs: u64, nanos: u32) -> String {
+	if nanos == 0 {
+		return secs.to_string()
+	}
+	let out = format!("{}.{:09}", secs, nanos);
+	out.trim_end_matches('0').to_string()
+}
+
+
+/// Parse a decimal number followed by a unit of time into nanoseconds
+fn parse_duration(data: &str) -> Option<Duration> {
+	let data = data.trim();
+	let (number, unit) = data.split_at(data.find(|c: char| !c.is_ascii_digit() && c != '.')?);
+	let scale: u128 = match unit {
+		"ns" => 1,
+		"us" | "µs" => 1_000,
+		"ms" => 1_000_000,
+		"s" => 1_000_000_000,
+		"m" => 60_000_000_000,
+		"h" => 3_600_000_000_000,
+		_ => return None
+	};
+	let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+	if whole.is_empty() && fraction.is_empty() {
+		return None
+	}
+	let mut nanos = if whole.is_empty() { 0 } else { whole.parse::<u128>().ok()?.checked_mul(scale)? };
+	let mut place = scale;
+	for c in fraction.chars() {
+		place /= 10;
+		nanos += c.to_digit(10)? as u128 * place;
+	}
+	let secs = u64::try_from(nanos / NANOS_PER_SEC as u128).ok()?;
+	Some(Duration::new(secs, (nanos % NANOS_PER_SEC as u128) as u32))
+}
+
+
+/// The number of days from the unix epoch to the given date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+	let year = if month <= 2 { year - 1 } else { year };
+	let era = year.div_euclid(400);
+	let year_of_era = year - era * 400;
+	let month = month as i64;
+	let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+	let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+	era * 146097 + day_of_era - 719468
+}
+
+
+/// The date of the given number of days from the unix epoch, as its year, month and day
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+	let days = days + 719468;
+	let era = days.div_euclid(146097);
+	let day_of_era = days - era * 146097;
+	let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let month = (5 * day_of_year + 2) / 153;
+	let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+	let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+	let year = year_of_era + era * 400 + (month <= 2) as i64;
+	(year, month, day)
+}
+
+
+/// Write seconds and nanoseconds since the unix epoch as an RFC 3339 timestamp in UTC
+pub(crate) fn to_rfc3339(secs: i64, nanos: u32) -> String {
+	let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+	let time = secs.rem_euclid(86400);
+	let mut out = format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}", year, month, day, time / 3600, time / 60 % 60, time % 60);
+	if nanos != 0 {
+		out += format!(".{:09}", nanos).trim_end_matches('0');
+	}
+	out.push('Z');
+	out
+}
+
+
+/// Parse an RFC 3339 timestamp into seconds and nanoseconds since the unix epoch
+pub(crate) fn from_rfc3339(data: &str) -> Option<(i64, u32)> {
+	let data = data.trim();
+	let field = |range: std::ops::Range<usize>| -> Option<u32> {
+		let digits = data.get(range)?;
+		if !digits.bytes().all(|x| x.is_ascii_digit()) {
+			return None
+		}
+		digits.parse().ok()
+	};
+	let separators = data.as_bytes();
+	if separators.len() < 20 || separators[4] != b'-' || separators[7] != b'-' || !matches!(separators[10], b'T' | b't' | b' ') || separators[13] != b':' || separators[16] != b':' {
+		return None
+	}
+	let (year, month, day) = (field(0..4)?, field(5..7)?, field(8..10)?);
+	let (hour, minute, second) = (field(11..13)?, field(14..16)?, field(17..19)?);
+	if !(1..=12).contains(&month) || day == 0 || day > 31 || hour > 23 || minute > 59 || second > 60 {
+		return None
+	}
+
+	let mut rest = &data[19..];
+	let mut nanos = 0;
+	if let Some(fraction) = rest.strip_prefix('.') {
+		let len = fraction.find(|c: char| !c.is_ascii_digit()).unwrap_or(fraction.len());
+		if len == 0 {
+			return None
+		}
+		let mut place = 100_000_000;
+		for c in fraction[..len].chars() {
+			nanos += c.to_digit(10)? * place;
+			place /= 10;
+		}
+		rest = &fraction[len..];
+	}
+	let offset = match rest {
+		"Z" | "z" => 0,
+		_ if rest.len() == 6 && rest.as_bytes()[3] == b':' => {
+			let hours: i64 = rest[1..3].parse().ok()?;
+			let minutes: i64 = rest[4..6].parse().ok()?;
+			match rest.as_bytes()[0] {
+				b'+' => hours * 3600 + minutes * 60,
+				b'-' => -(hours * 3600 + minutes * 60),
+				_ => return None
+			}
+		}
+		_ => return None
+	};
+
+	let days = days_from_civil(year as i64, month, day);
+	let secs = days * 86400 + hour as i64 * 3600 + minute as i64 * 60 + second as i64 - offset;
+	Some((secs, nanos))
+}
+
+
+impl Serialize<ReadableProfile> for Duration {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_string(write_seconds(self.as_secs(), self.subsec_nanos()) + "s");
+	}
+}
+
+
+impl Deserialize<ReadableProfile> for Duration {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		let string = data.deserialize_string()?;
+		parse_duration(&string).ok_or_else(|| not_a(&string, "a duration such as \"1.5s\""))
+	}
+}
+
+
+impl Serialize<EfficientProfile> for Duration {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		<(u64, u32) as Serialize>::serialize((self.as_secs(), self.subsec_nanos()), data);
+	}
+}
+
+
+impl Deserialize<EfficientProfile> for Duration {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		let (secs, nanos) = <(u64, u32) as Deserialize>::deserialize(data)?;
+		if nanos as i128 >= NANOS_PER_SEC {
+			return Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: "nanoseconds must be less than a second".into() }))
+		}
+		Ok(Duration::new(secs, nanos))
+	}
+}
+
+
+/// The seconds and nanoseconds since the unix epoch, where the nanoseconds always count forward
+fn to_unix(time: SystemTime) -> (i64, u32) {
+	match time.duration_since(UNIX_EPOCH) {
+		Ok(x) => (x.as_secs() as i64, x.subsec_nanos()),
+		Err(e) => {
+			let nanos = -(e.duration().as_nanos() as i128);
+			(nanos.div_euclid(NANOS_PER_SEC) as i64, nanos.rem_euclid(NANOS_PER_SEC) as u32)
+		}
+	}
+}
+
+
+fn from_unix(secs: i64, nanos: u32) -> Option<SystemTime> {
+	if nanos as i128 >= NANOS_PER_SEC {
+		return None
+	}
+	if secs >= 0 {
+		UNIX_EPOCH.checked_add(Duration::new(secs as u64, nanos))
+	} else {
+		UNIX_EPOCH.checked_sub(Duration::new(secs.unsigned_abs(), 0))?.checked_add(Duration::new(0, nanos))
+	}
+}
+
+
+fn out_of_range() -> DeserializationError {
+	DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: "time is out of range".into() })
+}
+
+
+impl Serialize<ReadableProfile> for SystemTime {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		let (secs, nanos) = to_unix(self);
+		data.serialize_string(to_rfc3339(secs, nanos));
+	}
+}
+
+
+impl Deserialize<ReadableProfile> for SystemTime {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		let string = data.deserialize_string()?;
+		let (secs, nanos) = from_rfc3339(&string).ok_or_else(|| not_a(&string, "an RFC 3339 timestamp"))?;
+		from_unix(secs, nanos).ok_or_else(out_of_range)
+	}
+}
+
+
+impl Serialize<EfficientProfile> for SystemTime {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		<(i64, u32) as Serialize>::serialize(to_unix(self), data);
+	}
+}
+
+
+impl Deserialize<EfficientProfile> for SystemTime {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		let (secs, nanos) = <(i64, u32) as Deserialize>::deserialize(data)?;
+		from_unix(secs, nanos).ok_or_else(out_of_range)
+	}
+}
+
+
+/// Nanoseconds since the unix epoch, clamped to the range of an i64
+#[cfg(feature = "chrono")]
+fn chrono_nanos(time: chrono::DateTime<chrono::Utc>) -> i64 {
+	time.timestamp_nanos_opt().unwrap_or(if time.timestamp() < 0 { i64::MIN } else { i64::MAX })
+}
+
+
+#[cfg(feature = "chrono")]
+impl Serialize for chrono::DateTime<chrono::Utc> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		if data.is_human_readable() {
+			data.serialize_string(to_rfc3339(self.timestamp(), self.timestamp_subsec_nanos()));
+		} else {
+			data.serialize_num(chrono_nanos(self));
+		}
+	}
+}
+
+
+#[cfg(feature = "chrono")]
+impl Deserialize for chrono::DateTime<chrono::Utc> {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		if data.is_human_readable() {
+			let string = data.deserialize_string()?;
+			chrono::DateTime::parse_from_rfc3339(&string)
+				.map(|x| x.to_utc())
+				.map_err(|_| not_a(&string, "an RFC 3339 timestamp"))
+		} else {
+			data.deserialize_num().map(chrono::DateTime::from_timestamp_nanos)
+		}
+	}
+}
+
+
+#[cfg(feature = "chrono")]
+impl Serialize for chrono::NaiveDate {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		if data.is_human_readable() {
+			data.serialize_string(self.to_string());
+		} else {
+			data.serialize_num(chrono_nanos(self.and_time(chrono::NaiveTime::MIN).and_utc()));
+		}
+	}
+}
+
+
+#[cfg(feature = "chrono")]
+impl Deserialize for chrono::NaiveDate {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		if data.is_human_readable() {
+			let string = data.deserialize_string()?;
+			string.parse().map_err(|_| not_a(&string, "a date such as \"2024-02-29\""))
+		} else {
+			data.deserialize_num().map(|x| chrono::DateTime::from_timestamp_nanos(x).date_naive())
+		}
+	}
+}
+
+
+#[cfg(feature = "chrono")]
+impl Serialize for chrono::NaiveDateTime {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		if data.is_human_readable() {
+			data.serialize_string(self.format("%Y-%m-%dT%H:%M:%S%.f").to_string());
+		} else {
+			data.serialize_num(chrono_nanos(self.and_utc()));
+		}
+	}
+}
+
+
+#[cfg(feature = "chrono")]
+impl Deserialize for chrono::NaiveDateTime {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		if data.is_human_readable() {
+			let string = data.deserialize_string()?;
+			string.parse().map_err(|_| not_a(&string, "a date and time such as \"2024-02-29T12:00:00\""))
+		} else {
+			data.deserialize_num().map(|x| chrono::DateTime::from_timestamp_nanos(x).naive_utc())
+		}
+	}
+}
+
+
+/// Nanoseconds since the unix epoch, clamped to the range of an i64
+#[cfg(feature = "time")]
+fn time_nanos(time: time::OffsetDateTime) -> i64 {
+	time.unix_timestamp_nanos().clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+
+#[cfg(feature = "time")]
+impl Serialize for time::OffsetDateTime {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		if data.is_human_readable() {
+			data.serialize_string(to_rfc3339(self.unix_timestamp(), self.nanosecond()));
+		} else {
+			data.serialize_num(time_nanos(self));
+		}
+	}
+}
+
+
+#[cfg(feature = "time")]
+impl Deserialize for time::OffsetDateTime {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		let nanos = if data.is_human_readable() {
+			let string = data.deserialize_string()?;
+			let (secs, nanos) = from_rfc3339(&string).ok_or_else(|| not_a(&string, "an RFC 3339 timestamp"))?;
+			secs as i128 * NANOS_PER_SEC + nanos as i128
+		} else {
+			data.deserialize_num::<i64>()? as i128
+		};
+		time::OffsetDateTime::from_unix_timestamp_nanos(nanos).map_err(|_| out_of_range())
+	}
+}
+
+
+#[cfg(feature = "time")]
+impl Serialize for time::Date {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		if data.is_human_readable() {
+			data.serialize_string(format!("{:04}-{:02}-{:02}", self.year(), self.month() as u8, self.day()));
+		} else {
+			data.serialize_num(time_nanos(self.midnight().assume_utc()));
+		}
+	}
+}
+
+
+#[cfg(feature = "time")]
+impl Deserialize for time::Date {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		let nanos = if data.is_human_readable() {
+			let string = data.deserialize_string()?;
+			let (secs, _) = from_rfc3339(&format!("{}T00:00:00Z", string)).ok_or_else(|| not_a(&string, "a date such as \"2024-02-29\""))?;
+			secs as i128 * NANOS_PER_SEC
+		} else {
+			data.deserialize_num::<i64>()? as i128
+		};
+		time::OffsetDateTime::from_unix_timestamp_nanos(nanos).map(|x| x.date()).map_err(|_| out_of_range())
+	}
+}
+
+
+#[cfg(feature = "time")]
+impl Serialize for time::Time {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		let (hour, minute, second, nanos) = self.as_hms_nano();
+		if data.is_human_readable() {
+			let mut out = format!("{:02}:{:02}:{:02}", hour, minute, second);
+			if nanos != 0 {
+				out += format!(".{:09}", nanos).trim_end_matches('0');
+			}
+			data.serialize_string(out);
+		} else {
+			data.serialize_num(((hour as i64 * 60 + minute as i64) * 60 + second as i64) * NANOS_PER_SEC as i64 + nanos as i64);
+		}
+	}
+}
+
+
+#[cfg(feature = "time")]
+impl Deserialize for time::Time {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		let (secs, nanos) = if data.is_human_readable() {
+			let string = data.deserialize_string()?;
+			from_rfc3339(&format!("1970-01-01T{}Z", string)).ok_or_else(|| not_a(&string, "a time such as \"12:30:00\""))?
+		} else {
+			let nanos: i64 = data.deserialize_num()?;
+			(nanos.div_euclid(NANOS_PER_SEC as i64), nanos.rem_euclid(NANOS_PER_SEC as i64) as u32)
+		};
+		if !(0..86400).contains(&secs) {
+			return Err(out_of_range())
+		}
+		time::Time::from_hms_nano((secs / 3600) as u8, (secs / 60 % 60) as u8, (secs % 60) as u8, nanos).map_err(|_| out_of_range())
+	}
+}
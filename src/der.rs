@@ -0,0 +1,509 @@
+//! ASN.1 DER, for structures that sit next to certificates or telecom protocols.
+//!
+//! Keyed values are written as a SEQUENCE of their fields, in order and without their keys,
+//! so they must be read back in the same order, as impl_key_serde does. Fields missing from the end of a
+//! SEQUENCE are missing fields, like OPTIONAL components. Sequences are a SEQUENCE OF, while unordered
+//! collections are a SET OF sorted by their encodings. Integers are INTEGER, floats are binary REAL, strings
+//! are UTF8String, bytes are OCTET STRING, None is NULL and variants are their value wrapped in a context specific tag
+//! numbered by their index. Every length is definite and as short as possible
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+#[cfg(feature = "text")]
+use crate::text::TextRepr;
+use crate::{DeserializationError, DeserializationErrorKind, DeserializationResult, Deserialize, DeserializeSeed, Number, NumberType, PrimitiveSerializer, Serialize, Serializer};
+
+
+const BOOLEAN: u8 = 0x01;
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const NULL: u8 = 0x05;
+const REAL: u8 = 0x09;
+const UTF8_STRING: u8 = 0x0C;
+const PRINTABLE_STRING: u8 = 0x13;
+const IA5_STRING: u8 = 0x16;
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+/// The class and constructed bits of a context specific tag
+const CONTEXT: u8 = 0xA0;
+const CONSTRUCTED: u8 = 0x20;
+
+
+fn tag_name(tag: u8) -> &'static str {
+	match tag {
+		BOOLEAN => "BOOLEAN",
+		INTEGER => "INTEGER",
+		OCTET_STRING => "OCTET STRING",
+		NULL => "NULL",
+		REAL => "REAL",
+		UTF8_STRING | PRINTABLE_STRING | IA5_STRING => "string",
+		SEQUENCE => "SEQUENCE",
+		SET => "SET",
+		_ => "other tag"
+	}
+}
+
+
+/// Write the identifier octets of a context specific tag
+fn context_tag(out: &mut Vec<u8>, number: u8) {
+	match number {
+		0..=30 => out.push(CONTEXT | number),
+		31..=127 => out.extend([CONTEXT | 0x1F, number]),
+		_ => out.extend([CONTEXT | 0x1F, 0x81, number & 0x7F])
+	}
+}
+
+
+fn push_length(out: &mut Vec<u8>, len: usize) {
+	if len < 0x80 {
+		out.push(len as u8);
+		return
+	}
+	let bytes = len.to_be_bytes();
+	let skip = bytes.iter().take_while(|x| **x == 0).count();
+	out.push(0x80 | (bytes.len() - skip) as u8);
+	out.extend_from_slice(&bytes[skip..]);
+}
+
+
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+	let mut out = vec![tag];
+	push_length(&mut out, content.len());
+	out.extend_from_slice(content);
+	out
+}
+
+
+/// The shortest two's complement encoding of an integer
+fn int_bytes(value: i128) -> Vec<u8> {
+	let bytes = value.to_be_bytes();
+	let mut skip = 0;
+	while skip < bytes.len() - 1 && ((bytes[skip] == 0 && bytes[skip + 1] & 0x80 == 0) || (bytes[skip] == 0xFF && bytes[skip + 1] & 0x80 != 0)) {
+		skip += 1;
+	}
+	bytes[skip..].to_vec()
+}
+
+
+fn int_from_bytes(bytes: &[u8]) -> Result<i128, DeserializationError> {
+	if bytes.is_empty() || bytes.len() > 16 {
+		return Err(DeserializationError::invalid_format("INTEGER must be between 1 and 16 bytes long"))
+	}
+	let fill = if bytes[0] & 0x80 == 0 { 0 } else { 0xFF };
+	let mut out = [fill; 16];
+	out[(16 - bytes.len())..].copy_from_slice(bytes);
+	Ok(i128::from_be_bytes(out))
+}
+
+
+/// The contents of a binary REAL, with an odd mantissa as DER requires
+fn real_bytes(value: f64) -> Vec<u8> {
+	if value == 0.0 {
+		return if value.is_sign_negative() { vec![0x43] } else { Vec::new() }
+	}
+	if value.is_nan() {
+		return vec![0x42]
+	}
+	if value.is_infinite() {
+		return vec![if value > 0.0 { 0x40 } else { 0x41 }]
+	}
+	let bits = value.to_bits();
+	let exponent_bits = ((bits >> 52) & 0x7FF) as i64;
+	let fraction = bits & ((1 << 52) - 1);
+	let (mut mantissa, mut exponent) = match exponent_bits {
+		0 => (fraction, -1074),
+		x => (fraction | 1 << 52, x - 1075)
+	};
+	let zeros = mantissa.trailing_zeros();
+	mantissa >>= zeros;
+	exponent += zeros as i64;
+
+	let exponent = int_bytes(exponent as i128);
+	let mut out = vec![0x80 | ((bits >> 63) as u8) << 6 | (exponent.len() - 1) as u8];
+	out.extend(exponent);
+	let mantissa = mantissa.to_be_bytes();
+	out.extend(mantissa.iter().skip_while(|x| **x == 0));
+	out
+}
+
+
+fn real_from_bytes(bytes: &[u8]) -> Result<f64, DeserializationError> {
+	let first = match bytes.first() {
+		Some(x) => *x,
+		None => return Ok(0.0)
+	};
+	match first {
+		0x40 => return Ok(f64::INFINITY),
+		0x41 => return Ok(f64::NEG_INFINITY),
+		0x42 => return Ok(f64::NAN),
+		0x43 => return Ok(-0.0),
+		x if x & 0x80 == 0 => return Err(DeserializationError::invalid_format("decimal REAL is not supported")),
+		_ => {}
+	}
+	let base_bits = match (first >> 4) & 3 {
+		0 => 1,
+		1 => 3,
+		2 => 4,
+		_ => return Err(DeserializationError::invalid_format("REAL has a reserved base"))
+	};
+	let scale = ((first >> 2) & 3) as i64;
+	let (exponent_len, start) = match first & 3 {
+		3 => (*bytes.get(1).ok_or(DeserializationError::EOF)? as usize, 2),
+		x => (x as usize + 1, 1)
+	};
+	let exponent = bytes.get(start..(start + exponent_len)).ok_or(DeserializationError::EOF)?;
+	let exponent = i64::try_from(int_from_bytes(exponent)?).map_err(|_| DeserializationError::invalid_format("REAL exponent is too large"))?;
+	let mantissa = &bytes[(start + exponent_len)..];
+	if mantissa.len() > 8 {
+		return Err(DeserializationError::invalid_format("REAL mantissa is too large"))
+	}
+	let mantissa = mantissa.iter().fold(0u64, |acc, x| acc << 8 | *x as u64) as f64;
+	let power = (exponent.saturating_mul(base_bits) + scale).clamp(-2200, 2200) as i32;
+	let value = mantissa * 2f64.powi(power / 2) * 2f64.powi(power - power / 2);
+	Ok(if first & 0x40 == 0 { value } else { -value })
+}
+
+
+/// Compare encodings in the order of a SET OF, which pads the shorter one with trailing zeros
+fn set_order(a: &[u8], b: &[u8]) -> Ordering {
+	let len = a.len().max(b.len());
+	(0..len)
+		.map(|i| a.get(i).copied().unwrap_or(0).cmp(&b.get(i).copied().unwrap_or(0)))
+		.find(|x| x.is_ne())
+		.unwrap_or(Ordering::Equal)
+}
+
+
+/// A decoded header. The tag holds the class and constructed bits, along with the low tag number if there is one
+struct Header<'a> {
+	tag: u8,
+	number: u32,
+	content: &'a [u8],
+	len: usize
+}
+
+
+fn read_header(bytes: &[u8]) -> Result<Header<'_>, DeserializationError> {
+	let tag = *bytes.first().ok_or(DeserializationError::EOF)?;
+	let mut pos = 1;
+	let number = match tag & 0x1F {
+		0x1F => {
+			let mut number = 0u32;
+			loop {
+				let byte = *bytes.get(pos).ok_or(DeserializationError::EOF)?;
+				pos += 1;
+				number = number.checked_mul(128).ok_or_else(|| DeserializationError::invalid_format("tag number is too large"))? | (byte & 0x7F) as u32;
+				if byte & 0x80 == 0 {
+					break number
+				}
+			}
+		}
+		x => x as u32
+	};
+	let first = *bytes.get(pos).ok_or(DeserializationError::EOF)?;
+	pos += 1;
+	let len = match first {
+		0x80 => return Err(DeserializationError::invalid_format("indefinite lengths are not allowed in DER")),
+		x if x < 0x80 => x as usize,
+		x => {
+			let count = (x & 0x7F) as usize;
+			let len_bytes = bytes.get(pos..(pos + count)).ok_or(DeserializationError::EOF)?;
+			pos += count;
+			if count > std::mem::size_of::<usize>() {
+				return Err(DeserializationError::invalid_format("length is too large"))
+			}
+			len_bytes.iter().fold(0usize, |acc, x| acc << 8 | *x as usize)
+		}
+	};
+	let content = bytes.get(pos..pos.checked_add(len).ok_or(DeserializationError::EOF)?).ok_or(DeserializationError::EOF)?;
+	Ok(Header { tag, number, content, len: pos + len })
+}
+
+
+/// Split the contents of a constructed value into the encodings of its elements
+fn read_elements(mut bytes: &[u8]) -> Result<VecDeque<Vec<u8>>, DeserializationError> {
+	let mut out = VecDeque::new();
+	while !bytes.is_empty() {
+		let len = read_header(bytes)?.len;
+		out.push_back(bytes[..len].to_vec());
+		bytes = &bytes[len..];
+	}
+	Ok(out)
+}
+
+
+/// Nest an UnexpectedEOF from a sequence item, so it is not mistaken for the end of the sequence
+fn cut_short(e: DeserializationError) -> DeserializationError {
+	match e.kind {
+		DeserializationErrorKind::UnexpectedEOF => e.nest(),
+		_ => e
+	}
+}
+
+
+/// The encodings of the values written, or of the values left to read.
+/// When reading, `opened` is set once the constructed value being read has been split into its elements,
+/// and when writing, it is set once a keyed field is written
+#[derive(Debug, Default)]
+struct Der {
+	elements: VecDeque<Vec<u8>>,
+	opened: bool
+}
+
+
+impl Der {
+	fn from_encoding(encoding: Vec<u8>) -> Self {
+		Self { elements: VecDeque::from([encoding]), opened: false }
+	}
+
+	fn into_encoding(mut self) -> Vec<u8> {
+		if !self.opened && self.elements.len() == 1 {
+			return self.elements.pop_front().unwrap()
+		}
+		if !self.opened && self.elements.is_empty() {
+			return tlv(NULL, &[])
+		}
+		tlv(SEQUENCE, &self.elements.into_iter().flatten().collect::<Vec<_>>())
+	}
+
+	fn next(&mut self) -> Result<Vec<u8>, DeserializationError> {
+		self.elements.pop_front().ok_or(DeserializationError::EOF)
+	}
+
+	/// The contents of the next value, which must have one of the given tags
+	fn contents(&mut self, tags: &[u8], expected: &'static str) -> Result<Vec<u8>, DeserializationError> {
+		let encoding = self.next()?;
+		let header = read_header(&encoding)?;
+		if !tags.contains(&header.tag) {
+			return Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected, actual: tag_name(header.tag) }))
+		}
+		Ok(header.content.to_vec())
+	}
+
+	/// Split the constructed value being read into its elements, if that was not done yet
+	fn open(&mut self) -> Result<(), DeserializationError> {
+		if self.opened {
+			return Ok(())
+		}
+		let encoding = self.next()?;
+		let header = read_header(&encoding)?;
+		if header.tag & CONSTRUCTED == 0 {
+			return Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "SEQUENCE", actual: tag_name(header.tag) }))
+		}
+		self.elements = read_elements(header.content)?;
+		self.opened = true;
+		Ok(())
+	}
+
+	fn key_deserialize<T, F>(&mut self, key: &str, f: F) -> Result<T, DeserializationError>
+		where
+			F: FnOnce(&mut Der) -> Result<T, DeserializationError>
+	{
+		self.open().map_err(|e| e.nest().set_field(key))?;
+		let encoding = self.elements.pop_front().ok_or_else(|| DeserializationError::missing_field(key))?;
+		f(&mut Der::from_encoding(encoding)).map_err(|e| e.nest().set_field(key))
+	}
+
+	fn collection<P, T: Serialize<P>, I: IntoIterator<Item=T>>(&mut self, tag: u8, iter: I) {
+		let mut items = Der::default();
+		for item in iter {
+			items.serialize(item);
+		}
+		if tag == SET {
+			items.elements.make_contiguous().sort_by(|a, b| set_order(a, b));
+		}
+		self.elements.push_back(tlv(tag, &items.elements.into_iter().flatten().collect::<Vec<_>>()));
+	}
+
+	#[cfg(feature = "text")]
+	fn text(encoding: &[u8]) -> Result<TextRepr, DeserializationError> {
+		let header = read_header(encoding)?;
+		Ok(match header.tag {
+			BOOLEAN => TextRepr::Boolean(header.content.first().is_some_and(|x| *x != 0)),
+			INTEGER => TextRepr::Integer(int_from_bytes(header.content)? as i64),
+			REAL => TextRepr::Float(real_from_bytes(header.content)?),
+			NULL => TextRepr::Null,
+			UTF8_STRING | PRINTABLE_STRING | IA5_STRING => TextRepr::String(String::from_utf8(header.content.to_vec()).map_err(DeserializationErrorKind::from).no_field()?),
+			OCTET_STRING => TextRepr::Array(header.content.iter().map(|x| TextRepr::Integer(*x as i64)).collect()),
+			x if x & CONSTRUCTED != 0 => TextRepr::Array(read_elements(header.content)?.iter().map(|x| Self::text(x)).collect::<Result<_, _>>()?),
+			x => return Err(DeserializationError::invalid_format(format!("unsupported tag {}", x)))
+		})
+	}
+}
+
+
+impl PrimitiveSerializer for Der {
+	fn serialize_bool(&mut self, boolean: bool) {
+		self.elements.push_back(tlv(BOOLEAN, &[if boolean { 0xFF } else { 0 }]));
+	}
+
+	fn deserialize_bool(&mut self) -> Result<bool, DeserializationError> {
+		match self.contents(&[BOOLEAN], "BOOLEAN")?.as_slice() {
+			[0xFF] => Ok(true),
+			[0] => Ok(false),
+			x => Err(DeserializationError::new_kind(DeserializationErrorKind::NoMatch { actual: format!("{:?}", x) }))
+		}
+	}
+
+	fn serialize_num<T: NumberType>(&mut self, num: T) {
+		self.elements.push_back(match num.to_number() {
+			Number::Unsigned(x) => tlv(INTEGER, &int_bytes(x as i128)),
+			Number::Signed(x) => tlv(INTEGER, &int_bytes(x as i128)),
+			Number::F32(x) => tlv(REAL, &real_bytes(x as f64)),
+			Number::F64(x) => tlv(REAL, &real_bytes(x))
+		});
+	}
+
+	fn deserialize_num<T: NumberType>(&mut self) -> Result<T, DeserializationError> {
+		let encoding = self.next()?;
+		let header = read_header(&encoding)?;
+		let number = match header.tag {
+			INTEGER => {
+				let value = int_from_bytes(header.content)?;
+				u64::try_from(value).map(Number::Unsigned)
+					.or_else(|_| i64::try_from(value).map(Number::Signed))
+					.map_err(|_| DeserializationError::invalid_format("INTEGER is too large"))?
+			}
+			REAL => Number::F64(real_from_bytes(header.content)?),
+			x => return Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "INTEGER or REAL", actual: tag_name(x) }))
+		};
+		T::from_number(number).ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: "number does not fit its type".into() }))
+	}
+
+	fn serialize_string<T: Into<String>>(&mut self, string: T) {
+		self.elements.push_back(tlv(UTF8_STRING, string.into().as_bytes()));
+	}
+
+	fn deserialize_string(&mut self) -> Result<String, DeserializationError> {
+		let bytes = self.contents(&[UTF8_STRING, PRINTABLE_STRING, IA5_STRING], "string")?;
+		String::from_utf8(bytes).map_err(DeserializationErrorKind::from).no_field()
+	}
+
+	fn serialize_bytes<T: Into<VecDeque<u8>>>(&mut self, bytes: T) {
+		self.elements.push_back(tlv(OCTET_STRING, &Vec::from(bytes.into())));
+	}
+
+	fn deserialize_bytes<T: FromIterator<u8>>(&mut self) -> Result<T, DeserializationError> {
+		Ok(self.contents(&[OCTET_STRING], "OCTET STRING")?.into_iter().collect())
+	}
+}
+
+
+impl Serializer for Der {
+	fn serialize<P, T: Serialize<P>>(&mut self, item: T) {
+		let mut der = Der::default();
+		item.serialize(&mut der);
+		self.elements.push_back(der.into_encoding());
+	}
+
+	fn serialize_iter<P, T: Serialize<P>, I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		self.collection(SEQUENCE, iter);
+	}
+
+	fn serialize_set<P, T: Serialize<P>, I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		self.collection(SET, iter);
+	}
+
+	fn serialize_key<P, T: Serialize<P>, K: Borrow<str>>(&mut self, _key: K, item: T) {
+		self.serialize(item);
+		self.opened = true;
+	}
+
+	fn deserialize<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError> {
+		self.open()?;
+		let encoding = self.next()?;
+		T::deserialize(&mut Der::from_encoding(encoding)).map_err(cut_short)
+	}
+
+	fn deserialize_key_internal<P, T: Deserialize<P>>(&mut self, key: &str) -> Result<T, DeserializationError> {
+		self.key_deserialize(key, |x| T::deserialize(x))
+	}
+
+	fn deserialize_seed<P, S: DeserializeSeed<P>>(&mut self, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		self.open()?;
+		let encoding = self.next()?;
+		seed.deserialize(&mut Der::from_encoding(encoding)).map_err(cut_short)
+	}
+
+	fn deserialize_key_seed<P, S: DeserializeSeed<P>, K: Borrow<str>>(&mut self, key: K, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		self.key_deserialize(key.borrow(), |x| seed.deserialize(x))
+	}
+
+	fn serialize_option<P, T: Serialize<P>>(&mut self, item: Option<T>) {
+		match item {
+			Some(x) => x.serialize(self),
+			None => self.elements.push_back(tlv(NULL, &[]))
+		}
+	}
+
+	/// A NULL is read as None
+	fn deserialize_option<P, T: Deserialize<P>>(&mut self) -> Result<Option<T>, DeserializationError> {
+		if !self.opened && self.elements.front().is_some_and(|x| x.first() == Some(&NULL)) {
+			self.elements.pop_front();
+			return Ok(None)
+		}
+		T::deserialize(self).map(Some)
+	}
+
+	fn try_get_key<K: FromStr>(&mut self) -> Option<K> {
+		None
+	}
+
+	fn is_human_readable(&self) -> bool {
+		false
+	}
+
+	fn serialize_variant<P, T: Serialize<P>>(&mut self, _name: &str, index: u8, item: T) {
+		let mut der = Der::default();
+		item.serialize(&mut der);
+		let content = der.into_encoding();
+		let mut out = Vec::new();
+		context_tag(&mut out, index);
+		push_length(&mut out, content.len());
+		out.extend(content);
+		self.elements.push_back(out);
+	}
+
+	fn deserialize_variant_index(&mut self, variants: &[(&str, u8)]) -> Result<u8, DeserializationError> {
+		let encoding = self.elements.front().ok_or(DeserializationError::EOF)?;
+		let header = read_header(encoding)?;
+		if header.tag & 0xE0 != CONTEXT {
+			return Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "context specific tag", actual: tag_name(header.tag) }))
+		}
+		variants.iter()
+			.find(|x| x.1 as u32 == header.number)
+			.map(|x| x.1)
+			.ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::NoMatch { actual: header.number.to_string() }))
+	}
+
+	fn deserialize_variant<P, T: Deserialize<P>>(&mut self, _name: &str) -> Result<T, DeserializationError> {
+		let encoding = self.next()?;
+		let content = read_header(&encoding)?.content.to_vec();
+		T::deserialize(&mut Der::from_encoding(content))
+	}
+
+	#[cfg(feature = "text")]
+	fn deserialize_text(&mut self) -> Result<TextRepr, DeserializationError> {
+		if self.opened {
+			let elements: Vec<_> = self.elements.drain(..).collect();
+			return Ok(TextRepr::Array(elements.iter().map(|x| Self::text(x)).collect::<Result<_, _>>()?))
+		}
+		Self::text(&self.next()?)
+	}
+}
+
+
+/// Serialize any value in DER
+pub fn to_vec<P, T: Serialize<P>>(value: T) -> Vec<u8> {
+	let mut der = Der::default();
+	value.serialize(&mut der);
+	der.into_encoding()
+}
+
+
+/// Deserialize any value from DER
+pub fn from_slice<P, T: Deserialize<P>>(data: &[u8]) -> Result<T, DeserializationError> {
+	T::deserialize(&mut Der::from_encoding(data.to_vec()))
+}
@@ -0,0 +1,28 @@
+//! Serde for either::Either, stored as a variant named "left" or "right".
+//! Binary formats write the variant as a single tag byte, 0 for left and 1 for right
+use either::Either;
+use crate::{DeserializationError, DeserializationErrorKind, Deserialize, Serialize, Serializer};
+
+
+const VARIANTS: [(&str, u8); 2] = [("left", 0), ("right", 1)];
+
+
+impl<P, L: Serialize<P>, R: Serialize<P>> Serialize<P> for Either<L, R> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		match self {
+			Either::Left(x) => data.serialize_variant(VARIANTS[0].0, 0, x),
+			Either::Right(x) => data.serialize_variant(VARIANTS[1].0, 1, x)
+		}
+	}
+}
+
+
+impl<P, L: Deserialize<P>, R: Deserialize<P>> Deserialize<P> for Either<L, R> {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		match data.deserialize_variant_index(&VARIANTS)? {
+			0 => data.deserialize_variant(VARIANTS[0].0).map(Either::Left),
+			1 => data.deserialize_variant(VARIANTS[1].0).map(Either::Right),
+			x => Err(DeserializationError::new_kind(DeserializationErrorKind::NoMatch { actual: x.to_string() }))
+		}
+	}
+}
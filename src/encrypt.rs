@@ -0,0 +1,68 @@
+//! Field level encryption. An `Encrypted<T>` is serialized to binary, then encrypted by the Cipher
+//! the marshall provides, so that secret fields can live in otherwise plain text configs.
+//! Text formats store the ciphertext as a base64 string, while binary stores the bytes as is.
+//!
+//! With the zeroize feature, the plaintext buffers used along the way are zeroed before they are freed,
+//! and Encrypted implements Zeroize
+use std::collections::VecDeque;
+
+use crate::{base64, DeserializationError, Deserialize, MarshalledDeserialize, MarshalledSerialize, Serialize, Serializer};
+
+
+/// Encrypts and decrypts the serialized bytes of Encrypted values. Implemented by the marshall, which holds the key
+pub trait Cipher {
+	fn encrypt(&self, plaintext: &[u8]) -> Vec<u8>;
+	fn decrypt(&self, ciphertext: Vec<u8>) -> Result<Vec<u8>, DeserializationError>;
+}
+
+
+/// A value that is encrypted with the Cipher of the marshall when serialized
+#[derive(Debug, Clone, PartialEq)]
+pub struct Encrypted<T>(pub T);
+
+
+impl<P, M: Cipher, T: Serialize<P>> MarshalledSerialize<P, M> for Encrypted<T> {
+	fn serialize<S: Serializer>(self, data: &mut S, marshall: &M) {
+		let mut plaintext = VecDeque::new();
+		Serializer::serialize::<P, _>(&mut plaintext, self.0);
+		let plaintext = Vec::from(plaintext);
+		let ciphertext = marshall.encrypt(&plaintext);
+		wipe(plaintext);
+		if data.is_human_readable() {
+			data.serialize_string(base64::encode(&ciphertext));
+		} else {
+			data.serialize_bytes(ciphertext);
+		}
+	}
+}
+
+
+impl<'a, P, M: Cipher, T: Deserialize<P>> MarshalledDeserialize<'a, P, M> for Encrypted<T> {
+	fn deserialize<S: Serializer>(data: &mut S, marshall: &'a M) -> Result<Self, DeserializationError> {
+		let ciphertext = if data.is_human_readable() {
+			base64::decode(&data.deserialize_string()?)?
+		} else {
+			data.deserialize_bytes()?
+		};
+		let mut plaintext: VecDeque<u8> = marshall.decrypt(ciphertext)?.into();
+		let result = Serializer::deserialize::<P, _>(&mut plaintext).map(Self);
+		wipe(plaintext.into());
+		result
+	}
+}
+
+
+#[cfg(feature = "zeroize")]
+impl<T: zeroize::Zeroize> zeroize::Zeroize for Encrypted<T> {
+	fn zeroize(&mut self) {
+		self.0.zeroize();
+	}
+}
+
+
+/// Zero the whole allocation of a plaintext buffer if the zeroize feature is enabled
+fn wipe(mut buffer: Vec<u8>) {
+	#[cfg(feature = "zeroize")]
+	zeroize::Zeroize::zeroize(&mut buffer);
+	buffer.clear();
+}
@@ -0,0 +1,563 @@
+//! The FlexBuffers format, the schema-less sibling of FlatBuffers, so documents can be read in place
+//! by the FlexBuffers readers of other languages.
+//!
+//! Values are written with a width of 64 bits and aligned to 8 bytes, which every reader accepts.
+//! Keyed values become maps with sorted keys, sequences become untyped vectors, and bytes become blobs.
+//! Reading accepts any width, along with the typed and fixed length vectors other writers produce
+use std::borrow::Borrow;
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+#[cfg(feature = "text")]
+use crate::text::TextRepr;
+use crate::{DeserializationError, DeserializationErrorKind, Deserialize, DeserializeSeed, Number, NumberType, PrimitiveSerializer, Serialize, Serializer};
+
+
+const NULL: u8 = 0;
+const INT: u8 = 1;
+const UINT: u8 = 2;
+const FLOAT: u8 = 3;
+const KEY: u8 = 4;
+const STRING: u8 = 5;
+const INDIRECT_INT: u8 = 6;
+const INDIRECT_UINT: u8 = 7;
+const INDIRECT_FLOAT: u8 = 8;
+const MAP: u8 = 9;
+const VECTOR: u8 = 10;
+const VECTOR_INT: u8 = 11;
+const VECTOR_STRING: u8 = 15;
+const VECTOR_INT2: u8 = 16;
+const VECTOR_FLOAT4: u8 = 24;
+const BLOB: u8 = 25;
+const BOOL: u8 = 26;
+const VECTOR_BOOL: u8 = 36;
+
+/// How deeply vectors and maps may nest when reading, so crafted documents cannot overflow the stack
+const MAX_DEPTH: usize = 256;
+
+/// The width every value is written with, and its code in a packed type
+const WIDTH: usize = 8;
+const WIDTH_CODE: u8 = 3;
+
+
+/// A value of a document, before it is written or after it is read
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+	Null,
+	Int(i64),
+	UInt(u64),
+	Float(f64),
+	Bool(bool),
+	String(String),
+	Blob(Vec<u8>),
+	Vector(Vec<Value>),
+	Map(Vec<(String, Value)>)
+}
+
+
+impl Value {
+	fn type_name(&self) -> &'static str {
+		match self {
+			Value::Null => "null",
+			Value::Int(_) | Value::UInt(_) | Value::Float(_) => "number",
+			Value::Bool(_) => "bool",
+			Value::String(_) => "string",
+			Value::Blob(_) => "blob",
+			Value::Vector(_) => "vector",
+			Value::Map(_) => "map"
+		}
+	}
+
+	fn invalid_type(&self, expected: &'static str) -> DeserializationError {
+		DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected, actual: self.type_name() })
+	}
+
+	#[cfg(feature = "text")]
+	fn into_text(self) -> TextRepr {
+		match self {
+			Value::Null => TextRepr::Null,
+			Value::Int(x) => TextRepr::Integer(x),
+			Value::UInt(x) => TextRepr::Integer(x as i64),
+			Value::Float(x) => TextRepr::Float(x),
+			Value::Bool(x) => TextRepr::Boolean(x),
+			Value::String(x) => TextRepr::String(x),
+			Value::Blob(x) => TextRepr::Array(x.into_iter().map(|x| TextRepr::Integer(x as i64)).collect()),
+			Value::Vector(x) => TextRepr::Array(x.into_iter().map(Value::into_text).collect()),
+			Value::Map(x) => TextRepr::Table(x.into_iter().map(|(key, value)| (key.into(), value.into_text())).collect())
+		}
+	}
+}
+
+
+/// Where a value is stored, as seen from the slot of its parent
+enum Slot {
+	/// Stored in the slot itself
+	Inline(u64, u8),
+	/// Stored at the given position, with the slot holding the distance back to it
+	Offset(usize, u8)
+}
+
+
+#[derive(Default)]
+struct Encoder {
+	buf: Vec<u8>
+}
+
+
+impl Encoder {
+	fn align(&mut self) {
+		while !self.buf.len().is_multiple_of(WIDTH) {
+			self.buf.push(0);
+		}
+	}
+
+	fn push_uint(&mut self, value: u64) {
+		self.buf.extend_from_slice(&value.to_le_bytes());
+	}
+
+	fn push_slot(&mut self, slot: &Slot) {
+		match slot {
+			Slot::Inline(x, _) => self.push_uint(*x),
+			Slot::Offset(x, _) => self.push_uint((self.buf.len() - x) as u64)
+		}
+	}
+
+	fn packed_type(slot: &Slot) -> u8 {
+		match slot {
+			Slot::Inline(_, x) | Slot::Offset(_, x) => x << 2 | WIDTH_CODE
+		}
+	}
+
+	/// Write the size of a vector, followed by its slots, returning the position of the first slot
+	fn push_vector(&mut self, slots: &[Slot], types: bool) -> usize {
+		self.align();
+		self.push_uint(slots.len() as u64);
+		let start = self.buf.len();
+		for slot in slots {
+			self.push_slot(slot);
+		}
+		if types {
+			self.buf.extend(slots.iter().map(Self::packed_type));
+		}
+		start
+	}
+
+	fn write(&mut self, value: Value) -> Slot {
+		match value {
+			Value::Null => Slot::Inline(0, NULL),
+			Value::Int(x) => Slot::Inline(x as u64, INT),
+			Value::UInt(x) => Slot::Inline(x, UINT),
+			Value::Float(x) => Slot::Inline(x.to_bits(), FLOAT),
+			Value::Bool(x) => Slot::Inline(x as u64, BOOL),
+			Value::String(x) => {
+				self.align();
+				self.push_uint(x.len() as u64);
+				let start = self.buf.len();
+				self.buf.extend_from_slice(x.as_bytes());
+				self.buf.push(0);
+				Slot::Offset(start, STRING)
+			}
+			Value::Blob(x) => {
+				self.align();
+				self.push_uint(x.len() as u64);
+				let start = self.buf.len();
+				self.buf.extend(x);
+				Slot::Offset(start, BLOB)
+			}
+			Value::Vector(x) => {
+				let slots: Vec<_> = x.into_iter().map(|x| self.write(x)).collect();
+				Slot::Offset(self.push_vector(&slots, true), VECTOR)
+			}
+			Value::Map(mut x) => {
+				x.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+				let mut keys = Vec::with_capacity(x.len());
+				let mut values = Vec::with_capacity(x.len());
+				for (key, value) in x {
+					keys.push(Slot::Offset(self.buf.len(), KEY));
+					self.buf.extend_from_slice(key.as_bytes());
+					self.buf.push(0);
+					values.push(self.write(value));
+				}
+				let keys_start = self.push_vector(&keys, false);
+				self.align();
+				self.push_uint((self.buf.len() - keys_start) as u64);
+				self.push_uint(WIDTH as u64);
+				Slot::Offset(self.push_vector(&values, true), MAP)
+			}
+		}
+	}
+
+	fn finish(mut self, value: Value) -> Vec<u8> {
+		let root = self.write(value);
+		self.align();
+		self.push_slot(&root);
+		self.buf.push(Self::packed_type(&root));
+		self.buf.push(WIDTH as u8);
+		self.buf
+	}
+}
+
+
+fn out_of_bounds() -> DeserializationError {
+	DeserializationError::invalid_format("offset is out of bounds")
+}
+
+
+fn bad_width() -> DeserializationError {
+	DeserializationError::invalid_format("unsupported width")
+}
+
+
+struct Decoder<'a> {
+	buf: &'a [u8]
+}
+
+
+impl<'a> Decoder<'a> {
+	fn bytes(&self, pos: usize, len: usize) -> Result<&'a [u8], DeserializationError> {
+		self.buf.get(pos..pos.checked_add(len).ok_or_else(out_of_bounds)?).ok_or(DeserializationError::EOF)
+	}
+
+	fn uint(&self, pos: usize, width: usize) -> Result<u64, DeserializationError> {
+		let bytes = self.bytes(pos, width)?;
+		Ok(match width {
+			1 => bytes[0] as u64,
+			2 => u16::from_le_bytes(bytes.try_into().unwrap()) as u64,
+			4 => u32::from_le_bytes(bytes.try_into().unwrap()) as u64,
+			8 => u64::from_le_bytes(bytes.try_into().unwrap()),
+			_ => return Err(bad_width())
+		})
+	}
+
+	fn int(&self, pos: usize, width: usize) -> Result<i64, DeserializationError> {
+		let bytes = self.bytes(pos, width)?;
+		Ok(match width {
+			1 => bytes[0] as i8 as i64,
+			2 => i16::from_le_bytes(bytes.try_into().unwrap()) as i64,
+			4 => i32::from_le_bytes(bytes.try_into().unwrap()) as i64,
+			8 => i64::from_le_bytes(bytes.try_into().unwrap()),
+			_ => return Err(bad_width())
+		})
+	}
+
+	fn float(&self, pos: usize, width: usize) -> Result<f64, DeserializationError> {
+		let bytes = self.bytes(pos, width)?;
+		match width {
+			4 => Ok(f32::from_le_bytes(bytes.try_into().unwrap()) as f64),
+			8 => Ok(f64::from_le_bytes(bytes.try_into().unwrap())),
+			_ => Err(bad_width())
+		}
+	}
+
+	/// The position that the offset stored at pos points back to.
+	/// Offsets must point strictly backwards, so a value cannot contain itself
+	fn indirect(&self, pos: usize, width: usize) -> Result<usize, DeserializationError> {
+		let offset = self.uint(pos, width)?;
+		if offset == 0 {
+			return Err(DeserializationError::invalid_format(format!("offset at {} points to itself", pos)))
+		}
+		pos.checked_sub(offset as usize).ok_or_else(out_of_bounds)
+	}
+
+	fn size(&self, start: usize, width: usize) -> Result<usize, DeserializationError> {
+		let size = self.uint(start.checked_sub(width).ok_or_else(out_of_bounds)?, width)? as usize;
+		if size > self.buf.len() {
+			return Err(DeserializationError::EOF)
+		}
+		Ok(size)
+	}
+
+	fn key(&self, start: usize) -> Result<String, DeserializationError> {
+		let rest = self.buf.get(start..).ok_or_else(out_of_bounds)?;
+		let len = rest.iter().position(|x| *x == 0).ok_or(DeserializationError::EOF)?;
+		String::from_utf8(rest[..len].to_vec()).map_err(|e| DeserializationError::new_kind(DeserializationErrorKind::from(e)))
+	}
+
+	/// Read the value whose slot is at pos, given the width of the slot and the packed type of the value
+	fn read(&self, pos: usize, slot_width: usize, packed: u8, depth: usize) -> Result<Value, DeserializationError> {
+		if depth > MAX_DEPTH {
+			return Err(DeserializationError::invalid_format(format!("document is nested deeper than {} levels", MAX_DEPTH)))
+		}
+		let kind = packed >> 2;
+		let width = 1 << (packed & 3);
+		Ok(match kind {
+			NULL => Value::Null,
+			INT => Value::Int(self.int(pos, slot_width)?),
+			UINT => Value::UInt(self.uint(pos, slot_width)?),
+			FLOAT => Value::Float(self.float(pos, slot_width)?),
+			BOOL => Value::Bool(self.uint(pos, slot_width)? != 0),
+			_ => {
+				let start = self.indirect(pos, slot_width)?;
+				match kind {
+					INDIRECT_INT => Value::Int(self.int(start, width)?),
+					INDIRECT_UINT => Value::UInt(self.uint(start, width)?),
+					INDIRECT_FLOAT => Value::Float(self.float(start, width)?),
+					KEY => Value::String(self.key(start)?),
+					STRING => {
+						let len = self.size(start, width)?;
+						String::from_utf8(self.bytes(start, len)?.to_vec())
+							.map(Value::String)
+							.map_err(|e| DeserializationError::new_kind(DeserializationErrorKind::from(e)))?
+					}
+					BLOB => {
+						let len = self.size(start, width)?;
+						Value::Blob(self.bytes(start, len)?.to_vec())
+					}
+					VECTOR => self.read_vector(start, width, depth)?,
+					VECTOR_INT..=VECTOR_STRING | VECTOR_BOOL => {
+						let len = self.size(start, width)?;
+						let element = match kind {
+							VECTOR_BOOL => BOOL,
+							_ => kind - VECTOR_INT + INT
+						};
+						self.read_typed(start, len, width, element, depth)?
+					}
+					VECTOR_INT2..=VECTOR_FLOAT4 => {
+						let len = ((kind - VECTOR_INT2) / 3 + 2) as usize;
+						self.read_typed(start, len, width, (kind - VECTOR_INT2) % 3 + INT, depth)?
+					}
+					MAP => self.read_map(start, width, depth)?,
+					x => return Err(DeserializationError::invalid_format(format!("unsupported type {}", x)))
+				}
+			}
+		})
+	}
+
+	fn read_vector(&self, start: usize, width: usize, depth: usize) -> Result<Value, DeserializationError> {
+		let len = self.size(start, width)?;
+		let types = self.bytes(start + len * width, len)?;
+		let mut values = Vec::with_capacity(len);
+		for (i, packed) in types.iter().enumerate() {
+			values.push(self.read(start + i * width, width, *packed, depth + 1)?);
+		}
+		Ok(Value::Vector(values))
+	}
+
+	fn read_map(&self, start: usize, width: usize, depth: usize) -> Result<Value, DeserializationError> {
+		let len = self.size(start, width)?;
+		let prefix = start.checked_sub(3 * width).ok_or_else(out_of_bounds)?;
+		let keys_start = self.indirect(prefix, width)?;
+		let keys_width = self.uint(prefix + width, width)? as usize;
+		let types = self.bytes(start + len * width, len)?;
+		let mut entries = Vec::with_capacity(len);
+		for (i, packed) in types.iter().enumerate() {
+			let key = self.key(self.indirect(keys_start + i * keys_width, keys_width)?)?;
+			entries.push((key, self.read(start + i * width, width, *packed, depth + 1)?));
+		}
+		Ok(Value::Map(entries))
+	}
+
+	fn read_typed(&self, start: usize, len: usize, width: usize, element: u8, depth: usize) -> Result<Value, DeserializationError> {
+		let packed = element << 2 | (width.trailing_zeros() as u8);
+		Ok(Value::Vector((0..len).map(|i| self.read(start + i * width, width, packed, depth + 1)).collect::<Result<_, _>>()?))
+	}
+
+	fn root(&self) -> Result<Value, DeserializationError> {
+		let len = self.buf.len();
+		if len < 3 {
+			return Err(DeserializationError::EOF)
+		}
+		let width = self.buf[len - 1] as usize;
+		let pos = (len - 2).checked_sub(width).ok_or_else(out_of_bounds)?;
+		self.read(pos, width, self.buf[len - 2], 0)
+	}
+}
+
+
+/// A value being written or read.
+/// Values that are not keyed are kept in order, and keyed values are kept in a map
+#[derive(Debug, Default)]
+struct Document {
+	values: VecDeque<Value>,
+	map: Option<Vec<(String, Value)>>
+}
+
+
+impl Document {
+	fn from_value(value: Value) -> Self {
+		match value {
+			Value::Vector(x) => Self { values: x.into(), map: None },
+			Value::Map(x) => Self { values: VecDeque::new(), map: Some(x) },
+			Value::Null => Self::default(),
+			x => Self { values: VecDeque::from([x]), map: None }
+		}
+	}
+
+	fn into_value(mut self) -> Value {
+		if let Some(x) = self.map {
+			return Value::Map(x)
+		}
+		match self.values.len() {
+			0 => Value::Null,
+			1 => self.values.pop_front().unwrap(),
+			_ => Value::Vector(self.values.into())
+		}
+	}
+
+	fn next_value(&mut self) -> Result<Value, DeserializationError> {
+		self.values.pop_front().ok_or(DeserializationError::EOF)
+	}
+
+	/// Remove the value at the given key and deserialize it with the given fn.
+	/// A null value is only missing if the fn fails for lack of values
+	fn key_deserialize<T, F>(&mut self, key: &str, f: F) -> Result<T, DeserializationError>
+		where
+			F: FnOnce(&mut Document) -> Result<T, DeserializationError>
+	{
+		let map = self.map.as_mut().ok_or_else(|| DeserializationError::missing_field(key))?;
+		let idx = map.iter().position(|x| x.0 == key).ok_or_else(|| DeserializationError::missing_field(key))?;
+		let value = map.remove(idx).1;
+		let null = value == Value::Null;
+		f(&mut Document::from_value(value)).map_err(|e| match e.kind {
+			DeserializationErrorKind::UnexpectedEOF if null => DeserializationError::missing_field(key),
+			_ => e.nest().set_field(key)
+		})
+	}
+}
+
+
+impl PrimitiveSerializer for Document {
+	fn serialize_bool(&mut self, boolean: bool) {
+		self.values.push_back(Value::Bool(boolean));
+	}
+
+	fn deserialize_bool(&mut self) -> Result<bool, DeserializationError> {
+		match self.next_value()? {
+			Value::Bool(x) => Ok(x),
+			x => Err(x.invalid_type("bool"))
+		}
+	}
+
+	fn serialize_num<T: NumberType>(&mut self, num: T) {
+		self.values.push_back(match num.to_number() {
+			Number::Unsigned(x) => Value::UInt(x),
+			Number::Signed(x) => Value::Int(x),
+			Number::F32(x) => Value::Float(x as f64),
+			Number::F64(x) => Value::Float(x)
+		});
+	}
+
+	fn deserialize_num<T: NumberType>(&mut self) -> Result<T, DeserializationError> {
+		let number = match self.next_value()? {
+			Value::Int(x) => Number::Signed(x),
+			Value::UInt(x) => Number::Unsigned(x),
+			Value::Float(x) => Number::F64(x),
+			x => return Err(x.invalid_type("number"))
+		};
+		T::from_number(number).ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: "number does not fit its type".into() }))
+	}
+
+	fn serialize_string<T: Into<String>>(&mut self, string: T) {
+		self.values.push_back(Value::String(string.into()));
+	}
+
+	fn deserialize_string(&mut self) -> Result<String, DeserializationError> {
+		match self.next_value()? {
+			Value::String(x) => Ok(x),
+			x => Err(x.invalid_type("string"))
+		}
+	}
+
+	fn serialize_bytes<T: Into<VecDeque<u8>>>(&mut self, bytes: T) {
+		self.values.push_back(Value::Blob(bytes.into().into()));
+	}
+
+	fn deserialize_bytes<T: FromIterator<u8>>(&mut self) -> Result<T, DeserializationError> {
+		match self.next_value()? {
+			Value::Blob(x) => Ok(x.into_iter().collect()),
+			x => Err(x.invalid_type("blob"))
+		}
+	}
+}
+
+
+impl Serializer for Document {
+	fn serialize<P, T: Serialize<P>>(&mut self, item: T) {
+		let mut document = Document::default();
+		item.serialize(&mut document);
+		self.values.push_back(document.into_value());
+	}
+
+	/// Always writes a vector, even with one or no items
+	fn serialize_iter<P, T: Serialize<P>, I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		let mut document = Document::default();
+		for item in iter {
+			document.serialize(item);
+		}
+		self.values.push_back(Value::Vector(document.values.into()));
+	}
+
+	fn serialize_key<P, T: Serialize<P>, K: Borrow<str>>(&mut self, key: K, item: T) {
+		let mut document = Document::default();
+		item.serialize(&mut document);
+		self.map.get_or_insert_with(Vec::new).push((key.borrow().into(), document.into_value()));
+	}
+
+	fn deserialize<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError> {
+		let value = self.next_value()?;
+		T::deserialize(&mut Document::from_value(value))
+	}
+
+	fn deserialize_key_internal<P, T: Deserialize<P>>(&mut self, key: &str) -> Result<T, DeserializationError> {
+		self.key_deserialize(key, |x| T::deserialize(x))
+	}
+
+	fn deserialize_seed<P, S: DeserializeSeed<P>>(&mut self, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		let value = self.next_value()?;
+		seed.deserialize(&mut Document::from_value(value))
+	}
+
+	fn deserialize_key_seed<P, S: DeserializeSeed<P>, K: Borrow<str>>(&mut self, key: K, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		self.key_deserialize(key.borrow(), |x| seed.deserialize(x))
+	}
+
+	fn serialize_option<P, T: Serialize<P>>(&mut self, item: Option<T>) {
+		match item {
+			Some(x) => x.serialize(self),
+			None => self.values.push_back(Value::Null)
+		}
+	}
+
+	/// A null is read as a document with nothing in it
+	fn deserialize_option<P, T: Deserialize<P>>(&mut self) -> Result<Option<T>, DeserializationError> {
+		if self.values.is_empty() && self.map.is_none() {
+			return Ok(None)
+		}
+		T::deserialize(self).map(Some)
+	}
+
+	fn try_get_key<K: FromStr>(&mut self) -> Option<K> {
+		self.map.as_ref()?.first().and_then(|x| K::from_str(&x.0).ok())
+	}
+
+	fn is_human_readable(&self) -> bool {
+		false
+	}
+
+	fn remaining_keys(&self) -> Vec<String> {
+		self.map.iter().flatten().map(|x| x.0.clone()).collect()
+	}
+
+	#[cfg(feature = "text")]
+	fn deserialize_text(&mut self) -> Result<TextRepr, DeserializationError> {
+		if let Some(x) = self.map.take() {
+			return Ok(Value::Map(x).into_text())
+		}
+		self.next_value().map(Value::into_text)
+	}
+}
+
+
+/// Serialize any value as a FlexBuffers document
+pub fn to_vec<P, T: Serialize<P>>(value: T) -> Vec<u8> {
+	let mut document = Document::default();
+	value.serialize(&mut document);
+	Encoder::default().finish(document.into_value())
+}
+
+
+/// Deserialize any value from a FlexBuffers document
+pub fn from_slice<P, T: Deserialize<P>>(data: &[u8]) -> Result<T, DeserializationError> {
+	let root = Decoder { buf: data }.root()?;
+	T::deserialize(&mut Document::from_value(root))
+}
@@ -0,0 +1,72 @@
+//! Lifecycle hooks. Types whose Serialize and Deserialize impls come from the impl_key macros
+//! run these automatically whenever they are implemented, with no changes to the macro call
+use crate::DeserializationError;
+
+
+/// Runs on a value right before it is serialized, such as to normalize paths or clear caches
+pub trait BeforeSerialize {
+	fn before_serialize(&mut self);
+}
+
+
+/// Runs on a value right after it is deserialized, such as to fill in derived fields.
+/// Returning an error fails the deserialization
+pub trait AfterDeserialize {
+	fn after_deserialize(&mut self) -> Result<(), DeserializationError>;
+}
+
+
+/// Calls a hook if the wrapped value implements it, and does nothing otherwise.
+/// Used by the impl_key macros, where the type is known, to pick between the two by autoref
+#[doc(hidden)]
+pub struct Hook<'a, T>(pub &'a mut T);
+
+
+#[doc(hidden)]
+pub trait RunBeforeSerialize {
+	fn before_serialize(self);
+}
+
+
+impl<'a, T: BeforeSerialize> RunBeforeSerialize for Hook<'a, T> {
+	fn before_serialize(self) {
+		self.0.before_serialize();
+	}
+}
+
+
+#[doc(hidden)]
+pub trait SkipBeforeSerialize {
+	fn before_serialize(self);
+}
+
+
+impl<'a, 'b, T> SkipBeforeSerialize for &'b Hook<'a, T> {
+	fn before_serialize(self) {}
+}
+
+
+#[doc(hidden)]
+pub trait RunAfterDeserialize {
+	fn after_deserialize(self) -> Result<(), DeserializationError>;
+}
+
+
+impl<'a, T: AfterDeserialize> RunAfterDeserialize for Hook<'a, T> {
+	fn after_deserialize(self) -> Result<(), DeserializationError> {
+		self.0.after_deserialize()
+	}
+}
+
+
+#[doc(hidden)]
+pub trait SkipAfterDeserialize {
+	fn after_deserialize(self) -> Result<(), DeserializationError>;
+}
+
+
+impl<'a, 'b, T> SkipAfterDeserialize for &'b Hook<'a, T> {
+	fn after_deserialize(self) -> Result<(), DeserializationError> {
+		Ok(())
+	}
+}
@@ -1,24 +1,70 @@
-use std::borrow::{Borrow, BorrowMut};
+use std::borrow::{Borrow, BorrowMut, Cow};
 use std::collections::VecDeque;
-use std::fmt::{Debug, Formatter};
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::marker::PhantomData;
+use std::rc::Rc;
 use std::str::FromStr;
 use std::string::FromUtf8Error;
+use std::sync::Arc;
 
 #[cfg(feature = "bin")]
 pub use bin::prelude as bin_prelude;
-pub use primitives::NumberType;
+pub use primitives::{Number, NumberType};
 #[cfg(feature = "text")]
-pub use text::{json, json_prelude, toml, toml_prelude, mlist, mlist_prelude};
+pub use text::{config, csv, env, json, json_prelude, jsonl, toml, toml_prelude, mlist, mlist_prelude, yaml, yaml_prelude, ron, ron_prelude, hcl, hcl_prelude, plist, sexpr, urlencoded};
+#[cfg(feature = "text")]
+use text::TextRepr;
 
 #[cfg(feature = "bin")]
 pub mod bin;
+#[cfg(any(feature = "bin", feature = "text"))]
+mod base64;
+#[cfg(feature = "bin")]
+pub mod bencode;
+#[cfg(feature = "bin")]
+pub mod bits;
+#[cfg(feature = "bin")]
+pub mod cbor;
+pub mod case;
+pub mod check;
 pub mod common;
+mod datetime;
+#[cfg(feature = "bin")]
+pub mod der;
+#[cfg(feature = "bin")]
+pub mod encrypt;
+#[cfg(feature = "bin")]
+pub mod flexbuffers;
+pub mod hooks;
+#[cfg(feature = "either")]
+mod either;
+pub mod observe;
+#[cfg(feature = "bin")]
+pub mod postcard;
+#[cfg(any(feature = "glam", feature = "nalgebra"))]
+mod math;
 mod primitives;
+pub mod quantize;
+#[cfg(feature = "bin")]
+pub mod protobuf;
+pub mod reflect;
+pub mod remap;
+#[cfg(feature = "bin")]
+pub mod resp;
+#[cfg(any(feature = "rusqlite", feature = "postgres"))]
+pub mod sql;
+pub mod stats;
+#[cfg(feature = "sled")]
+pub mod store;
+pub mod validate;
 #[cfg(feature = "text")]
 pub mod text;
+pub mod version;
+pub mod warning;
 
 pub mod prelude {
-	pub use crate::{DeserializationError, Deserialize, EfficientProfile, impl_key_deser, impl_key_ser, impl_key_serde, ReadableProfile, Serialize, Serializer};
+	pub use crate::{Codec, DeserializationError, Deserialize, DeserializeSeed, EfficientProfile, FieldDocs, impl_field_docs, impl_key_deser, impl_key_ser, impl_key_serde, impl_variant_deser, impl_variant_ser, impl_variant_serde, ReadableProfile, Serialize, Serializer, Update};
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -29,7 +75,7 @@ pub enum SizeType {
 }
 
 /// An error that can occur when trying to deserialize data
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum DeserializationErrorKind {
 	/// An expected field could not be found
 	MissingField,
@@ -49,14 +95,51 @@ pub enum DeserializationErrorKind {
 	FromUTF8Error(FromUtf8Error),
 	/// The data we are deserializing from is too short
 	UnexpectedEOF,
+	/// A fixed length sequence, such as an array, has the wrong number of elements
+	WrongLength {
+		expected: usize,
+		actual: usize
+	},
 	Nested(Box<DeserializationError>),
 	InvalidFormat {
-		reason: String
+		reason: String,
+		/// E010_InvalidFormat, or a more specific code given by the parser that found the problem
+		code: ErrorCode
 	},
 	FromStrError(String),
+	/// A field was read correctly, but failed one of its checks
+	InvalidValue {
+		reason: String
+	},
+	/// A table in a parsed document holds the same key more than once,
+	/// and the parser was told to reject duplicate keys
+	DuplicateKey {
+		key: String
+	},
 	#[cfg(feature = "regex")]
 	/// An error only produced when a regex failed to parse
-	RegexParseError(regex::Error)
+	RegexParseError(regex::Error),
+	/// A domain specific error from a user impl or a third party backend
+	Custom(CustomError)
+}
+
+
+/// A shared handle to any error, which is equal only to clones of itself
+#[derive(Debug, Clone)]
+pub struct CustomError(pub Arc<dyn Error + Send + Sync>);
+
+
+impl PartialEq for CustomError {
+	fn eq(&self, other: &Self) -> bool {
+		Arc::ptr_eq(&self.0, &other.0)
+	}
+}
+
+
+impl Display for CustomError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.0)
+	}
 }
 
 
@@ -114,6 +197,7 @@ impl<T> DeserializationResult for Result<T, DeserializationError> {
 
 
 /// Represents an error, and the field the error occurred on if possible
+#[derive(Clone, PartialEq)]
 pub struct DeserializationError {
 	pub field: Option<String>,
 	pub kind: DeserializationErrorKind
@@ -130,7 +214,138 @@ impl Debug for DeserializationError {
 }
 
 
+/// A stable code for each kind of deserialization failure, which programs can branch on
+/// or use to look up localized messages
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+	E001_MissingField,
+	E002_InvalidType,
+	E003_NoMatch,
+	E004_InvalidUtf8,
+	E005_UnexpectedEof,
+	E006_FromStrError,
+	E007_InvalidValue,
+	E008_InvalidRegex,
+	E009_Custom,
+	/// An invalid format that has no more specific code
+	E010_InvalidFormat,
+	E011_MissingClosingBrace,
+	E012_UnbalancedBraces,
+	E013_MissingValue,
+	E014_MissingKey,
+	E015_UnterminatedString,
+	E016_InvalidTableName,
+	E017_IncludeCycle,
+	E018_TrailingData,
+	E019_WrongLength,
+	E020_DuplicateKey
+}
+
+
+impl ErrorCode {
+	/// The code alone, such as `E001`
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::E001_MissingField => "E001",
+			Self::E002_InvalidType => "E002",
+			Self::E003_NoMatch => "E003",
+			Self::E004_InvalidUtf8 => "E004",
+			Self::E005_UnexpectedEof => "E005",
+			Self::E006_FromStrError => "E006",
+			Self::E007_InvalidValue => "E007",
+			Self::E008_InvalidRegex => "E008",
+			Self::E009_Custom => "E009",
+			Self::E010_InvalidFormat => "E010",
+			Self::E011_MissingClosingBrace => "E011",
+			Self::E012_UnbalancedBraces => "E012",
+			Self::E013_MissingValue => "E013",
+			Self::E014_MissingKey => "E014",
+			Self::E015_UnterminatedString => "E015",
+			Self::E016_InvalidTableName => "E016",
+			Self::E017_IncludeCycle => "E017",
+			Self::E018_TrailingData => "E018",
+			Self::E019_WrongLength => "E019",
+			Self::E020_DuplicateKey => "E020"
+		}
+	}
+}
+
+
+impl DeserializationErrorKind {
+	/// The code of this kind. Nested errors take the code of the innermost error
+	pub fn code(&self) -> ErrorCode {
+		match self {
+			Self::MissingField => ErrorCode::E001_MissingField,
+			Self::InvalidType { .. } => ErrorCode::E002_InvalidType,
+			Self::NoMatch { .. } => ErrorCode::E003_NoMatch,
+			Self::FromUTF8Error(_) => ErrorCode::E004_InvalidUtf8,
+			Self::UnexpectedEOF => ErrorCode::E005_UnexpectedEof,
+			Self::WrongLength { .. } => ErrorCode::E019_WrongLength,
+			Self::Nested(e) => e.kind.code(),
+			Self::FromStrError(_) => ErrorCode::E006_FromStrError,
+			Self::InvalidValue { .. } => ErrorCode::E007_InvalidValue,
+			Self::DuplicateKey { .. } => ErrorCode::E020_DuplicateKey,
+			#[cfg(feature = "regex")]
+			Self::RegexParseError(_) => ErrorCode::E008_InvalidRegex,
+			Self::Custom(_) => ErrorCode::E009_Custom,
+			Self::InvalidFormat { code, .. } => *code
+		}
+	}
+}
+
+
+impl Display for DeserializationErrorKind {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::MissingField => write!(f, "missing field"),
+			Self::InvalidType { expected, actual } => write!(f, "expected {}, found {}", expected, actual),
+			Self::NoMatch { actual } => write!(f, "{} does not match any expected value", actual),
+			Self::FromUTF8Error(e) => write!(f, "invalid utf-8: {}", e),
+			Self::UnexpectedEOF => write!(f, "unexpected end of data"),
+			Self::WrongLength { expected, actual } => write!(f, "expected {} elements, found {}", expected, actual),
+			Self::Nested(e) => write!(f, "{}", e),
+			Self::InvalidFormat { reason, .. } => write!(f, "invalid format: {}", reason),
+			Self::FromStrError(e) => write!(f, "could not parse value: {}", e),
+			Self::InvalidValue { reason } => write!(f, "invalid value: {}", reason),
+			Self::DuplicateKey { key } => write!(f, "duplicate key: {}", key),
+			#[cfg(feature = "regex")]
+			Self::RegexParseError(e) => write!(f, "invalid regex: {}", e),
+			Self::Custom(e) => write!(f, "{}", e)
+		}
+	}
+}
+
+
+impl Display for DeserializationError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match &self.field {
+			None => write!(f, "{}", self.kind),
+			Some(x) => match &self.kind {
+				DeserializationErrorKind::Nested(_) => write!(f, "{}.{}", x, self.kind),
+				kind => write!(f, "{}: {}", x, kind)
+			}
+		}
+	}
+}
+
+
+impl Error for DeserializationError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match &self.kind {
+			DeserializationErrorKind::Nested(e) => Some(e.as_ref()),
+			DeserializationErrorKind::FromUTF8Error(e) => Some(e),
+			#[cfg(feature = "regex")]
+			DeserializationErrorKind::RegexParseError(e) => Some(e),
+			DeserializationErrorKind::Custom(e) => Some(e.0.as_ref()),
+			_ => None
+		}
+	}
+}
+
+
 impl DeserializationError {
+	#[cfg_attr(not(feature = "text"), allow(dead_code))]
 	const EOF: Self = Self { field: None, kind: DeserializationErrorKind::UnexpectedEOF };
 
 	pub fn new_kind<E: Into<DeserializationErrorKind>>(error: E) -> Self {
@@ -143,12 +358,23 @@ impl DeserializationError {
 		Self { field: Some(field.to_string()), kind: DeserializationErrorKind::MissingField }
 	}
 	pub fn invalid_format<T: ToString>(reason: T) -> Self {
-		Self { field: None, kind: DeserializationErrorKind::InvalidFormat { reason: reason.to_string() } }
+		Self::invalid_format_code(ErrorCode::E010_InvalidFormat, reason)
+	}
+	/// An invalid format with a more specific code than E010_InvalidFormat
+	pub fn invalid_format_code<T: ToString>(code: ErrorCode, reason: T) -> Self {
+		Self { field: None, kind: DeserializationErrorKind::InvalidFormat { reason: reason.to_string(), code } }
+	}
+	/// Wrap a domain specific error
+	pub fn custom<E: Error + Send + Sync + 'static>(error: E) -> Self {
+		Self::new_kind(DeserializationErrorKind::Custom(CustomError(Arc::new(error))))
 	}
 	pub fn set_field<T: ToString>(mut self, field: T) -> Self {
 		self.field = Some(field.to_string());
 		self
 	}
+	pub fn code(&self) -> ErrorCode {
+		self.kind.code()
+	}
 	pub fn nest(self) -> Self {
 		Self::new_kind(DeserializationErrorKind::from(self))
 	}
@@ -178,15 +404,22 @@ pub trait PrimitiveSerializer {
 }
 
 
+impl<P, V: Serialize<P>> Serialize<P> for Option<V> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_option(self);
+	}
+}
+
+
 impl<P, V: Deserialize<P>> Deserialize<P> for Option<V> {
-	#[warn(deprecated)]
-	fn deserialize<T: Serializer>(_: &mut T) -> Result<Self, DeserializationError> {
-		unimplemented!()
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		data.deserialize_option()
 	}
 
+	/// A missing key is deserialized as None
 	fn deserialize_key<T: Serializer>(data: &mut T, key: &str) -> Result<Self, DeserializationError> {
-		match data.deserialize_key_internal(key) {
-			Ok(x) => Ok(Some(x)),
+		match data.deserialize_key_internal::<P, Self>(key) {
+			Ok(x) => Ok(x),
 			Err(e) => match &e.kind {
 				DeserializationErrorKind::MissingField => Ok(None),
 				_ => Err(e)
@@ -200,11 +433,67 @@ impl<P, V: Deserialize<P>> Deserialize<P> for Option<V> {
 pub trait Serializer: PrimitiveSerializer + Debug + Sized {
 	fn serialize<P, T: Serialize<P>>(&mut self, item: T);
 	fn serialize_key<P, T: Serialize<P>, K: Borrow<str>>(&mut self, key: K, item: T);
+	/// Serialize every item of an iterator in order, without collecting it first
+	fn serialize_iter<P, T: Serialize<P>, I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		for item in iter {
+			self.serialize(item);
+		}
+	}
+	/// Serialize the items of an unordered collection, such as a HashSet.
+	/// Formats with a canonical order for sets override this to sort them
+	fn serialize_set<P, T: Serialize<P>, I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		self.serialize_iter(iter);
+	}
+	/// Serialize the elements of a tuple, which f serializes one by one.
+	/// Self describing formats override this to group the elements like a sequence
+	fn serialize_tuple<F: FnOnce(&mut Self)>(&mut self, f: F) {
+		f(self);
+	}
+	/// Deserialize the elements of a tuple that was serialized with serialize_tuple
+	fn deserialize_tuple<R, F: FnOnce(&mut Self) -> Result<R, DeserializationError>>(&mut self, f: F) -> Result<R, DeserializationError> {
+		f(self)
+	}
+	/// Serialize an optional value.
+	///
+	/// By default, a bool is written before the value to tell whether it is there,
+	/// which suits formats that are not self describing. Formats with a null override this
+	fn serialize_option<P, T: Serialize<P>>(&mut self, item: Option<T>) {
+		match item {
+			Some(x) => {
+				self.serialize_bool(true);
+				x.serialize(self);
+			}
+			None => self.serialize_bool(false)
+		}
+	}
+	/// Deserialize an optional value that was serialized with serialize_option
+	fn deserialize_option<P, T: Deserialize<P>>(&mut self) -> Result<Option<T>, DeserializationError> {
+		if self.deserialize_bool()? {
+			T::deserialize(self).map(Some)
+		} else {
+			Ok(None)
+		}
+	}
+	/// Serialize a number in the given number of bits.
+	/// Formats without bit granularity serialize the number as is
+	fn serialize_bits<T: NumberType>(&mut self, num: T, bits: u32) {
+		let _ = bits;
+		self.serialize_num(num);
+	}
+	/// Deserialize a number that was serialized with serialize_bits
+	fn deserialize_bits<T: NumberType>(&mut self, bits: u32) -> Result<T, DeserializationError> {
+		let _ = bits;
+		self.deserialize_num()
+	}
 	fn deserialize<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError>;
 	fn deserialize_key<P, T: Deserialize<P>, K: Borrow<str>>(&mut self, key: K) -> Result<T, DeserializationError> {
 		T::deserialize_key(self, key.borrow())
 	}
 	fn deserialize_key_internal<P, T: Deserialize<P>>(&mut self, key: &str) -> Result<T, DeserializationError>;
+	/// Deserialize the next item using the given seed
+	fn deserialize_seed<P, S: DeserializeSeed<P>>(&mut self, seed: &mut S) -> Result<S::Value, DeserializationError>;
+	/// Deserialize the item at the given key using the given seed
+	fn deserialize_key_seed<P, S: DeserializeSeed<P>, K: Borrow<str>>(&mut self, key: K, seed: &mut S) -> Result<S::Value, DeserializationError>;
 	fn deserialize_key_or<P, T, K, V>(&mut self, key: K, or: V) -> Result<T, DeserializationError>
 		where
 			T: Deserialize<P>,
@@ -229,6 +518,61 @@ pub trait Serializer: PrimitiveSerializer + Debug + Sized {
 	}
 	/// Try to get a key if it is the next item
 	fn try_get_key<K: FromStr>(&mut self) -> Option<K>;
+	/// Whether the format is meant to be read by people.
+	/// Values that have a compact binary form, such as ciphertext, can check this to pick a text form instead
+	fn is_human_readable(&self) -> bool {
+		true
+	}
+	/// The keys that have not been deserialized yet. Formats that do not store keys return none
+	fn remaining_keys(&self) -> Vec<String> {
+		Vec::new()
+	}
+	/// Serialize item as the variant of a sum type with the given name and index.
+	///
+	/// By default, the item is stored under the name of the variant
+	fn serialize_variant<P, T: Serialize<P>>(&mut self, name: &str, index: u8, item: T) {
+		let _ = index;
+		self.serialize_key(name, item);
+	}
+	/// Get the index of the variant that is stored next, out of the given variant names and indices
+	fn deserialize_variant_index(&mut self, variants: &[(&str, u8)]) -> Result<u8, DeserializationError> {
+		let key: String = self.try_get_key().ok_or(DeserializationErrorKind::UnexpectedEOF).no_field()?;
+		variants.iter()
+			.find(|x| x.0 == key)
+			.map(|x| x.1)
+			.ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::NoMatch { actual: key }))
+	}
+	/// Deserialize the value of a variant whose index was returned by deserialize_variant_index
+	fn deserialize_variant<P, T: Deserialize<P>>(&mut self, name: &str) -> Result<T, DeserializationError> {
+		self.deserialize_key_internal(name)
+	}
+	/// Serialize an untyped TextRepr as is.
+	///
+	/// The default implementation walks the TextRepr and serializes each value with its natural profile
+	#[cfg(feature = "text")]
+	fn serialize_text(&mut self, text: TextRepr) {
+		match text {
+			TextRepr::Empty => {}
+			TextRepr::Null => self.serialize_option(None::<TextRepr>),
+			TextRepr::String(x) => self.serialize_string(x),
+			TextRepr::Integer(x) => self.serialize_num(x),
+			TextRepr::Float(x) => self.serialize_num(x),
+			TextRepr::Boolean(x) => self.serialize_bool(x),
+			TextRepr::Table(x) => for (key, value) in x {
+				self.serialize_key(key, value);
+			}
+			TextRepr::Array(x) => for value in x {
+				self.serialize(value);
+			}
+		}
+	}
+	/// Deserialize the next value as an untyped TextRepr.
+	///
+	/// The default implementation fails, as only self describing formats know the type of the next value
+	#[cfg(feature = "text")]
+	fn deserialize_text(&mut self) -> Result<TextRepr, DeserializationError> {
+		Err(DeserializationError::invalid_format("the format is not self describing"))
+	}
 }
 
 
@@ -252,6 +596,38 @@ pub trait Deserialize<ProfileMarker = NaturalProfile>: Sized {
 }
 
 
+/// Allows the keys present in data to be deserialized into an existing value, leaving its other fields untouched,
+/// such as for PATCH style partial updates. Implemented by impl_key_deser
+pub trait Update<ProfileMarker = NaturalProfile> {
+	/// Replace the fields whose keys are in data. If any of them fail to deserialize, none are replaced.
+	/// An AfterDeserialize hook runs after the fields are replaced
+	fn update<T: Serializer>(&mut self, data: &mut T) -> Result<(), DeserializationError>;
+	/// Replace the fields whose keys are in the given document
+	#[cfg(feature = "text")]
+	fn update_from(&mut self, mut text: TextRepr) -> Result<(), DeserializationError> {
+		self.update(&mut text)
+	}
+}
+
+
+/// Allows some state to decode values from any type that implements ItemAccess.
+/// Unlike a marshall, the state is borrowed mutably and can change between each value,
+/// such as when assigning ids to each element of a collection
+pub trait DeserializeSeed<ProfileMarker = NaturalProfile> {
+	type Value;
+	fn deserialize<T: Serializer>(&mut self, data: &mut T) -> Result<Self::Value, DeserializationError>;
+}
+
+
+impl<P, V: Deserialize<P>> DeserializeSeed<P> for PhantomData<V> {
+	type Value = V;
+
+	fn deserialize<T: Serializer>(&mut self, data: &mut T) -> Result<Self::Value, DeserializationError> {
+		V::deserialize(data)
+	}
+}
+
+
 /// Allows the implementing type to be encoded in any type that implements ItemAccess.
 /// A marshall is passed by reference. Marshalls can be used in any way that is required
 pub trait MarshalledSerialize<ProfileMarker, Marshall> {
@@ -266,6 +642,37 @@ pub trait MarshalledDeserialize<'a, ProfileMarker, Marshall>: Sized {
 	fn deserialize<T: Serializer>(data: &mut T, marshall: &'a Marshall) -> Result<Self, DeserializationError>;
 }
 
+/// A value paired with a marshall, so that it can be serialized wherever a Serialize is expected, such as under a key
+pub struct WithMarshall<'a, T, M>(pub T, pub &'a M);
+
+
+impl<'a, P, M, T: MarshalledSerialize<P, M>> Serialize<P> for WithMarshall<'a, T, M> {
+	fn serialize<S: Serializer>(self, data: &mut S) {
+		self.0.serialize(data, self.1);
+	}
+}
+
+
+/// A seed that deserializes T with the given marshall, such as with deserialize_key_seed
+pub struct MarshallSeed<'a, T, M>(pub &'a M, PhantomData<T>);
+
+
+impl<'a, T, M> MarshallSeed<'a, T, M> {
+	pub fn new(marshall: &'a M) -> Self {
+		Self(marshall, PhantomData)
+	}
+}
+
+
+impl<'a, P, M, T: MarshalledDeserialize<'a, P, M>> DeserializeSeed<P> for MarshallSeed<'a, T, M> {
+	type Value = T;
+
+	fn deserialize<S: Serializer>(&mut self, data: &mut S) -> Result<T, DeserializationError> {
+		T::deserialize(data, self.0)
+	}
+}
+
+
 /// A marker trait for types that can be serialized and deserialized with the same profile,
 /// without a marshall. Is automatically implemented on all appropriate types
 pub trait Serde<ProfileMarker>: Serialize<ProfileMarker> + Deserialize<ProfileMarker> {}
@@ -303,135 +710,635 @@ impl<P, S: Deserialize<P>> Deserialize<P> for Box<S> {
 }
 
 
+/// Serializes the shared value, which is cloned if there are other references to it
+impl<P, S: Serialize<P> + Clone> Serialize<P> for Rc<S> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		Rc::unwrap_or_clone(self).serialize(data);
+	}
+}
+
+
+impl<P, S: Deserialize<P>> Deserialize<P> for Rc<S> {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		S::deserialize(data).map(Rc::new)
+	}
+}
+
+
+/// Serializes the shared value, which is cloned if there are other references to it
+impl<P, S: Serialize<P> + Clone> Serialize<P> for Arc<S> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		Arc::unwrap_or_clone(self).serialize(data);
+	}
+}
+
+
+impl<P, S: Deserialize<P>> Deserialize<P> for Arc<S> {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		S::deserialize(data).map(Arc::new)
+	}
+}
+
+
+impl<'a, P, S: ToOwned + ?Sized> Serialize<P> for Cow<'a, S> where S::Owned: Serialize<P> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		self.into_owned().serialize(data);
+	}
+}
+
+
+/// Always deserializes into an owned value
+impl<'a, P, S: ToOwned + ?Sized> Deserialize<P> for Cow<'a, S> where S::Owned: Deserialize<P> {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		S::Owned::deserialize(data).map(Cow::Owned)
+	}
+}
+
+
+/// Implements Serialize and Deserialize for a struct by storing each field under its name.
+/// The field names may be converted to another case with `rename_all`, such as
+/// `impl_key_serde!(Config, ReadableProfile, rename_all = Camel, max_connections, host)`.
+/// A field may also be handled by a Codec with `created with Timestamp`, and may be marked
+/// with the format version it was added or removed in with `since 2` or `until 3`.
+/// BeforeSerialize and AfterDeserialize from the hooks module are run if the struct implements them
 #[macro_export]
 macro_rules! impl_key_serde {
-    ($name: ty, $profile: ty, $($field: ident),*) => {
-		impl_key_ser!($name, $profile, $($field),*);
-		impl_key_deser!($name, $profile, $($field),*);
+    ($name: ty, $profile: ty, rename_all = $case: ident, $($field: ident $(since $since: literal)? $(until $until: literal)? $(with $codec: ty)? $(: $check: expr)?),*) => {
+		impl_key_ser!($name, $profile, rename_all = $case, $($field $(with $codec)?),*);
+		impl_key_deser!($name, $profile, rename_all = $case, $($field $(since $since)? $(until $until)? $(with $codec)? $(: $check)?),*);
+	};
+    ($name: ty, $profile: ty, $($field: ident $(since $since: literal)? $(until $until: literal)? $(with $codec: ty)? $(: $check: expr)?),*) => {
+		impl_key_ser!($name, $profile, $($field $(with $codec)?),*);
+		impl_key_deser!($name, $profile, $($field $(since $since)? $(until $until)? $(with $codec)? $(: $check)?),*);
 	};
 }
 
 #[macro_export]
 macro_rules! impl_key_ser {
-    ($name: ty, $profile: ty, $($field: ident),*) => {
+    (@value $value: expr) => { $value };
+    (@value $value: expr, $codec: ty) => { $crate::With::<$codec, _>::new($value) };
+    (@keys $name: ty, $profile: ty, $key: expr, $($field: ident $(with $codec: ty)?),*) => {
 		impl Serialize<$profile> for $name {
-			fn serialize<T: Serializer>(self, data: &mut T) {
-				$(data.serialize_key(stringify!($field), self.$field);)*
+			fn serialize<T: Serializer>(mut self, data: &mut T) {
+				#[allow(unused_imports)]
+				use $crate::hooks::{RunBeforeSerialize, SkipBeforeSerialize};
+				$crate::hooks::Hook(&mut self).before_serialize();
+				$(data.serialize_key(($key)(stringify!($field)), impl_key_ser!(@value self.$field $(, $codec)?));)*
 			}
 		}
 	};
+    ($name: ty, $profile: ty, rename_all = $case: ident, $($field: ident $(since $since: literal)? $(until $until: literal)? $(with $codec: ty)? $(: $check: expr)?),*) => {
+		impl_key_ser!(@keys $name, $profile, |x| $crate::case::Case::$case.apply(x), $($field $(with $codec)?),*);
+	};
+    ($name: ty, $profile: ty, $($field: ident $(since $since: literal)? $(until $until: literal)? $(with $codec: ty)? $(: $check: expr)?),*) => {
+		impl_key_ser!(@keys $name, $profile, |x| x, $($field $(with $codec)?),*);
+	};
 }
 
+/// Each field may be followed by a check from the check module, which runs after the field is deserialized,
+/// such as `impl_key_deser!(Config, ReadableProfile, port: check::range(1..=65535), host)`
 #[macro_export]
 macro_rules! impl_key_deser {
-    ($name: ty, $profile: ty, $($field: ident),*) => {
+    (@read $data: ident, $key: expr) => { $data.deserialize_key(AsRef::<str>::as_ref(&$key)) };
+    (@read $data: ident, $key: expr, $codec: ty) => { $data.deserialize_key::<_, $crate::With<$codec, _>, _>(AsRef::<str>::as_ref(&$key)).map(|x| x.0) };
+    (@keys $name: ty, $profile: ty, $key: expr, $($field: ident $(since $since: literal)? $(until $until: literal)? $(with $codec: ty)? $(: $check: expr)?),*) => {
 		impl Deserialize<$profile> for $name {
 			fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
-				Ok(Self {
-					$($field: data.deserialize_key(stringify!($field))?,)*
-				})
+				#[allow(unused_imports)]
+				use $crate::hooks::{RunAfterDeserialize, SkipAfterDeserialize};
+				let mut out = Self {
+					$($field: {
+						let key = ($key)(stringify!($field));
+						let value = impl_key_deser!(@read data, key $(, $codec)?);
+						$(let value = $crate::version::since(value, $since);)?
+						$(let value = $crate::version::until(value, $until, AsRef::<str>::as_ref(&key));)?
+						let value = value?;
+						$(($check)(&value).map_err(|reason| $crate::DeserializationError::new(key, $crate::DeserializationErrorKind::InvalidValue { reason }))?;)?
+						value
+					},)*
+				};
+				if $crate::warning::is_collecting() {
+					$crate::warning::unknown_fields(data, &[$(AsRef::<str>::as_ref(&($key)(stringify!($field)))),*]);
+				}
+				$crate::hooks::Hook(&mut out).after_deserialize()?;
+				Ok(out)
+			}
+		}
+
+		impl $crate::Update<$profile> for $name {
+			fn update<T: Serializer>(&mut self, data: &mut T) -> Result<(), DeserializationError> {
+				#[allow(unused_imports)]
+				use $crate::hooks::{RunAfterDeserialize, SkipAfterDeserialize};
+				let values = ($({
+					let key = ($key)(stringify!($field));
+					match impl_key_deser!(@read data, key $(, $codec)?) {
+						Ok(value) => {
+							$(($check)(&value).map_err(|reason| $crate::DeserializationError::new(key, $crate::DeserializationErrorKind::InvalidValue { reason }))?;)?
+							Some(value)
+						}
+						Err(e) if matches!(e.kind, $crate::DeserializationErrorKind::MissingField) => None,
+						Err(e) => return Err(e)
+					}
+				},)*);
+				let ($($field,)*) = values;
+				$(if let Some(value) = $field {
+					self.$field = value;
+				})*
+				$crate::hooks::Hook(self).after_deserialize()
 			}
 		}
 	};
+    ($name: ty, $profile: ty, rename_all = $case: ident, $($field: ident $(since $since: literal)? $(until $until: literal)? $(with $codec: ty)? $(: $check: expr)?),*) => {
+		impl_key_deser!(@keys $name, $profile, |x| $crate::case::Case::$case.apply(x), $($field $(since $since)? $(until $until)? $(with $codec)? $(: $check)?),*);
+	};
+    ($name: ty, $profile: ty, $($field: ident $(since $since: literal)? $(until $until: literal)? $(with $codec: ty)? $(: $check: expr)?),*) => {
+		impl_key_deser!(@keys $name, $profile, |x| x, $($field $(since $since)? $(until $until)? $(with $codec)? $(: $check)?),*);
+	};
 }
 
 
-#[cfg(test)]
-mod tests {
-	use std::collections::VecDeque;
+/// Custom serialization for the values of a field, for types without impls of their own
+/// or fields that need a special encoding. It is implemented on a marker type,
+/// which is named after the field in the impl_key macros, such as `created with Timestamp`
+pub trait Codec<V, ProfileMarker = NaturalProfile> {
+	fn serialize<T: Serializer>(value: V, data: &mut T);
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<V, DeserializationError>;
+}
 
-	use crate::{DeserializationErrorKind, MarshalledDeserialize, prelude::*};
-	#[cfg(feature = "bin")]
-	use crate::bin::{BinDeserialize, BinSerialize};
-	#[cfg(feature = "bin")]
-	use crate::bin_prelude::*;
-	#[cfg(feature = "text")]
-	use crate::text::{json_prelude::*, toml_prelude::*};
 
-	#[derive(Debug)]
-	struct TestStruct {
-		name: String,
-		id: String,
-		age: u16
-	}
+/// A value that is serialized and deserialized with the codec C
+pub struct With<C, V>(pub V, PhantomData<C>);
 
-	#[derive(Debug)]
-	struct TestStruct2 {
-		one: TestStruct,
-		two: TestStruct
-	}
 
-	#[derive(Debug)]
-	struct TestStruct3<'a> {
-		one: &'a TestStruct
+impl<C, V> With<C, V> {
+	pub fn new(value: V) -> Self {
+		Self(value, PhantomData)
 	}
+}
 
-	#[derive(Debug)]
-	struct TestStruct4 {
-		arr: Vec<String>
+
+impl<P, C: Codec<V, P>, V> Serialize<P> for With<C, V> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		C::serialize(self.0, data);
 	}
+}
 
-	impl_key_serde!(TestStruct, ReadableProfile, name, id, age);
 
-	impl Serialize<EfficientProfile> for TestStruct {
-		fn serialize<T: Serializer>(self, data: &mut T) {
-			data.serialize(self.name);
-			data.serialize(self.age);
-			data.serialize(self.id);
-		}
+impl<P, C: Codec<V, P>, V> Deserialize<P> for With<C, V> {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		C::deserialize(data).map(Self::new)
 	}
+}
 
-	impl Deserialize<EfficientProfile> for TestStruct {
-		fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
-			Ok(Self {
-				name: data.deserialize()?,
-				age: data.deserialize()?,
-				id: data.deserialize()?,
-			})
-		}
-	}
 
-	impl Serialize<ReadableProfile> for TestStruct2 {
-		fn serialize<T: Serializer>(self, data: &mut T) {
-			data.serialize_key::<ReadableProfile, _, _>("one", self.one);
-			data.serialize_key::<ReadableProfile, _, _>("two", self.two);
-		}
-	}
+/// Documentation for the keyed fields of a type, which text formats that support comments can write out
+pub trait FieldDocs {
+	/// Pairs of dotted field paths and their documentation
+	const FIELD_DOCS: &'static [(&'static str, &'static str)];
+}
 
-	impl Deserialize<ReadableProfile> for TestStruct2 {
-		fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
-			Ok(Self {
-				one: data.deserialize_key::<ReadableProfile, _, _>("one")?,
-				two: data.deserialize_key::<ReadableProfile, _, _>("two")?
-			})
-		}
-	}
 
-	impl<'a> Serialize<ReadableProfile> for TestStruct3<'a> {
-		fn serialize<T: Serializer>(self, data: &mut T) {
-			data.serialize_key("name", self.one.name.clone());
+/// Implements FieldDocs using the doc comments written on each field name, such as
+/// ```ignore
+/// impl_field_docs!(Config,
+///     /// The port to listen on
+///     port,
+///     host
+/// );
+/// ```
+#[macro_export]
+macro_rules! impl_field_docs {
+    ($name: ty, $($(#[doc = $doc: literal])* $field: ident),*) => {
+		impl FieldDocs for $name {
+			const FIELD_DOCS: &'static [(&'static str, &'static str)] = &[
+				$((stringify!($field), concat!($($doc, "\n"),*)),)*
+			];
 		}
-	}
+	};
+}
 
-	impl<'a> MarshalledDeserialize<'a, ReadableProfile, TestStruct2> for TestStruct3<'a> {
-		fn deserialize<T: Serializer>(data: &mut T, marshall: &'a TestStruct2) -> Result<Self, DeserializationError> {
-			let name: String = data.deserialize_key("name")?;
-			if marshall.one.name == name {
-				Ok(Self { one: &marshall.one })
-			} else if marshall.two.name == name {
-				Ok(Self { one: &marshall.two })
-			} else {
-				Err(DeserializationError::new_kind(DeserializationErrorKind::NoMatch { actual: "todo!".to_string() }))
-			}
-		}
-	}
 
-	impl Serialize<ReadableProfile> for TestStruct4 {
-		fn serialize<T: Serializer>(self, data: &mut T) {
+/// Implements Serialize and Deserialize for an enum whose variants each hold a single value,
+/// such as `enum Shape { Circle(f32), Square(Square) }`.
+///
+/// Self describing formats store the value under the name of the variant,
+/// while binary stores the index of the variant as a single byte.
+/// A variant can be given another name with `as`, and another index with `=`,
+/// such as `Circle as "circle" = 4`. Like Rust enums, variants without an index
+/// take the index after the previous variant. Repeated names or indices, and indices past 255, fail to compile.
+///
+/// Alternatively, the name of the variant can be stored next to the value under the key given by `tag`.
+/// With only `tag = "type"`, the value is internally tagged, so its own keys sit next to the tag,
+/// as in `{ type: "Circle", radius: 1.0 }`. The value must then be a keyed struct.
+/// With `tag = "t", content = "c"`, the value is adjacently tagged, as in `{ t: "Circle", c: { radius: 1.0 } }`.
+/// Tagged enums store the name of the variant in binary too, and ignore indices
+#[macro_export]
+macro_rules! impl_variant_serde {
+    ($name: ty, $profile: ty, tag = $tag: literal, content = $content: literal, $($variant: ident $(as $rename: literal)? $(= $index: literal)?),*) => {
+		impl_variant_ser!($name, $profile, tag = $tag, content = $content, $($variant $(as $rename)? $(= $index)?),*);
+		impl_variant_deser!($name, $profile, tag = $tag, content = $content, $($variant $(as $rename)? $(= $index)?),*);
+	};
+    ($name: ty, $profile: ty, tag = $tag: literal, $($variant: ident $(as $rename: literal)? $(= $index: literal)?),*) => {
+		impl_variant_ser!($name, $profile, tag = $tag, $($variant $(as $rename)? $(= $index)?),*);
+		impl_variant_deser!($name, $profile, tag = $tag, $($variant $(as $rename)? $(= $index)?),*);
+	};
+    ($name: ty, $profile: ty, $($variant: ident $(as $rename: literal)? $(= $index: literal)?),*) => {
+		impl_variant_ser!($name, $profile, $($variant $(as $rename)? $(= $index)?),*);
+		impl_variant_deser!($name, $profile, $($variant $(as $rename)? $(= $index)?),*);
+	};
+}
+
+#[macro_export]
+macro_rules! impl_variant_ser {
+    (@name $variant: ident) => { stringify!($variant) };
+    (@name $variant: ident, $rename: literal) => { $rename };
+    (@index) => { None };
+    (@index $index: literal) => { Some($index) };
+    ($name: ty, $profile: ty, tag = $tag: literal, content = $content: literal, $($variant: ident $(as $rename: literal)? $(= $index: literal)?),*) => {
+		impl Serialize<$profile> for $name {
+			fn serialize<T: Serializer>(self, data: &mut T) {
+				match self {
+					$(Self::$variant(item) => {
+						data.serialize_key($tag, impl_variant_ser!(@name $variant $(, $rename)?));
+						data.serialize_key($content, item);
+					})*
+				}
+			}
+		}
+	};
+    ($name: ty, $profile: ty, tag = $tag: literal, $($variant: ident $(as $rename: literal)? $(= $index: literal)?),*) => {
+		impl Serialize<$profile> for $name {
+			fn serialize<T: Serializer>(self, data: &mut T) {
+				match self {
+					$(Self::$variant(item) => {
+						data.serialize_key($tag, impl_variant_ser!(@name $variant $(, $rename)?));
+						Serialize::serialize(item, data);
+					})*
+				}
+			}
+		}
+	};
+    ($name: ty, $profile: ty, $($variant: ident $(as $rename: literal)? $(= $index: literal)?),*) => {
+		impl Serialize<$profile> for $name {
+			fn serialize<T: Serializer>(self, data: &mut T) {
+				const VARIANTS: &[(&str, u8)] = &$crate::variant_indices([$((impl_variant_ser!(@name $variant $(, $rename)?), impl_variant_ser!(@index $($index)?))),*]);
+				match self {
+					$(Self::$variant(item) => {
+						let name = impl_variant_ser!(@name $variant $(, $rename)?);
+						let index = VARIANTS.iter().find(|x| x.0 == name).unwrap().1;
+						data.serialize_variant(name, index, item);
+					})*
+				}
+			}
+		}
+	};
+}
+
+#[macro_export]
+macro_rules! impl_variant_deser {
+    ($name: ty, $profile: ty, tag = $tag: literal, content = $content: literal, $($variant: ident $(as $rename: literal)? $(= $index: literal)?),*) => {
+		impl Deserialize<$profile> for $name {
+			fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+				let name: String = data.deserialize_key($tag)?;
+				$(if name == impl_variant_ser!(@name $variant $(, $rename)?) {
+					return Ok(Self::$variant(data.deserialize_key($content)?))
+				})*
+				Err($crate::DeserializationError::new($tag, $crate::DeserializationErrorKind::NoMatch { actual: name }))
+			}
+		}
+	};
+    ($name: ty, $profile: ty, tag = $tag: literal, $($variant: ident $(as $rename: literal)? $(= $index: literal)?),*) => {
+		impl Deserialize<$profile> for $name {
+			fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+				let name: String = data.deserialize_key($tag)?;
+				$(if name == impl_variant_ser!(@name $variant $(, $rename)?) {
+					return Ok(Self::$variant($crate::deserialize_inline(data)?))
+				})*
+				Err($crate::DeserializationError::new($tag, $crate::DeserializationErrorKind::NoMatch { actual: name }))
+			}
+		}
+	};
+    ($name: ty, $profile: ty, $($variant: ident $(as $rename: literal)? $(= $index: literal)?),*) => {
+		impl Deserialize<$profile> for $name {
+			fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+				const VARIANTS: &[(&str, u8)] = &$crate::variant_indices([$((impl_variant_ser!(@name $variant $(, $rename)?), impl_variant_ser!(@index $($index)?))),*]);
+				let index = data.deserialize_variant_index(VARIANTS)?;
+				let name = match VARIANTS.iter().find(|x| x.1 == index) {
+					Some(x) => x.0,
+					None => return Err(DeserializationError::invalid_format("variant index out of range"))
+				};
+				$(if name == impl_variant_ser!(@name $variant $(, $rename)?) {
+					return Ok(Self::$variant(data.deserialize_variant(name)?))
+				})*
+				unreachable!()
+			}
+		}
+	};
+}
+
+
+/// Deserializes a value straight from data, letting the type of the value be inferred
+#[doc(hidden)]
+pub fn deserialize_inline<P, V: Deserialize<P>, T: Serializer>(data: &mut T) -> Result<V, DeserializationError> {
+	V::deserialize(data)
+}
+
+
+/// Whether two strings are equal, in a const context
+const fn str_eq(x: &str, y: &str) -> bool {
+	let (x, y) = (x.as_bytes(), y.as_bytes());
+	if x.len() != y.len() {
+		return false
+	}
+	let mut i = 0;
+	while i < x.len() {
+		if x[i] != y[i] {
+			return false
+		}
+		i += 1;
+	}
+	true
+}
+
+
+/// Fills in the indices of variants that were not given one, counting up from the previous variant.
+///
+/// The macros evaluate this in a const, so panics on duplicate names or indices,
+/// or on an index past 255, stop the build
+#[doc(hidden)]
+pub const fn variant_indices<const N: usize>(variants: [(&'static str, Option<u8>); N]) -> [(&'static str, u8); N] {
+	let mut out = [("", 0u8); N];
+	let mut next = Some(0u8);
+	let mut i = 0;
+	while i < N {
+		let (name, index) = variants[i];
+		let index = match (index, next) {
+			(Some(x), _) | (None, Some(x)) => x,
+			(None, None) => panic!("variant index overflows u8")
+		};
+		let mut j = 0;
+		while j < i {
+			if str_eq(out[j].0, name) {
+				panic!("duplicate variant name")
+			}
+			if out[j].1 == index {
+				panic!("duplicate variant index")
+			}
+			j += 1;
+		}
+		out[i] = (name, index);
+		next = index.checked_add(1);
+		i += 1;
+	}
+	out
+}
+
+
+#[cfg(test)]
+mod tests {
+	#[cfg(feature = "bin")]
+	use std::collections::VecDeque;
+
+	use crate::prelude::*;
+	#[cfg(any(feature = "text", feature = "bin"))]
+	use crate::{DeserializationErrorKind, MarshalledDeserialize};
+	#[cfg(feature = "bin")]
+	use crate::bin::{BinDeserialize, BinSerialize};
+	#[cfg(feature = "bin")]
+	use crate::bin_prelude::*;
+	#[cfg(feature = "text")]
+	use crate::text::{json_prelude::*, toml_prelude::*};
+	#[cfg(feature = "text")]
+	use crate::text::TextRepr;
+
+	#[derive(Debug, Default, Clone)]
+	struct TestStruct {
+		name: String,
+		id: String,
+		age: u16
+	}
+
+	#[derive(Debug, Default)]
+	struct TestStruct2 {
+		one: TestStruct,
+		two: TestStruct
+	}
+
+	#[cfg(feature = "bin")]
+	#[derive(Debug)]
+	struct TestStruct3<'a> {
+		one: &'a TestStruct
+	}
+
+	#[cfg(feature = "text")]
+	#[derive(Debug)]
+	struct TestStruct4 {
+		arr: Vec<String>
+	}
+
+	#[cfg(all(feature = "text", feature = "bin"))]
+	#[derive(Debug)]
+	struct TestStruct5 {
+		name: String,
+		extra: TextRepr
+	}
+
+	#[cfg(any(feature = "text", feature = "bin"))]
+	#[derive(Debug)]
+	struct TestStruct6 {
+		port: u16,
+		host: String,
+		tags: Vec<String>
+	}
+
+	#[cfg(feature = "text")]
+	#[derive(Debug)]
+	struct TestStruct7 {
+		max_connections: u16,
+		host_name: String
+	}
+
+	#[cfg(feature = "text")]
+	#[derive(Debug)]
+	struct TestStruct8 {
+		name: String,
+		color: u32
+	}
+
+	#[cfg(feature = "text")]
+	#[derive(Debug)]
+	struct TestStruct9 {
+		name: String,
+		nickname: String,
+		legacy_id: u16
+	}
+
+	#[cfg(feature = "bin")]
+	#[derive(Debug)]
+	enum TestEnum2 {
+		Small(u8),
+		Large(u16)
+	}
+
+	#[cfg(feature = "text")]
+	#[derive(Debug)]
+	enum TestEnum3 {
+		Server(TestStruct6),
+		Limits(TestStruct7)
+	}
+
+	#[cfg(feature = "text")]
+	#[derive(Debug)]
+	enum TestEnum4 {
+		Limits(TestStruct7),
+		Number(u16)
+	}
+
+	#[cfg(feature = "text")]
+	struct HexCodec;
+
+	#[cfg(feature = "text")]
+	impl Codec<u32> for HexCodec {
+		fn serialize<T: Serializer>(value: u32, data: &mut T) {
+			data.serialize_string(format!("{:06x}", value));
+		}
+
+		fn deserialize<T: Serializer>(data: &mut T) -> Result<u32, DeserializationError> {
+			u32::from_str_radix(&data.deserialize_string()?, 16).map_err(|e| DeserializationError::new_kind(DeserializationErrorKind::from_str_err(e)))
+		}
+	}
+
+	#[cfg(all(feature = "text", feature = "bin"))]
+	#[derive(Debug)]
+	enum TestEnum {
+		Number(u16),
+		Text(String)
+	}
+
+	impl_key_serde!(TestStruct, ReadableProfile, name, id, age);
+	impl_field_docs!(TestStruct,
+		/// The name of the person
+		name,
+		id,
+		/// Age in years
+		///
+		/// Must fit in a u16
+		age
+	);
+	#[cfg(all(feature = "text", feature = "bin"))]
+	impl_variant_serde!(TestEnum, ReadableProfile, Number, Text);
+	#[cfg(feature = "bin")]
+	impl_variant_serde!(TestEnum2, ReadableProfile, Small as "small" = 4, Large);
+	#[cfg(feature = "text")]
+	impl_variant_serde!(TestEnum3, ReadableProfile, tag = "type", Server as "server", Limits);
+	#[cfg(feature = "text")]
+	impl_variant_serde!(TestEnum4, ReadableProfile, tag = "t", content = "c", Limits, Number);
+	#[cfg(feature = "text")]
+	impl_json!(TestEnum3, ReadableProfile);
+	#[cfg(feature = "text")]
+	impl_json!(TestEnum4, ReadableProfile);
+	#[cfg(all(feature = "text", feature = "bin"))]
+	impl_json!(TestEnum2, ReadableProfile);
+	#[cfg(feature = "bin")]
+	impl_bin!(TestEnum2, ReadableProfile);
+	#[cfg(any(feature = "text", feature = "bin"))]
+	impl_key_serde!(TestStruct6, ReadableProfile, port: crate::check::range(1..=65535), host: crate::check::non_empty(), tags: crate::check::length(..3));
+	#[cfg(feature = "text")]
+	impl_toml!(TestStruct6, ReadableProfile);
+	#[cfg(feature = "text")]
+	impl_key_serde!(TestStruct7, ReadableProfile, rename_all = Camel, max_connections, host_name);
+
+	#[cfg(feature = "text")]
+	impl crate::hooks::BeforeSerialize for TestStruct7 {
+		fn before_serialize(&mut self) {
+			self.host_name = self.host_name.trim().to_lowercase();
+		}
+	}
+
+	#[cfg(feature = "text")]
+	impl crate::hooks::AfterDeserialize for TestStruct7 {
+		fn after_deserialize(&mut self) -> Result<(), DeserializationError> {
+			if self.max_connections == 0 {
+				return Err(DeserializationError::new("maxConnections", DeserializationErrorKind::InvalidValue { reason: "must not be 0".into() }))
+			}
+			Ok(())
+		}
+	}
+
+	#[cfg(feature = "text")]
+	impl_key_serde!(TestStruct8, ReadableProfile, name, color with HexCodec);
+	#[cfg(feature = "text")]
+	impl_key_serde!(TestStruct9, ReadableProfile, name, nickname since 2, legacy_id until 3);
+	#[cfg(feature = "text")]
+	impl_json!(TestStruct8, ReadableProfile);
+	#[cfg(feature = "text")]
+	impl_json!(TestStruct7, ReadableProfile);
+	#[cfg(all(feature = "text", feature = "bin"))]
+	impl_key_serde!(TestStruct5, ReadableProfile, name, extra);
+
+	impl Serialize<EfficientProfile> for TestStruct {
+		fn serialize<T: Serializer>(self, data: &mut T) {
+			data.serialize(self.name);
+			data.serialize(self.age);
+			data.serialize(self.id);
+		}
+	}
+
+	impl Deserialize<EfficientProfile> for TestStruct {
+		fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+			Ok(Self {
+				name: data.deserialize()?,
+				age: data.deserialize()?,
+				id: data.deserialize()?,
+			})
+		}
+	}
+
+	impl Serialize<ReadableProfile> for TestStruct2 {
+		fn serialize<T: Serializer>(self, data: &mut T) {
+			data.serialize_key::<ReadableProfile, _, _>("one", self.one);
+			data.serialize_key::<ReadableProfile, _, _>("two", self.two);
+		}
+	}
+
+	impl Deserialize<ReadableProfile> for TestStruct2 {
+		fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+			Ok(Self {
+				one: data.deserialize_key::<ReadableProfile, _, _>("one")?,
+				two: data.deserialize_key::<ReadableProfile, _, _>("two")?
+			})
+		}
+	}
+
+	#[cfg(feature = "bin")]
+	impl<'a> Serialize<ReadableProfile> for TestStruct3<'a> {
+		fn serialize<T: Serializer>(self, data: &mut T) {
+			data.serialize_key("name", self.one.name.clone());
+		}
+	}
+
+	#[cfg(feature = "bin")]
+	impl<'a> MarshalledDeserialize<'a, ReadableProfile, TestStruct2> for TestStruct3<'a> {
+		fn deserialize<T: Serializer>(data: &mut T, marshall: &'a TestStruct2) -> Result<Self, DeserializationError> {
+			let name: String = data.deserialize_key("name")?;
+			if marshall.one.name == name {
+				Ok(Self { one: &marshall.one })
+			} else if marshall.two.name == name {
+				Ok(Self { one: &marshall.two })
+			} else {
+				Err(DeserializationError::new_kind(DeserializationErrorKind::NoMatch { actual: "todo!".to_string() }))
+			}
+		}
+	}
+
+	#[cfg(feature = "text")]
+	impl Serialize<ReadableProfile> for TestStruct4 {
+		fn serialize<T: Serializer>(self, data: &mut T) {
 			data.serialize_key("arr", self.arr);
 		}
 	}
 
+	#[cfg(feature = "text")]
 	impl Deserialize<ReadableProfile> for TestStruct4 {
 		fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
 			Ok(Self {
@@ -454,6 +1361,14 @@ mod tests {
 	impl_json!(TestStruct2, ReadableProfile);
 	#[cfg(feature = "text")]
 	impl_toml!(TestStruct4, ReadableProfile);
+	#[cfg(all(feature = "text", feature = "bin"))]
+	impl_json!(TestStruct5, ReadableProfile);
+	#[cfg(all(feature = "text", feature = "bin"))]
+	impl_bin!(TestStruct5, ReadableProfile);
+	#[cfg(all(feature = "text", feature = "bin"))]
+	impl_json!(TestEnum, ReadableProfile);
+	#[cfg(all(feature = "text", feature = "bin"))]
+	impl_bin!(TestEnum, ReadableProfile);
 
 	#[cfg(feature = "text")]
 	#[test]
@@ -574,4 +1489,2680 @@ mod tests {
 		println!("{}", ser);
 		println!("{:?}", TestStruct4::deserialize_toml(ser).unwrap());
 	}
+
+	#[cfg(all(feature = "text", feature = "bin"))]
+	#[test]
+	fn test_serde_7() {
+		let extra = TextRepr::from_json("{ a: 1, b: \"c\" }".into()).unwrap();
+		let test = TestStruct5 { name: "lmf".into(), extra };
+		let ser = test.serialize_json();
+		println!("{}", ser);
+		let test = TestStruct5::deserialize_json(ser).unwrap();
+		println!("{:?}", test);
+		let ser = test.serialize_bin();
+		println!("{:?}", ser);
+		let mut test = TestStruct5::deserialize_bin(ser).unwrap();
+		println!("{:?}", test);
+		assert_eq!(test.name, "lmf");
+		assert!(matches!(test.extra.pull_entry("a".to_string()), Ok(TextRepr::Integer(1))));
+		assert!(matches!(test.extra.pull_entry("b".to_string()), Ok(TextRepr::String(x)) if x == "c"));
+	}
+
+	#[cfg(all(feature = "text", feature = "bin"))]
+	#[test]
+	fn test_serde_8() {
+		let ser = TestEnum::Text("lmf".into()).serialize_json();
+		println!("{}", ser);
+		assert!(matches!(TestEnum::deserialize_json(ser).unwrap(), TestEnum::Text(x) if x == "lmf"));
+		let ser = TestEnum::Number(22).serialize_bin();
+		assert_eq!(ser, vec![0, 0, 22]);
+		assert!(matches!(TestEnum::deserialize_bin(ser).unwrap(), TestEnum::Number(22)));
+
+		#[cfg(feature = "either")]
+		{
+			use either::Either;
+			let mut ser = TextRepr::new();
+			Serialize::serialize(Either::<u16, String>::Right("lmf".into()), &mut ser);
+			println!("{:?}", ser);
+			let deser: Either<u16, String> = Deserialize::deserialize(&mut ser).unwrap();
+			assert_eq!(deser, Either::Right("lmf".to_string()));
+		}
+	}
+
+	#[cfg(feature = "bin")]
+	#[test]
+	fn test_serde_9() {
+		struct IdCounter(u32);
+
+		impl DeserializeSeed for IdCounter {
+			type Value = (u32, String);
+
+			fn deserialize<T: Serializer>(&mut self, data: &mut T) -> Result<Self::Value, DeserializationError> {
+				self.0 += 1;
+				Ok((self.0, data.deserialize()?))
+			}
+		}
+
+		let mut ser: VecDeque<u8> = VecDeque::new();
+		Serializer::serialize(&mut ser, vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+		let deser = ser.deserialize_seed(&mut crate::common::SeqSeed(IdCounter(0))).unwrap();
+		println!("{:?}", deser);
+		assert_eq!(deser, vec![(1, "a".to_string()), (2, "b".to_string()), (3, "c".to_string())]);
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_10() {
+		use crate::text::arena::TextArena;
+
+		let mut arena = TextArena::new();
+		let mut doc = arena.parse_json("{ one: { name: \"a\", id: \"b\", age: 0 }, \"two\": { name: \"c\", id: \"d\", age: 2 } }").unwrap();
+		let test: TestStruct2 = Deserialize::<ReadableProfile>::deserialize(&mut doc).unwrap();
+		println!("{:?}", test);
+		assert!(doc.is_empty());
+		assert_eq!(test.two.name, "c");
+		assert_eq!(test.two.age, 2);
+
+		let mut doc = arena.new_doc();
+		Serialize::<ReadableProfile>::serialize(test, &mut doc);
+		println!("{}", doc.to_repr().to_json());
+		let test: TestStruct2 = Deserialize::<ReadableProfile>::deserialize(&mut doc).unwrap();
+		assert_eq!(test.one.id, "b");
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_11() {
+		use std::sync::Arc;
+
+		let repr = TextRepr::from_toml("[one]\nname = \"a\"\n[two]\nname = \"b\"\n".into()).unwrap();
+		let key = |table: &str| match &repr {
+			TextRepr::Table(x) => match &x[table] {
+				TextRepr::Table(x) => x.keys().next().unwrap().clone(),
+				_ => unreachable!()
+			},
+			_ => unreachable!()
+		};
+		assert!(Arc::ptr_eq(&key("one"), &key("two")));
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_12() {
+		use crate::text::iter::PathSegment;
+
+		let mut repr = TextRepr::from_json("{ name: \"a\", inner: { secret: \"b\", id: \"c\" } }".into()).unwrap();
+		assert_eq!(repr.entries().count(), 2);
+		assert_eq!(repr.values().filter(|x| matches!(x, TextRepr::Table(_))).count(), 1);
+
+		repr.walk_mut(|path, value| if path.last() == Some(&PathSegment::Key("secret")) {
+			*value = TextRepr::String("***".into());
+		});
+		let mut paths = Vec::new();
+		repr.walk(|path, value| if let TextRepr::String(x) = value {
+			paths.push((path.iter().map(ToString::to_string).collect::<Vec<_>>().join("."), x.clone()));
+		});
+		paths.sort();
+		println!("{:?}", paths);
+		assert_eq!(paths[1], ("inner.secret".to_string(), "***".to_string()));
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_13() {
+		use crate::text::TextFormat;
+
+		let repr = TextRepr::from_json("{ one: { name: \"a\", id: \"b\", age: 0 }, two: { name: \"c\", id: \"d\", age: 2 } }".into()).unwrap();
+		println!("{}", repr);
+		println!("{}", repr.format(TextFormat::Toml));
+		assert!(!repr.to_string().contains('\n'));
+		let test = TestStruct2::deserialize_json(repr.to_string()).unwrap();
+		assert_eq!(test.two.age, 2);
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_14() {
+		let json: TextRepr = "{ name: \"a\", id: \"b\", age: 3 }".parse().unwrap();
+		let toml: TextRepr = "name = \"a\"\nid = \"b\"\nage = 3\n".parse().unwrap();
+		println!("{}\n{}", json, toml);
+		let json = TestStruct::deserialize_json(json.to_json()).unwrap();
+		let toml = TestStruct::deserialize_json(toml.to_json()).unwrap();
+		assert_eq!(json.age, toml.age);
+		assert_eq!(json.id, toml.id);
+
+		// The error of the format the data looks like is kept
+		let data = "{\"a\": [1, 2}";
+		assert_eq!(data.parse::<TextRepr>().unwrap_err(), TextRepr::from_json(data.into()).unwrap_err());
+		let data = "[server]\nport = 80x\n";
+		assert_eq!(data.parse::<TextRepr>().unwrap_err(), TextRepr::from_toml(data.into()).unwrap_err());
+	}
+
+	#[cfg(feature = "tracing")]
+	#[test]
+	fn test_serde_15() {
+		use crate::text::trace::{flatten_fields, record_fields};
+
+		let test = TestStruct2::deserialize_json("{ one: { name: \"a\", id: \"b\", age: 0 }, two: { name: \"c\", id: \"d\", age: 2 } }".into()).unwrap();
+		let mut fields: Vec<_> = flatten_fields::<ReadableProfile, _>(test).into_iter().map(|(name, value)| format!("{name}={value}")).collect();
+		fields.sort();
+		println!("{:?}", fields);
+		assert_eq!(fields[0], "one.age=0");
+		assert_eq!(fields[5], "two.name=\"c\"");
+
+		let span = ::tracing::info_span!("test", one.name = ::tracing::field::Empty);
+		record_fields::<ReadableProfile, _>(&span, TestStruct { name: "a".into(), id: "b".into(), age: 3 });
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_16() {
+		use crate::config::{ConfigLoader, LoadError};
+
+		let path = std::env::temp_dir().join("simple_serde_test_serde_16.toml");
+		std::fs::write(&path, "[two]\nname = \"c\"\nage = 2\n").unwrap();
+
+		let mut loader = ConfigLoader::new();
+		loader.add_defaults(TextRepr::from_json("{ one: { name: \"a\", id: \"b\", age: 0 }, two: { name: \"x\", id: \"d\", age: 0 } }".into()).unwrap());
+		loader.add_file(&path).unwrap();
+		loader.add_overrides(TextRepr::from_json("{ one: { age: 1 } }".into()).unwrap());
+		let test: TestStruct2 = loader.load::<ReadableProfile, _>().unwrap();
+		println!("{:?}", test);
+		assert_eq!(test.one.age, 1);
+		assert_eq!(test.one.name, "a");
+		assert_eq!(test.two.name, "c");
+		assert_eq!(test.two.age, 2);
+
+		let mut loader = ConfigLoader::new();
+		loader.add_defaults(TextRepr::from_json("{ one: { name: \"a\", id: \"b\", age: 0 }, two: { id: \"d\" } }".into()).unwrap());
+		loader.add_file(&path).unwrap();
+		loader.add_overrides(TextRepr::from_json("{ two: { age: \"old\" } }".into()).unwrap());
+		match loader.load::<ReadableProfile, TestStruct2>() {
+			Err(LoadError::Deserialize { path, source, .. }) => {
+				println!("{:?} {:?}", path, source);
+				assert_eq!(path, vec!["two".to_string(), "age".to_string()]);
+				assert_eq!(source.as_deref(), Some("overrides"));
+			}
+			x => panic!("{:?}", x)
+		}
+		std::fs::remove_file(path).unwrap();
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_17() {
+		use crate::config::{ConfigLoader, LoadError};
+
+		let vars = [("APP_ONE__AGE", "4"), ("APP_TWO__NAME", "e"), ("APP_TWO__AGE", "x"), ("OTHER_ONE__AGE", "5")];
+		let mut loader = ConfigLoader::new();
+		loader.add_defaults(TextRepr::from_json("{ one: { name: \"a\", id: \"b\", age: 0 }, two: { name: \"c\", id: \"d\", age: 2 } }".into()).unwrap());
+		loader.add_env_from("APP_", vars[..2].iter().map(|(x, y)| (x.to_string(), y.to_string())));
+		let test: TestStruct2 = loader.load::<ReadableProfile, _>().unwrap();
+		assert_eq!(test.one.age, 4);
+		assert_eq!(test.two.name, "e");
+
+		let mut loader = ConfigLoader::new();
+		loader.add_defaults(TextRepr::from_json("{ one: { name: \"a\", id: \"b\", age: 0 }, two: { name: \"c\", id: \"d\", age: 2 } }".into()).unwrap());
+		loader.add_env_from("APP_", vars.iter().map(|(x, y)| (x.to_string(), y.to_string())));
+		match loader.load::<ReadableProfile, TestStruct2>() {
+			Err(LoadError::Deserialize { source, .. }) => assert_eq!(source.as_deref(), Some("APP_TWO__AGE")),
+			x => panic!("{:?}", x)
+		}
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_18() {
+		let dir = std::env::temp_dir().join("simple_serde_test_serde_18");
+		std::fs::create_dir_all(dir.join("sub")).unwrap();
+		std::fs::write(dir.join("main.toml"), "include = \"sub/two.toml\"\n[one]\nname = \"a\"\nid = \"b\"\nage = 1\n").unwrap();
+		std::fs::write(dir.join("sub/two.toml"), "[two]\nname = \"c\"\nid = \"d\"\nage = 2\n[one]\nage = 5\n").unwrap();
+		std::fs::write(dir.join("sub/cycle.toml"), "include = [\"../main.toml\", \"cycle.toml\"]\n").unwrap();
+
+		let test: TestStruct2 = Deserialize::<ReadableProfile>::deserialize(&mut TextRepr::from_toml_file(dir.join("main.toml")).unwrap()).unwrap();
+		println!("{:?}", test);
+		assert_eq!(test.one.age, 1);
+		assert_eq!(test.two.age, 2);
+
+		let err = TextRepr::from_toml_file(dir.join("sub/cycle.toml")).unwrap_err();
+		println!("{:?}", err);
+		assert!(matches!(err.kind, DeserializationErrorKind::InvalidFormat { reason, code: crate::ErrorCode::E017_IncludeCycle } if reason.starts_with("include cycle")));
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_19() {
+		let template = crate::config::template::<TestStruct2>();
+		println!("{}", template);
+		let test: TestStruct2 = Deserialize::<ReadableProfile>::deserialize(&mut TextRepr::from_toml(template).unwrap()).unwrap();
+		assert_eq!(test.two.age, 0);
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_20() {
+		let template = crate::config::documented_template::<TestStruct>();
+		println!("{}", template);
+		assert!(template.contains("# The name of the person\nname = \"\"\n"));
+		assert!(template.contains("# Age in years\n#\n# Must fit in a u16\nage = 0\n"));
+		let test: TestStruct = Deserialize::<ReadableProfile>::deserialize(&mut TextRepr::from_toml(template).unwrap()).unwrap();
+		assert_eq!(test.age, 0);
+
+		let template = crate::config::documented_json_template::<TestStruct>();
+		println!("{}", template);
+		let mut repr = TextRepr::from_json(template).unwrap();
+		let TextRepr::Table(table) = &repr else { panic!("{:?}", repr) };
+		let TextRepr::Table(comments) = &table["_comments"] else { panic!("{:?}", table) };
+		assert_eq!(comments["name"], TextRepr::String("The name of the person".into()));
+		assert_eq!(comments["age"], TextRepr::String("Age in years\n\nMust fit in a u16".into()));
+		assert!(!comments.contains_key("id"));
+		let test: TestStruct = Deserialize::<ReadableProfile>::deserialize(&mut repr).unwrap();
+		assert_eq!(test.age, 0);
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_21() {
+		let test = TestStruct6::deserialize_toml("port = 80\nhost = \"a\"\ntags = [\"b\", \"c\"]\n".into()).unwrap();
+		assert_eq!(test.port, 80);
+		let err = TestStruct6::deserialize_toml("port = 0\nhost = \"a\"\ntags = [\"b\", \"c\"]\n".into()).unwrap_err();
+		println!("{:?}", err);
+		assert_eq!(err.field.as_deref(), Some("port"));
+		assert!(matches!(err.kind, DeserializationErrorKind::InvalidValue { .. }));
+		let err = TestStruct6::deserialize_toml("port = 80\nhost = \"\"\ntags = [\"b\", \"c\", \"d\"]\n".into()).unwrap_err();
+		assert_eq!(err.field.as_deref(), Some("host"));
+
+		#[cfg(feature = "regex")]
+		{
+			let check = crate::check::regex("[a-z]+");
+			assert!(check(&"abc".into()).is_ok());
+			assert!(check(&"abc1".into()).is_err());
+			assert!(std::panic::catch_unwind(|| crate::check::regex("(")).is_err());
+		}
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_22() {
+		use crate::case::Case;
+
+		assert_eq!(Case::ScreamingSnake.apply("max_connections"), "MAX_CONNECTIONS");
+		assert_eq!(Case::Kebab.apply("max_connections"), "max-connections");
+		assert_eq!(Case::Pascal.apply("max_connections"), "MaxConnections");
+
+		let ser = TestStruct7 { max_connections: 8, host_name: "a".into() }.serialize_json();
+		println!("{}", ser);
+		assert!(ser.contains("maxConnections"));
+		assert!(ser.contains("hostName"));
+		let test = TestStruct7::deserialize_json(ser).unwrap();
+		assert_eq!(test.max_connections, 8);
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_23() {
+		let ser = TestStruct8 { name: "a".into(), color: 0xff00ff }.serialize_json();
+		println!("{}", ser);
+		assert!(ser.contains("\"ff00ff\""));
+		let test = TestStruct8::deserialize_json(ser).unwrap();
+		assert_eq!(test.color, 0xff00ff);
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_24() {
+		use crate::version::Versioned;
+
+		let deser = |json: &str| -> Result<Versioned<TestStruct9>, DeserializationError> {
+			Deserialize::<ReadableProfile>::deserialize(&mut TextRepr::from_json(json.into()).unwrap())
+		};
+		let old = deser("{ version: 1, data: { name: \"a\", legacy_id: 4 } }").unwrap();
+		println!("{:?}", old);
+		assert_eq!(old.value.nickname, "");
+		assert_eq!(old.value.legacy_id, 4);
+		let new = deser("{ version: 3, data: { name: \"a\", nickname: \"b\" } }").unwrap();
+		assert_eq!(new.value.legacy_id, 0);
+		assert!(deser("{ version: 2, data: { name: \"a\", legacy_id: 4 } }").is_err());
+
+		let mut repr = TextRepr::new();
+		Serialize::<ReadableProfile>::serialize(Versioned::new(3, new.value), &mut repr);
+		println!("{}", repr);
+		let test: Versioned<TestStruct9> = Deserialize::<ReadableProfile>::deserialize(&mut repr).unwrap();
+		assert_eq!(test.value.nickname, "b");
+	}
+
+	#[cfg(all(feature = "text", feature = "bin"))]
+	#[test]
+	fn test_serde_25() {
+		let ser = TestEnum2::Small(3).serialize_json();
+		println!("{}", ser);
+		assert!(ser.contains("small"));
+		assert!(matches!(TestEnum2::deserialize_json(ser).unwrap(), TestEnum2::Small(3)));
+		assert!(TestEnum2::Large(1).serialize_json().contains("Large"));
+
+		assert_eq!(TestEnum2::Small(3).serialize_bin(), vec![4, 3]);
+		assert_eq!(TestEnum2::Large(1).serialize_bin(), vec![5, 0, 1]);
+		assert!(matches!(TestEnum2::deserialize_bin(vec![5, 0, 1]).unwrap(), TestEnum2::Large(1)));
+		assert!(TestEnum2::deserialize_bin(vec![0, 3]).is_err());
+
+		// The macros evaluate these in a const, so the panics fail the build
+		assert_eq!(crate::variant_indices([("A", None), ("B", Some(7)), ("C", None)]), [("A", 0), ("B", 7), ("C", 8)]);
+		assert!(std::panic::catch_unwind(|| crate::variant_indices([("A", Some(1)), ("B", Some(1))])).is_err());
+		assert!(std::panic::catch_unwind(|| crate::variant_indices([("A", None), ("A", Some(3))])).is_err());
+		assert!(std::panic::catch_unwind(|| crate::variant_indices([("A", Some(255)), ("B", None)])).is_err());
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_26() {
+		let ser = TestEnum3::Limits(TestStruct7 { max_connections: 3, host_name: "a".into() }).serialize_json();
+		println!("{}", ser);
+		let repr = TextRepr::from_json(ser.clone()).unwrap();
+		assert!(matches!(repr.entries().find(|x| x.0 == "type"), Some((_, TextRepr::String(x))) if x == "Limits"));
+		assert!(matches!(repr.entries().find(|x| x.0 == "maxConnections"), Some((_, TextRepr::Integer(3)))));
+		assert!(matches!(TestEnum3::deserialize_json(ser).unwrap(), TestEnum3::Limits(x) if x.max_connections == 3));
+
+		let ser = TestEnum4::Number(7).serialize_json();
+		println!("{}", ser);
+		assert!(matches!(TestEnum4::deserialize_json(ser).unwrap(), TestEnum4::Number(7)));
+		let ser = TestEnum4::Limits(TestStruct7 { max_connections: 3, host_name: "a".into() }).serialize_json();
+		println!("{}", ser);
+		assert!(matches!(TestEnum4::deserialize_json(ser).unwrap(), TestEnum4::Limits(x) if x.host_name == "a"));
+		assert!(TestEnum4::deserialize_json("{ t: \"Other\", c: 1 }".into()).is_err());
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_27() {
+		let repr = TextRepr::from_json("{ name: \"a\", age: 3, ratio: 0.5 }".into()).unwrap();
+		assert_eq!(repr, TextRepr::from_toml("name = \"a\"\nage = 3\nratio = 0.5\n".into()).unwrap());
+		assert_ne!(repr, TextRepr::from_json("{ name: \"a\", age: 4, ratio: 0.5 }".into()).unwrap());
+		assert_ne!(TextRepr::Float(f64::NAN), TextRepr::Float(f64::NAN));
+
+		let err = TestStruct::deserialize_json("{ name: \"a\", id: \"b\" }".into()).unwrap_err();
+		assert_eq!(err, DeserializationError::missing_field("age"));
+		assert_eq!(err.clone().kind, DeserializationErrorKind::MissingField);
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_28() {
+		fn assert_send_sync<T: Send + Sync + 'static>() {}
+		assert_send_sync::<DeserializationError>();
+
+		fn load(json: &str) -> anyhow::Result<TestStruct2> {
+			Ok(TestStruct2::deserialize_json(json.into())?)
+		}
+		let err = std::thread::spawn(|| load("{ one: { name: \"a\", id: \"b\", age: 0 }, two: { name: \"c\", id: \"d\" } }").unwrap_err())
+			.join()
+			.unwrap();
+		println!("{}", err);
+		assert_eq!(err.to_string(), "two.age: missing field");
+	}
+
+	#[cfg(feature = "miette")]
+	#[test]
+	fn test_serde_29() {
+		use miette::Diagnostic;
+		use crate::text::TextFormat;
+
+		let data = "[one]\nname = \"a\"\nage = \"b\n[two]\n".to_string();
+		let err = TextRepr::parse_with_source("test.toml", data, TextFormat::Toml).unwrap_err();
+		println!("{:?}", err);
+		let label = err.labels().unwrap().next().unwrap();
+		assert_eq!(label.offset(), 17);
+		assert_eq!(label.len(), 8);
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_30() {
+		use crate::ErrorCode;
+
+		let err = TestStruct2::deserialize_json("{ one: { name: \"a\", id: \"b\", age: 0 }, two: { name: \"c\", id: \"d\" } }".into()).unwrap_err();
+		assert_eq!(err.code(), ErrorCode::E001_MissingField);
+		assert_eq!(TextRepr::from_json("{ a: 1 ".into()).unwrap_err().code(), ErrorCode::E011_MissingClosingBrace);
+		assert_eq!(TextRepr::from_json("{ a: 1 }}".into()).unwrap_err().code().as_str(), "E012");
+		assert_eq!(TextRepr::from_toml("a = \"b\n".into()).unwrap_err().code(), ErrorCode::E015_UnterminatedString);
+		assert_eq!(TextRepr::from_json("{ \"a\": }".into()).unwrap_err().code(), ErrorCode::E013_MissingValue);
+		assert_eq!(TextRepr::from_json("[1] 2".into()).unwrap_err().code(), ErrorCode::E018_TrailingData);
+
+		// Codes are given where the error is made, not read from the message
+		assert_eq!(DeserializationError::invalid_format("missing key").code(), ErrorCode::E010_InvalidFormat);
+		assert_eq!(DeserializationError::invalid_format_code(ErrorCode::E014_MissingKey, "no key here").code(), ErrorCode::E014_MissingKey);
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_31() {
+		use std::error::Error;
+		use std::num::ParseIntError;
+
+		struct StrictHexCodec;
+
+		impl Codec<u32> for StrictHexCodec {
+			fn serialize<T: Serializer>(value: u32, data: &mut T) {
+				data.serialize_string(format!("{:06x}", value));
+			}
+
+			fn deserialize<T: Serializer>(data: &mut T) -> Result<u32, DeserializationError> {
+				u32::from_str_radix(&data.deserialize_string()?, 16).map_err(DeserializationError::custom)
+			}
+		}
+
+		let err = StrictHexCodec::deserialize(&mut TextRepr::String("xyz".into())).unwrap_err();
+		println!("{}", err);
+		assert_eq!(err.code(), crate::ErrorCode::E009_Custom);
+		assert!(err.source().unwrap().downcast_ref::<ParseIntError>().is_some());
+		assert_eq!(err.clone(), err);
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_32() {
+		use crate::version::Versioned;
+		use crate::warning::{collect_warnings, Warning, WarningKind};
+
+		let (test, warnings) = collect_warnings(|| -> Result<Versioned<TestStruct9>, DeserializationError> {
+			Deserialize::<ReadableProfile>::deserialize(&mut TextRepr::from_json("{ version: 3, data: { name: \"a\", nickname: \"b\", legacy_id: 4, extra: 1 } }".into()).unwrap())
+		});
+		assert_eq!(test.unwrap().value.legacy_id, 4);
+		for warning in &warnings {
+			println!("{}", warning);
+		}
+		assert_eq!(warnings, vec![
+			Warning { field: Some("data.legacy_id".into()), kind: WarningKind::DeprecatedKey { replacement: None } },
+			Warning { field: Some("data.extra".into()), kind: WarningKind::UnknownField }
+		]);
+		assert!(!crate::warning::is_collecting());
+
+		// Integers that do not fit are an error rather than being truncated
+		let err = Deserialize::<ReadableProfile>::deserialize(&mut TextRepr::from_json("{ version: 3, data: { name: \"a\", nickname: \"b\", legacy_id: 65537 } }".into()).unwrap())
+			.map(|_: Versioned<TestStruct9>| ())
+			.unwrap_err();
+		println!("{}", err);
+		assert!(u64::deserialize(&mut TextRepr::Integer(-1)).is_err());
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, u8>("300").unwrap_err().kind, DeserializationErrorKind::InvalidValue { reason: "300 is out of the range of u8".into() });
+
+		// Integers above i64::MAX are written as strings, so they round trip
+		let json = crate::json::to_string(u64::MAX);
+		assert_eq!(json, "\"18446744073709551615\"");
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, u64>(&json).unwrap(), u64::MAX);
+		let json = crate::json::to_string(std::num::Wrapping(u64::MAX));
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, std::num::Wrapping<u64>>(&json).unwrap(), std::num::Wrapping(u64::MAX));
+		assert!(crate::json::from_str::<crate::NaturalProfile, u32>(&json).is_err());
+		assert!(crate::json::from_str::<crate::NaturalProfile, u64>("\"5\"").is_err());
+
+		// Integers past the precision of a float are rounded with a warning
+		let (float, warnings) = collect_warnings(|| f32::deserialize(&mut TextRepr::Integer(16777217)));
+		assert_eq!(float.unwrap(), 16777216.0);
+		assert_eq!(warnings, vec![Warning { field: None, kind: WarningKind::Coerced { from: "16777217".into(), to: "16777216".into() } }]);
+	}
+
+	#[cfg(feature = "bin")]
+	#[test]
+	fn test_serde_33() {
+		use crate::observe::{observe, FieldSizes};
+
+		let test = TestStruct2 {
+			one: TestStruct { name: "abc".into(), id: "d".into(), age: 3 },
+			two: TestStruct::default()
+		};
+		let (ser, sizes) = observe(FieldSizes::new(), || test.serialize_bin());
+		println!("{:?}", sizes.largest());
+		assert_eq!(sizes.sizes.len(), 8);
+		assert_eq!(sizes.sizes[0], ("one.name".into(), 11));
+		assert_eq!(sizes.largest()[0], ("one", 26));
+		assert_eq!(sizes.largest()[1], ("two", 22));
+		assert_eq!(ser.len(), 48);
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_34() {
+		let ser = TestStruct7 { max_connections: 2, host_name: " Example.COM ".into() }.serialize_json();
+		println!("{}", ser);
+		let test = TestStruct7::deserialize_json(ser).unwrap();
+		assert_eq!(test.host_name, "example.com");
+
+		let err = TestStruct7::deserialize_json("{ maxConnections: 0, hostName: \"a\" }".into()).unwrap_err();
+		assert_eq!(err.code(), crate::ErrorCode::E007_InvalidValue);
+		// TestStruct8 has no hooks, and is left as is
+		let test = TestStruct8::deserialize_json(TestStruct8 { name: " A ".into(), color: 0 }.serialize_json()).unwrap();
+		assert_eq!(test.name, " A ");
+	}
+
+	#[cfg(all(feature = "text", feature = "bin"))]
+	#[test]
+	fn test_serde_35() {
+		use crate::encrypt::{Cipher, Encrypted};
+		use crate::{MarshallSeed, MarshalledSerialize, NaturalProfile, WithMarshall};
+
+		struct XorKey(u8);
+
+		impl Cipher for XorKey {
+			fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+				plaintext.iter().map(|x| x ^ self.0).collect()
+			}
+
+			fn decrypt(&self, ciphertext: Vec<u8>) -> Result<Vec<u8>, DeserializationError> {
+				Ok(self.encrypt(&ciphertext))
+			}
+		}
+
+		assert_eq!(crate::base64::encode(b"foobar"), "Zm9vYmFy");
+		assert_eq!(crate::base64::encode(b"fo"), "Zm8=");
+		assert_eq!(crate::base64::decode("Zm8=").unwrap(), b"fo");
+
+		let key = XorKey(42);
+		let mut repr = TextRepr::new();
+		repr.serialize_key("user", "a");
+		repr.serialize_key("password", WithMarshall(Encrypted(String::from("hunter2")), &key));
+		let json = repr.to_json();
+		println!("{}", json);
+		assert!(!json.contains("hunter2"));
+
+		let mut repr = TextRepr::from_json(json).unwrap();
+		let password: Encrypted<String> = repr.deserialize_key_seed::<NaturalProfile, _, _>("password", &mut MarshallSeed::new(&key)).unwrap();
+		assert_eq!(password.0, "hunter2");
+
+		let mut bin = VecDeque::new();
+		MarshalledSerialize::<NaturalProfile, _>::serialize(Encrypted(7u16), &mut bin, &key);
+		let test: Encrypted<u16> = MarshalledDeserialize::<NaturalProfile, _>::deserialize(&mut bin, &key).unwrap();
+		assert_eq!(test.0, 7);
+		#[cfg(feature = "zeroize")]
+		{
+			let mut password = password;
+			zeroize::Zeroize::zeroize(&mut password);
+			assert!(password.0.is_empty());
+		}
+	}
+
+	#[cfg(all(feature = "text", feature = "bin"))]
+	#[test]
+	fn test_serde_36() {
+		let test = TestStruct7 { max_connections: 4, host_name: "a".into() };
+		let ser = crate::json::to_string::<ReadableProfile, _>(test);
+		let test: TestStruct7 = crate::json::from_str::<ReadableProfile, _>(&ser).unwrap();
+		assert_eq!(test.max_connections, 4);
+		let ser = crate::toml::to_string::<ReadableProfile, _>(test);
+		assert_eq!(crate::toml::from_str::<ReadableProfile, TestStruct7>(&ser).unwrap().host_name, "a");
+
+		let ser = crate::bin::to_vec::<crate::NaturalProfile, _>(300u16);
+		assert_eq!(crate::bin::from_slice::<crate::NaturalProfile, u16>(&ser).unwrap(), 300);
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_37() {
+		use crate::NaturalProfile;
+
+		let ser = JSONSerialize::<NaturalProfile>::serialize_json(vec![1u16, 2, 3]);
+		assert_eq!(ser, "[1, 2, 3]");
+		assert_eq!(<Vec<u16> as JSONDeserialize>::deserialize_json(ser).unwrap(), vec![1, 2, 3]);
+		for test in [vec![], vec![String::from("a")]] {
+			let ser = JSONSerialize::<NaturalProfile>::serialize_json(test.clone());
+			println!("{}", ser);
+			assert_eq!(<Vec<String> as JSONDeserialize>::deserialize_json(ser).unwrap(), test);
+		}
+		assert_eq!(<Vec<u8> as JSONDeserialize>::deserialize_json("[ ]".into()).unwrap(), Vec::<u8>::new());
+
+		assert_eq!(crate::json::to_string::<NaturalProfile, _>(12.5f64), "12.5");
+		assert!(crate::json::from_str::<NaturalProfile, bool>("true").unwrap());
+		let test = vec![TestStruct7 { max_connections: 1, host_name: "a".into() }];
+		let ser = crate::json::to_string::<ReadableProfile, _>(test);
+		let test: Vec<TestStruct7> = crate::json::from_str::<ReadableProfile, _>(&ser).unwrap();
+		assert_eq!(test[0].max_connections, 1);
+	}
+
+	#[cfg(feature = "bin")]
+	#[test]
+	fn test_serde_38() {
+		use crate::bin::{RecordReader, RecordWriter};
+
+		let mut writer = RecordWriter::<_, ReadableProfile>::new(Vec::new());
+		for age in 0..3 {
+			writer.write(TestStruct { name: "a".into(), id: age.to_string(), age }).unwrap();
+		}
+		let mut data = writer.into_inner();
+		let reader = RecordReader::<_, TestStruct, ReadableProfile>::new(data.as_slice());
+		let ages: Vec<_> = reader.map(|x| x.unwrap().age).collect();
+		assert_eq!(ages, vec![0, 1, 2]);
+
+		data.pop();
+		let results: Vec<_> = RecordReader::<_, TestStruct, ReadableProfile>::new(data.as_slice()).collect();
+		assert_eq!(results.len(), 3);
+		assert_eq!(results[2].as_ref().unwrap_err().kind, DeserializationErrorKind::UnexpectedEOF);
+
+		// A huge length is not allocated up front, so a corrupt header is just a short record
+		let mut results = RecordReader::<_, TestStruct, ReadableProfile>::new([0xFF, 0xFF, 0xFF, 0xF0, 1, 2].as_slice());
+		assert_eq!(results.next().unwrap().unwrap_err().kind, DeserializationErrorKind::UnexpectedEOF);
+		assert!(results.next().is_none());
+	}
+
+	#[cfg(feature = "bin")]
+	#[test]
+	fn test_serde_39() {
+		use crate::bin::{LogReader, LogWriter};
+
+		let mut writer = LogWriter::<_, ReadableProfile>::new(Vec::new());
+		for age in 0..3 {
+			writer.append(TestStruct { name: "a".into(), id: age.to_string(), age }).unwrap();
+		}
+		let mut data = writer.into_inner();
+		let full_len = data.len() as u64;
+		data.truncate(data.len() - 3);
+
+		let mut reader = LogReader::<_, TestStruct, ReadableProfile>::new(data.as_slice());
+		let ages: Vec<_> = reader.by_ref().map(|x| x.unwrap().age).collect();
+		assert_eq!(ages, vec![0, 1]);
+		assert!(reader.is_torn());
+		assert_eq!(reader.valid_len(), full_len / 3 * 2);
+
+		data.truncate(reader.valid_len() as usize);
+		let last = data.len() - 1;
+		data[last] ^= 1;
+		let results: Vec<_> = LogReader::<_, TestStruct, ReadableProfile>::new(data.as_slice()).collect();
+		assert_eq!(results.len(), 2);
+		assert_eq!(results[1].as_ref().unwrap_err().code(), crate::ErrorCode::E010_InvalidFormat);
+
+		// A torn header with a huge length is not allocated up front
+		let mut reader = LogReader::<_, TestStruct, ReadableProfile>::new([0xFF, 0xFF, 0xFF, 0xF0, 0, 0, 0, 0, 1].as_slice());
+		assert!(reader.next().is_none());
+		assert!(reader.is_torn());
+		assert_eq!(reader.valid_len(), 0);
+	}
+
+	#[cfg(all(feature = "text", feature = "bin"))]
+	#[test]
+	fn test_serde_40() {
+		use crate::text::delta::{Delta, Snapshot};
+
+		let state = |age| TestStruct2 {
+			one: TestStruct { name: "a".into(), id: "b".into(), age },
+			two: TestStruct::default()
+		};
+		let mut sender = Snapshot::new::<ReadableProfile, _>(state(1));
+		let mut receiver = Snapshot::from_base(sender.base().clone());
+
+		let delta = sender.delta::<ReadableProfile, _>(state(2));
+		assert_eq!(delta.changed.len(), 1);
+		assert_eq!(delta.changed[0].path, vec!["one", "age"]);
+		let json = crate::json::to_string::<crate::NaturalProfile, _>(delta);
+		println!("{}", json);
+		let delta: Delta = crate::json::from_str::<crate::NaturalProfile, _>(&json).unwrap();
+		let test: TestStruct2 = receiver.apply::<ReadableProfile, _>(delta).unwrap();
+		assert_eq!(test.one.age, 2);
+		assert_eq!(receiver.base(), sender.base());
+
+		let mut old = TextRepr::from_json("{ a: 1, b: { c: 2, d: 3 } }".into()).unwrap();
+		let new = TextRepr::from_json("{ a: 1, b: { c: 4 }, e: true }".into()).unwrap();
+		let delta = old.diff(&new);
+		assert_eq!(delta.removed, vec![vec!["b".to_string(), "d".to_string()]]);
+		old.apply_delta(delta);
+		assert_eq!(old, new);
+
+		let base = state(1).serialize_bin();
+		let new = state(300).serialize_bin();
+		let delta = crate::bin::delta(&base, &new);
+		assert!(delta.len() < 12);
+		assert_eq!(crate::bin::apply_delta(&base, &delta).unwrap(), new);
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_41() {
+		use crate::text::tracked::Tracked;
+
+		let mut test = Tracked::new(TestStruct { name: "a".into(), id: "b".into(), age: 1 });
+		assert_eq!(test.dirty_fields::<ReadableProfile>(), vec!["age", "id", "name"]);
+		let mut saved = TextRepr::new();
+		test.serialize_dirty::<ReadableProfile, _>(&mut saved);
+		assert!(test.dirty_fields::<ReadableProfile>().is_empty());
+
+		test.age = 2;
+		test.mark("name");
+		let mut patch = TextRepr::new();
+		test.serialize_dirty::<ReadableProfile, _>(&mut patch);
+		println!("{}", patch);
+		assert_eq!(patch.entries().count(), 2);
+		assert_eq!(patch.entries().find(|x| x.0 == "age").unwrap().1, &TextRepr::Integer(2));
+		saved.merge(patch);
+
+		let loaded: Tracked<TestStruct> = Deserialize::<ReadableProfile>::deserialize(&mut saved).unwrap();
+		assert_eq!(loaded.age, 2);
+		assert!(loaded.dirty_fields::<ReadableProfile>().is_empty());
+	}
+
+	#[cfg(feature = "text")]
+	#[test]
+	fn test_serde_42() {
+		let mut test = TestStruct6 { port: 80, host: "a".into(), tags: vec!["b".into()] };
+		Update::<ReadableProfile>::update_from(&mut test, TextRepr::from_json("{ port: 8080 }".into()).unwrap()).unwrap();
+		assert_eq!(test.port, 8080);
+		assert_eq!(test.host, "a");
+
+		let err = Update::<ReadableProfile>::update_from(&mut test, TextRepr::from_json("{ port: 0, host: \"c\" }".into()).unwrap()).unwrap_err();
+		assert_eq!(err.code(), crate::ErrorCode::E007_InvalidValue);
+		assert_eq!(test.port, 8080);
+		assert_eq!(test.host, "a");
+
+		let mut test = TestStruct7 { max_connections: 1, host_name: "a".into() };
+		assert!(Update::<ReadableProfile>::update_from(&mut test, TextRepr::from_json("{ maxConnections: 0 }".into()).unwrap()).is_err());
+	}
+
+	#[test]
+	fn test_serde_43() {
+		use crate::reflect::{Field, FieldKind, FieldRecorder};
+
+		let kind = FieldRecorder::record_default::<ReadableProfile, TestStruct2>();
+		let fields = match kind {
+			FieldKind::Table(x) => x,
+			x => panic!("{:?}", x)
+		};
+		assert_eq!(fields.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(), vec!["one", "two"]);
+		assert_eq!(fields[0].kind, FieldKind::Table(vec![
+			Field { name: "name".into(), kind: FieldKind::String },
+			Field { name: "id".into(), kind: FieldKind::String },
+			Field { name: "age".into(), kind: FieldKind::Number("u16") }
+		]));
+
+		let kind = FieldRecorder::record::<crate::NaturalProfile, _>(Vec::<bool>::new());
+		assert_eq!(kind, FieldKind::Sequence(Box::new(FieldKind::Empty)));
+		let kind = FieldRecorder::record::<crate::NaturalProfile, _>(vec![vec![1u8]]);
+		assert_eq!(kind, FieldKind::Sequence(Box::new(FieldKind::Sequence(Box::new(FieldKind::Number("u8"))))));
+	}
+
+	#[test]
+	fn test_serde_44() {
+		use crate::validate::validate_serializable;
+
+		struct Mixed;
+
+		impl Serialize for Mixed {
+			fn serialize<T: Serializer>(self, data: &mut T) {
+				data.serialize_num(1u8);
+				data.serialize_key("a", 2u8);
+			}
+		}
+
+		assert!(validate_serializable::<ReadableProfile, _>(TestStruct2::default()).is_ok());
+		let mut map = std::collections::HashMap::new();
+		map.insert("mixed", Mixed);
+		let err = validate_serializable(map).unwrap_err();
+		println!("{}", err);
+		assert_eq!(err.field.as_deref(), Some("mixed"));
+		assert_eq!(err.code(), crate::ErrorCode::E010_InvalidFormat);
+	}
+
+	#[test]
+	fn test_serde_45() {
+		use crate::stats::StatsSerializer;
+
+		let one = TestStruct { name: "ab".into(), id: "c".into(), age: 3 };
+		let stats = StatsSerializer::stats::<ReadableProfile, _>(TestStruct2 { one: one.clone(), two: TestStruct::default() });
+		assert_eq!(stats.get("one.name").unwrap().bytes, 10);
+		assert_eq!(stats.get("one").unwrap().bytes, 25);
+		assert_eq!(stats.get("two").unwrap().bytes, 22);
+		assert_eq!(stats.bytes, 47);
+		assert_eq!(stats.max_depth, 2);
+		assert!(stats.get("one.missing").is_none());
+
+		let stats = StatsSerializer::stats::<ReadableProfile, _>(vec![one.clone(), one]);
+		assert_eq!(stats.elements, 2);
+		assert_eq!(stats.max_depth, 2);
+		assert_eq!(stats.get("age").unwrap().bytes, 10);
+		assert_eq!(stats.get("age").unwrap().elements, 0);
+	}
+
+	#[test]
+	#[cfg(feature = "bin")]
+	fn test_serde_46() {
+		use crate::protobuf;
+
+		let one = TestStruct { name: "ab".into(), id: "c".into(), age: 150 };
+		let bytes = protobuf::to_vec::<ReadableProfile, _>(one.clone());
+		assert_eq!(bytes, vec![0x0A, 2, b'a', b'b', 0x12, 1, b'c', 0x18, 0x96, 0x01]);
+
+		let bytes = protobuf::to_vec::<ReadableProfile, _>(TestStruct2 { one, two: TestStruct::default() });
+		let two: TestStruct2 = protobuf::from_slice::<ReadableProfile, _>(&bytes).unwrap();
+		assert_eq!(two.one.name, "ab");
+		assert_eq!(two.one.age, 150);
+		assert_eq!(two.two.name, "");
+
+		let server = TestStruct6 { port: 80, host: "a".into(), tags: vec!["b".into(), "c".into()] };
+		let bytes = protobuf::to_vec::<ReadableProfile, _>(server);
+		let server: TestStruct6 = protobuf::from_slice::<ReadableProfile, _>(&bytes).unwrap();
+		assert_eq!(server.tags, vec!["b".to_string(), "c".to_string()]);
+		let empty = protobuf::to_vec::<ReadableProfile, _>(TestStruct6 { port: 80, host: "a".into(), tags: Vec::new() });
+		let server: TestStruct6 = protobuf::from_slice::<ReadableProfile, _>(&empty).unwrap();
+		assert!(server.tags.is_empty());
+
+		let err = protobuf::from_slice::<ReadableProfile, TestStruct>(&[0x0A, 2, b'a', b'b']).unwrap_err();
+		assert_eq!(err.field.as_deref(), Some("id"));
+		assert_eq!(err.kind, DeserializationErrorKind::MissingField);
+
+		#[derive(Debug, PartialEq)]
+		struct Signed {
+			a: i32,
+			b: f32
+		}
+		impl_key_serde!(Signed, crate::NaturalProfile, a, b);
+		let bytes = protobuf::to_vec(Signed { a: -1, b: 0.5 });
+		assert_eq!(bytes.len(), 16);
+		assert_eq!(protobuf::from_slice::<crate::NaturalProfile, Signed>(&bytes).unwrap(), Signed { a: -1, b: 0.5 });
+	}
+
+	#[test]
+	#[cfg(feature = "bin")]
+	fn test_serde_47() {
+		use crate::flexbuffers;
+
+		let bytes = flexbuffers::to_vec(7u8);
+		assert_eq!(bytes, vec![7, 0, 0, 0, 0, 0, 0, 0, 2 << 2 | 3, 8]);
+
+		let one = TestStruct { name: "ab".into(), id: "c".into(), age: 150 };
+		let bytes = flexbuffers::to_vec::<ReadableProfile, _>(TestStruct2 { one, two: TestStruct::default() });
+		let two: TestStruct2 = flexbuffers::from_slice::<ReadableProfile, _>(&bytes).unwrap();
+		assert_eq!(two.one.name, "ab");
+		assert_eq!(two.one.age, 150);
+
+		for tags in [vec![], vec!["b".to_string()], vec!["b".to_string(), "c".to_string()]] {
+			let bytes = flexbuffers::to_vec::<ReadableProfile, _>(TestStruct6 { port: 80, host: "a".into(), tags: tags.clone() });
+			let server: TestStruct6 = flexbuffers::from_slice::<ReadableProfile, _>(&bytes).unwrap();
+			assert_eq!(server.tags, tags);
+		}
+
+		let err = flexbuffers::from_slice::<ReadableProfile, TestStruct>(&bytes[..bytes.len() - 4]).unwrap_err();
+		println!("{}", err);
+		assert!(flexbuffers::from_slice::<ReadableProfile, TestStruct>(&[0xFF, 0xFF, 9 << 2, 1]).is_err());
+
+		// Vectors of one element, each pointing back at the one before, with an int at the bottom
+		let nested = |depth: usize| {
+			let mut bytes = vec![1, 0, 1 << 2];
+			for _ in 1..depth {
+				bytes.extend([1, 3, 10 << 2]);
+			}
+			bytes.extend([2, 10 << 2, 1]);
+			bytes
+		};
+		let too_deep = |bytes: &[u8]| matches!(
+			flexbuffers::from_slice::<crate::NaturalProfile, u8>(bytes).unwrap_err().kind,
+			DeserializationErrorKind::InvalidFormat { reason, .. } if reason.contains("nested deeper")
+		);
+		assert!(!too_deep(&nested(10)));
+		assert!(too_deep(&nested(300)));
+		// A vector whose element points at itself
+		let err = flexbuffers::from_slice::<crate::NaturalProfile, u8>(&[1, 0, 10 << 2, 2, 10 << 2, 1]).unwrap_err();
+		assert!(err.to_string().contains("points to itself"), "{}", err);
+	}
+
+	#[test]
+	#[cfg(feature = "bin")]
+	fn test_serde_48() {
+		use crate::postcard;
+
+		let one = TestStruct { name: "ab".into(), id: "c".into(), age: 300 };
+		let bytes = postcard::to_vec::<ReadableProfile, _>(one);
+		assert_eq!(bytes, vec![2, b'a', b'b', 1, b'c', 0xAC, 0x02]);
+		let one: TestStruct = postcard::from_slice::<ReadableProfile, _>(&bytes).unwrap();
+		assert_eq!(one.age, 300);
+
+		assert_eq!(postcard::to_vec(vec![-1i32, 1]), vec![2, 1, 2]);
+		assert_eq!(postcard::from_slice::<crate::NaturalProfile, Vec<i32>>(&[2, 1, 2]).unwrap(), vec![-1, 1]);
+		assert_eq!(postcard::to_vec::<ReadableProfile, _>(TestEnum2::Small(9)), vec![4, 9]);
+
+		let bytes = postcard::to_vec::<ReadableProfile, _>(TestStruct6 { port: 80, host: "a".into(), tags: vec!["b".into()] });
+		let server: TestStruct6 = postcard::from_slice::<ReadableProfile, _>(&bytes).unwrap();
+		assert_eq!(server.tags, vec!["b".to_string()]);
+		let err = postcard::from_slice::<ReadableProfile, TestStruct6>(&bytes[..bytes.len() - 1]).unwrap_err();
+		assert_eq!(err.field.as_deref(), Some("tags"));
+	}
+
+	#[test]
+	#[cfg(feature = "bin")]
+	fn test_serde_49() {
+		use crate::der;
+
+		let one = TestStruct { name: "ab".into(), id: "c".into(), age: 150 };
+		let bytes = der::to_vec::<ReadableProfile, _>(one);
+		assert_eq!(bytes, vec![0x30, 11, 0x0C, 2, b'a', b'b', 0x0C, 1, b'c', 0x02, 2, 0x00, 0x96]);
+		let one: TestStruct = der::from_slice::<ReadableProfile, _>(&bytes).unwrap();
+		assert_eq!(one.age, 150);
+
+		assert_eq!(der::to_vec(0.5f64), vec![0x09, 3, 0x80, 0xFF, 0x01]);
+		for x in [0.5f64, -3.75, 1e300, 5e-324, 0.0, f64::INFINITY] {
+			assert_eq!(der::from_slice::<crate::NaturalProfile, f64>(&der::to_vec(x)).unwrap(), x);
+		}
+		assert_eq!(der::from_slice::<crate::NaturalProfile, i64>(&der::to_vec(-129i64)).unwrap(), -129);
+
+		let set: std::collections::HashSet<u16> = [300, 5].into_iter().collect();
+		assert_eq!(der::to_vec(set), vec![0x31, 7, 0x02, 1, 5, 0x02, 2, 0x01, 0x2C]);
+		assert_eq!(der::to_vec::<ReadableProfile, _>(TestEnum2::Small(9)), vec![0xA4, 3, 0x02, 1, 9]);
+		let small: TestEnum2 = der::from_slice::<ReadableProfile, _>(&[0xA4, 3, 0x02, 1, 9]).unwrap();
+		assert!(matches!(small, TestEnum2::Small(9)));
+
+		let err = der::from_slice::<ReadableProfile, TestStruct>(&[0x30, 4, 0x0C, 2, b'a', b'b']).unwrap_err();
+		assert_eq!(err.field.as_deref(), Some("id"));
+		assert_eq!(err.kind, DeserializationErrorKind::MissingField);
+		assert!(der::from_slice::<ReadableProfile, TestStruct>(&[0x30, 0x80, 0, 0]).is_err());
+	}
+
+	#[test]
+	#[cfg(feature = "ion")]
+	fn test_serde_50() {
+		use crate::text::{ion, TextRepr};
+
+		let one = TestStruct { name: "a\"b".into(), id: "c".into(), age: 150 };
+		let text = ion::to_string::<ReadableProfile, _>(one.clone());
+		let two: TestStruct = ion::from_str::<ReadableProfile, _>(&text).unwrap();
+		assert_eq!(two.name, "a\"b");
+		let bytes = ion::to_vec::<ReadableProfile, _>(one);
+		assert_eq!(bytes[..4], [0xE0, 0x01, 0x00, 0xEA]);
+		let two: TestStruct = ion::from_slice::<ReadableProfile, _>(&bytes).unwrap();
+		assert_eq!(two.age, 150);
+
+		let repr = TextRepr::from_ion("$ion_1_0 // comment\n tag::{ 'the name': '''a''' '''b''', id: sym, age: 0x10, list: (+ 1 -2), f: 1.5d0, blob: {{aGk=}}, t: 2007-02-23T12:14Z, n: null.int }").unwrap();
+		let TextRepr::Table(table) = repr else { panic!() };
+		assert_eq!(table["the name"], TextRepr::String("ab".into()));
+		assert_eq!(table["id"], TextRepr::String("sym".into()));
+		assert_eq!(table["age"], TextRepr::Integer(16));
+		assert_eq!(table["list"], TextRepr::Array([TextRepr::String("+".into()), TextRepr::Integer(1), TextRepr::Integer(-2)].into()));
+		assert_eq!(table["f"], TextRepr::Float(1.5));
+		assert_eq!(table["blob"], TextRepr::Array([TextRepr::Integer(104), TextRepr::Integer(105)].into()));
+		assert_eq!(table["t"], TextRepr::String("2007-02-23T12:14Z".into()));
+		assert_eq!(table["n"], TextRepr::Null);
+
+		assert_eq!(TextRepr::Array([TextRepr::Float(f64::NEG_INFINITY), TextRepr::Integer(-1)].into()).to_ion(), "[-inf, -1]");
+		let repr = TextRepr::Array([TextRepr::Integer(-300), TextRepr::Boolean(true), TextRepr::Null, TextRepr::Float(0.25)].into());
+		assert_eq!(TextRepr::from_ion_binary(&repr.clone().to_ion_binary()).unwrap(), repr);
+		// An int, a decimal of 15 * 10^-1, and a symbol from a symbol table of its own
+		let data = [0xE0, 0x01, 0x00, 0xEA, 0xEB, 0x81, 0x83, 0xD8, 0x87, 0xB6, 0x85, b'h', b'e', b'l', b'l', b'o', 0x21, 0x05, 0x52, 0xC1, 0x0F, 0x71, 0x0A];
+		assert_eq!(TextRepr::from_ion_binary(&data).unwrap(), TextRepr::Array([TextRepr::Integer(5), TextRepr::Float(1.5), TextRepr::String("hello".into())].into()));
+		assert!(TextRepr::from_ion_binary(&[0xE0, 0x01, 0x00, 0xEA, 0x8E]).is_err());
+		assert!(TextRepr::from_ion("{a: [1, 2}").is_err());
+
+		// Hostile documents are rejected without overflowing the stack
+		use crate::text::MAX_DEPTH;
+		let err = TextRepr::from_ion(&"[".repeat(200_000)).unwrap_err();
+		assert!(err.to_string().contains("nested deeper"), "{}", err);
+		assert!(TextRepr::from_ion(&("[".repeat(MAX_DEPTH) + &"]".repeat(MAX_DEPTH))).is_ok());
+		assert!(TextRepr::from_ion(&"a::".repeat(200_000)).is_err());
+		// Lists of one list each, with the length of each written as a VarUInt
+		let nested = |depth: usize| {
+			let mut data = vec![0xB0];
+			for _ in 1..depth {
+				let mut len = data.len();
+				let mut header = vec![0x80 | (len & 0x7F) as u8];
+				while len > 0x7F {
+					len >>= 7;
+					header.insert(0, (len & 0x7F) as u8);
+				}
+				header.insert(0, 0xBE);
+				data.splice(0..0, header);
+			}
+			data.splice(0..0, [0xE0, 0x01, 0x00, 0xEA]);
+			data
+		};
+		assert!(TextRepr::from_ion_binary(&nested(MAX_DEPTH)).is_ok());
+		let err = TextRepr::from_ion_binary(&nested(MAX_DEPTH + 1)).unwrap_err();
+		assert!(err.to_string().contains("nested deeper"), "{}", err);
+	}
+
+	#[test]
+	#[cfg(feature = "bin")]
+	fn test_serde_51() {
+		use crate::resp::{self, ErrorReply, Version};
+
+		assert_eq!(resp::command(vec!["SET", "key", "value"]), b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n");
+		assert_eq!(resp::command(vec![vec!["EXPIRE".to_string(), "key".into()], vec!["10".into()]]), b"*3\r\n$6\r\nEXPIRE\r\n$3\r\nkey\r\n$2\r\n10\r\n");
+		assert_eq!(resp::to_vec(vec![1.5f64], Version::Resp3), b"*1\r\n,1.5\r\n");
+		assert_eq!(resp::to_vec(true, Version::Resp2), b":1\r\n");
+
+		let one = TestStruct { name: "ab".into(), id: "c".into(), age: 150 };
+		for version in [Version::Resp2, Version::Resp3] {
+			let bytes = resp::to_vec::<ReadableProfile, _>(one.clone(), version);
+			let two: TestStruct = resp::from_slice::<ReadableProfile, _>(&bytes).unwrap();
+			assert_eq!(two.age, 150);
+		}
+
+		// An HGETALL reply in RESP2, with the age as a bulk string
+		let reply = b"*6\r\n$4\r\nname\r\n$2\r\nab\r\n$2\r\nid\r\n+c\r\n$3\r\nage\r\n$3\r\n150\r\n";
+		let two: TestStruct = resp::from_slice::<ReadableProfile, _>(reply).unwrap();
+		assert_eq!((two.name, two.age), ("ab".to_string(), 150));
+		let two: TestStruct = resp::from_slice::<ReadableProfile, _>(b"%3\r\n+name\r\n=6\r\ntxt:ab\r\n+id\r\n+c\r\n+age\r\n:150\r\n").unwrap();
+		assert_eq!(two.name, "ab");
+
+		let (x, len) = resp::next_reply::<crate::NaturalProfile, Vec<u32>>(b"*2\r\n:1\r\n:2\r\n+OK\r\n").unwrap();
+		assert_eq!((x, len), (vec![1, 2], 12));
+		let err = resp::next_reply::<crate::NaturalProfile, Vec<u32>>(b"*2\r\n:1\r\n:").unwrap_err();
+		assert_eq!(err.kind, DeserializationErrorKind::UnexpectedEOF);
+		let err = resp::from_slice::<crate::NaturalProfile, String>(b"-ERR unknown command\r\n").unwrap_err();
+		let DeserializationErrorKind::Custom(err) = err.kind else { panic!() };
+		assert_eq!(err.0.downcast_ref::<ErrorReply>(), Some(&ErrorReply("ERR unknown command".into())));
+
+		// Hostile replies are rejected without overflowing the stack
+		for nested in [b"*1\r\n".repeat(10_000), b"|0\r\n".repeat(10_000)] {
+			let err = resp::from_slice::<crate::NaturalProfile, Vec<u32>>(&nested).unwrap_err();
+			assert!(err.to_string().contains("nested deeper"), "{}", err);
+		}
+		let nested = [b"|0\r\n".repeat(100), b"*1\r\n:1\r\n".to_vec()].concat();
+		assert_eq!(resp::from_slice::<crate::NaturalProfile, Vec<u32>>(&nested).unwrap(), [1]);
+
+		#[cfg(feature = "text")] {
+			let repr = resp::from_slice::<crate::NaturalProfile, crate::text::TextRepr>(b"*3\r\n_\r\n#t\r\n$-1\r\n").unwrap();
+			assert_eq!(repr, crate::text::TextRepr::Array([crate::text::TextRepr::Null, crate::text::TextRepr::Boolean(true), crate::text::TextRepr::Null].into()));
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "rusqlite")]
+	fn test_serde_52() {
+		use crate::sql::{self, Cell, Row};
+
+		let conn = rusqlite::Connection::open_in_memory().unwrap();
+		conn.execute_batch("CREATE TABLE people (name TEXT, id TEXT, age INTEGER, nickname TEXT);
+			INSERT INTO people VALUES ('ab', 'c', 150, NULL);").unwrap();
+		let mut statement = conn.prepare("SELECT * FROM people").unwrap();
+		let one: TestStruct = statement.query_row([], |row| Ok(sql::from_sqlite_row::<ReadableProfile, _>(row))).unwrap().unwrap();
+		assert_eq!((one.name, one.id, one.age), ("ab".to_string(), "c".to_string(), 150));
+
+		let mut statement = conn.prepare("SELECT age, nickname FROM people").unwrap();
+		let row = statement.query_row([], |row| Ok(Row::try_from(row))).unwrap().unwrap();
+		assert_eq!(row.columns(), [("age".to_string(), Cell::Integer(150)), ("nickname".to_string(), Cell::Null)]);
+		let mut row = Row::new(vec![("nickname".into(), Cell::Null), ("age".into(), Cell::Text("7".into()))]);
+		assert_eq!(row.deserialize_key::<crate::NaturalProfile, Option<String>, _>("nickname").unwrap(), None);
+		assert_eq!(row.deserialize_key::<crate::NaturalProfile, u8, _>("age").unwrap(), 7);
+
+		let mut statement = conn.prepare("SELECT name, age FROM people").unwrap();
+		let err = statement.query_row([], |row| Ok(sql::from_sqlite_row::<ReadableProfile, TestStruct>(row))).unwrap().unwrap_err();
+		assert_eq!(err.field.as_deref(), Some("id"));
+		assert_eq!(err.kind, DeserializationErrorKind::MissingField);
+	}
+
+	#[test]
+	#[cfg(all(feature = "axum", feature = "actix"))]
+	fn test_serde_53() {
+		use crate::text::web::{Json, Rejection, Toml};
+
+		let one = TestStruct { name: "ab".into(), id: "c".into(), age: 150 };
+		let body = Json::<_, ReadableProfile>::new(one).to_body();
+		let two = Json::<TestStruct, ReadableProfile>::from_body(Some("application/json; charset=utf-8"), body.as_bytes()).unwrap();
+		assert_eq!(two.0.age, 150);
+		assert!(Json::<TestStruct, ReadableProfile>::from_body(Some("application/problem+json"), body.as_bytes()).is_ok());
+		assert!(matches!(Json::<TestStruct, ReadableProfile>::from_body(Some("text/plain"), body.as_bytes()), Err(Rejection::ContentType { .. })));
+		assert_eq!(Json::<TestStruct, ReadableProfile>::from_body(Some("application/json"), b"{}").unwrap_err().status(), 422);
+
+		let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+		runtime.block_on(async {
+			use axum::extract::FromRequest;
+			use axum::response::IntoResponse;
+
+			let request = axum::extract::Request::builder()
+				.header("content-type", "application/toml")
+				.body(axum::body::Body::from("name = \"ab\"\nid = \"c\"\nage = 3\n"))
+				.unwrap();
+			let one = Toml::<TestStruct, ReadableProfile>::from_request(request, &()).await.unwrap();
+			assert_eq!(one.0.age, 3);
+			let response = Toml::<_, ReadableProfile>::new(one.0).into_response();
+			assert_eq!(response.headers()["content-type"], "application/toml");
+
+			let request = axum::extract::Request::builder().body(axum::body::Body::from("{}")).unwrap();
+			let response = Json::<TestStruct, ReadableProfile>::from_request(request, &()).await.unwrap_err().into_response();
+			assert_eq!(response.status(), 415);
+		});
+
+		actix_web::rt::System::new().block_on(async {
+			use actix_web::{FromRequest, Responder};
+
+			let (request, mut payload) = actix_web::test::TestRequest::default()
+				.insert_header(("content-type", "application/json"))
+				.set_payload(body)
+				.to_http_parts();
+			let one = Json::<TestStruct, ReadableProfile>::from_request(&request, &mut payload).await.unwrap();
+			assert_eq!(one.0.name, "ab");
+			let response = one.respond_to(&request);
+			assert_eq!(response.headers().get("content-type").unwrap(), "application/json");
+		});
+	}
+
+	#[test]
+	#[cfg(feature = "reqwest")]
+	fn test_serde_54() {
+		use crate::text::client::{RequestBuilderExt, ResponseError, ResponseExt};
+
+		let one = TestStruct { name: "ab".into(), id: "c".into(), age: 150 };
+		let request = reqwest::Client::new().post("http://localhost/people").simple_json(one).build().unwrap();
+		assert_eq!(request.headers()["content-type"], "application/json");
+		let body = request.body().unwrap().as_bytes().unwrap().to_vec();
+
+		let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+		runtime.block_on(async {
+			let response = reqwest::Response::from(http::Response::new(body));
+			let two = response.simple_json::<TestStruct, _>().await.unwrap();
+			assert_eq!(two.age, 150);
+			let response = reqwest::Response::from(http::Response::new("{\"name\": \"ab\"}"));
+			let err = response.simple_json::<TestStruct, _>().await.unwrap_err();
+			assert!(matches!(err, ResponseError::Deserialization(_)));
+		});
+	}
+
+	#[test]
+	#[cfg(all(feature = "half", feature = "bin", feature = "text"))]
+	fn test_serde_55() {
+		use half::{bf16, f16};
+
+		let values = vec![f16::from_f32(0.5), f16::from_f32(-2.25), f16::MAX];
+		let bytes = crate::bin::to_vec(values.clone());
+		assert_eq!(bytes.len() + 6, crate::bin::to_vec(vec![0f32; 3]).len());
+		assert_eq!(crate::bin::from_slice::<crate::NaturalProfile, Vec<f16>>(&bytes).unwrap(), values);
+		assert_eq!(crate::bin::to_vec(bf16::ONE), vec![0x3F, 0x80]);
+
+		assert_eq!(crate::json::to_string(vec![f16::from_f32(0.5)]), "[0.5]");
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, Vec<bf16>>("[1.5, 2]").unwrap(), vec![bf16::from_f32(1.5), bf16::from_f32(2.0)]);
+		assert_eq!(crate::postcard::from_slice::<crate::NaturalProfile, f16>(&crate::postcard::to_vec(f16::from_f32(3.0))).unwrap(), f16::from_f32(3.0));
+	}
+
+	#[test]
+	#[cfg(all(feature = "glam", feature = "nalgebra", feature = "bin", feature = "text"))]
+	fn test_serde_56() {
+		use crate::NaturalProfile;
+
+		let position = glam::Vec3::new(1.0, 2.5, -3.0);
+		assert_eq!(crate::bin::to_vec(position).len(), 12);
+		assert_eq!(crate::bin::from_slice::<NaturalProfile, glam::Vec3>(&crate::bin::to_vec(position)).unwrap(), position);
+		assert_eq!(crate::json::to_string(position), "[1, 2.5, -3]");
+		let transform = glam::Mat4::from_scale_rotation_translation(glam::Vec3::ONE, glam::Quat::from_rotation_z(0.5), position);
+		assert_eq!(crate::json::from_str::<NaturalProfile, glam::Mat4>(&crate::json::to_string(transform)).unwrap(), transform);
+		let err = crate::json::from_str::<NaturalProfile, glam::Quat>("[0, 0, 1]").unwrap_err();
+		assert!(matches!(err.kind, DeserializationErrorKind::InvalidValue { .. }));
+
+		let matrix = nalgebra::Matrix2x3::new(1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0);
+		// Column major, so the first column comes first
+		assert_eq!(crate::json::to_string(matrix), crate::json::to_string(vec![1.0f32, 4.0, 2.0, 5.0, 3.0, 6.0]));
+		assert_eq!(crate::bin::from_slice::<NaturalProfile, nalgebra::Matrix2x3<f32>>(&crate::bin::to_vec(matrix)).unwrap(), matrix);
+		assert_eq!(crate::bin::to_vec(nalgebra::Vector3::new(1u8, 2, 3)), vec![1, 2, 3]);
+	}
+
+	#[test]
+	#[cfg(all(feature = "bin", feature = "text"))]
+	fn test_serde_57() {
+		use crate::NaturalProfile;
+		use crate::quantize::{Quantized, QuantizeRange, SignedUnitRange, UnitRange};
+
+		struct Angle;
+
+		impl QuantizeRange for Angle {
+			const MIN: f64 = 0.0;
+			const MAX: f64 = 360.0;
+		}
+
+		#[derive(Debug)]
+		struct Replicated {
+			health: f32,
+			heading: f64,
+			normal: f32
+		}
+
+		impl_key_serde!(Replicated, NaturalProfile, health with Quantized<UnitRange, u8>, heading with Quantized<Angle>, normal with Quantized<SignedUnitRange, u8>);
+
+		let state = Replicated { health: 0.5, heading: 90.0, normal: -2.0 };
+		let bytes = crate::bin::to_vec(state);
+		// Each key, then 1 byte of health, 2 bytes of heading and 1 byte of normal
+		assert_eq!(bytes.len(), "health".len() + "heading".len() + "normal".len() + 4);
+		let state: Replicated = crate::bin::from_slice::<NaturalProfile, _>(&bytes).unwrap();
+		assert!((state.health - 0.5).abs() <= 1.0 / 255.0);
+		assert!((state.heading - 90.0).abs() <= 360.0 / 65535.0);
+		assert_eq!(state.normal, -1.0);
+
+		assert_eq!(Quantized::<UnitRange, u8>::quantize(1.0), 255);
+		assert_eq!(Quantized::<UnitRange, u8>::dequantize(0), 0.0);
+		let text = crate::json::to_string(Replicated { health: 0.1, heading: 1.5, normal: 0.25 });
+		assert!(text.contains("0.1") && text.contains("1.5"));
+	}
+
+	#[test]
+	#[cfg(all(feature = "bin", feature = "text"))]
+	fn test_serde_58() {
+		use crate::NaturalProfile;
+		use crate::bits::{BitReader, BitWidth, BitWriter};
+
+		#[derive(Debug, PartialEq)]
+		struct PlayerState {
+			health: u8,
+			ammo: u16,
+			offset: i8,
+			crouching: bool,
+			jumping: bool,
+			name: String
+		}
+
+		impl_key_serde!(PlayerState, NaturalProfile, health with BitWidth<7>, ammo with BitWidth<10>, offset with BitWidth<4>, crouching, jumping, name);
+
+		let state = PlayerState { health: 100, ammo: 999, offset: -3, crouching: true, jumping: false, name: "ab".into() };
+		let bytes = crate::bits::to_vec(state);
+		// 7 + 10 + 4 + 1 + 1 bits, then an 8 bit length and 2 bytes of name
+		assert_eq!(bytes.len(), (7 + 10 + 4 + 2 + 8 + 16usize).div_ceil(8));
+		let state: PlayerState = crate::bits::from_slice::<NaturalProfile, _>(&bytes).unwrap();
+		assert_eq!(state, PlayerState { health: 100, ammo: 999, offset: -3, crouching: true, jumping: false, name: "ab".into() });
+
+		// Values that do not fit are clamped
+		let state = PlayerState { health: 200, ammo: 0, offset: -100, crouching: false, jumping: true, name: String::new() };
+		let state: PlayerState = crate::bits::from_slice::<NaturalProfile, _>(&crate::bits::to_vec(state)).unwrap();
+		assert_eq!((state.health, state.offset), (127, -8));
+
+		let mut writer = BitWriter::new();
+		writer.write(vec![true, false, true]);
+		writer.write(-2i32);
+		writer.write(1.5f32);
+		writer.write_bits(5, 3);
+		assert_eq!(writer.bit_len(), 8 + 3 + 32 + 32 + 3);
+		let mut reader = BitReader::new(&writer.into_bytes());
+		assert_eq!(reader.read::<NaturalProfile, Vec<bool>>().unwrap(), vec![true, false, true]);
+		assert_eq!(reader.read::<NaturalProfile, i32>().unwrap(), -2);
+		assert_eq!(reader.read::<NaturalProfile, f32>().unwrap(), 1.5);
+		assert_eq!(reader.read_bits(3).unwrap(), 5);
+		assert_eq!(reader.read_bits(8).unwrap_err().kind, DeserializationErrorKind::UnexpectedEOF);
+
+		// Other formats write the numbers in full
+		let text = crate::json::to_string(PlayerState { health: 200, ammo: 0, offset: 0, crouching: false, jumping: false, name: String::new() });
+		assert!(text.contains("200"));
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_59() {
+		use crate::{MarshallSeed, NaturalProfile};
+		use crate::remap::{EntityRef, EntityRefs, IdRemap};
+
+		#[derive(Debug, PartialEq)]
+		struct Ship {
+			name: String,
+			owner: EntityRef,
+			crew: EntityRefs
+		}
+
+		impl<'a> MarshalledDeserialize<'a, NaturalProfile, IdRemap> for Ship {
+			fn deserialize<T: Serializer>(data: &mut T, marshall: &'a IdRemap) -> Result<Self, DeserializationError> {
+				Ok(Self {
+					name: data.deserialize_key("name")?,
+					owner: data.deserialize_key_seed("owner", &mut MarshallSeed::new(marshall))?,
+					crew: data.deserialize_key_seed("crew", &mut MarshallSeed::new(marshall))?
+				})
+			}
+		}
+
+		let mut chunk = TextRepr::new();
+		chunk.serialize_key("name", "Argo");
+		chunk.serialize_key("owner", EntityRef(1u64));
+		chunk.serialize_key("crew", EntityRefs(vec![1u64, 2, 3]));
+		let remap: IdRemap = [(1, 101), (2, 102), (3, 103)].into_iter().collect();
+		let ship: Ship = MarshalledDeserialize::<NaturalProfile, _>::deserialize(&mut chunk.clone(), &remap).unwrap();
+		assert_eq!(ship, Ship { name: "Argo".into(), owner: EntityRef(101), crew: EntityRefs(vec![101, 102, 103]) });
+
+		let mut remap: IdRemap = IdRemap::new();
+		remap.insert(1, 7);
+		let err = MarshalledDeserialize::<NaturalProfile, _>::deserialize(&mut chunk.clone(), &remap).map(|_: Ship| ()).unwrap_err();
+		assert_eq!(err.field.as_deref(), Some("crew"));
+		let DeserializationErrorKind::Nested(inner) = err.kind else { panic!("{:?}", err) };
+		assert_eq!(inner.kind, DeserializationErrorKind::NoMatch { actual: "2".into() });
+
+		let ship: Ship = MarshalledDeserialize::<NaturalProfile, _>::deserialize(&mut chunk.clone(), &remap.keep_unmapped()).unwrap();
+		assert_eq!(ship.owner, EntityRef(7));
+		assert_eq!(ship.crew, EntityRefs(vec![7, 2, 3]));
+	}
+
+	#[test]
+	#[cfg(feature = "sled")]
+	fn test_serde_60() {
+		use crate::store::{StoreError, TreeExt};
+
+		let db = sled::Config::new().temporary(true).open().unwrap();
+		let tree = db.open_tree("people").unwrap();
+		tree.put_ser(2u32, TestStruct { name: "b".into(), id: "2".into(), age: 30 }).unwrap();
+		tree.put_ser(1u32, TestStruct { name: "a".into(), id: "1".into(), age: 20 }).unwrap();
+
+		let test: TestStruct = tree.get_de(1u32).unwrap().unwrap();
+		assert_eq!(test.name, "a");
+		assert!(tree.get_de::<EfficientProfile, _, TestStruct>(3u32).unwrap().is_none());
+
+		let all: Vec<(u32, TestStruct)> = tree.iter_de().collect::<Result<_, _>>().unwrap();
+		assert_eq!(all.iter().map(|x| x.0).collect::<Vec<_>>(), vec![1, 2]);
+		assert_eq!(all[1].1.age, 30);
+
+		let test: TestStruct = tree.remove_de(2u32).unwrap().unwrap();
+		assert_eq!(test.id, "2");
+		assert!(tree.get_de::<EfficientProfile, _, TestStruct>(2u32).unwrap().is_none());
+
+		tree.insert(crate::bin::to_vec(4u32), vec![1]).unwrap();
+		assert!(matches!(tree.get_de::<EfficientProfile, _, TestStruct>(4u32), Err(StoreError::Deserialization(_))));
+	}
+
+	#[test]
+	#[cfg(feature = "bin")]
+	fn test_serde_61() {
+		let mut data = TestStruct { name: "a".into(), id: "1".into(), age: 20 }.serialize_bin();
+		data.extend(TestStruct { name: "b".into(), id: "2".into(), age: 30 }.serialize_bin());
+		data.extend(crate::bin::to_vec(7u16));
+
+		let (first, data) = TestStruct::deserialize_bin_partial(data).unwrap();
+		let (second, data) = TestStruct::deserialize_bin_partial(data).unwrap();
+		assert_eq!((first.name.as_str(), first.age), ("a", 20));
+		assert_eq!((second.name.as_str(), second.age), ("b", 30));
+		let (last, data) = crate::bin::from_slice_partial::<crate::NaturalProfile, u16>(&data).unwrap();
+		assert_eq!(last, 7);
+		assert!(data.is_empty());
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_62() {
+		use crate::text::MAX_DEPTH;
+
+		fn depth(mut value: &TextRepr) -> usize {
+			let mut depth = 0;
+			loop {
+				value = match value {
+					TextRepr::Array(x) => &x[0],
+					TextRepr::Table(x) => x.values().next().unwrap(),
+					_ => return depth
+				};
+				depth += 1;
+			}
+		}
+
+		// Hostile documents are rejected without overflowing the stack, whether they are closed or not
+		for data in [
+			"[".repeat(100_000) + "1" + &"]".repeat(100_000),
+			"[".repeat(100_000) + &"]".repeat(99_999),
+			"{a: ".repeat(100_000) + "1" + &"}".repeat(100_000),
+			"[{a: ".repeat(50_000)
+		] {
+			let err = TextRepr::from_json(data).unwrap_err();
+			assert!(err.to_string().contains("nested deeper"), "{}", err);
+		}
+		let data = format!("[{}]\nb = 1\n", vec!["a"; 100_000].join("."));
+		assert!(TextRepr::from_toml(data).is_err());
+
+		let data = "[".repeat(MAX_DEPTH) + "1" + &"]".repeat(MAX_DEPTH);
+		assert_eq!(depth(&TextRepr::from_json(data).unwrap()), MAX_DEPTH);
+		let data = "{a: ".repeat(MAX_DEPTH) + "1" + &"}".repeat(MAX_DEPTH);
+		assert_eq!(depth(&TextRepr::from_json(data).unwrap()), MAX_DEPTH);
+		let data = format!("[{}]\nb = 1\n", vec!["a"; MAX_DEPTH - 1].join("."));
+		assert_eq!(depth(&TextRepr::from_toml(data).unwrap()), MAX_DEPTH);
+	}
+
+	#[test]
+	#[cfg(all(feature = "bin", feature = "text"))]
+	fn test_serde_63() {
+		use crate::postcard;
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Profile {
+			name: String,
+			nickname: Option<String>,
+			age: Option<u8>
+		}
+		impl_key_serde!(Profile, crate::NaturalProfile, name, nickname, age);
+
+		let profile = Profile { name: "a".into(), nickname: None, age: Some(3) };
+
+		let json = crate::json::to_string(profile.clone());
+		assert!(json.contains("\"nickname\": null"));
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, Profile>(&json).unwrap(), profile);
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, Profile>("{ name: \"a\", age: 3 }").unwrap(), profile);
+
+		// TOML has no null, so the key is left out
+		let toml = crate::toml::to_string(profile.clone());
+		assert!(!toml.contains("nickname"));
+		assert_eq!(crate::toml::from_str::<crate::NaturalProfile, Profile>(&toml).unwrap(), profile);
+
+		assert_eq!(crate::bin::from_slice::<crate::NaturalProfile, Profile>(&crate::bin::to_vec(profile.clone())).unwrap(), profile);
+		assert_eq!(postcard::from_slice::<crate::NaturalProfile, Profile>(&postcard::to_vec(profile.clone())).unwrap(), profile);
+
+		let items = vec![Some(1u8), None, Some(2)];
+		assert_eq!(crate::json::to_string(items.clone()), "[1, null, 2]");
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, Vec<Option<u8>>>("[1, null, 2]").unwrap(), items);
+		assert_eq!(crate::bin::from_slice::<crate::NaturalProfile, Vec<Option<u8>>>(&crate::bin::to_vec(items.clone())).unwrap(), items);
+		assert_eq!(crate::flexbuffers::from_slice::<crate::NaturalProfile, Vec<Option<u8>>>(&crate::flexbuffers::to_vec(items.clone())).unwrap(), items);
+		assert_eq!(TextRepr::from_json("[null]".into()).unwrap(), TextRepr::Array([TextRepr::Null].into()));
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Scores {
+			best: Option<u16>,
+			worst: Option<u16>,
+			all: Vec<Option<u16>>
+		}
+		impl_key_serde!(Scores, crate::NaturalProfile, best, worst, all);
+
+		let scores = Scores { best: Some(5), worst: None, all: vec![Some(1), None, Some(0)] };
+		// Every binary format reads back the options it wrote, whether they are fields, items or the whole value
+		macro_rules! round_trip {
+			($($format:ident),*) => {$({
+				use crate::$format::{from_slice, to_vec};
+				assert_eq!(from_slice::<crate::NaturalProfile, Profile>(&to_vec(profile.clone())).unwrap(), profile, stringify!($format));
+				assert_eq!(from_slice::<crate::NaturalProfile, Scores>(&to_vec(scores.clone())).unwrap(), scores, stringify!($format));
+				if stringify!($format) != "protobuf" {
+					assert_eq!(from_slice::<crate::NaturalProfile, Option<u16>>(&to_vec(Some(5u16))).unwrap(), Some(5), stringify!($format));
+					assert_eq!(from_slice::<crate::NaturalProfile, Option<u16>>(&to_vec(None::<u16>)).unwrap(), None, stringify!($format));
+				}
+			})*}
+		}
+		round_trip!(bin, postcard, bits, cbor, bencode, flexbuffers, der, protobuf);
+		for version in [crate::resp::Version::Resp2, crate::resp::Version::Resp3] {
+			let bytes = crate::resp::to_vec(scores.clone(), version);
+			assert_eq!(crate::resp::from_slice::<crate::NaturalProfile, Scores>(&bytes).unwrap(), scores);
+			let bytes = crate::resp::to_vec(Some(5u16), version);
+			assert_eq!(crate::resp::from_slice::<crate::NaturalProfile, Option<u16>>(&bytes).unwrap(), Some(5));
+		}
+		#[cfg(feature = "ion")] {
+			let bytes = crate::text::ion::to_vec(scores.clone());
+			assert_eq!(crate::text::ion::from_slice::<crate::NaturalProfile, Scores>(&bytes).unwrap(), scores);
+		}
+	}
+
+	#[test]
+	#[cfg(all(feature = "bin", feature = "text"))]
+	fn test_serde_64() {
+		use crate::postcard;
+		use crate::text::arena::TextArena;
+
+		let group = ("a".to_string(), 3u32, true);
+		let json = crate::json::to_string(group.clone());
+		assert_eq!(json, "[\"a\", 3, true]");
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, (String, u32, bool)>(&json).unwrap(), group);
+		assert_eq!(crate::bin::from_slice::<crate::NaturalProfile, (String, u32, bool)>(&crate::bin::to_vec(group.clone())).unwrap(), group);
+		assert_eq!(postcard::to_vec((1u8, 2u8)), vec![1, 2]);
+
+		// Elements that are sequences of their own keep their shape
+		let pairs = vec![(1u8, vec![2u8, 3]), (4, vec![])];
+		assert_eq!(crate::json::to_string(pairs.clone()), "[[1, [2, 3]], [4, []]]");
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, Vec<(u8, Vec<u8>)>>("[[1, [2, 3]], [4, []]]").unwrap(), pairs);
+		assert_eq!(crate::flexbuffers::from_slice::<crate::NaturalProfile, Vec<(u8, Vec<u8>)>>(&crate::flexbuffers::to_vec(pairs.clone())).unwrap(), pairs);
+
+		let wide = (1u8, 2u16, 3u32, 4u64, 5i8, 6i16, 7i32, 8i64, 9.5f32, 10.5f64, "k".to_string(), false);
+		assert_eq!(postcard::from_slice::<crate::NaturalProfile, _>(&postcard::to_vec(wide.clone())), Ok(wide.clone()));
+
+		let mut arena = TextArena::new();
+		let mut doc = arena.new_doc();
+		Serialize::<crate::NaturalProfile>::serialize((vec![1u8], 2u8), &mut doc);
+		assert_eq!(doc.to_repr().to_json(), "[[1], 2]");
+	}
+
+	#[test]
+	#[cfg(all(feature = "bin", feature = "text"))]
+	fn test_serde_65() {
+		use crate::postcard;
+
+		let items = [1u16, 2, 3];
+		assert_eq!(crate::json::to_string(items), "[1, 2, 3]");
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, [u16; 3]>("[1, 2, 3]").unwrap(), items);
+		assert_eq!(crate::bin::from_slice::<crate::NaturalProfile, [u16; 3]>(&crate::bin::to_vec(items)).unwrap(), items);
+		assert_eq!(postcard::from_slice::<crate::NaturalProfile, [[u8; 2]; 2]>(&postcard::to_vec([[1u8, 2], [3, 4]])).unwrap(), [[1, 2], [3, 4]]);
+
+		let err = crate::json::from_str::<crate::NaturalProfile, [u16; 3]>("[1, 2]").unwrap_err();
+		assert_eq!(err.kind, DeserializationErrorKind::WrongLength { expected: 3, actual: 2 });
+		assert_eq!(err.code(), crate::ErrorCode::E019_WrongLength);
+		let err = crate::json::from_str::<crate::NaturalProfile, [u16; 3]>("[1, 2, 3, 4]").unwrap_err();
+		assert_eq!(err.kind, DeserializationErrorKind::WrongLength { expected: 3, actual: 4 });
+
+		let bytes = postcard::to_vec(items);
+		let err = postcard::from_slice::<crate::NaturalProfile, [u16; 3]>(&bytes[..bytes.len() - 1]).unwrap_err();
+		assert_eq!(err.code(), crate::ErrorCode::E005_UnexpectedEof);
+	}
+
+	#[test]
+	#[cfg(all(feature = "bin", feature = "text"))]
+	fn test_serde_66() {
+		use std::collections::{BinaryHeap, LinkedList, VecDeque};
+		use crate::postcard;
+
+		let queue: VecDeque<u8> = [3, 1, 2].into();
+		assert_eq!(crate::json::to_string(queue.clone()), "[3, 1, 2]");
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, VecDeque<u8>>("[3, 1, 2]").unwrap(), queue);
+		assert_eq!(postcard::from_slice::<crate::NaturalProfile, VecDeque<u8>>(&postcard::to_vec(queue.clone())).unwrap(), queue);
+
+		let list: LinkedList<String> = ["a".to_string(), "b".into()].into();
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, LinkedList<String>>(&crate::json::to_string(list.clone())).unwrap(), list);
+		assert_eq!(crate::bin::from_slice::<crate::NaturalProfile, LinkedList<String>>(&crate::bin::to_vec(list.clone())).unwrap(), list);
+
+		let heap: BinaryHeap<i32> = [5, -1, 3].into();
+		assert_eq!(crate::json::to_string(heap.clone()), "[-1, 3, 5]");
+		let heap: BinaryHeap<i32> = postcard::from_slice::<crate::NaturalProfile, _>(&postcard::to_vec(heap)).unwrap();
+		assert_eq!(heap.into_sorted_vec(), vec![-1, 3, 5]);
+	}
+
+	#[test]
+	#[cfg(all(feature = "bin", feature = "text"))]
+	fn test_serde_67() {
+		use std::borrow::Cow;
+		use std::rc::Rc;
+		use std::sync::Arc;
+		use crate::postcard;
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Shared {
+			name: Arc<String>,
+			tags: Rc<Vec<u8>>,
+			label: Cow<'static, str>
+		}
+		impl_key_serde!(Shared, crate::NaturalProfile, name, tags, label);
+
+		let shared = Shared { name: Arc::new("a".into()), tags: Rc::new(vec![1, 2]), label: Cow::Borrowed("b") };
+		let json = crate::json::to_string(shared.clone());
+		let back: Shared = crate::json::from_str::<crate::NaturalProfile, _>(&json).unwrap();
+		assert_eq!(back, shared);
+		assert!(matches!(back.label, Cow::Owned(_)));
+
+		assert_eq!(postcard::from_slice::<crate::NaturalProfile, Rc<u32>>(&postcard::to_vec(Rc::new(300u32))).unwrap(), Rc::new(300));
+		let bytes = crate::bin::to_vec(Cow::<[u8]>::Borrowed(&[1, 2]));
+		assert_eq!(crate::bin::from_slice::<crate::NaturalProfile, Cow<[u8]>>(&bytes).unwrap(), Cow::<[u8]>::Owned(vec![1, 2]));
+	}
+
+	#[test]
+	#[cfg(all(feature = "bin", feature = "text"))]
+	fn test_serde_68() {
+		use crate::postcard;
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Key {
+			symbol: char,
+			marker: ()
+		}
+		impl_key_serde!(Key, crate::NaturalProfile, symbol, marker);
+
+		let key = Key { symbol: 'é', marker: () };
+		let json = crate::json::to_string(key.clone());
+		assert!(json.contains("\"symbol\": \"é\""));
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, Key>(&json).unwrap(), key);
+		assert_eq!(crate::bin::from_slice::<crate::NaturalProfile, Key>(&crate::bin::to_vec(key.clone())).unwrap(), key);
+		assert_eq!(postcard::to_vec(()), Vec::<u8>::new());
+		assert_eq!(postcard::from_slice::<crate::NaturalProfile, (char, (), u8)>(&postcard::to_vec(('x', (), 1u8))).unwrap(), ('x', (), 1));
+
+		let err = crate::json::from_str::<crate::NaturalProfile, char>("\"ab\"").unwrap_err();
+		assert_eq!(err.code(), crate::ErrorCode::E007_InvalidValue);
+	}
+
+	#[test]
+	#[cfg(all(feature = "bin", feature = "text"))]
+	fn test_serde_69() {
+		use std::num::{NonZeroI32, NonZeroU16, NonZeroU8};
+
+		let id = NonZeroU16::new(500).unwrap();
+		assert_eq!(crate::json::to_string(id), "500");
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, NonZeroU16>("500").unwrap(), id);
+		assert_eq!(crate::bin::to_vec(id), crate::bin::to_vec(500u16));
+		let offset = NonZeroI32::new(-3).unwrap();
+		assert_eq!(crate::postcard::from_slice::<crate::NaturalProfile, NonZeroI32>(&crate::postcard::to_vec(offset)).unwrap(), offset);
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, Option<NonZeroU8>>("null").unwrap(), None);
+
+		let err = crate::json::from_str::<crate::NaturalProfile, NonZeroU16>("0").unwrap_err();
+		assert_eq!(err.kind, DeserializationErrorKind::InvalidValue { reason: "expected a non-zero integer, found 0".into() });
+		assert!(crate::bin::from_slice::<crate::NaturalProfile, NonZeroU8>(&[0]).is_err());
+	}
+
+	#[test]
+	#[cfg(all(feature = "bin", feature = "text"))]
+	fn test_serde_70() {
+		use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Timing {
+			timeout: Duration,
+			started: SystemTime
+		}
+		impl Serialize<ReadableProfile> for Timing {
+			fn serialize<T: Serializer>(self, data: &mut T) {
+				data.serialize_key::<ReadableProfile, _, _>("timeout", self.timeout);
+				data.serialize_key::<ReadableProfile, _, _>("started", self.started);
+			}
+		}
+		impl Deserialize<ReadableProfile> for Timing {
+			fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+				Ok(Self {
+					timeout: data.deserialize_key::<ReadableProfile, _, _>("timeout")?,
+					started: data.deserialize_key::<ReadableProfile, _, _>("started")?
+				})
+			}
+		}
+		impl Serialize<EfficientProfile> for Timing {
+			fn serialize<T: Serializer>(self, data: &mut T) {
+				data.serialize::<EfficientProfile, _>(self.timeout);
+				data.serialize::<EfficientProfile, _>(self.started);
+			}
+		}
+		impl Deserialize<EfficientProfile> for Timing {
+			fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+				Ok(Self {
+					timeout: data.deserialize::<EfficientProfile, _>()?,
+					started: data.deserialize::<EfficientProfile, _>()?
+				})
+			}
+		}
+
+		let timing = Timing { timeout: Duration::from_millis(1500), started: UNIX_EPOCH + Duration::new(1709208000, 500_000_000) };
+		let toml = crate::toml::to_string::<ReadableProfile, _>(timing.clone());
+		assert!(toml.contains("timeout = \"1.5s\""));
+		assert!(toml.contains("started = \"2024-02-29T12:00:00.5Z\""));
+		assert_eq!(crate::toml::from_str::<ReadableProfile, Timing>(&toml).unwrap(), timing);
+		let bytes = crate::bin::to_vec::<EfficientProfile, _>(timing.clone());
+		assert_eq!(crate::bin::from_slice::<EfficientProfile, Timing>(&bytes).unwrap(), timing);
+
+		// The efficient profile writes seconds and nanoseconds even in human readable formats
+		assert_eq!(crate::json::to_string::<EfficientProfile, _>(Duration::from_millis(1500)), "[1, 500000000]");
+		assert_eq!(crate::json::from_str::<EfficientProfile, Duration>("[1, 500000000]").unwrap(), Duration::from_millis(1500));
+		assert!(crate::json::from_str::<EfficientProfile, Duration>("[1, 1000000000]").is_err());
+
+		let parse = |x: &str| crate::json::from_str::<ReadableProfile, Duration>(x);
+		assert_eq!(parse("\"250ms\"").unwrap(), Duration::from_millis(250));
+		assert_eq!(parse("\"1.25m\"").unwrap(), Duration::from_secs(75));
+		assert_eq!(parse("\"0s\"").unwrap(), Duration::ZERO);
+		assert_eq!(parse("\"1.5\"").unwrap_err().code(), crate::ErrorCode::E006_FromStrError);
+
+		let parse = |x: &str| crate::json::from_str::<ReadableProfile, SystemTime>(x);
+		assert_eq!(parse("\"2024-02-29T14:00:00+02:00\"").unwrap(), UNIX_EPOCH + Duration::from_secs(1709208000));
+		assert!(parse("\"2024-13-01T00:00:00Z\"").is_err());
+		let before = UNIX_EPOCH - Duration::from_millis(1500);
+		assert_eq!(crate::json::to_string::<ReadableProfile, _>(before), "\"1969-12-31T23:59:58.5Z\"");
+		assert_eq!(parse("\"1969-12-31T23:59:58.5Z\"").unwrap(), before);
+		assert_eq!(crate::postcard::from_slice::<EfficientProfile, SystemTime>(&crate::postcard::to_vec::<EfficientProfile, _>(before)).unwrap(), before);
+	}
+
+	#[test]
+	#[cfg(all(feature = "chrono", feature = "bin", feature = "text"))]
+	fn test_serde_71() {
+		use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Event {
+			at: DateTime<Utc>,
+			day: NaiveDate,
+			local: NaiveDateTime
+		}
+		impl_key_serde!(Event, crate::NaturalProfile, at, day, local);
+
+		let at = DateTime::from_timestamp(1709208000, 250_000_000).unwrap();
+		let event = Event { at, day: NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(), local: at.naive_utc() };
+		let toml = crate::toml::to_string(event.clone());
+		assert!(toml.contains("at = \"2024-02-29T12:00:00.25Z\""));
+		assert!(toml.contains("day = \"2024-02-29\""));
+		assert!(toml.contains("local = \"2024-02-29T12:00:00.250\""));
+		assert_eq!(crate::toml::from_str::<crate::NaturalProfile, Event>(&toml).unwrap(), event);
+
+		assert_eq!(crate::bin::to_vec(at), crate::bin::to_vec(1_709_208_000_250_000_000_i64));
+		assert_eq!(crate::bin::from_slice::<crate::NaturalProfile, Event>(&crate::bin::to_vec(event.clone())).unwrap(), event);
+
+		let parsed = crate::json::from_str::<crate::NaturalProfile, DateTime<Utc>>("\"2024-02-29T14:00:00.25+02:00\"").unwrap();
+		assert_eq!(parsed, at);
+		let err = crate::json::from_str::<crate::NaturalProfile, NaiveDate>("\"29/02/2024\"").unwrap_err();
+		assert_eq!(err.code(), crate::ErrorCode::E006_FromStrError);
+	}
+
+	#[test]
+	#[cfg(all(feature = "time", feature = "bin", feature = "text"))]
+	fn test_serde_72() {
+		use time::{Date, Month, OffsetDateTime, Time};
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Shift {
+			at: OffsetDateTime,
+			day: Date,
+			start: Time
+		}
+		impl_key_serde!(Shift, crate::NaturalProfile, at, day, start);
+
+		let at = OffsetDateTime::from_unix_timestamp_nanos(1_709_208_000_250_000_000).unwrap();
+		let shift = Shift { at, day: Date::from_calendar_date(2024, Month::February, 29).unwrap(), start: Time::from_hms_milli(9, 30, 0, 500).unwrap() };
+		let toml = crate::toml::to_string(shift.clone());
+		assert!(toml.contains("at = \"2024-02-29T12:00:00.25Z\""));
+		assert!(toml.contains("day = \"2024-02-29\""));
+		assert!(toml.contains("start = \"09:30:00.5\""));
+		assert_eq!(crate::toml::from_str::<crate::NaturalProfile, Shift>(&toml).unwrap(), shift);
+
+		assert_eq!(crate::bin::to_vec(at), crate::bin::to_vec(1_709_208_000_250_000_000_i64));
+		assert_eq!(crate::bin::from_slice::<crate::NaturalProfile, Shift>(&crate::bin::to_vec(shift.clone())).unwrap(), shift);
+
+		let parsed = crate::json::from_str::<crate::NaturalProfile, OffsetDateTime>("\"2024-02-29T14:00:00.25+02:00\"").unwrap();
+		assert_eq!(parsed, at);
+		assert!(crate::json::from_str::<crate::NaturalProfile, Time>("\"25:00:00\"").is_err());
+		assert!(crate::bin::from_slice::<crate::NaturalProfile, Time>(&crate::bin::to_vec(-1i64)).is_err());
+	}
+
+	#[test]
+	#[cfg(all(feature = "rust_decimal", feature = "bin", feature = "text"))]
+	fn test_serde_73() {
+		use rust_decimal::Decimal;
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Price {
+			amount: Decimal,
+			currency: String
+		}
+		impl_key_serde!(Price, crate::NaturalProfile, amount, currency);
+
+		let price = Price { amount: "0.30000000000000000001".parse().unwrap(), currency: "EUR".into() };
+		let json = crate::json::to_string(price.clone());
+		assert!(json.contains("\"amount\": \"0.30000000000000000001\""));
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, Price>(&json).unwrap(), price);
+
+		assert_eq!(crate::postcard::to_vec(Decimal::ONE).len(), 17);
+		assert_eq!(crate::bin::from_slice::<crate::NaturalProfile, Price>(&crate::bin::to_vec(price.clone())).unwrap(), price);
+		let err = crate::postcard::from_slice::<crate::NaturalProfile, Decimal>(&[2, 0, 0]).unwrap_err();
+		assert_eq!(err.kind, DeserializationErrorKind::WrongLength { expected: 16, actual: 2 });
+		assert!(crate::json::from_str::<crate::NaturalProfile, Decimal>("\"1.2.3\"").is_err());
+	}
+
+	#[test]
+	#[cfg(all(feature = "preserve_order", feature = "bin"))]
+	fn test_serde_74() {
+		use indexmap::{IndexMap, IndexSet};
+
+		let map: IndexMap<String, u32> = [("zeta".to_string(), 1), ("alpha".to_string(), 2), ("mid".to_string(), 3)].into_iter().collect();
+		let json = crate::json::to_string(map.clone());
+		let (zeta, alpha, mid) = (json.find("zeta").unwrap(), json.find("alpha").unwrap(), json.find("mid").unwrap());
+		assert!(zeta < alpha && alpha < mid);
+		let parsed = crate::json::from_str::<crate::NaturalProfile, IndexMap<String, u32>>(&json).unwrap();
+		assert!(parsed.keys().eq(map.keys()));
+		let parsed = crate::toml::from_str::<crate::NaturalProfile, IndexMap<String, u32>>(&crate::toml::to_string(map.clone())).unwrap();
+		assert!(parsed.keys().eq(map.keys()));
+
+		let set: IndexSet<u32> = [30, 10, 20].into_iter().collect();
+		let parsed = crate::bin::from_slice::<crate::NaturalProfile, IndexSet<u32>>(&crate::bin::to_vec(set.clone())).unwrap();
+		assert!(parsed.iter().eq(set.iter()));
+		assert_eq!(crate::json::to_string(set), "[30, 10, 20]");
+	}
+
+	#[test]
+	#[cfg(all(feature = "smallvec", feature = "bin", feature = "text"))]
+	fn test_serde_75() {
+		use smallvec::{smallvec, SmallVec};
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Path {
+			hops: SmallVec<[u16; 4]>
+		}
+		impl_key_serde!(Path, crate::NaturalProfile, hops);
+
+		let short = Path { hops: smallvec![1, 2, 3] };
+		let long = Path { hops: (0..10).collect() };
+		assert_eq!(crate::bin::to_vec(short.hops.clone()), crate::bin::to_vec(vec![1u16, 2, 3]));
+		assert_eq!(crate::json::to_string(short.hops.clone()), crate::json::to_string(vec![1u16, 2, 3]));
+		let parsed = crate::bin::from_slice::<crate::NaturalProfile, Path>(&crate::bin::to_vec(short.clone())).unwrap();
+		assert!(!parsed.hops.spilled());
+		assert_eq!(parsed, short);
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, Path>(&crate::json::to_string(long.clone())).unwrap(), long);
+	}
+
+	#[test]
+	#[cfg(all(feature = "either", feature = "bin", feature = "text"))]
+	fn test_serde_76() {
+		use either::Either;
+
+		let left = Either::<u8, String>::Left(5);
+		let right = Either::<u8, String>::Right("lmf".into());
+		assert_eq!(crate::bin::to_vec(left.clone()), vec![0, 5]);
+		assert_eq!(crate::bin::to_vec(right.clone())[0], 1);
+		assert_eq!(crate::bin::from_slice::<crate::NaturalProfile, Either<u8, String>>(&crate::bin::to_vec(right.clone())).unwrap(), right);
+		assert_eq!(crate::bin::from_slice::<crate::NaturalProfile, Either<u8, String>>(&[2, 5]).unwrap_err().code(), crate::ErrorCode::E003_NoMatch);
+
+		let json = crate::json::to_string(left.clone());
+		assert!(json.contains("left"));
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, Either<u8, String>>(&json).unwrap(), left);
+		assert_eq!(crate::toml::from_str::<crate::NaturalProfile, Either<u8, String>>("right = \"lmf\"").unwrap(), right);
+		assert!(crate::json::from_str::<crate::NaturalProfile, Either<u8, String>>("{middle: 1}").is_err());
+	}
+
+	#[test]
+	#[cfg(all(unix, feature = "bin", feature = "text"))]
+	fn test_serde_77() {
+		use std::ffi::{CString, OsString};
+		use std::os::unix::ffi::OsStringExt;
+
+		let name = OsString::from_vec(vec![b'a', 0xFF, b'b']);
+		assert_eq!(crate::bin::from_slice::<EfficientProfile, OsString>(&crate::bin::to_vec::<EfficientProfile, _>(name.clone())).unwrap(), name);
+		assert_eq!(crate::postcard::from_slice::<EfficientProfile, OsString>(&crate::postcard::to_vec::<EfficientProfile, _>(name.clone())).unwrap(), name);
+		assert_eq!(crate::json::from_str::<EfficientProfile, OsString>(&crate::json::to_string::<EfficientProfile, _>(name.clone())).unwrap(), name);
+		assert_eq!(crate::json::to_string::<ReadableProfile, _>(name.clone()), "\"a\u{FFFD}b\"");
+		assert_eq!(crate::bin::from_slice::<ReadableProfile, OsString>(&crate::bin::to_vec::<ReadableProfile, _>(name)).unwrap(), OsString::from("a\u{FFFD}b"));
+		assert_eq!(crate::json::from_str::<ReadableProfile, OsString>("\"dir\"").unwrap(), OsString::from("dir"));
+
+		let c_string = CString::new("lmf").unwrap();
+		assert_eq!(crate::bin::from_slice::<EfficientProfile, CString>(&crate::bin::to_vec::<EfficientProfile, _>(c_string.clone())).unwrap(), c_string);
+		assert_eq!(crate::json::from_str::<ReadableProfile, CString>(&crate::json::to_string::<ReadableProfile, _>(c_string.clone())).unwrap(), c_string);
+		let err = crate::postcard::from_slice::<EfficientProfile, CString>(&crate::postcard::to_vec(vec![b'a', 0, b'b'])).unwrap_err();
+		assert_eq!(err.code(), crate::ErrorCode::E007_InvalidValue);
+	}
+
+	#[test]
+	#[cfg(all(feature = "bin", feature = "text"))]
+	fn test_serde_78() {
+		use std::path::{Path, PathBuf};
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Paths {
+			root: PathBuf,
+			logs: PathBuf
+		}
+		impl_key_serde!(Paths, crate::NaturalProfile, root, logs);
+
+		let paths = Paths { root: "/srv/app".into(), logs: PathBuf::from("/srv/app").join("logs") };
+		let json = crate::json::to_string(paths.clone());
+		assert!(json.contains("\"/srv/app/logs\""));
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, Paths>(&json).unwrap(), paths);
+		assert_eq!(crate::toml::from_str::<crate::NaturalProfile, Paths>(&crate::toml::to_string(paths.clone())).unwrap(), paths);
+		assert_eq!(crate::bin::from_slice::<crate::NaturalProfile, Paths>(&crate::bin::to_vec(paths.clone())).unwrap(), paths);
+		assert_eq!(crate::bin::to_vec(Path::new("/srv/app")), crate::bin::to_vec(paths.root));
+	}
+
+	#[test]
+	#[cfg(all(feature = "bin", feature = "text"))]
+	fn test_serde_79() {
+		use std::ops::{Range, RangeInclusive, RangeTo};
+
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, Range<u16>>("{end: 5, start: 2}").unwrap(), 2..5);
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, Range<u16>>(&crate::json::to_string(2u16..5)).unwrap(), 2..5);
+		let toml = crate::toml::to_string(-1.5f64..=2.5);
+		assert!(toml.contains("start = -1.5") && toml.contains("end = 2.5"));
+		assert_eq!(crate::toml::from_str::<crate::NaturalProfile, RangeInclusive<f64>>(&toml).unwrap(), -1.5..=2.5);
+
+		assert_eq!(crate::bin::to_vec(2u16..5), vec![0, 2, 0, 5]);
+		assert_eq!(crate::bin::from_slice::<crate::NaturalProfile, Range<u16>>(&[0, 2, 0, 5]).unwrap(), 2..5);
+		assert_eq!(crate::bin::to_vec(..7u8), vec![7]);
+		assert_eq!(crate::postcard::from_slice::<crate::NaturalProfile, RangeTo<i32>>(&crate::postcard::to_vec(..-7)).unwrap(), ..-7);
+		assert!(crate::json::from_str::<crate::NaturalProfile, Range<u8>>("{start: 1}").is_err());
+	}
+
+	#[test]
+	#[cfg(all(feature = "bin", feature = "text"))]
+	fn test_serde_80() {
+		use std::num::{Saturating, Wrapping};
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Counters {
+			ticks: Wrapping<u8>,
+			credit: Saturating<i16>
+		}
+		impl_key_serde!(Counters, crate::NaturalProfile, ticks, credit);
+
+		let counters = Counters { ticks: Wrapping(250u8) + Wrapping(10), credit: Saturating(i16::MAX) + Saturating(1) };
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, Counters>("{ticks: 4, credit: 32767}").unwrap(), counters);
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, Counters>(&crate::json::to_string(counters.clone())).unwrap(), counters);
+		assert_eq!(crate::bin::to_vec(counters.ticks), crate::bin::to_vec(4u8));
+		assert_eq!(crate::bin::from_slice::<crate::NaturalProfile, Counters>(&crate::bin::to_vec(counters.clone())).unwrap(), counters);
+		assert!(crate::json::from_str::<crate::NaturalProfile, Wrapping<u8>>("1.5").is_err());
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_81() {
+		use crate::yaml_prelude::*;
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Server {
+			host: String,
+			port: u16
+		}
+		impl_key_serde!(Server, crate::NaturalProfile, host, port);
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Config {
+			name: String,
+			motd: String,
+			ratio: f64,
+			debug: bool,
+			proxy: Option<Server>,
+			servers: Vec<Server>,
+			tags: Vec<String>,
+			matrix: Vec<Vec<i32>>
+		}
+		impl_key_serde!(Config, crate::NaturalProfile, name, motd, ratio, debug, proxy, servers, tags, matrix);
+		impl_yaml!(Config, crate::NaturalProfile);
+
+		let config = Config {
+			name: "main".into(),
+			motd: "Say \"hi\"\n# not a comment: really".into(),
+			ratio: 0.5,
+			debug: false,
+			proxy: None,
+			servers: vec![Server { host: "a.example".into(), port: 80 }, Server { host: "b.example".into(), port: 8080 }],
+			tags: vec!["true".into(), "- dash".into(), "plain text".into()],
+			matrix: vec![vec![1, 2], vec![], vec![3]]
+		};
+		let yaml = config.clone().serialize_yaml();
+		// Keys are written in the order of the table, so the layout is checked by parsing it back
+		assert!(yaml.contains("servers:\n  - "));
+		let TextRepr::Array(servers) = TextRepr::from_yaml(yaml.clone()).unwrap().pull_entry("servers").unwrap() else { panic!() };
+		let TextRepr::Table(server) = &servers[0] else { panic!() };
+		assert_eq!((&server["host"], &server["port"]), (&TextRepr::String("a.example".into()), &TextRepr::Integer(80)));
+		assert!(yaml.contains("proxy: null\n"));
+		assert!(yaml.contains("- \"true\"\n"));
+		assert_eq!(Config::deserialize_yaml(yaml).unwrap(), config);
+
+		let yaml = "%YAML 1.2\n---\n# The main server\nname: main   # trailing comment\nmotd: |\n  Say \"hi\"\n  # not a comment: really\nratio: .5\ndebug: False\nproxy:\n  host: 'proxy''s host'\n  port: 3128\nservers:\n- {host: a.example, port: 80}\n- host: \"b.example\"\n  port: 8080\ntags: [\"true\", '- dash', plain text]\nmatrix:\n  - - 1\n    - 2\n  - []\n  - [3]\n";
+		let parsed = Config::deserialize_yaml(yaml.into()).unwrap();
+		assert_eq!(parsed.motd, "Say \"hi\"\n# not a comment: really\n");
+		assert_eq!(parsed.proxy, Some(Server { host: "proxy's host".into(), port: 3128 }));
+		assert_eq!(Config { motd: config.motd.clone(), proxy: None, ..parsed }, config);
+
+		let mut folded = TextRepr::from_yaml("text: >-\n  folded\n  line\n\n  next\n".into()).unwrap();
+		assert_eq!(folded.pull_entry("text").unwrap(), TextRepr::String("folded line\nnext".into()));
+		assert!(!TextRepr::is_valid_yaml("a: 1\n   b: 2"));
+		assert!(!TextRepr::is_valid_yaml("a: [1, 2"));
+		assert!(!TextRepr::is_valid_yaml("a: *alias"));
+		assert!(TextRepr::from_yaml("a: 1\nb: [1, 2".into()).unwrap_err().to_string().contains("line 2"));
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_82() {
+		use crate::csv::{CSVDeserialize, CSVSerialize};
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Point {
+			x: i32,
+			y: i32
+		}
+		impl_key_serde!(Point, crate::ReadableProfile, x, y);
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Record {
+			name: String,
+			score: f64,
+			passed: bool,
+			note: Option<String>,
+			at: Point,
+			tags: Vec<String>
+		}
+		impl_key_serde!(Record, crate::ReadableProfile, name, score, passed, note, at, tags);
+
+		let records = vec![
+			Record { name: "Smith, Jo".into(), score: 9.5, passed: true, note: Some("said \"hi\"\non two lines".into()), at: Point { x: 1, y: -2 }, tags: vec!["a".into(), "b".into()] },
+			Record { name: "42".into(), score: -1.25, passed: false, note: None, at: Point { x: 0, y: 0 }, tags: vec![] }
+		];
+		let csv = CSVSerialize::<crate::ReadableProfile>::serialize_csv(records.clone());
+		let header = csv.lines().next().unwrap();
+		for column in ["name", "score", "passed", "note", "at.x", "at.y", "tags.0", "tags.1"] {
+			assert!(header.split(',').any(|x| x == column), "{} missing from {}", column, header);
+		}
+		assert!(csv.contains("\"Smith, Jo\"") && csv.contains("\"42\"") && csv.contains("\"said \"\"hi\"\"\non two lines\""));
+		assert_eq!(<Vec<Record> as CSVDeserialize<crate::ReadableProfile>>::deserialize_csv(csv).unwrap(), records);
+
+		let csv = "name,score,passed,at.x,at.y,tags\r\nJo,3,true,1,2,[]\r\n\r\nAl,4.5,false,,2,[]\r\n";
+		let err = crate::csv::from_str::<crate::ReadableProfile, Record>(csv).unwrap_err();
+		assert_eq!(err.field.as_deref(), Some("row 2"));
+		let parsed = crate::csv::from_str::<crate::ReadableProfile, Record>(&csv.replace(",,", ",0,")).unwrap();
+		assert_eq!(parsed[1], Record { name: "Al".into(), score: 4.5, passed: false, note: None, at: Point { x: 0, y: 2 }, tags: vec![] });
+
+		assert!(!TextRepr::is_valid_csv("a,b\n\"1\"x,2\n"));
+		assert!(!TextRepr::is_valid_csv("a,b\n1,2,3\n"));
+		assert!(!TextRepr::is_valid_csv("a,a.b\n1,2\n"));
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_83() {
+		use std::collections::HashMap;
+		use crate::ron_prelude::*;
+
+		#[derive(Debug, PartialEq, Clone)]
+		enum Shape {
+			Number(u16),
+			Text(String)
+		}
+		impl_variant_serde!(Shape, crate::NaturalProfile, Number, Text);
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Scene {
+			name: String,
+			shapes: Vec<Shape>,
+			origin: (f64, f64),
+			parent: Option<String>,
+			labels: HashMap<String, u32>
+		}
+		impl_key_serde!(Scene, crate::NaturalProfile, name, shapes, origin, parent, labels);
+		impl_ron!(Scene, crate::NaturalProfile);
+
+		let scene = Scene {
+			name: "main \"scene\"".into(),
+			shapes: vec![Shape::Number(22), Shape::Text("lmf".into())],
+			origin: (1.0, -0.5),
+			parent: None,
+			labels: [("a key".to_string(), 1)].into_iter().collect()
+		};
+		let ron = scene.clone().serialize_ron();
+		assert!(ron.contains("Number(22)") && ron.contains("Text(\"lmf\")"));
+		assert!(ron.contains("origin: [1.0, -0.5],") && ron.contains("parent: None,") && ron.contains("{\n\t\t\"a key\": 1,\n\t}"));
+		assert_eq!(Scene::deserialize_ron(ron).unwrap(), scene);
+
+		let ron = r##"
+			#![enable(implicit_some)]
+			// A hand written scene
+			Scene(
+				name: r#"main "scene""#,
+				shapes: [Number(0x16), Text('\u{6c}' /* not a string */)],
+				origin: (1, -.5),
+				parent: Some("root"),
+				labels: {"a key": 1_0},
+			)
+		"##;
+		let parsed = Scene::deserialize_ron(ron.into()).unwrap();
+		assert_eq!(parsed.shapes, vec![Shape::Number(22), Shape::Text("l".into())]);
+		assert_eq!(parsed.parent.as_deref(), Some("root"));
+		assert_eq!(parsed.labels["a key"], 10);
+		assert_eq!(Scene { shapes: scene.shapes.clone(), parent: None, labels: scene.labels.clone(), ..parsed }, scene);
+
+		assert!(!TextRepr::is_valid_ron("(a: 1,"));
+		assert!(!TextRepr::is_valid_ron("(a: 1) (b: 2)"));
+		assert!(TextRepr::from_ron("(\n\ta: \"1)".into()).unwrap_err().to_string().contains("line 2"));
+	}
+
+	#[test]
+	#[cfg(feature = "bin")]
+	fn test_serde_84() {
+		use crate::cbor;
+
+		assert_eq!(cbor::to_vec(100u8), vec![0x18, 0x64]);
+		assert_eq!(cbor::to_vec(-1000i32), vec![0x39, 0x03, 0xE7]);
+		assert_eq!(cbor::to_vec(1.5f64), vec![0xFA, 0x3F, 0xC0, 0x00, 0x00]);
+		assert_eq!(cbor::to_vec("a"), vec![0x61, 0x61]);
+		assert_eq!(cbor::to_vec(vec![1u8, 2, 3]), vec![0x83, 1, 2, 3]);
+		assert_eq!(cbor::to_vec_indefinite(vec![1u8, 2, 3]), vec![0x9F, 1, 2, 3, 0xFF]);
+		assert_eq!(cbor::to_vec(Option::<u8>::None), vec![0xF6]);
+
+		let one = TestStruct { name: "ab".into(), id: "c".into(), age: 150 };
+		for bytes in [
+			cbor::to_vec::<ReadableProfile, _>(TestStruct2 { one: one.clone(), two: TestStruct::default() }),
+			cbor::to_vec_indefinite::<ReadableProfile, _>(TestStruct2 { one, two: TestStruct::default() })
+		] {
+			let two: TestStruct2 = cbor::from_slice::<ReadableProfile, _>(&bytes).unwrap();
+			assert_eq!(two.one.name, "ab");
+			assert_eq!(two.one.age, 150);
+		}
+
+		// An indefinite map holding a chunked string, a half precision float, a tag and an integer key
+		let bytes = [
+			0xBF,
+			0x64, b'p', b'o', b'r', b't', 0x19, 0x1F, 0x90,
+			0x64, b'h', b'o', b's', b't', 0x7F, 0x61, b'l', 0x62, b'm', b'f', 0xFF,
+			0x64, b't', b'a', b'g', b's', 0x9F, 0xC0, 0x61, b'x', 0xFF,
+			0xFF
+		];
+		let server: TestStruct6 = cbor::from_slice::<ReadableProfile, _>(&bytes).unwrap();
+		assert_eq!((server.port, server.host, server.tags), (8080, "lmf".to_string(), vec!["x".to_string()]));
+		assert_eq!(cbor::from_slice::<crate::NaturalProfile, f32>(&[0xF9, 0x3E, 0x00]).unwrap(), 1.5);
+		let map: std::collections::HashMap<u8, bool> = cbor::from_slice::<crate::NaturalProfile, _>(&[0xA1, 0x07, 0xF5]).unwrap();
+		assert!(map[&7]);
+
+		let bytes = cbor::to_vec::<ReadableProfile, _>(TestStruct::default());
+		let err = cbor::from_slice::<ReadableProfile, TestStruct>(&bytes[..bytes.len() - 1]).unwrap_err();
+		assert!(matches!(err.kind, DeserializationErrorKind::UnexpectedEOF));
+		assert!(cbor::from_slice::<crate::NaturalProfile, Vec<u8>>(&[0x9F, 1, 2]).is_err());
+		assert!(cbor::from_slice::<crate::NaturalProfile, Vec<u8>>(&[0x9B, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]).is_err());
+		assert!(cbor::from_slice::<crate::NaturalProfile, Vec<u8>>(&[0x81; 4096]).is_err());
+	}
+
+	#[test]
+	#[cfg(feature = "bin")]
+	fn test_serde_85() {
+		use crate::bencode;
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Info {
+			name: String,
+			length: u64,
+			pieces: Vec<u8>,
+			private: bool,
+			comment: Option<String>
+		}
+		impl_key_serde!(Info, crate::NaturalProfile, name, length, pieces, private, comment);
+
+		assert_eq!(bencode::to_vec(-42i32), b"i-42e");
+		assert_eq!(bencode::to_vec("spam"), b"4:spam");
+		assert_eq!(bencode::to_vec(vec!["a", "bc"]), b"l1:a2:bce");
+
+		let info = Info { name: "lmf.iso".into(), length: 1 << 40, pieces: vec![0, 255], private: true, comment: None };
+		let bytes = bencode::to_vec(info.clone());
+		assert_eq!(bytes, b"d6:lengthi1099511627776e4:name7:lmf.iso6:piecesli0ei255ee7:privatei1ee");
+		assert_eq!(bencode::from_slice::<crate::NaturalProfile, Info>(&bytes).unwrap(), info);
+		let info = Info { comment: Some("hi".into()), ..info };
+		assert_eq!(bencode::from_slice::<crate::NaturalProfile, Info>(&bencode::to_vec(info.clone())).unwrap(), info);
+
+		let server: TestStruct6 = bencode::from_slice::<ReadableProfile, _>(b"d4:host1:a4:porti80e4:tagsl1:b1:cee").unwrap();
+		assert_eq!((server.port, server.tags), (80, vec!["b".to_string(), "c".to_string()]));
+		assert_eq!(bencode::from_slice::<crate::NaturalProfile, f64>(&bencode::to_vec(0.25f64)).unwrap(), 0.25);
+
+		assert!(matches!(bencode::from_slice::<crate::NaturalProfile, String>(b"5:spam").unwrap_err().kind, DeserializationErrorKind::UnexpectedEOF));
+		assert!(bencode::from_slice::<crate::NaturalProfile, i32>(b"i03e").is_err());
+		assert!(bencode::from_slice::<crate::NaturalProfile, i32>(b"i-0e").is_err());
+		assert!(bencode::from_slice::<crate::NaturalProfile, u8>(b"i256e").is_err());
+		assert!(bencode::from_slice::<crate::NaturalProfile, Vec<u8>>(b"li1ei2e").is_err());
+		assert!(bencode::from_slice::<crate::NaturalProfile, Vec<u8>>(&b"l".repeat(4096)).is_err());
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_86() {
+		use crate::urlencoded;
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Search {
+			query: String,
+			page: u32,
+			exact: bool,
+			tags: Vec<String>,
+			lang: Option<String>
+		}
+		impl_key_serde!(Search, crate::NaturalProfile, query, page, exact, tags, lang);
+
+		let search = Search { query: "a&b = 100% c".into(), page: 2, exact: false, tags: vec!["x".into(), "é".into()], lang: None };
+		let form = urlencoded::to_string(search.clone());
+		assert_eq!(form, "query=a%26b+%3D+100%25+c&page=2&exact=false&tags=x&tags=%C3%A9");
+		assert_eq!(urlencoded::from_str::<crate::NaturalProfile, Search>(&form).unwrap(), search);
+
+		let search: Search = urlencoded::from_str::<crate::NaturalProfile, _>("lang=en&exact=on&tags=one&page=7&query=").unwrap();
+		assert_eq!(search, Search { query: "".into(), page: 7, exact: true, tags: vec!["one".into()], lang: Some("en".into()) });
+
+		let err = urlencoded::from_str::<crate::NaturalProfile, Search>("query=a&page=x&exact=true&tags=").unwrap_err();
+		assert_eq!(err.field.as_deref(), Some("page"));
+		assert!(urlencoded::from_str::<crate::NaturalProfile, Search>("query=%G1").is_err());
+		assert!(urlencoded::from_str::<crate::NaturalProfile, Search>("query=a&exact=true&tags=").is_err());
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_87() {
+		use crate::jsonl::{self, JSONLinesDeserialize, JSONLinesSerialize};
+
+		let lines = JSONLinesSerialize::<ReadableProfile>::serialize_json_lines((1..=3u16).map(|port| TestStruct6 { port, host: "a".into(), tags: vec![] }));
+		assert_eq!(lines.lines().count(), 3);
+		let servers: Vec<TestStruct6> = JSONLinesDeserialize::<ReadableProfile>::deserialize_json_lines(lines.clone()).unwrap();
+		assert_eq!(servers.iter().map(|x| x.port).collect::<Vec<_>>(), vec![1, 2, 3]);
+
+		let mut buf = Vec::new();
+		jsonl::to_writer::<crate::NaturalProfile, _, _, _>(&mut buf, vec![vec![1u8, 2], vec![]]).unwrap();
+		assert_eq!(String::from_utf8(buf).unwrap(), "[1, 2]\n[]\n");
+
+		let data = "{port: 1, host: \"a\", tags: []}\n\n{port: \"x\"}\n{port: 3, host: \"c\", tags: [\"d\"]}\n";
+		let mut reader = jsonl::from_reader::<ReadableProfile, TestStruct6, _>(data.as_bytes());
+		assert_eq!(reader.next().unwrap().unwrap().port, 1);
+		let err = reader.next().unwrap().unwrap_err();
+		assert_eq!(err.field.as_deref(), Some("line 3"));
+		assert_eq!(reader.next().unwrap().unwrap().tags, vec!["d".to_string()]);
+		assert!(reader.next().is_none());
+		assert!(jsonl::from_str::<ReadableProfile, TestStruct6>(data).is_err());
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_88() {
+		use std::collections::HashMap;
+		use crate::sexpr;
+		use crate::text::MAX_DEPTH;
+
+		let server = TestStruct6 { port: 80, host: "a \"b\"".into(), tags: vec!["x".into(), "y z".into()] };
+		let data = sexpr::to_string::<ReadableProfile, _>(TestStruct6 { port: 80, host: server.host.clone(), tags: server.tags.clone() });
+		assert!(data.contains(":port 80") && data.contains(":host \"a \\\"b\\\"\"") && data.contains(":tags (\"x\" \"y z\")"));
+		let read: TestStruct6 = sexpr::from_str::<ReadableProfile, _>(&data).unwrap();
+		assert_eq!((read.port, read.host, read.tags), (server.port, server.host, server.tags));
+
+		let repr = TextRepr::from_sexpr("; a build target\n(:name lmf :deps (core \"std\") :opt nil :debug #t :\"the ratio\" 0.5 :size -inf.0)".into()).unwrap();
+		let TextRepr::Table(table) = &repr else { panic!() };
+		assert_eq!(table["name"], TextRepr::String("lmf".into()));
+		assert_eq!(table["deps"], TextRepr::Array([TextRepr::String("core".into()), TextRepr::String("std".into())].into()));
+		assert_eq!(table["opt"], TextRepr::Null);
+		assert_eq!(table["debug"], TextRepr::Boolean(true));
+		assert_eq!(table["the ratio"], TextRepr::Float(0.5));
+		assert_eq!(TextRepr::from_sexpr(repr.clone().to_sexpr()).unwrap(), repr);
+
+		let floats: Vec<f64> = sexpr::from_str::<crate::NaturalProfile, _>(&sexpr::to_string(vec![1.0f64, f64::INFINITY])).unwrap();
+		assert_eq!(floats, vec![1.0, f64::INFINITY]);
+		let map: HashMap<String, u8> = sexpr::from_str::<crate::NaturalProfile, _>("(:a 1 :b 2)").unwrap();
+		assert_eq!(map["b"], 2);
+
+		assert!(!TextRepr::is_valid_sexpr("(:a 1 :b)"));
+		assert!(!TextRepr::is_valid_sexpr("(1 :a 2)"));
+		assert!(!TextRepr::is_valid_sexpr("(1 2"));
+		assert!(!TextRepr::is_valid_sexpr("(1) 2"));
+		assert!(!TextRepr::is_valid_sexpr("(\"a)"));
+		assert!(!TextRepr::is_valid_sexpr("(".repeat(MAX_DEPTH + 1) + &")".repeat(MAX_DEPTH + 1)));
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_89() {
+		use std::collections::HashMap;
+		use crate::hcl_prelude::*;
+		use crate::text::MAX_DEPTH;
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Listener {
+			port: u16,
+			tls: bool
+		}
+		impl_key_serde!(Listener, crate::NaturalProfile, port, tls);
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Server {
+			image: String,
+			env: HashMap<String, String>,
+			listener: Vec<Listener>
+		}
+		impl_key_serde!(Server, crate::NaturalProfile, image, env, listener);
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Config {
+			region: String,
+			replicas: Option<u8>,
+			server: HashMap<String, Server>
+		}
+		impl_key_serde!(Config, crate::NaturalProfile, region, replicas, server);
+		impl_hcl!(Config, crate::NaturalProfile);
+
+		let data = r#"
+			# Deployed by hand
+			region = "us-east-1"
+			replicas = null
+
+			server "web" {
+				image = "nginx:${var.tag}"
+				env = { MODE = "prod", "LOG LEVEL": "info" }
+
+				listener {
+					port = 80
+					tls = false
+				}
+				listener {
+					port = 443 // the default
+					tls = true
+				}
+			}
+
+			/* The worker has no listeners */
+			server "worker" {
+				image = <<-EOT
+				  busybox
+				  EOT
+				env = {
+					DEBUG = "1"
+				}
+				listener = []
+			}
+		"#;
+		let config = Config::deserialize_hcl(data.into()).unwrap();
+		assert_eq!(config.region, "us-east-1");
+		assert_eq!(config.replicas, None);
+		let web = &config.server["web"];
+		assert_eq!(web.image, "nginx:${var.tag}");
+		assert_eq!(web.env["LOG LEVEL"], "info");
+		assert_eq!(web.listener, vec![Listener { port: 80, tls: false }, Listener { port: 443, tls: true }]);
+		assert_eq!(config.server["worker"].image, "busybox\n");
+
+		let hcl = config.clone().serialize_hcl();
+		assert!(hcl.starts_with("region = \"us-east-1\"\n") || hcl.contains("\nregion = \"us-east-1\"\n"));
+		assert!(hcl.contains("image = \"nginx:$${var.tag}\"") && hcl.contains("    listener {\n") && hcl.contains("      port = 80\n"));
+		assert_eq!(Config::deserialize_hcl(hcl).unwrap(), config);
+
+		assert_eq!(
+			TextRepr::from_hcl("a \"b\" \"c\" { x = 1 }".into()).unwrap(),
+			TextRepr::from_hcl("a { b { c { x = 1 } } }".into()).unwrap()
+		);
+		assert_eq!(TextRepr::from_hcl("x = var.region".into()).unwrap(), TextRepr::from_hcl("x = \"var.region\"".into()).unwrap());
+		assert!(!TextRepr::is_valid_hcl("a = 1\na = 2"));
+		assert!(!TextRepr::is_valid_hcl("a \"b\" {}\na \"b\" {}"));
+		assert!(!TextRepr::is_valid_hcl("a {"));
+		assert!(!TextRepr::is_valid_hcl("a = [1 2]"));
+		assert!(!TextRepr::is_valid_hcl("a = \"b"));
+		assert!(TextRepr::is_valid_hcl("a = ".to_string() + &"[".repeat(MAX_DEPTH - 1) + &"]".repeat(MAX_DEPTH - 1)));
+		assert!(!TextRepr::is_valid_hcl("a = ".to_string() + &"[".repeat(MAX_DEPTH + 1) + &"]".repeat(MAX_DEPTH + 1)));
+		assert!(!TextRepr::is_valid_hcl("a { ".repeat(MAX_DEPTH + 1) + &"}".repeat(MAX_DEPTH + 1)));
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_90() {
+		use crate::plist;
+		use crate::text::MAX_DEPTH;
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct Bundle {
+			identifier: String,
+			version: u32,
+			scale: f64,
+			sandboxed: bool,
+			icon: Vec<u8>,
+			languages: Vec<String>,
+			team: Option<String>
+		}
+		impl_key_serde!(Bundle, crate::NaturalProfile, identifier, version, scale, sandboxed, icon, languages, team);
+
+		let data = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<!-- Written by hand -->
+	<key>identifier</key>
+	<string>com.example.a&amp;b</string>
+	<key>version</key>
+	<integer> 12 </integer>
+	<key>scale</key>
+	<real>2</real>
+	<key>sandboxed</key>
+	<true/>
+	<key>icon</key>
+	<data>
+	AAH/
+	</data>
+	<key>languages</key>
+	<array>
+		<string>en</string>
+		<string><![CDATA[<fr>]]></string>
+	</array>
+</dict>
+</plist>
+"#;
+		let bundle: Bundle = plist::from_str::<crate::NaturalProfile, _>(data).unwrap();
+		assert_eq!(bundle, Bundle {
+			identifier: "com.example.a&b".into(),
+			version: 12,
+			scale: 2.0,
+			sandboxed: true,
+			icon: vec![0, 1, 255],
+			languages: vec!["en".into(), "<fr>".into()],
+			team: None
+		});
+
+		let bundle = Bundle { team: Some("T".into()), ..bundle };
+		let out = plist::to_string(bundle.clone());
+		assert!(out.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n") && out.ends_with("</dict>\n</plist>\n"));
+		assert!(out.contains("\t<key>identifier</key>\n\t<string>com.example.a&amp;b</string>\n") && out.contains("<string>&lt;fr&gt;</string>"));
+		assert_eq!(plist::from_str::<crate::NaturalProfile, Bundle>(&out).unwrap(), bundle);
+
+		assert_eq!(TextRepr::from_plist("<array><integer>1</integer><dict/></array>".into()).unwrap(), TextRepr::Array([TextRepr::Integer(1), TextRepr::Table(Default::default())].into()));
+		assert!(!TextRepr::is_valid_plist("<plist><dict><key>a</key></dict></plist>"));
+		assert!(!TextRepr::is_valid_plist("<plist><dict><string>a</string></dict></plist>"));
+		assert!(!TextRepr::is_valid_plist("<plist><array><string>a</array></plist>"));
+		assert!(!TextRepr::is_valid_plist("<plist><integer>x</integer></plist>"));
+		assert!(!TextRepr::is_valid_plist("<plist><array></plist>"));
+		assert!(!TextRepr::is_valid_plist("<array>".repeat(MAX_DEPTH + 1) + &"</array>".repeat(MAX_DEPTH + 1)));
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_91() {
+		use crate::env;
+
+		#[derive(Debug, PartialEq, Clone)]
+		struct App {
+			host: String,
+			port: u16,
+			debug: bool,
+			ratio: f64,
+			motd: String,
+			secret: Option<String>
+		}
+		impl_key_serde!(App, crate::NaturalProfile, host, port, debug, ratio, motd, secret);
+
+		let data = r#"
+# Written by hand
+export HOST=example.com # the public name
+PORT = 8080
+DEBUG=true
+RATIO=0.5
+MOTD="Hello,
+\"world\" \$HOME"
+SECRET=
+"#;
+		let app: App = env::from_str::<crate::NaturalProfile, _>(data).unwrap();
+		assert_eq!(app, App {
+			host: "example.com".into(),
+			port: 8080,
+			debug: true,
+			ratio: 0.5,
+			motd: "Hello,\n\"world\" $HOME".into(),
+			secret: None
+		});
+
+		let app = App { secret: Some("123".into()), ratio: 2.0, ..app };
+		let out = env::to_string(app.clone());
+		assert!(out.contains("HOST=example.com\n") && out.contains("RATIO=2.0\n") && out.contains("SECRET=\"123\"\n"));
+		assert!(out.contains("MOTD=\"Hello,\\n\\\"world\\\" \\$HOME\"\n"));
+		assert_eq!(env::from_str::<crate::NaturalProfile, App>(&out).unwrap(), app);
+
+		let repr = TextRepr::from_env("export=1\nA='x #y' # comment\nB=#fff\nA=2".into()).unwrap();
+		assert_eq!(repr, TextRepr::Table([("export".into(), TextRepr::Integer(1)), ("a".into(), TextRepr::Integer(2)), ("b".into(), TextRepr::String("#fff".into()))].into_iter().collect()));
+		assert_eq!(TextRepr::from_env("A='x #y'".into()).unwrap(), TextRepr::Table([("a".into(), TextRepr::String("x #y".into()))].into_iter().collect()));
+		assert!(!TextRepr::is_valid_env("A=\"never closed"));
+		assert!(!TextRepr::is_valid_env("A B=1"));
+		assert!(!TextRepr::is_valid_env("A='x' y"));
+	}
+
+	#[test]
+	#[cfg(feature = "bin")]
+	fn test_serde_92() {
+		assert_eq!(TestEnum2::Large(1).serialize_hex(), "050001");
+		assert!(matches!(TestEnum2::deserialize_hex("050001".into()).unwrap(), TestEnum2::Large(1)));
+
+		let test = TestStruct { name: "a".into(), id: "1".into(), age: 255 };
+		let hex = test.clone().serialize_hex();
+		assert_eq!(hex, test.serialize_bin().iter().map(|x| format!("{:02x}", x)).collect::<String>());
+		assert_eq!(TestStruct::deserialize_hex(hex.to_uppercase()).unwrap().age, 255);
+
+		let err = TestEnum2::deserialize_hex("05000".into()).unwrap_err();
+		assert!(matches!(err.kind, DeserializationErrorKind::InvalidValue { .. }));
+		assert!(TestEnum2::deserialize_hex("0500zz".into()).is_err());
+	}
+
+	#[test]
+	#[cfg(feature = "base64")]
+	fn test_serde_93() {
+		assert_eq!(TestEnum2::Large(1).serialize_bin_base64(), "BQAB");
+		assert!(matches!(TestEnum2::deserialize_bin_base64("BQAB".into()).unwrap(), TestEnum2::Large(1)));
+
+		let test = TestStruct { name: "a b".into(), id: "1".into(), age: 300 };
+		let encoded = test.clone().serialize_bin_base64();
+		assert_eq!(encoded, crate::base64::encode(&test.serialize_bin()));
+		let test = TestStruct::deserialize_bin_base64(encoded).unwrap();
+		assert_eq!((test.name.as_str(), test.age), ("a b", 300));
+
+		let err = TestEnum2::deserialize_bin_base64("BQ!B".into()).unwrap_err();
+		assert!(matches!(err.kind, DeserializationErrorKind::InvalidValue { .. }));
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_94() {
+		let test = TestStruct2 {
+			one: TestStruct { name: "a".into(), id: "b".into(), age: 1 },
+			two: TestStruct { name: "c".into(), id: "d".into(), age: 2 }
+		};
+		let json = test.serialize_json();
+		assert!(json.contains("\"one\": {") && json.contains("\t\"age\": 1") && !json.contains(",\n}"));
+		assert!(!json.contains("\tone:"));
+		let test = TestStruct2::deserialize_json(json).unwrap();
+		assert_eq!((test.one.age, test.two.name.as_str()), (1, "c"));
+
+		let repr = TextRepr::from_json(r#"{"a": 1, "b c": [{"d": true}]}"#.into()).unwrap();
+		assert_eq!(TextRepr::from_json(repr.clone().to_json()).unwrap(), repr);
+		assert_eq!(TextRepr::from_json(repr.clone().to_legacy_json()).unwrap(), repr);
+		assert!(repr.to_legacy_json().contains("\ta: 1,\n"));
+
+		assert_eq!(TextRepr::from_json("{ a: 1, \"a b\": 2, }".into()).unwrap(), TextRepr::from_json("{\"a\": 1, \"a b\": 2}".into()).unwrap());
+		assert!(!TextRepr::is_valid_json("{ \"a: 1 }"));
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_95() {
+		let test = TestStruct { name: "say \"hi\", then\n\tleave".into(), id: "C:\\dir\u{1}".into(), age: 1 };
+		let json = test.clone().serialize_json();
+		assert!(json.contains(r#""say \"hi\", then\n\tleave""#) && json.contains(r#""C:\\dir\u0001""#));
+		let out = TestStruct::deserialize_json(json).unwrap();
+		assert_eq!((out.name, out.id), (test.name, test.id));
+
+		let repr = TextRepr::from_json(r#"{"a\"b": "\u00e9\ud83d\ude00\/", "c": ["x,y]", "}"]}"#.into()).unwrap();
+		assert_eq!(repr, TextRepr::Table([
+			("a\"b".into(), TextRepr::String("é😀/".into())),
+			("c".into(), TextRepr::Array([TextRepr::String("x,y]".into()), TextRepr::String("}".into())].into()))
+		].into_iter().collect()));
+		assert_eq!(TextRepr::from_json(repr.to_string()).unwrap(), repr);
+
+		assert!(!TextRepr::is_valid_json(r#"["\q"]"#));
+		assert!(!TextRepr::is_valid_json(r#"["\u12"]"#));
+		assert!(!TextRepr::is_valid_json(r#"["a" "b"]"#));
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_96() {
+		use crate::json;
+
+		assert_eq!(json::from_str::<crate::NaturalProfile, Vec<Option<u8>>>("[1, null, 3]").unwrap(), vec![Some(1), None, Some(3)]);
+		assert_eq!(json::from_str::<crate::NaturalProfile, Vec<Option<u8>>>("[null, null]").unwrap(), vec![None, None]);
+		assert_eq!(json::from_str::<crate::NaturalProfile, Vec<Vec<Option<u8>>>>("[[null], [null, 2]]").unwrap(), vec![vec![None], vec![None, Some(2)]]);
+		assert_eq!(json::from_str::<crate::NaturalProfile, Option<Vec<u8>>>("null").unwrap(), None);
+		assert_eq!(json::to_string::<crate::NaturalProfile, _>(vec![None::<u8>, None]), "[null, null]");
+
+		let err = TestStruct::deserialize_json("{\"name\": null, \"id\": \"a\", \"age\": 1}".into()).unwrap_err();
+		assert_eq!(err.field.as_deref(), Some("name"));
+		let DeserializationErrorKind::Nested(inner) = err.kind else { panic!("{:?}", err) };
+		assert_eq!(inner.kind, DeserializationErrorKind::InvalidType { expected: "string", actual: "null" });
+		let err = json::from_str::<crate::NaturalProfile, Vec<u8>>("[1, null]").unwrap_err();
+		assert!(matches!(err.kind, DeserializationErrorKind::InvalidType { expected: "number", actual: "null" }));
+		let err = json::from_str::<crate::NaturalProfile, bool>("\"yes\"").unwrap_err();
+		assert!(matches!(err.kind, DeserializationErrorKind::InvalidType { expected: "boolean", actual: "string" }));
+
+		let mut arena = crate::text::arena::TextArena::new();
+		let mut doc = arena.parse_json("[null]").unwrap();
+		let err = Deserialize::<crate::NaturalProfile>::deserialize(&mut doc).map(|x: Vec<String>| x).unwrap_err();
+		assert_eq!(err.kind, DeserializationErrorKind::InvalidType { expected: "string", actual: "null" });
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_97() {
+		let repr = TextRepr::from_json("[1e-5, 1.2E+10, +3, -0, +2.5, -0.0, 7]".into()).unwrap();
+		let TextRepr::Array(values) = repr else { panic!("{:?}", repr) };
+		assert_eq!(values, [
+			TextRepr::Float(1e-5),
+			TextRepr::Float(1.2e10),
+			TextRepr::Integer(3),
+			TextRepr::Integer(0),
+			TextRepr::Float(2.5),
+			TextRepr::Float(0.0),
+			TextRepr::Integer(7)
+		]);
+		let TextRepr::Float(zero) = values[5] else { unreachable!() };
+		assert!(zero.is_sign_negative());
+		let TextRepr::Float(zero) = TextRepr::from_json(TextRepr::Float(-0.0).to_json()).unwrap() else { panic!() };
+		assert!(zero.is_sign_negative());
+		// An integer negative zero is still an integer
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, u16>("-0").unwrap(), 0);
+		let TextRepr::Table(table) = TextRepr::from_toml("a = -0".into()).unwrap() else { panic!() };
+		assert_eq!(table["a"], TextRepr::Integer(0));
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, f32>("2.5e-1").unwrap(), 0.25);
+
+		let err = TextRepr::from_json("{\"a\": 1e5x}".into()).unwrap_err();
+		assert_eq!(err.kind, DeserializationErrorKind::InvalidFormat { reason: "1e5x is not a string, number or boolean".into(), code: crate::ErrorCode::E010_InvalidFormat });
+		assert!(!TextRepr::is_valid_json("[-]"));
+
+		// Rust parses these as floats, but JSON has no infinity or NaN
+		for token in ["inf", "-inf", "NaN", "infinity", "1.", ".5", "1e"] {
+			let err = TextRepr::from_json(format!("[{}]", token)).unwrap_err();
+			assert_eq!(err.kind, DeserializationErrorKind::InvalidFormat { reason: format!("{} is not a string, number or boolean", token), code: crate::ErrorCode::E010_InvalidFormat });
+		}
+		let err = TextRepr::from_json("[1e400]".into()).unwrap_err();
+		assert_eq!(err.kind, DeserializationErrorKind::InvalidFormat { reason: "1e400 is out of the range of a float".into(), code: crate::ErrorCode::E010_InvalidFormat });
+		let repr = TextRepr::Array([TextRepr::Float(f64::INFINITY), TextRepr::Float(f64::NAN), TextRepr::Float(1.5)].into());
+		assert_eq!(repr.clone().to_json(), "[null, null, 1.5]");
+		assert_eq!(repr.to_string(), "[null, null, 1.5]");
+		assert_eq!(crate::json::to_string(f32::NEG_INFINITY), "null");
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_98() {
+		use std::collections::HashMap;
+
+		for json in [
+			"[{\"a\": {\"b\": 1}}]",
+			"[[[]]]",
+			"[{}, [], [{}]]",
+			"{\"a\": {}, \"b\": [[1, [2, {\"c\": [3, {\"d\": null}]}]], {\"e\": [[4], {\"f\": {}}]}]}"
+		] {
+			let repr = TextRepr::from_json(json.into()).unwrap();
+			assert_eq!(TextRepr::from_json(repr.clone().to_json()).unwrap(), repr, "{}", json);
+			assert_eq!(TextRepr::from_json(repr.to_string()).unwrap(), repr, "{}", json);
+		}
+		let repr = TextRepr::from_json("[{\"a\": {\"b\": 1}}, 2]".into()).unwrap();
+		assert_eq!(repr.to_string(), "[{\"a\": {\"b\": 1}}, 2]");
+
+		let groups = vec![vec![HashMap::from([("a".to_string(), vec![1u8, 2])])], vec![], vec![HashMap::new()]];
+		let json = crate::json::to_string(groups.clone());
+		assert_eq!(crate::json::from_str::<crate::NaturalProfile, Vec<Vec<HashMap<String, Vec<u8>>>>>(&json).unwrap(), groups);
+		assert_eq!(crate::json::to_string(HashMap::<String, u8>::new()), "{}");
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_99() {
+		use crate::json::JsonFormat;
+
+		let repr = TextRepr::from_json("{\"a\": {\"b\": [1, {\"c\": true}]}}".into()).unwrap();
+		assert_eq!(repr.clone().to_json(), "{\n\t\"a\": {\n\t\t\"b\": [1, {\n\t\t\t\"c\": true\n\t\t}]\n\t}\n}");
+		assert_eq!(repr.clone().to_json_with(&JsonFormat::compact()), "{\"a\":{\"b\":[1,{\"c\":true}]}}");
+		let format = JsonFormat { indent: "  ".into(), spacing: false, trailing_newline: true, compact: false };
+		assert_eq!(repr.clone().to_json_with(&format), "{\n  \"a\":{\n    \"b\":[1,{\n      \"c\":true\n    }]\n  }\n}\n");
+		let format = JsonFormat { compact: true, ..Default::default() };
+		assert_eq!(repr.clone().to_json_with(&format), "{\"a\": {\"b\": [1, {\"c\": true}]}}");
+		assert_eq!(TextRepr::from_json(repr.clone().to_json_with(&JsonFormat::compact())).unwrap(), repr);
+
+		let test = TestStruct { name: "a".into(), id: "b".into(), age: 3 };
+		let json = test.clone().serialize_json_compact();
+		assert!(!json.contains([' ', '\n', '\t']) && json.contains("\"age\":3"));
+		assert_eq!(TestStruct::deserialize_json(json).unwrap().age, 3);
+		assert_eq!(JSONSerialize::<crate::NaturalProfile>::serialize_json_compact(vec![1u8, 2]), "[1,2]");
+	}
+
+	#[test]
+	#[cfg(feature = "text")]
+	fn test_serde_100() {
+		use crate::text::{DuplicateKeys, ParseOptions};
+
+		let json = "{\"a\": 1, \"b\": {\"c\": 2, \"c\": 3}, \"a\": 4}";
+		let repr = TextRepr::from_json(json.into()).unwrap();
+		assert_eq!(repr, TextRepr::from_json("{\"a\": 4, \"b\": {\"c\": 3}}".into()).unwrap());
+
+		let first = ParseOptions { duplicate_keys: DuplicateKeys::FirstWins };
+		let repr = TextRepr::from_json_with(json.into(), &first).unwrap();
+		assert_eq!(repr, TextRepr::from_json("{\"a\": 1, \"b\": {\"c\": 2}}".into()).unwrap());
+
+		let strict = ParseOptions { duplicate_keys: DuplicateKeys::Error };
+		let err = TextRepr::from_json_with(json.into(), &strict).unwrap_err();
+		assert_eq!(err.kind, DeserializationErrorKind::DuplicateKey { key: "c".into() });
+		assert_eq!(err.code(), crate::ErrorCode::E020_DuplicateKey);
+		assert!(TextRepr::from_json_with("[{\"a\": 1}, {\"a\": 2}]".into(), &strict).is_ok());
+
+		let toml = "name = \"a\"\n[server]\nport = 80\n[server]\nport = 8080\n";
+		assert_eq!(crate::toml::from_str::<ReadableProfile, TestStruct6>("port = 1\nhost = \"a\"\ntags = []\nport = 2").unwrap().port, 2);
+		assert_eq!(crate::toml::from_str_with::<ReadableProfile, TestStruct6>("port = 1\nhost = \"a\"\ntags = []\nport = 2", &first).unwrap().port, 1);
+		let err = TextRepr::from_toml_with(toml.into(), &strict).unwrap_err();
+		assert_eq!(err.kind, DeserializationErrorKind::DuplicateKey { key: "server.port".into() });
+		assert!(TextRepr::from_toml_with("[server]\nport = 80\n[client]\nport = 8080\n".into(), &strict).is_ok());
+		assert_eq!(crate::json::from_str_with::<crate::NaturalProfile, Vec<u8>>("[1, 2]", &strict).unwrap(), vec![1, 2]);
+
+		// A key that holds a value cannot also be a table, whichever duplicate wins
+		let last = ParseOptions { duplicate_keys: DuplicateKeys::LastWins };
+		for options in [&first, &last] {
+			let err = TextRepr::from_toml_with("a = 1\n[a]\nb = 2".into(), options).unwrap_err();
+			assert_eq!(err.code(), crate::ErrorCode::E010_InvalidFormat);
+			assert!(TextRepr::from_toml_with("[a]\nb = 1\n[a.b]\nc = 2".into(), options).is_err());
+		}
+		let err = TextRepr::from_toml_with("a = 1\n[a]\nb = 2".into(), &strict).unwrap_err();
+		assert_eq!(err.kind, DeserializationErrorKind::DuplicateKey { key: "a".into() });
+		let err = TextRepr::from_toml_with("[a]\nb = 1\n[a.b]\nc = 2".into(), &strict).unwrap_err();
+		assert_eq!(err.kind, DeserializationErrorKind::DuplicateKey { key: "a.b".into() });
+		assert!(TextRepr::from_mlist("[a]\n1\n[a.b]\n2\n".into()).is_err());
+		assert!(TextRepr::from_mlist("1\n[a]\n2\n".into()).is_err());
+	}
 }
@@ -0,0 +1,89 @@
+//! Vectors, quaternions and matrices from glam and nalgebra.
+//! Each is a sequence of its components, so text formats write an array and bin packs the components
+//! back to back. Matrices are flattened in column major order
+use crate::{DeserializationError, DeserializationErrorKind, Deserialize, Serializer};
+#[cfg(feature = "nalgebra")]
+use crate::Serialize;
+
+
+/// Deserialize exactly len components
+fn deserialize_components<P, T: Serializer, V: Deserialize<P>>(data: &mut T, len: usize) -> Result<Vec<V>, DeserializationError> {
+	let mut out = Vec::with_capacity(len);
+	for _ in 0..len {
+		match data.deserialize() {
+			Ok(x) => out.push(x),
+			Err(e) if e.kind == DeserializationErrorKind::UnexpectedEOF => return Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidValue {
+				reason: format!("expected {} components, but found {}", len, out.len())
+			})),
+			Err(e) => return Err(e)
+		}
+	}
+	Ok(out)
+}
+
+
+#[cfg(feature = "glam")]
+macro_rules! glam_type {
+    ($type: ty, $len: literal, $to: ident, $from: ident) => {
+impl crate::Serialize for $type {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_iter(self.$to());
+	}
+}
+impl Deserialize for $type {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		deserialize_components::<crate::NaturalProfile, _, _>(data, $len).map(|x| <$type>::$from(&x))
+	}
+}
+	};
+}
+
+#[cfg(feature = "glam")]
+mod glam_impls {
+	use super::*;
+
+	glam_type!(glam::Vec2, 2, to_array, from_slice);
+	glam_type!(glam::Vec3, 3, to_array, from_slice);
+	glam_type!(glam::Vec3A, 3, to_array, from_slice);
+	glam_type!(glam::Vec4, 4, to_array, from_slice);
+	glam_type!(glam::DVec2, 2, to_array, from_slice);
+	glam_type!(glam::DVec3, 3, to_array, from_slice);
+	glam_type!(glam::DVec4, 4, to_array, from_slice);
+	glam_type!(glam::IVec2, 2, to_array, from_slice);
+	glam_type!(glam::IVec3, 3, to_array, from_slice);
+	glam_type!(glam::IVec4, 4, to_array, from_slice);
+	glam_type!(glam::UVec2, 2, to_array, from_slice);
+	glam_type!(glam::UVec3, 3, to_array, from_slice);
+	glam_type!(glam::UVec4, 4, to_array, from_slice);
+	glam_type!(glam::Quat, 4, to_array, from_slice);
+	glam_type!(glam::DQuat, 4, to_array, from_slice);
+	glam_type!(glam::Mat2, 4, to_cols_array, from_cols_slice);
+	glam_type!(glam::Mat3, 9, to_cols_array, from_cols_slice);
+	glam_type!(glam::Mat4, 16, to_cols_array, from_cols_slice);
+	glam_type!(glam::DMat2, 4, to_cols_array, from_cols_slice);
+	glam_type!(glam::DMat3, 9, to_cols_array, from_cols_slice);
+	glam_type!(glam::DMat4, 16, to_cols_array, from_cols_slice);
+}
+
+
+#[cfg(feature = "nalgebra")]
+impl<P, V, const R: usize, const C: usize> Serialize<P> for nalgebra::SMatrix<V, R, C>
+	where
+		V: nalgebra::Scalar + Serialize<P>
+{
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_iter(self.iter().cloned());
+	}
+}
+
+
+#[cfg(feature = "nalgebra")]
+impl<P, V, const R: usize, const C: usize> Deserialize<P> for nalgebra::SMatrix<V, R, C>
+	where
+		V: nalgebra::Scalar + Deserialize<P>
+{
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		let out: Vec<V> = deserialize_components(data, R * C)?;
+		Ok(Self::from_iterator(out))
+	}
+}
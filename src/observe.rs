@@ -0,0 +1,93 @@
+//! Instrumentation of serialization. An Observer given to `observe` is told about every keyed field
+//! the backends serialize, along with how deep it is and how many bytes it took up, so the parts of a
+//! large struct that dominate the size of its payload can be found without changing its impls.
+//!
+//! Binary reports exact sizes, including the key. Text formats report the length of the value as compact JSON
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+
+thread_local! {
+	static OBSERVER: RefCell<Option<Rc<RefCell<dyn Observer>>>> = const { RefCell::new(None) };
+	static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+
+pub trait Observer {
+	/// Called before the value of a field is serialized.
+	/// The depth is the number of fields the field is nested in
+	fn field_entered(&mut self, key: &str, depth: usize) {
+		let _ = (key, depth);
+	}
+	/// Called after the value of a field is serialized, with the number of bytes written for it
+	fn field_exited(&mut self, key: &str, depth: usize, bytes: usize) {
+		let _ = (key, depth, bytes);
+	}
+}
+
+
+/// An Observer that records the size of every field under its dotted path
+#[derive(Debug, Default)]
+pub struct FieldSizes {
+	path: Vec<String>,
+	/// The dotted path and size in bytes of every field, in the order the fields finished serializing
+	pub sizes: Vec<(String, usize)>
+}
+
+
+impl FieldSizes {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The recorded fields, largest first
+	pub fn largest(&self) -> Vec<(&str, usize)> {
+		let mut out: Vec<_> = self.sizes.iter().map(|(path, size)| (path.as_str(), *size)).collect();
+		out.sort_by_key(|x| std::cmp::Reverse(x.1));
+		out
+	}
+}
+
+
+impl Observer for FieldSizes {
+	fn field_entered(&mut self, key: &str, _depth: usize) {
+		self.path.push(key.into());
+	}
+
+	fn field_exited(&mut self, _key: &str, _depth: usize, bytes: usize) {
+		self.sizes.push((self.path.join("."), bytes));
+		self.path.pop();
+	}
+}
+
+
+/// Run f with the given observer watching any serialization it does, returning the output of f and the observer
+pub fn observe<O: Observer + 'static, R, F: FnOnce() -> R>(observer: O, f: F) -> (R, O) {
+	let observer = Rc::new(RefCell::new(observer));
+	let previous = OBSERVER.with(|x| x.replace(Some(observer.clone())));
+	let previous_depth = DEPTH.with(|x| x.replace(0));
+	let out = f();
+	OBSERVER.with(|x| x.replace(previous));
+	DEPTH.with(|x| x.set(previous_depth));
+	let observer = Rc::try_unwrap(observer).ok().expect("Observer was still in use after observing. Please report this to the developer.");
+	(out, observer.into_inner())
+}
+
+
+/// Used by the backends to serialize a field. serialize returns the number of bytes it wrote,
+/// and is told whether anything is observing, so sizes that are costly to measure can be skipped
+#[cfg_attr(not(any(feature = "text", feature = "bin")), allow(dead_code))]
+pub(crate) fn field<F: FnOnce(bool) -> usize>(key: &str, serialize: F) {
+	let observer = match OBSERVER.with(|x| x.borrow().clone()) {
+		Some(x) => x,
+		None => {
+			serialize(false);
+			return
+		}
+	};
+	let depth = DEPTH.with(|x| x.replace(x.get() + 1));
+	observer.borrow_mut().field_entered(key, depth);
+	let bytes = serialize(true);
+	DEPTH.with(|x| x.set(depth));
+	observer.borrow_mut().field_exited(key, depth, bytes);
+}
@@ -0,0 +1,245 @@
+//! The varint layout of postcard, so values can be exchanged with devices that already run it.
+//!
+//! Keyed values are written as struct fields, in order and without their keys, so they must be read back
+//! in the same order, as impl_key_serde does. u8 and i8 are single bytes, wider integers are varints,
+//! with signed ones zigzag encoded, and floats are little endian. Strings, bytes and sequences are prefixed
+//! with their length as a varint, and variants with their index
+use std::borrow::Borrow;
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+#[cfg(feature = "text")]
+use crate::text::TextRepr;
+use crate::{DeserializationError, DeserializationErrorKind, DeserializationResult, Deserialize, DeserializeSeed, Number, NumberType, PrimitiveSerializer, Serialize, Serializer};
+use crate::primitives::{is_float, is_signed};
+
+
+type Binary = VecDeque<u8>;
+
+
+fn write_varint(out: &mut Binary, mut value: u64) {
+	while value >= 0x80 {
+		out.push_back(value as u8 | 0x80);
+		value >>= 7;
+	}
+	out.push_back(value as u8);
+}
+
+
+fn read_varint(bytes: &mut Binary) -> Result<u64, DeserializationError> {
+	let mut value = 0u64;
+	for shift in (0..64).step_by(7) {
+		let byte = bytes.pop_front().ok_or(DeserializationError::EOF)?;
+		value |= ((byte & 0x7F) as u64) << shift;
+		if byte & 0x80 == 0 {
+			return Ok(value)
+		}
+	}
+	Err(DeserializationError::invalid_format("varint is longer than 10 bytes"))
+}
+
+
+fn read_len(bytes: &mut Binary) -> Result<usize, DeserializationError> {
+	let len = read_varint(bytes)? as usize;
+	if len > bytes.len() {
+		return Err(DeserializationError::EOF)
+	}
+	Ok(len)
+}
+
+
+/// Nest an UnexpectedEOF from a sequence item, so it is not mistaken for the end of the sequence
+fn cut_short(e: DeserializationError) -> DeserializationError {
+	match e.kind {
+		DeserializationErrorKind::UnexpectedEOF => e.nest(),
+		_ => e
+	}
+}
+
+
+/// A view of the bytes of a value. Sequences read their length on the first item, so `items` is None until then
+#[derive(Debug)]
+struct Postcard<'a> {
+	bytes: &'a mut Binary,
+	items: Option<usize>
+}
+
+
+impl<'a> Postcard<'a> {
+	fn new(bytes: &'a mut Binary) -> Self {
+		Self { bytes, items: None }
+	}
+
+	fn child(&mut self) -> Postcard<'_> {
+		Postcard::new(self.bytes)
+	}
+
+	/// Count off the next item of a sequence, returning UnexpectedEOF once there are none left
+	fn next_item(&mut self) -> Result<(), DeserializationError> {
+		let items = match self.items {
+			Some(x) => x,
+			None => read_varint(self.bytes)? as usize
+		};
+		if items == 0 {
+			self.items = Some(0);
+			return Err(DeserializationError::EOF)
+		}
+		self.items = Some(items - 1);
+		Ok(())
+	}
+}
+
+
+impl PrimitiveSerializer for Postcard<'_> {
+	fn serialize_bool(&mut self, boolean: bool) {
+		self.bytes.push_back(boolean as u8);
+	}
+
+	fn deserialize_bool(&mut self) -> Result<bool, DeserializationError> {
+		match self.bytes.pop_front().ok_or(DeserializationError::EOF)? {
+			0 => Ok(false),
+			1 => Ok(true),
+			x => Err(DeserializationError::new_kind(DeserializationErrorKind::NoMatch { actual: x.to_string() }))
+		}
+	}
+
+	fn serialize_num<T: NumberType>(&mut self, num: T) {
+		match num.to_number() {
+			Number::Unsigned(x) if std::mem::size_of::<T>() == 1 => self.bytes.push_back(x as u8),
+			Number::Signed(x) if std::mem::size_of::<T>() == 1 => self.bytes.push_back(x as u8),
+			Number::Unsigned(x) => write_varint(self.bytes, x),
+			Number::Signed(x) => write_varint(self.bytes, ((x << 1) ^ (x >> 63)) as u64),
+			Number::F32(x) => self.bytes.extend(x.to_le_bytes()),
+			Number::F64(x) => self.bytes.extend(x.to_le_bytes())
+		}
+	}
+
+	fn deserialize_num<T: NumberType>(&mut self) -> Result<T, DeserializationError> {
+		let number = if is_float::<T>() {
+			match std::mem::size_of::<T>() {
+				// Half precision floats are written as f32s
+				..=4 => Number::F32(f32::from_le_bytes(crate::bin::split_first(self.bytes).no_field()?)),
+				_ => Number::F64(f64::from_le_bytes(crate::bin::split_first(self.bytes).no_field()?))
+			}
+		} else if std::mem::size_of::<T>() == 1 {
+			let byte = self.bytes.pop_front().ok_or(DeserializationError::EOF)?;
+			if is_signed::<T>() { Number::Signed(byte as i8 as i64) } else { Number::Unsigned(byte as u64) }
+		} else {
+			let x = read_varint(self.bytes)?;
+			if is_signed::<T>() { Number::Signed((x >> 1) as i64 ^ -((x & 1) as i64)) } else { Number::Unsigned(x) }
+		};
+		T::from_number(number).ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: "number does not fit its type".into() }))
+	}
+
+	fn serialize_string<T: Into<String>>(&mut self, string: T) {
+		self.serialize_bytes(string.into().into_bytes());
+	}
+
+	fn deserialize_string(&mut self) -> Result<String, DeserializationError> {
+		String::from_utf8(self.deserialize_bytes()?).map_err(DeserializationErrorKind::from).no_field()
+	}
+
+	fn serialize_bytes<T: Into<VecDeque<u8>>>(&mut self, bytes: T) {
+		let mut bytes = bytes.into();
+		write_varint(self.bytes, bytes.len() as u64);
+		self.bytes.append(&mut bytes);
+	}
+
+	fn deserialize_bytes<T: FromIterator<u8>>(&mut self) -> Result<T, DeserializationError> {
+		let len = read_len(self.bytes)?;
+		Ok(self.bytes.drain(..len).collect())
+	}
+}
+
+
+impl Serializer for Postcard<'_> {
+	fn serialize<P, T: Serialize<P>>(&mut self, item: T) {
+		item.serialize(&mut self.child());
+	}
+
+	/// Prefixes the items with their count
+	fn serialize_iter<P, T: Serialize<P>, I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		let mut items = Binary::new();
+		let mut count = 0;
+		for item in iter {
+			item.serialize(&mut Postcard::new(&mut items));
+			count += 1;
+		}
+		write_varint(self.bytes, count);
+		self.bytes.append(&mut items);
+	}
+
+	fn serialize_key<P, T: Serialize<P>, K: Borrow<str>>(&mut self, _key: K, item: T) {
+		item.serialize(&mut self.child());
+	}
+
+	fn deserialize<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError> {
+		self.next_item()?;
+		T::deserialize(&mut self.child()).map_err(cut_short)
+	}
+
+	fn deserialize_key_internal<P, T: Deserialize<P>>(&mut self, key: &str) -> Result<T, DeserializationError> {
+		T::deserialize(&mut self.child()).map_err(|e| e.nest().set_field(key))
+	}
+
+	fn deserialize_seed<P, S: DeserializeSeed<P>>(&mut self, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		self.next_item()?;
+		seed.deserialize(&mut self.child()).map_err(cut_short)
+	}
+
+	fn deserialize_key_seed<P, S: DeserializeSeed<P>, K: Borrow<str>>(&mut self, key: K, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		seed.deserialize(&mut self.child()).map_err(|e| e.nest().set_field(key.borrow()))
+	}
+
+	/// Tuples have a fixed length, so their elements are not prefixed with a count
+	fn deserialize_tuple<R, F: FnOnce(&mut Self) -> Result<R, DeserializationError>>(&mut self, f: F) -> Result<R, DeserializationError> {
+		let items = self.items.replace(usize::MAX);
+		let out = f(self);
+		self.items = items;
+		out
+	}
+
+	fn try_get_key<K: FromStr>(&mut self) -> Option<K> {
+		None
+	}
+
+	fn is_human_readable(&self) -> bool {
+		false
+	}
+
+	fn serialize_variant<P, T: Serialize<P>>(&mut self, _name: &str, index: u8, item: T) {
+		write_varint(self.bytes, index as u64);
+		item.serialize(&mut self.child());
+	}
+
+	fn deserialize_variant_index(&mut self, variants: &[(&str, u8)]) -> Result<u8, DeserializationError> {
+		let index = read_varint(self.bytes)?;
+		variants.iter()
+			.find(|x| x.1 as u64 == index)
+			.map(|x| x.1)
+			.ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::NoMatch { actual: index.to_string() }))
+	}
+
+	fn deserialize_variant<P, T: Deserialize<P>>(&mut self, _name: &str) -> Result<T, DeserializationError> {
+		T::deserialize(&mut self.child())
+	}
+
+	#[cfg(feature = "text")]
+	fn deserialize_text(&mut self) -> Result<TextRepr, DeserializationError> {
+		Err(DeserializationError::invalid_format("postcard is not self describing"))
+	}
+}
+
+
+/// Serialize any value in the postcard layout
+pub fn to_vec<P, T: Serialize<P>>(value: T) -> Vec<u8> {
+	let mut bytes = Binary::new();
+	value.serialize(&mut Postcard::new(&mut bytes));
+	bytes.into()
+}
+
+
+/// Deserialize any value from the postcard layout
+pub fn from_slice<P, T: Deserialize<P>>(data: &[u8]) -> Result<T, DeserializationError> {
+	T::deserialize(&mut Postcard::new(&mut Binary::from(data.to_vec())))
+}
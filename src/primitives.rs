@@ -1,14 +1,31 @@
 #[cfg(feature = "bin")]
 use std::collections::VecDeque;
+use std::ffi::{CString, OsString};
+use std::num::{NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize, Saturating, Wrapping};
+use std::ops::{Range, RangeInclusive, RangeTo};
 
+#[cfg(feature = "text")]
 use crate::text::TextRepr;
 
-use super::{DeserializationError, Deserialize, Serialize, Serializer};
+use super::{DeserializationError, DeserializationErrorKind, Deserialize, EfficientProfile, ReadableProfile, Serialize, Serializer};
 #[cfg(feature = "bin")]
-use super::{bin, DeserializationErrorKind};
+use super::bin;
+
+/// A number widened to the largest type of its kind, for formats that encode each kind differently
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Number {
+	Unsigned(u64),
+	Signed(i64),
+	F32(f32),
+	F64(f64)
+}
+
 
 /// Trait for types that are either integers or floats
 pub trait NumberType: Sized {
+	fn to_number(self) -> Number;
+	/// Convert from a Number, returning None if the value does not fit without changing it
+	fn from_number(num: Number) -> Option<Self>;
 	#[cfg(feature = "text")]
 	fn to_text(self) -> TextRepr;
 	#[cfg(feature = "text")]
@@ -22,6 +39,20 @@ pub trait NumberType: Sized {
 }
 
 
+/// Whether values of T are signed, found by whether it can hold -1
+#[cfg(feature = "bin")]
+pub(crate) fn is_signed<T: NumberType>() -> bool {
+	T::from_number(Number::Signed(-1)).is_some()
+}
+
+
+/// Whether T is a float, found by whether it can hold a half
+#[cfg(feature = "bin")]
+pub(crate) fn is_float<T: NumberType>() -> bool {
+	T::from_number(Number::F64(0.5)).is_some()
+}
+
+
 macro_rules! impl_serde_number {
     ($type: ty) => {
 impl Serialize for $type {
@@ -40,15 +71,29 @@ impl Deserialize for $type {
 
 /// Implement serialize and deserialize for integer types
 macro_rules! serial_int {
-    ($type: ty) => {
+    ($type: ty, $kind: ident, $wide: ty) => {
 impl NumberType for $type {
+	fn to_number(self) -> Number {
+		Number::$kind(self as $wide)
+	}
+	fn from_number(num: Number) -> Option<Self> {
+		match num {
+			Number::Unsigned(x) => <$type>::try_from(x).ok(),
+			Number::Signed(x) => <$type>::try_from(x).ok(),
+			Number::F32(_) | Number::F64(_) => None
+		}
+	}
+	/// Integers above i64::MAX do not fit in TextRepr::Integer, so they are written as a string of their digits
 	#[cfg(feature = "text")]
 	fn to_text(self) -> TextRepr {
-		TextRepr::Integer(self as i64)
+		match i64::try_from(self) {
+			Ok(x) => TextRepr::Integer(x),
+			Err(_) => TextRepr::String(self.to_string())
+		}
 	}
 	#[cfg(feature = "text")]
 	fn from_i64(int: i64) -> Option<Self> {
-		Some(int as $type)
+		<$type>::try_from(int).ok()
 	}
 	#[cfg(feature = "text")]
 	fn from_f64(_float: f64) -> Option<Self> {
@@ -67,19 +112,156 @@ impl_serde_number!($type);
 	};
 }
 
-serial_int!(u8);
-serial_int!(u16);
-serial_int!(u32);
-serial_int!(u64);
-serial_int!(usize);
-serial_int!(i8);
-serial_int!(i16);
-serial_int!(i32);
-serial_int!(i64);
-serial_int!(isize);
+serial_int!(u8, Unsigned, u64);
+serial_int!(u16, Unsigned, u64);
+serial_int!(u32, Unsigned, u64);
+serial_int!(u64, Unsigned, u64);
+serial_int!(usize, Unsigned, u64);
+serial_int!(i8, Signed, i64);
+serial_int!(i16, Signed, i64);
+serial_int!(i32, Signed, i64);
+serial_int!(i64, Signed, i64);
+serial_int!(isize, Signed, i64);
+
+
+/// Implement serialize and deserialize for non-zero integers, which are serialized as the integer they wrap
+macro_rules! serial_non_zero {
+    ($type: ty, $int: ty) => {
+impl Serialize for $type {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_num(self.get());
+	}
+}
+impl Deserialize for $type {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		<$type>::new(data.deserialize_num::<$int>()?)
+			.ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: "expected a non-zero integer, found 0".into() }))
+	}
+}
+	};
+}
+
+serial_non_zero!(NonZeroU8, u8);
+serial_non_zero!(NonZeroU16, u16);
+serial_non_zero!(NonZeroU32, u32);
+serial_non_zero!(NonZeroU64, u64);
+serial_non_zero!(NonZeroUsize, usize);
+serial_non_zero!(NonZeroI8, i8);
+serial_non_zero!(NonZeroI16, i16);
+serial_non_zero!(NonZeroI32, i32);
+serial_non_zero!(NonZeroI64, i64);
+serial_non_zero!(NonZeroIsize, isize);
+
+
+/// Implement serialize and deserialize for arithmetic wrappers, which are serialized as the number they wrap
+macro_rules! serial_wrapper {
+    ($type: ident) => {
+impl<P, N: NumberType + Serialize<P>> Serialize<P> for $type<N> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		self.0.serialize(data);
+	}
+}
+impl<P, N: NumberType + Deserialize<P>> Deserialize<P> for $type<N> {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		N::deserialize(data).map($type)
+	}
+}
+	};
+}
+
+serial_wrapper!(Wrapping);
+serial_wrapper!(Saturating);
+
+
+/// Serialize the bounds of a range as a table with start and end keys, or as a tuple of the numbers in binary formats
+fn serialize_bounds<T: Serializer, N: NumberType + Serialize>(data: &mut T, start: Option<N>, end: N) {
+	if data.is_human_readable() {
+		if let Some(start) = start {
+			data.serialize_key("start", start);
+		}
+		data.serialize_key("end", end);
+	} else {
+		data.serialize_tuple(|data| {
+			if let Some(start) = start {
+				data.serialize(start);
+			}
+			data.serialize(end);
+		});
+	}
+}
+
+
+/// Deserialize the bounds of a range that were serialized with serialize_bounds
+fn deserialize_bounds<T: Serializer, N: NumberType + Deserialize>(data: &mut T, has_start: bool) -> Result<(Option<N>, N), DeserializationError> {
+	if data.is_human_readable() {
+		let start = if has_start { Some(data.deserialize_key("start")?) } else { None };
+		Ok((start, data.deserialize_key("end")?))
+	} else {
+		data.deserialize_tuple(|data| {
+			let start = if has_start { Some(data.deserialize()?) } else { None };
+			Ok((start, data.deserialize()?))
+		})
+	}
+}
+
+
+impl<N: NumberType + Serialize> Serialize for Range<N> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		serialize_bounds(data, Some(self.start), self.end);
+	}
+}
+
+
+impl<N: NumberType + Deserialize> Deserialize for Range<N> {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		deserialize_bounds(data, true).map(|(start, end)| start.unwrap()..end)
+	}
+}
+
+
+impl<N: NumberType + Serialize> Serialize for RangeInclusive<N> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		let (start, end) = self.into_inner();
+		serialize_bounds(data, Some(start), end);
+	}
+}
+
+
+impl<N: NumberType + Deserialize> Deserialize for RangeInclusive<N> {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		deserialize_bounds(data, true).map(|(start, end)| start.unwrap()..=end)
+	}
+}
+
+
+impl<N: NumberType + Serialize> Serialize for RangeTo<N> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		serialize_bounds(data, None, self.end);
+	}
+}
+
+
+impl<N: NumberType + Deserialize> Deserialize for RangeTo<N> {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		deserialize_bounds(data, false).map(|(_, end)| ..end)
+	}
+}
 
 
 impl NumberType for f32 {
+	fn to_number(self) -> Number {
+		Number::F32(self)
+	}
+
+	fn from_number(num: Number) -> Option<Self> {
+		match num {
+			Number::Unsigned(x) => Some(x as Self),
+			Number::Signed(x) => Some(x as Self),
+			Number::F32(x) => Some(x as Self),
+			Number::F64(x) => Some(x as Self)
+		}
+	}
+
 	#[cfg(feature = "text")]
 	fn to_text(self) -> TextRepr {
 		TextRepr::Float(self as f64)
@@ -97,7 +279,12 @@ impl NumberType for f32 {
 
 	#[cfg(feature = "text")]
 	fn from_i64(int: i64) -> Option<Self> {
-		Some(int as Self)
+		let out = int as Self;
+		// Integers past the precision of the float are rounded
+		if out as i128 != int as i128 {
+			crate::warning::warn(None, crate::warning::WarningKind::Coerced { from: int.to_string(), to: out.to_string() });
+		}
+		Some(out)
 	}
 
 	#[cfg(feature = "text")]
@@ -108,6 +295,19 @@ impl NumberType for f32 {
 
 
 impl NumberType for f64 {
+	fn to_number(self) -> Number {
+		Number::F64(self)
+	}
+
+	fn from_number(num: Number) -> Option<Self> {
+		match num {
+			Number::Unsigned(x) => Some(x as Self),
+			Number::Signed(x) => Some(x as Self),
+			Number::F32(x) => Some(x as Self),
+			Number::F64(x) => Some(x as Self)
+		}
+	}
+
 	#[cfg(feature = "text")]
 	fn to_text(self) -> TextRepr {
 		TextRepr::Float(self)
@@ -125,7 +325,12 @@ impl NumberType for f64 {
 
 	#[cfg(feature = "text")]
 	fn from_i64(int: i64) -> Option<Self> {
-		Some(int as Self)
+		let out = int as Self;
+		// Integers past the precision of the float are rounded
+		if out as i128 != int as i128 {
+			crate::warning::warn(None, crate::warning::WarningKind::Coerced { from: int.to_string(), to: out.to_string() });
+		}
+		Some(out)
 	}
 
 	#[cfg(feature = "text")]
@@ -137,6 +342,84 @@ impl NumberType for f64 {
 impl_serde_number!(f32);
 impl_serde_number!(f64);
 
+
+/// Implement NumberType for half precision floats, which are 2 bytes in bin and go through f32 elsewhere
+#[cfg(feature = "half")]
+macro_rules! serial_half {
+    ($type: ty) => {
+impl NumberType for $type {
+	fn to_number(self) -> Number {
+		Number::F32(self.to_f32())
+	}
+	fn from_number(num: Number) -> Option<Self> {
+		Some(match num {
+			Number::Unsigned(x) => <$type>::from_f32(x as f32),
+			Number::Signed(x) => <$type>::from_f32(x as f32),
+			Number::F32(x) => <$type>::from_f32(x),
+			Number::F64(x) => <$type>::from_f64(x)
+		})
+	}
+	#[cfg(feature = "text")]
+	fn to_text(self) -> TextRepr {
+		TextRepr::Float(self.to_f64())
+	}
+	#[cfg(feature = "text")]
+	fn from_i64(int: i64) -> Option<Self> {
+		Some(<$type>::from_f32(int as f32))
+	}
+	#[cfg(feature = "text")]
+	fn from_f64(float: f64) -> Option<Self> {
+		Some(<$type>::from_f64(float))
+	}
+	#[cfg(feature = "bin")]
+	fn from_bin(bin: &mut VecDeque<u8>) -> Result<Self, DeserializationErrorKind> {
+		Ok(Self::from_be_bytes(bin::split_first(bin)?))
+	}
+	#[cfg(feature = "bin")]
+	fn to_bin(self) -> VecDeque<u8> {
+		self.to_be_bytes().to_vec().into()
+	}
+}
+impl_serde_number!($type);
+	};
+}
+
+#[cfg(feature = "half")]
+serial_half!(half::f16);
+#[cfg(feature = "half")]
+serial_half!(half::bf16);
+
+
+
+/// Written as a string in human readable formats, so that no precision is lost to floats,
+/// and as its 16 byte representation in other formats
+#[cfg(feature = "rust_decimal")]
+impl Serialize for rust_decimal::Decimal {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		if data.is_human_readable() {
+			data.serialize_string(self.to_string());
+		} else {
+			data.serialize_bytes(rust_decimal::Decimal::serialize(&self).to_vec());
+		}
+	}
+}
+
+
+#[cfg(feature = "rust_decimal")]
+impl Deserialize for rust_decimal::Decimal {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		if data.is_human_readable() {
+			let string = data.deserialize_string()?;
+			string.parse().map_err(|e| DeserializationError::new_kind(DeserializationErrorKind::from_str_err(e)))
+		} else {
+			let bytes: Vec<u8> = data.deserialize_bytes()?;
+			let bytes: [u8; 16] = bytes.try_into()
+				.map_err(|x: Vec<u8>| DeserializationError::new_kind(DeserializationErrorKind::WrongLength { expected: 16, actual: x.len() }))?;
+			Ok(rust_decimal::Decimal::deserialize(bytes))
+		}
+	}
+}
+
 /// Implement Serialize for strings that can be converted to a String
 macro_rules! serial_string {
     ($type: ty) => {
@@ -173,6 +456,135 @@ impl Deserialize for $type {
 from_string!(std::path::PathBuf);
 
 
+/// Paths are stored as strings, so a path that is not valid UTF-8 is converted lossily
+impl Serialize for std::path::PathBuf {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_string(self.to_string_lossy());
+	}
+}
+
+
+impl Serialize for &std::path::Path {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_string(self.to_string_lossy());
+	}
+}
+
+
+/// The bytes of an OsString. Off unix, where the encoding is platform specific, they are converted lossily to UTF-8
+fn os_string_bytes(string: OsString) -> Vec<u8> {
+	#[cfg(unix)]
+	return std::os::unix::ffi::OsStringExt::into_vec(string);
+	#[cfg(not(unix))]
+	return string.to_string_lossy().into_owned().into_bytes();
+}
+
+
+fn os_string_from_bytes(bytes: Vec<u8>) -> OsString {
+	#[cfg(unix)]
+	return std::os::unix::ffi::OsStringExt::from_vec(bytes);
+	#[cfg(not(unix))]
+	return String::from_utf8_lossy(&bytes).into_owned().into();
+}
+
+
+impl Serialize<ReadableProfile> for OsString {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_string(self.to_string_lossy());
+	}
+}
+
+
+impl Deserialize<ReadableProfile> for OsString {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		data.deserialize_string().map(OsString::from)
+	}
+}
+
+
+/// The bytes are stored as they are, so strings that are not UTF-8 round trip
+impl Serialize<EfficientProfile> for OsString {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_bytes(os_string_bytes(self));
+	}
+}
+
+
+impl Deserialize<EfficientProfile> for OsString {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		data.deserialize_bytes().map(os_string_from_bytes)
+	}
+}
+
+
+fn c_string_from_bytes(bytes: Vec<u8>) -> Result<CString, DeserializationError> {
+	CString::new(bytes).map_err(|e| DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: format!("nul byte found at position {}", e.nul_position()) }))
+}
+
+
+impl Serialize<ReadableProfile> for CString {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_string(self.to_string_lossy());
+	}
+}
+
+
+impl Deserialize<ReadableProfile> for CString {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		c_string_from_bytes(data.deserialize_string()?.into_bytes())
+	}
+}
+
+
+/// The bytes are stored without the nul terminator
+impl Serialize<EfficientProfile> for CString {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_bytes(self.into_bytes());
+	}
+}
+
+
+impl Deserialize<EfficientProfile> for CString {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		c_string_from_bytes(data.deserialize_bytes()?)
+	}
+}
+
+
+impl Serialize for char {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_string(self);
+	}
+}
+
+
+impl Deserialize for char {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		let string = data.deserialize_string()?;
+		let mut chars = string.chars();
+		match (chars.next(), chars.next()) {
+			(Some(x), None) => Ok(x),
+			_ => Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: format!("{:?} is not a single character", string) }))
+		}
+	}
+}
+
+
+/// Serialized as a tuple with no elements
+impl Serialize for () {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_tuple(|_| {});
+	}
+}
+
+
+impl Deserialize for () {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		data.deserialize_tuple(|_| Ok(()))
+	}
+}
+
+
 impl Serialize for bool {
 	fn serialize<T: Serializer>(self, data: &mut T) {
 		data.serialize_bool(self);
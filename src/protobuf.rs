@@ -0,0 +1,349 @@
+//! The protobuf wire format, so simple messages can be exchanged with protobuf consumers without protoc.
+//!
+//! A key that is a number is used as the field number as is. Any other key is numbered one after the field before it,
+//! so the fields of impl_key_serde are numbered 1, 2, 3... in the order they are listed.
+//! Unsigned integers and bools are varints, signed integers are sign extended varints as in int32 and int64,
+//! f32 and f64 are fixed32 and fixed64, and strings, bytes and nested messages are length delimited.
+//! A sequence is written as a repeated field, with one entry per item.
+//! A None field is left out as in proto3 optional fields, while an optional item of a sequence is written
+//! as a wrapper message holding the value in field 1, which is empty for None
+use std::borrow::Borrow;
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+#[cfg(feature = "text")]
+use crate::text::TextRepr;
+use crate::{DeserializationError, DeserializationErrorKind, DeserializationResult, Deserialize, DeserializeSeed, Number, NumberType, PrimitiveSerializer, Serialize, Serializer};
+
+
+/// The value of a field, by wire type
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+	Varint(u64),
+	Fixed64(u64),
+	Bytes(Vec<u8>),
+	Fixed32(u32)
+}
+
+
+impl Value {
+	fn wire_type(&self) -> u64 {
+		match self {
+			Value::Varint(_) => 0,
+			Value::Fixed64(_) => 1,
+			Value::Bytes(_) => 2,
+			Value::Fixed32(_) => 5
+		}
+	}
+
+	fn wire_name(&self) -> &'static str {
+		match self {
+			Value::Varint(_) => "varint",
+			Value::Fixed64(_) => "fixed64",
+			Value::Bytes(_) => "length delimited",
+			Value::Fixed32(_) => "fixed32"
+		}
+	}
+
+	fn invalid_type(&self, expected: &'static str) -> DeserializationError {
+		DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected, actual: self.wire_name() })
+	}
+}
+
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+	while value >= 0x80 {
+		out.push(value as u8 | 0x80);
+		value >>= 7;
+	}
+	out.push(value as u8);
+}
+
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Result<u64, DeserializationError> {
+	let mut value = 0u64;
+	for shift in (0..64).step_by(7) {
+		let byte = *bytes.get(*pos).ok_or(DeserializationError::EOF)?;
+		*pos += 1;
+		value |= ((byte & 0x7F) as u64) << shift;
+		if byte & 0x80 == 0 {
+			return Ok(value)
+		}
+	}
+	Err(DeserializationError::invalid_format("varint is longer than 10 bytes"))
+}
+
+
+fn read_array<const SIZE: usize>(bytes: &[u8], pos: &mut usize) -> Result<[u8; SIZE], DeserializationError> {
+	let out = bytes.get(*pos..(*pos + SIZE)).ok_or(DeserializationError::EOF)?;
+	*pos += SIZE;
+	Ok(out.try_into().unwrap())
+}
+
+
+fn encode_fields(fields: Vec<(u32, Value)>) -> Vec<u8> {
+	let mut out = Vec::new();
+	for (number, value) in fields {
+		write_varint(&mut out, (number as u64) << 3 | value.wire_type());
+		match value {
+			Value::Varint(x) => write_varint(&mut out, x),
+			Value::Fixed64(x) => out.extend_from_slice(&x.to_le_bytes()),
+			Value::Bytes(x) => {
+				write_varint(&mut out, x.len() as u64);
+				out.extend(x);
+			}
+			Value::Fixed32(x) => out.extend_from_slice(&x.to_le_bytes())
+		}
+	}
+	out
+}
+
+
+fn decode_fields(bytes: &[u8]) -> Result<Vec<(u32, Value)>, DeserializationError> {
+	let mut out = Vec::new();
+	let mut pos = 0;
+	while pos < bytes.len() {
+		let key = read_varint(bytes, &mut pos)?;
+		let value = match key & 7 {
+			0 => Value::Varint(read_varint(bytes, &mut pos)?),
+			1 => Value::Fixed64(u64::from_le_bytes(read_array(bytes, &mut pos)?)),
+			2 => {
+				let len = read_varint(bytes, &mut pos)? as usize;
+				let value = bytes.get(pos..(pos.saturating_add(len))).ok_or(DeserializationError::EOF)?;
+				pos += len;
+				Value::Bytes(value.to_vec())
+			}
+			5 => Value::Fixed32(u32::from_le_bytes(read_array(bytes, &mut pos)?)),
+			x => return Err(DeserializationError::invalid_format(format!("unsupported wire type {}", x)))
+		};
+		out.push(((key >> 3) as u32, value));
+	}
+	Ok(out)
+}
+
+
+/// A message being written or read.
+/// Values that are not keyed are kept in order until they are given a field number by serialize_key
+#[derive(Debug)]
+struct Message {
+	values: VecDeque<Value>,
+	fields: Option<Vec<(u32, Value)>>,
+	last_number: u32,
+	/// Whether this holds an item of a sequence rather than a field, so a None cannot be left out
+	item: bool
+}
+
+
+impl Message {
+	fn new(values: VecDeque<Value>) -> Self {
+		Self { values, fields: None, last_number: 0, item: false }
+	}
+
+	fn item(values: VecDeque<Value>) -> Self {
+		Self { item: true, ..Self::new(values) }
+	}
+
+	fn number(&mut self, key: &str) -> u32 {
+		self.last_number = key.parse().unwrap_or(self.last_number + 1);
+		self.last_number
+	}
+
+	/// The values written, with any keyed fields encoded as a single nested message
+	fn into_values(self) -> VecDeque<Value> {
+		match self.fields {
+			Some(fields) => VecDeque::from([Value::Bytes(encode_fields(fields))]),
+			None => self.values
+		}
+	}
+
+	/// The fields of the message, decoding them from the next value first if needed
+	fn fields(&mut self) -> Result<&mut Vec<(u32, Value)>, DeserializationError> {
+		if self.fields.is_none() {
+			let fields = match self.next_value()? {
+				Value::Bytes(x) => decode_fields(&x)?,
+				x => return Err(x.invalid_type("length delimited"))
+			};
+			self.fields = Some(fields);
+		}
+		Ok(self.fields.as_mut().unwrap())
+	}
+
+	fn next_value(&mut self) -> Result<Value, DeserializationError> {
+		self.values.pop_front().ok_or(DeserializationError::EOF)
+	}
+
+	/// Take every entry of the field with the number of the given key, and deserialize them with the given fn.
+	/// A field with no entries is only missing if the fn fails for lack of values, so an empty repeated field is empty
+	fn key_deserialize<T, F>(&mut self, key: &str, f: F) -> Result<T, DeserializationError>
+		where
+			F: FnOnce(&mut Message) -> Result<T, DeserializationError>
+	{
+		let number = self.number(key);
+		let fields = self.fields().map_err(|e| e.nest().set_field(key))?;
+		let mut values = VecDeque::new();
+		fields.retain(|(x, value)| if *x == number {
+			values.push_back(value.clone());
+			false
+		} else {
+			true
+		});
+		let empty = values.is_empty();
+		f(&mut Message::new(values)).map_err(|e| match e.kind {
+			DeserializationErrorKind::UnexpectedEOF if empty => DeserializationError::missing_field(key),
+			_ => e.nest().set_field(key)
+		})
+	}
+}
+
+
+impl PrimitiveSerializer for Message {
+	fn serialize_bool(&mut self, boolean: bool) {
+		self.values.push_back(Value::Varint(boolean as u64));
+	}
+
+	fn deserialize_bool(&mut self) -> Result<bool, DeserializationError> {
+		match self.next_value()? {
+			Value::Varint(x) => Ok(x != 0),
+			x => Err(x.invalid_type("varint"))
+		}
+	}
+
+	fn serialize_num<T: NumberType>(&mut self, num: T) {
+		self.values.push_back(match num.to_number() {
+			Number::Unsigned(x) => Value::Varint(x),
+			Number::Signed(x) => Value::Varint(x as u64),
+			Number::F32(x) => Value::Fixed32(x.to_bits()),
+			Number::F64(x) => Value::Fixed64(x.to_bits())
+		});
+	}
+
+	fn deserialize_num<T: NumberType>(&mut self) -> Result<T, DeserializationError> {
+		let value = self.next_value()?;
+		let out = match &value {
+			Value::Varint(x) => T::from_number(Number::Unsigned(*x)).or_else(|| T::from_number(Number::Signed(*x as i64))),
+			Value::Fixed64(x) => T::from_number(Number::F64(f64::from_bits(*x))),
+			Value::Fixed32(x) => T::from_number(Number::F32(f32::from_bits(*x))),
+			Value::Bytes(_) => return Err(value.invalid_type("number"))
+		};
+		out.ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: "number does not fit its type".into() }))
+	}
+
+	fn serialize_string<T: Into<String>>(&mut self, string: T) {
+		self.values.push_back(Value::Bytes(string.into().into_bytes()));
+	}
+
+	fn deserialize_string(&mut self) -> Result<String, DeserializationError> {
+		match self.next_value()? {
+			Value::Bytes(x) => String::from_utf8(x).map_err(DeserializationErrorKind::from).no_field(),
+			x => Err(x.invalid_type("length delimited"))
+		}
+	}
+
+	fn serialize_bytes<T: Into<VecDeque<u8>>>(&mut self, bytes: T) {
+		self.values.push_back(Value::Bytes(bytes.into().into()));
+	}
+
+	fn deserialize_bytes<T: FromIterator<u8>>(&mut self) -> Result<T, DeserializationError> {
+		match self.next_value()? {
+			Value::Bytes(x) => Ok(x.into_iter().collect()),
+			x => Err(x.invalid_type("length delimited"))
+		}
+	}
+}
+
+
+impl Serializer for Message {
+	fn serialize<P, T: Serialize<P>>(&mut self, item: T) {
+		let mut message = Message::item(VecDeque::new());
+		item.serialize(&mut message);
+		self.values.append(&mut message.into_values());
+	}
+
+	fn serialize_key<P, T: Serialize<P>, K: Borrow<str>>(&mut self, key: K, item: T) {
+		let number = self.number(key.borrow());
+		let mut message = Message::new(VecDeque::new());
+		item.serialize(&mut message);
+		self.fields.get_or_insert_with(Vec::new).extend(message.into_values().into_iter().map(|x| (number, x)));
+	}
+
+	fn deserialize<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError> {
+		let value = self.next_value()?;
+		T::deserialize(&mut Message::item(VecDeque::from([value])))
+	}
+
+	fn deserialize_key_internal<P, T: Deserialize<P>>(&mut self, key: &str) -> Result<T, DeserializationError> {
+		self.key_deserialize(key, |x| T::deserialize(x))
+	}
+
+	fn deserialize_seed<P, S: DeserializeSeed<P>>(&mut self, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		let value = self.next_value()?;
+		seed.deserialize(&mut Message::item(VecDeque::from([value])))
+	}
+
+	fn deserialize_key_seed<P, S: DeserializeSeed<P>, K: Borrow<str>>(&mut self, key: K, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		self.key_deserialize(key.borrow(), |x| seed.deserialize(x))
+	}
+
+	fn serialize_option<P, T: Serialize<P>>(&mut self, item: Option<T>) {
+		if !self.item {
+			if let Some(x) = item {
+				x.serialize(self);
+			}
+			return
+		}
+		let mut fields = Vec::new();
+		if let Some(x) = item {
+			let mut message = Message::new(VecDeque::new());
+			x.serialize(&mut message);
+			fields.extend(message.into_values().into_iter().map(|x| (1, x)));
+		}
+		self.values.push_back(Value::Bytes(encode_fields(fields)));
+	}
+
+	/// A field with no entries is read as None, as is an item whose wrapper message is empty
+	fn deserialize_option<P, T: Deserialize<P>>(&mut self) -> Result<Option<T>, DeserializationError> {
+		if !self.item {
+			if self.values.is_empty() && self.fields.as_ref().is_none_or(Vec::is_empty) {
+				return Ok(None)
+			}
+			return T::deserialize(self).map(Some)
+		}
+		let fields = match self.next_value()? {
+			Value::Bytes(x) => decode_fields(&x)?,
+			x => return Err(x.invalid_type("length delimited"))
+		};
+		if fields.is_empty() {
+			return Ok(None)
+		}
+		T::deserialize(&mut Message::new(fields.into_iter().filter(|x| x.0 == 1).map(|x| x.1).collect())).map(Some)
+	}
+
+	fn try_get_key<K: FromStr>(&mut self) -> Option<K> {
+		None
+	}
+
+	fn is_human_readable(&self) -> bool {
+		false
+	}
+
+	#[cfg(feature = "text")]
+	fn deserialize_text(&mut self) -> Result<TextRepr, DeserializationError> {
+		Err(DeserializationError::invalid_format("protobuf is not self describing"))
+	}
+}
+
+
+/// Serialize any value as a protobuf message. The value should serialize keyed fields
+pub fn to_vec<P, T: Serialize<P>>(value: T) -> Vec<u8> {
+	let mut message = Message::new(VecDeque::new());
+	value.serialize(&mut message);
+	encode_fields(message.fields.unwrap_or_default())
+}
+
+
+/// Deserialize any value from a protobuf message
+pub fn from_slice<P, T: Deserialize<P>>(data: &[u8]) -> Result<T, DeserializationError> {
+	let mut message = Message::new(VecDeque::new());
+	message.fields = Some(decode_fields(data)?);
+	T::deserialize(&mut message)
+}
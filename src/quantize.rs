@@ -0,0 +1,121 @@
+//! Codecs that write floats as fixed point integers within a range in formats that are not human readable,
+//! for network replication where full precision is wasted bandwidth.
+//!
+//! A field is quantized with the codec `Quantized<R, S>`, where R gives the range and S is the unsigned
+//! integer to store it in, such as `impl_key_serde!(Player, NaturalProfile, health with Quantized<UnitRange, u8>)`.
+//! Values outside of the range are clamped to it. Human readable formats write the float as is
+use std::marker::PhantomData;
+
+use crate::{Codec, DeserializationError, NumberType, Serializer};
+
+
+/// The range that a quantized float is stored within
+pub trait QuantizeRange {
+	const MIN: f64;
+	const MAX: f64;
+}
+
+
+/// The range from 0 to 1
+pub struct UnitRange;
+
+
+impl QuantizeRange for UnitRange {
+	const MIN: f64 = 0.0;
+	const MAX: f64 = 1.0;
+}
+
+
+/// The range from -1 to 1, such as for the components of a normal
+pub struct SignedUnitRange;
+
+
+impl QuantizeRange for SignedUnitRange {
+	const MIN: f64 = -1.0;
+	const MAX: f64 = 1.0;
+}
+
+
+/// An unsigned integer that quantized floats can be stored in
+pub trait QuantizeStorage: NumberType {
+	const STEPS: f64;
+
+	fn from_step(step: f64) -> Self;
+	fn to_step(self) -> f64;
+}
+
+
+macro_rules! quantize_storage {
+    ($type: ty) => {
+impl QuantizeStorage for $type {
+	const STEPS: f64 = <$type>::MAX as f64;
+
+	fn from_step(step: f64) -> Self {
+		step as $type
+	}
+
+	fn to_step(self) -> f64 {
+		self as f64
+	}
+}
+	};
+}
+
+quantize_storage!(u8);
+quantize_storage!(u16);
+quantize_storage!(u32);
+
+
+/// A codec that stores floats within the range R in the integer S
+pub struct Quantized<R, S = u16>(PhantomData<(R, S)>);
+
+
+impl<R: QuantizeRange, S: QuantizeStorage> Quantized<R, S> {
+	/// The step that value is closest to
+	pub fn quantize(value: f64) -> S {
+		let value = value.clamp(R::MIN, R::MAX);
+		S::from_step(((value - R::MIN) / (R::MAX - R::MIN) * S::STEPS).round())
+	}
+
+	pub fn dequantize(step: S) -> f64 {
+		R::MIN + step.to_step() / S::STEPS * (R::MAX - R::MIN)
+	}
+}
+
+
+impl<R: QuantizeRange, S: QuantizeStorage> Codec<f64> for Quantized<R, S> {
+	fn serialize<T: Serializer>(value: f64, data: &mut T) {
+		if data.is_human_readable() {
+			data.serialize_num(value);
+		} else {
+			data.serialize_num(Self::quantize(value));
+		}
+	}
+
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<f64, DeserializationError> {
+		if data.is_human_readable() {
+			data.deserialize_num()
+		} else {
+			data.deserialize_num().map(Self::dequantize)
+		}
+	}
+}
+
+
+impl<R: QuantizeRange, S: QuantizeStorage> Codec<f32> for Quantized<R, S> {
+	fn serialize<T: Serializer>(value: f32, data: &mut T) {
+		if data.is_human_readable() {
+			data.serialize_num(value);
+		} else {
+			data.serialize_num(Self::quantize(value as f64));
+		}
+	}
+
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<f32, DeserializationError> {
+		if data.is_human_readable() {
+			data.deserialize_num()
+		} else {
+			data.deserialize_num().map(|x| Self::dequantize(x) as f32)
+		}
+	}
+}
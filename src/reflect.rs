@@ -0,0 +1,173 @@
+//! Field reflection. A FieldRecorder is a Serializer that writes nothing, and instead records the names
+//! and kinds of the fields it is given, so the shape of a type can be found from its Serialize impl,
+//! such as to generate help text for a CLI or a schema
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+#[cfg(feature = "text")]
+use crate::text::TextRepr;
+use crate::{DeserializationError, Deserialize, DeserializeSeed, NumberType, PrimitiveSerializer, Serialize, Serializer};
+
+
+/// The kind of value a field holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldKind {
+	/// Nothing was serialized
+	Empty,
+	Bool,
+	/// A number, with the name of its type
+	Number(&'static str),
+	String,
+	Bytes,
+	/// A sequence, with the kind of its first item, or Empty if it had none
+	Sequence(Box<FieldKind>),
+	/// A value with keyed fields
+	Table(Vec<Field>)
+}
+
+
+/// A keyed field and the kind of value it holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+	pub name: String,
+	pub kind: FieldKind
+}
+
+
+/// Records the fields a value serializes. Every deserialize method returns an error
+#[derive(Debug)]
+pub struct FieldRecorder {
+	kind: FieldKind,
+	items: usize
+}
+
+
+impl Default for FieldRecorder {
+	fn default() -> Self {
+		Self { kind: FieldKind::Empty, items: 0 }
+	}
+}
+
+
+impl FieldRecorder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Record the fields of the given value
+	pub fn record<P, T: Serialize<P>>(item: T) -> FieldKind {
+		let mut recorder = Self::new();
+		item.serialize(&mut recorder);
+		recorder.into_kind()
+	}
+
+	/// Record the fields of the default value of T
+	pub fn record_default<P, T: Serialize<P> + Default>() -> FieldKind {
+		Self::record(T::default())
+	}
+
+	pub fn into_kind(self) -> FieldKind {
+		self.kind
+	}
+
+	/// Record a value that is not keyed. The first value is recorded as is,
+	/// while later values turn it into a sequence
+	fn push(&mut self, kind: FieldKind) {
+		self.items += 1;
+		match self.items {
+			1 => self.kind = kind,
+			2 => self.kind = FieldKind::Sequence(Box::new(std::mem::replace(&mut self.kind, FieldKind::Empty))),
+			_ => {}
+		}
+	}
+
+	fn unsupported<T>() -> Result<T, DeserializationError> {
+		Err(DeserializationError::invalid_format("a FieldRecorder cannot be deserialized from"))
+	}
+}
+
+
+impl PrimitiveSerializer for FieldRecorder {
+	fn serialize_bool(&mut self, _boolean: bool) {
+		self.push(FieldKind::Bool);
+	}
+
+	fn deserialize_bool(&mut self) -> Result<bool, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn serialize_num<T: NumberType>(&mut self, _num: T) {
+		self.push(FieldKind::Number(std::any::type_name::<T>()));
+	}
+
+	fn deserialize_num<T: NumberType>(&mut self) -> Result<T, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn serialize_string<T: Into<String>>(&mut self, _string: T) {
+		self.push(FieldKind::String);
+	}
+
+	fn deserialize_string(&mut self) -> Result<String, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn serialize_bytes<T: Into<VecDeque<u8>>>(&mut self, _bytes: T) {
+		self.push(FieldKind::Bytes);
+	}
+
+	fn deserialize_bytes<T: FromIterator<u8>>(&mut self) -> Result<T, DeserializationError> {
+		Self::unsupported()
+	}
+}
+
+
+impl Serializer for FieldRecorder {
+	fn serialize<P, T: Serialize<P>>(&mut self, item: T) {
+		self.push(Self::record::<P, T>(item));
+	}
+
+	/// Records an empty sequence too, unlike serializing each item
+	fn serialize_iter<P, T: Serialize<P>, I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		let mut items = FieldRecorder::new();
+		for item in iter {
+			if items.items == 0 {
+				items.serialize::<P, T>(item);
+			}
+		}
+		self.push(FieldKind::Sequence(Box::new(items.into_kind())));
+	}
+
+	fn serialize_key<P, T: Serialize<P>, K: std::borrow::Borrow<str>>(&mut self, key: K, item: T) {
+		let field = Field { name: key.borrow().into(), kind: Self::record::<P, T>(item) };
+		match &mut self.kind {
+			FieldKind::Table(x) => x.push(field),
+			_ => self.kind = FieldKind::Table(vec![field])
+		}
+	}
+
+	fn deserialize<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn deserialize_key_internal<P, T: Deserialize<P>>(&mut self, _key: &str) -> Result<T, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn deserialize_seed<P, S: DeserializeSeed<P>>(&mut self, _seed: &mut S) -> Result<S::Value, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn deserialize_key_seed<P, S: DeserializeSeed<P>, K: std::borrow::Borrow<str>>(&mut self, _key: K, _seed: &mut S) -> Result<S::Value, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn try_get_key<K: FromStr>(&mut self) -> Option<K> {
+		None
+	}
+
+	#[cfg(feature = "text")]
+	fn deserialize_text(&mut self) -> Result<TextRepr, DeserializationError> {
+		Self::unsupported()
+	}
+}
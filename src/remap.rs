@@ -0,0 +1,142 @@
+//! Translation of entity ids while loading, such as when merging save files or world chunks whose ids overlap.
+//!
+//! Fields that refer to other entities are stored as an `EntityRef<K>` or `EntityRefs<K>`, which are
+//! written as the plain ids. When deserialized with a marshall that implements [Remap], such as [IdRemap],
+//! each id is replaced with the new id it maps to
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::{DeserializationError, DeserializationErrorKind, Deserialize, MarshalledDeserialize, MarshalledSerialize, Serialize, Serializer};
+
+
+/// Translates old ids into new ones. Implemented by the marshall, which holds the mapping
+pub trait Remap<K> {
+	fn remap(&self, id: K) -> Result<K, DeserializationError>;
+}
+
+
+/// A map from old ids to new ids.
+///
+/// By default, an id without a mapping fails to deserialize with NoMatch.
+/// Use `keep_unmapped` to keep such ids as they are instead
+#[derive(Debug, Clone)]
+pub struct IdRemap<K = u64> {
+	map: HashMap<K, K>,
+	keep_unmapped: bool
+}
+
+
+impl<K> Default for IdRemap<K> {
+	fn default() -> Self {
+		Self { map: HashMap::new(), keep_unmapped: false }
+	}
+}
+
+
+impl<K: Eq + Hash> IdRemap<K> {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Keep ids without a mapping as they are, instead of failing
+	pub fn keep_unmapped(mut self) -> Self {
+		self.keep_unmapped = true;
+		self
+	}
+
+	/// Map old to new, returning the id that old was previously mapped to
+	pub fn insert(&mut self, old: K, new: K) -> Option<K> {
+		self.map.insert(old, new)
+	}
+
+	pub fn get(&self, old: &K) -> Option<&K> {
+		self.map.get(old)
+	}
+
+	pub fn len(&self) -> usize {
+		self.map.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.map.is_empty()
+	}
+}
+
+
+impl<K: Eq + Hash> FromIterator<(K, K)> for IdRemap<K> {
+	fn from_iter<T: IntoIterator<Item=(K, K)>>(iter: T) -> Self {
+		Self { map: iter.into_iter().collect(), keep_unmapped: false }
+	}
+}
+
+
+impl<K: Eq + Hash> Extend<(K, K)> for IdRemap<K> {
+	fn extend<T: IntoIterator<Item=(K, K)>>(&mut self, iter: T) {
+		self.map.extend(iter);
+	}
+}
+
+
+impl<K: Eq + Hash + Clone + Debug> Remap<K> for IdRemap<K> {
+	fn remap(&self, id: K) -> Result<K, DeserializationError> {
+		match self.map.get(&id) {
+			Some(x) => Ok(x.clone()),
+			None if self.keep_unmapped => Ok(id),
+			None => Err(DeserializationError::new_kind(DeserializationErrorKind::NoMatch { actual: format!("{:?}", id) }))
+		}
+	}
+}
+
+
+/// The id of another entity, which is remapped by the marshall when deserialized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntityRef<K = u64>(pub K);
+
+
+impl<P, K: Serialize<P>> Serialize<P> for EntityRef<K> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		self.0.serialize(data);
+	}
+}
+
+
+impl<P, M, K: Serialize<P>> MarshalledSerialize<P, M> for EntityRef<K> {
+	fn serialize<T: Serializer>(self, data: &mut T, _marshall: &M) {
+		self.0.serialize(data);
+	}
+}
+
+
+impl<'a, P, M: Remap<K>, K: Deserialize<P>> MarshalledDeserialize<'a, P, M> for EntityRef<K> {
+	fn deserialize<T: Serializer>(data: &mut T, marshall: &'a M) -> Result<Self, DeserializationError> {
+		marshall.remap(K::deserialize(data)?).map(Self)
+	}
+}
+
+
+/// The ids of several other entities, which are remapped by the marshall when deserialized
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EntityRefs<K = u64>(pub Vec<K>);
+
+
+impl<P, K: Serialize<P>> Serialize<P> for EntityRefs<K> {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_iter(self.0);
+	}
+}
+
+
+impl<P, M, K: Serialize<P>> MarshalledSerialize<P, M> for EntityRefs<K> {
+	fn serialize<T: Serializer>(self, data: &mut T, _marshall: &M) {
+		data.serialize_iter(self.0);
+	}
+}
+
+
+impl<'a, P, M: Remap<K>, K: Deserialize<P>> MarshalledDeserialize<'a, P, M> for EntityRefs<K> {
+	fn deserialize<T: Serializer>(data: &mut T, marshall: &'a M) -> Result<Self, DeserializationError> {
+		let ids: Vec<K> = Deserialize::<P>::deserialize(data)?;
+		ids.into_iter().map(|x| marshall.remap(x)).collect::<Result<_, _>>().map(Self)
+	}
+}
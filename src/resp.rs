@@ -0,0 +1,573 @@
+//! The RESP protocol of Redis, so clients can write commands and read replies without hand rolling it.
+//!
+//! Values are written in either RESP2 or RESP3, and replies of both are read back.
+//! Strings and bytes are bulk strings, integers are integers, sequences are arrays, and keyed values are maps.
+//! RESP2 has no maps, booleans or doubles, so it writes maps as flat arrays of keys and values, as HGETALL does,
+//! booleans as 0 or 1, and doubles as bulk strings. Since replies often hold numbers as bulk strings, numbers are
+//! also read from strings. A null reply in a map is read as a missing field, and an error reply as an [ErrorReply]
+use std::borrow::Borrow;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[cfg(feature = "text")]
+use crate::text::{Table, TextRepr};
+use crate::{DeserializationError, DeserializationErrorKind, DeserializationResult, Deserialize, DeserializeSeed, Number, NumberType, PrimitiveSerializer, Serialize, Serializer};
+
+
+/// How deeply aggregates and attributes can be nested when reading
+const MAX_DEPTH: usize = 256;
+
+
+/// The version of RESP to write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+	Resp2,
+	Resp3
+}
+
+
+/// An error reply from the server, such as `-ERR unknown command`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorReply(pub String);
+
+
+impl Display for ErrorReply {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+
+impl Error for ErrorReply {}
+
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+	String(Vec<u8>),
+	Error(String),
+	Integer(i64),
+	Double(f64),
+	BigNumber(String),
+	Boolean(bool),
+	Null,
+	Array(VecDeque<Value>),
+	Map(Vec<(Value, Value)>)
+}
+
+
+impl Value {
+	fn type_name(&self) -> &'static str {
+		match self {
+			Value::String(_) => "string",
+			Value::Error(_) => "error",
+			Value::Integer(_) => "integer",
+			Value::Double(_) => "double",
+			Value::BigNumber(_) => "big number",
+			Value::Boolean(_) => "boolean",
+			Value::Null => "null",
+			Value::Array(_) => "array",
+			Value::Map(_) => "map"
+		}
+	}
+
+	fn invalid_type(&self, expected: &'static str) -> DeserializationError {
+		if let Value::Error(x) = self {
+			return DeserializationError::custom(ErrorReply(x.clone()))
+		}
+		DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected, actual: self.type_name() })
+	}
+
+	/// The text of a scalar, as a command argument or a map key
+	fn into_text(self) -> Result<String, DeserializationError> {
+		match self {
+			Value::String(x) => String::from_utf8(x).map_err(DeserializationErrorKind::from).no_field(),
+			Value::Integer(x) => Ok(x.to_string()),
+			Value::Double(x) => Ok(x.to_string()),
+			Value::BigNumber(x) => Ok(x),
+			Value::Boolean(x) => Ok((x as u8).to_string()),
+			x => Err(x.invalid_type("string"))
+		}
+	}
+
+	#[cfg(feature = "text")]
+	fn into_repr(self) -> Result<TextRepr, DeserializationError> {
+		Ok(match self {
+			Value::Integer(x) => TextRepr::Integer(x),
+			Value::Double(x) => TextRepr::Float(x),
+			Value::Boolean(x) => TextRepr::Boolean(x),
+			Value::Null => TextRepr::Null,
+			Value::Array(x) => TextRepr::Array(x.into_iter().map(Value::into_repr).collect::<Result<_, _>>()?),
+			Value::Map(x) => {
+				let mut out = Table::default();
+				for (key, value) in x {
+					let key = key.into_text()?;
+					let value = value.into_repr().map_err(|e| e.nest().set_field(&key))?;
+					out.insert(key.into(), value);
+				}
+				TextRepr::Table(out)
+			}
+			x => TextRepr::String(x.into_text()?)
+		})
+	}
+}
+
+
+fn write_line(out: &mut Vec<u8>, kind: u8, line: impl Display) {
+	out.push(kind);
+	out.extend(line.to_string().into_bytes());
+	out.extend(b"\r\n");
+}
+
+
+fn write_bulk(out: &mut Vec<u8>, bytes: &[u8]) {
+	write_line(out, b'$', bytes.len());
+	out.extend(bytes);
+	out.extend(b"\r\n");
+}
+
+
+fn write_double(out: &mut Vec<u8>, value: f64, version: Version) {
+	let text = if value.is_nan() { "nan".to_string() } else { value.to_string() };
+	match version {
+		Version::Resp2 => write_bulk(out, text.as_bytes()),
+		Version::Resp3 => write_line(out, b',', text)
+	}
+}
+
+
+fn encode(out: &mut Vec<u8>, value: Value, version: Version) {
+	match (value, version) {
+		(Value::String(x), _) => write_bulk(out, &x),
+		(Value::Error(x), _) => write_line(out, b'-', x),
+		(Value::Integer(x), _) => write_line(out, b':', x),
+		(Value::Double(x), _) => write_double(out, x, version),
+		(Value::BigNumber(x), Version::Resp2) => write_bulk(out, x.as_bytes()),
+		(Value::BigNumber(x), Version::Resp3) => write_line(out, b'(', x),
+		(Value::Boolean(x), Version::Resp2) => write_line(out, b':', x as u8),
+		(Value::Boolean(x), Version::Resp3) => write_line(out, b'#', if x { 't' } else { 'f' }),
+		(Value::Null, Version::Resp2) => write_line(out, b'$', -1),
+		(Value::Null, Version::Resp3) => write_line(out, b'_', ""),
+		(Value::Array(x), _) => {
+			write_line(out, b'*', x.len());
+			for value in x {
+				encode(out, value, version);
+			}
+		}
+		(Value::Map(x), _) => {
+			match version {
+				Version::Resp2 => write_line(out, b'*', x.len() * 2),
+				Version::Resp3 => write_line(out, b'%', x.len())
+			}
+			for (key, value) in x {
+				encode(out, key, version);
+				encode(out, value, version);
+			}
+		}
+	}
+}
+
+
+/// Append every scalar in value to args as a string, flattening arrays and maps
+fn flatten_args(args: &mut Vec<Value>, value: Value) {
+	match value {
+		Value::Null => {}
+		Value::Array(x) => for value in x {
+			flatten_args(args, value);
+		}
+		Value::Map(x) => for (key, value) in x {
+			flatten_args(args, key);
+			flatten_args(args, value);
+		}
+		Value::String(x) => args.push(Value::String(x)),
+		Value::Double(x) if x.is_nan() => args.push(Value::String(b"nan".to_vec())),
+		x => args.push(Value::String(x.into_text().unwrap().into_bytes()))
+	}
+}
+
+
+/// A reader of replies from a buffer. Any reply that is cut short gives UnexpectedEOF, so more data can be awaited
+struct Reader<'a> {
+	bytes: &'a [u8],
+	pos: usize
+}
+
+
+impl Reader<'_> {
+	fn line(&mut self) -> Result<&str, DeserializationError> {
+		let rest = &self.bytes[self.pos..];
+		let end = rest.windows(2).position(|x| x == b"\r\n").ok_or(DeserializationError::EOF)?;
+		self.pos += end + 2;
+		std::str::from_utf8(&rest[..end]).map_err(|_| DeserializationError::invalid_format("line is not UTF-8"))
+	}
+
+	fn number<T: FromStr>(&mut self) -> Result<T, DeserializationError> {
+		let line = self.line()?;
+		line.parse().map_err(|_| DeserializationError::invalid_format(format!("invalid number {}", line)))
+	}
+
+	/// Read the bytes of a blob, or None if its length is -1
+	fn blob(&mut self) -> Result<Option<Vec<u8>>, DeserializationError> {
+		let len: i64 = self.number()?;
+		if len < 0 {
+			return Ok(None)
+		}
+		let end = self.pos.checked_add(len as usize).filter(|x| x.checked_add(2).is_some_and(|x| x <= self.bytes.len())).ok_or(DeserializationError::EOF)?;
+		let out = self.bytes[self.pos..end].to_vec();
+		if &self.bytes[end..(end + 2)] != b"\r\n" {
+			return Err(DeserializationError::invalid_format("blob is not followed by CRLF"))
+		}
+		self.pos = end + 2;
+		Ok(Some(out))
+	}
+
+	/// Read the length of an aggregate, or None if it is -1
+	fn count(&mut self) -> Result<Option<usize>, DeserializationError> {
+		let len: i64 = self.number()?;
+		Ok(usize::try_from(len).ok())
+	}
+
+	fn values(&mut self, count: usize, depth: usize) -> Result<VecDeque<Value>, DeserializationError> {
+		// Every value is at least 3 bytes long, which bounds the allocation on a bad count
+		let mut out = VecDeque::with_capacity(count.min(self.bytes.len() / 3));
+		for _ in 0..count {
+			out.push_back(self.value(depth + 1)?);
+		}
+		Ok(out)
+	}
+
+	fn scalar(&mut self, kind: u8) -> Result<Value, DeserializationError> {
+		Ok(match kind {
+			b'+' => Value::String(self.line()?.as_bytes().to_vec()),
+			b'-' => Value::Error(self.line()?.into()),
+			b':' => Value::Integer(self.number()?),
+			b'$' => self.blob()?.map(Value::String).unwrap_or(Value::Null),
+			b'_' => {
+				self.line()?;
+				Value::Null
+			}
+			b'#' => match self.line()? {
+				"t" => Value::Boolean(true),
+				"f" => Value::Boolean(false),
+				x => return Err(DeserializationError::invalid_format(format!("invalid boolean {}", x)))
+			},
+			b',' => Value::Double(self.number()?),
+			b'(' => Value::BigNumber(self.line()?.into()),
+			b'!' => Value::Error(String::from_utf8_lossy(&self.blob()?.unwrap_or_default()).into()),
+			// Verbatim strings start with their format, such as txt:
+			b'=' => Value::String(self.blob()?.unwrap_or_default().into_iter().skip(4).collect()),
+			x => return Err(DeserializationError::invalid_format(format!("unknown type {:?}", x as char)))
+		})
+	}
+
+	/// Read a value nested depth levels deep. Scalars are read by a separate fn to keep each level of recursion small
+	fn value(&mut self, depth: usize) -> Result<Value, DeserializationError> {
+		if depth > MAX_DEPTH {
+			return Err(DeserializationError::invalid_format(format!("reply is nested deeper than {} levels", MAX_DEPTH)))
+		}
+		let kind = *self.bytes.get(self.pos).ok_or(DeserializationError::EOF)?;
+		self.pos += 1;
+		Ok(match kind {
+			b'*' | b'~' | b'>' => match self.count()? {
+				Some(x) => Value::Array(self.values(x, depth)?),
+				None => Value::Null
+			},
+			b'%' | b'|' => {
+				let count = self.count()?.unwrap_or(0);
+				let mut values = self.values(count * 2, depth)?;
+				let mut pairs = Vec::with_capacity(count);
+				while let (Some(key), Some(value)) = (values.pop_front(), values.pop_front()) {
+					pairs.push((key, value));
+				}
+				if kind == b'|' {
+					// Attributes describe the reply after them, which is the value
+					return self.value(depth + 1)
+				}
+				Value::Map(pairs)
+			}
+			x => self.scalar(x)?
+		})
+	}
+}
+
+
+/// Nest an UnexpectedEOF from an array item, so it is not mistaken for the end of the array
+fn cut_short(e: DeserializationError) -> DeserializationError {
+	match e.kind {
+		DeserializationErrorKind::UnexpectedEOF => e.nest(),
+		_ => e
+	}
+}
+
+
+/// A value being written or read. Keyed values are kept as pairs until the map is finished
+#[derive(Debug, Default)]
+struct Resp {
+	values: VecDeque<Value>,
+	pairs: Option<Vec<(String, Value)>>,
+	opened: bool
+}
+
+
+impl Resp {
+	fn from_value(value: Value) -> Self {
+		Self { values: VecDeque::from([value]), ..Default::default() }
+	}
+
+	fn into_value(mut self) -> Value {
+		if let Some(pairs) = self.pairs {
+			return Value::Map(pairs.into_iter().map(|(key, value)| (Value::String(key.into_bytes()), value)).collect())
+		}
+		match self.values.len() {
+			0 => Value::Null,
+			1 => self.values.pop_front().unwrap(),
+			_ => Value::Array(self.values)
+		}
+	}
+
+	fn next(&mut self) -> Result<Value, DeserializationError> {
+		self.values.pop_front().ok_or(DeserializationError::EOF)
+	}
+
+	/// Split the array being read into its items, if that was not done yet
+	fn open(&mut self) -> Result<(), DeserializationError> {
+		if self.opened {
+			return Ok(())
+		}
+		self.values = match self.next()? {
+			Value::Array(x) => x,
+			Value::Null => VecDeque::new(),
+			x => return Err(x.invalid_type("array"))
+		};
+		self.opened = true;
+		Ok(())
+	}
+
+	/// The pairs of the map being read, splitting them from the next value first if needed
+	fn pairs(&mut self) -> Result<&mut Vec<(String, Value)>, DeserializationError> {
+		if self.pairs.is_none() {
+			let pairs = match self.next()? {
+				Value::Map(x) => x,
+				// RESP2 replies hold maps as flat arrays of keys and values
+				Value::Array(x) if x.len() % 2 == 0 => {
+					let mut values = x;
+					let mut pairs = Vec::with_capacity(values.len() / 2);
+					while let (Some(key), Some(value)) = (values.pop_front(), values.pop_front()) {
+						pairs.push((key, value));
+					}
+					pairs
+				}
+				x => return Err(x.invalid_type("map"))
+			};
+			self.pairs = Some(pairs.into_iter().map(|(key, value)| Ok((key.into_text()?, value))).collect::<Result<_, DeserializationError>>()?);
+		}
+		Ok(self.pairs.as_mut().unwrap())
+	}
+
+	fn key_deserialize<T, F>(&mut self, key: &str, f: F) -> Result<T, DeserializationError>
+		where
+			F: FnOnce(&mut Resp) -> Result<T, DeserializationError>
+	{
+		let pairs = self.pairs().map_err(|e| e.nest().set_field(key))?;
+		let value = match pairs.iter().position(|x| x.0 == key) {
+			Some(i) => pairs.remove(i).1,
+			None => return Err(DeserializationError::missing_field(key))
+		};
+		if value == Value::Null {
+			return Err(DeserializationError::missing_field(key))
+		}
+		f(&mut Resp::from_value(value)).map_err(|e| e.nest().set_field(key))
+	}
+}
+
+
+impl PrimitiveSerializer for Resp {
+	fn serialize_bool(&mut self, boolean: bool) {
+		self.values.push_back(Value::Boolean(boolean));
+	}
+
+	fn deserialize_bool(&mut self) -> Result<bool, DeserializationError> {
+		match self.next()? {
+			Value::Boolean(x) => Ok(x),
+			Value::Integer(0) => Ok(false),
+			Value::Integer(1) => Ok(true),
+			x => Err(x.invalid_type("boolean"))
+		}
+	}
+
+	fn serialize_num<T: NumberType>(&mut self, num: T) {
+		self.values.push_back(match num.to_number() {
+			Number::Unsigned(x) => i64::try_from(x).map(Value::Integer).unwrap_or_else(|_| Value::BigNumber(x.to_string())),
+			Number::Signed(x) => Value::Integer(x),
+			// Going through the shortest text of the f32 keeps 0.1 from being written as 0.10000000149011612
+			Number::F32(x) => Value::Double(x.to_string().parse().unwrap()),
+			Number::F64(x) => Value::Double(x)
+		});
+	}
+
+	fn deserialize_num<T: NumberType>(&mut self) -> Result<T, DeserializationError> {
+		let number = match self.next()? {
+			Value::Integer(x) => Number::Signed(x),
+			Value::Double(x) => Number::F64(x),
+			x @ (Value::String(_) | Value::BigNumber(_)) => {
+				let text = x.into_text()?;
+				match (text.parse(), text.parse(), text.parse()) {
+					(Ok(x), _, _) => Number::Signed(x),
+					(_, Ok(x), _) => Number::Unsigned(x),
+					(_, _, Ok(x)) => Number::F64(x),
+					_ => return Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: format!("{} is not a number", text) }))
+				}
+			}
+			x => return Err(x.invalid_type("number"))
+		};
+		let out = match number {
+			Number::Signed(x) if x >= 0 => T::from_number(Number::Unsigned(x as u64)).or_else(|| T::from_number(number)),
+			_ => T::from_number(number)
+		};
+		out.ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: "number does not fit its type".into() }))
+	}
+
+	fn serialize_string<T: Into<String>>(&mut self, string: T) {
+		self.values.push_back(Value::String(string.into().into_bytes()));
+	}
+
+	fn deserialize_string(&mut self) -> Result<String, DeserializationError> {
+		match self.next()? {
+			Value::String(x) => String::from_utf8(x).map_err(DeserializationErrorKind::from).no_field(),
+			Value::BigNumber(x) => Ok(x),
+			x => Err(x.invalid_type("string"))
+		}
+	}
+
+	fn serialize_bytes<T: Into<VecDeque<u8>>>(&mut self, bytes: T) {
+		self.values.push_back(Value::String(bytes.into().into()));
+	}
+
+	fn deserialize_bytes<T: FromIterator<u8>>(&mut self) -> Result<T, DeserializationError> {
+		match self.next()? {
+			Value::String(x) => Ok(x.into_iter().collect()),
+			x => Err(x.invalid_type("string"))
+		}
+	}
+}
+
+
+impl Serializer for Resp {
+	fn serialize<P, T: Serialize<P>>(&mut self, item: T) {
+		let mut resp = Resp::default();
+		item.serialize(&mut resp);
+		self.values.push_back(resp.into_value());
+	}
+
+	fn serialize_iter<P, T: Serialize<P>, I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		let mut items = Resp::default();
+		for item in iter {
+			items.serialize(item);
+		}
+		self.values.push_back(Value::Array(items.values));
+	}
+
+	fn serialize_key<P, T: Serialize<P>, K: Borrow<str>>(&mut self, key: K, item: T) {
+		let mut resp = Resp::default();
+		item.serialize(&mut resp);
+		self.pairs.get_or_insert_with(Vec::new).push((key.borrow().into(), resp.into_value()));
+	}
+
+	fn deserialize<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError> {
+		self.open()?;
+		let value = self.next()?;
+		T::deserialize(&mut Resp::from_value(value)).map_err(cut_short)
+	}
+
+	fn deserialize_key_internal<P, T: Deserialize<P>>(&mut self, key: &str) -> Result<T, DeserializationError> {
+		self.key_deserialize(key, |x| T::deserialize(x))
+	}
+
+	fn deserialize_seed<P, S: DeserializeSeed<P>>(&mut self, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		self.open()?;
+		let value = self.next()?;
+		seed.deserialize(&mut Resp::from_value(value)).map_err(cut_short)
+	}
+
+	fn deserialize_key_seed<P, S: DeserializeSeed<P>, K: Borrow<str>>(&mut self, key: K, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		self.key_deserialize(key.borrow(), |x| seed.deserialize(x))
+	}
+
+	fn serialize_option<P, T: Serialize<P>>(&mut self, item: Option<T>) {
+		match item {
+			Some(x) => x.serialize(self),
+			None => self.values.push_back(Value::Null)
+		}
+	}
+
+	fn deserialize_option<P, T: Deserialize<P>>(&mut self) -> Result<Option<T>, DeserializationError> {
+		if self.values.front() == Some(&Value::Null) {
+			self.values.pop_front();
+			return Ok(None)
+		}
+		T::deserialize(self).map(Some)
+	}
+
+	fn try_get_key<K: FromStr>(&mut self) -> Option<K> {
+		self.pairs().ok()?.first()?.0.parse().ok()
+	}
+
+	fn is_human_readable(&self) -> bool {
+		false
+	}
+
+	fn remaining_keys(&self) -> Vec<String> {
+		self.pairs.iter().flatten().map(|x| x.0.clone()).collect()
+	}
+
+	#[cfg(feature = "text")]
+	fn deserialize_text(&mut self) -> Result<TextRepr, DeserializationError> {
+		if self.opened {
+			return self.values.drain(..).map(Value::into_repr).collect::<Result<_, _>>().map(TextRepr::Array)
+		}
+		self.next()?.into_repr()
+	}
+}
+
+
+/// Serialize any value as a single RESP value of the given version
+pub fn to_vec<P, T: Serialize<P>>(value: T, version: Version) -> Vec<u8> {
+	let mut resp = Resp::default();
+	value.serialize(&mut resp);
+	let mut out = Vec::new();
+	encode(&mut out, resp.into_value(), version);
+	out
+}
+
+
+/// Serialize the arguments of a command as the array of bulk strings that servers expect.
+/// Nested sequences and maps are flattened into the arguments, and numbers are written as their text
+pub fn command<P, T: Serialize<P>>(args: T) -> Vec<u8> {
+	let mut resp = Resp::default();
+	args.serialize(&mut resp);
+	let mut flat = Vec::new();
+	flatten_args(&mut flat, resp.into_value());
+	let mut out = Vec::new();
+	encode(&mut out, Value::Array(flat.into()), Version::Resp2);
+	out
+}
+
+
+/// Deserialize the reply at the start of data, returning it along with the number of bytes it took up.
+/// A reply that is not complete yet gives UnexpectedEOF
+pub fn next_reply<P, T: Deserialize<P>>(data: &[u8]) -> Result<(T, usize), DeserializationError> {
+	let mut reader = Reader { bytes: data, pos: 0 };
+	let value = reader.value(0)?;
+	Ok((T::deserialize(&mut Resp::from_value(value))?, reader.pos))
+}
+
+
+/// Deserialize any value from a single RESP reply
+pub fn from_slice<P, T: Deserialize<P>>(data: &[u8]) -> Result<T, DeserializationError> {
+	let (out, len) = next_reply(data)?;
+	if len != data.len() {
+		return Err(DeserializationError::invalid_format("trailing bytes after the reply"))
+	}
+	Ok(out)
+}
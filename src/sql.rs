@@ -0,0 +1,356 @@
+//! Loading values from the rows of database queries, with the column names as keys.
+//!
+//! A row is read into a [Row] of [Cell]s, which keyed values are deserialized from as from any other format.
+//! A NULL cell is read as a missing field, so Option fields of nullable columns are None.
+//! Sequences are read from the columns in order, and numbers and booleans are also read from text,
+//! since SQLite stores whatever it is given
+use std::borrow::Borrow;
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+#[cfg(feature = "text")]
+use crate::text::{Table, TextRepr};
+use crate::{DeserializationError, DeserializationErrorKind, DeserializationResult, Deserialize, DeserializeSeed, Number, NumberType, PrimitiveSerializer, Serialize, Serializer};
+
+
+/// The value of a single column
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+	Null,
+	Boolean(bool),
+	Integer(i64),
+	Real(f64),
+	Text(String),
+	Blob(Vec<u8>),
+	/// A column whose type has no equivalent here, such as a timestamp, which fails to deserialize
+	Unsupported(String)
+}
+
+
+impl Cell {
+	fn type_name(&self) -> &'static str {
+		match self {
+			Cell::Null => "NULL",
+			Cell::Boolean(_) => "boolean",
+			Cell::Integer(_) => "integer",
+			Cell::Real(_) => "real",
+			Cell::Text(_) => "text",
+			Cell::Blob(_) => "blob",
+			Cell::Unsupported(_) => "unsupported"
+		}
+	}
+
+	fn invalid_type(&self, expected: &'static str) -> DeserializationError {
+		if let Cell::Unsupported(x) = self {
+			return DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: format!("columns of type {} are not supported", x) })
+		}
+		DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected, actual: self.type_name() })
+	}
+
+	#[cfg(feature = "text")]
+	fn into_repr(self) -> Result<TextRepr, DeserializationError> {
+		Ok(match self {
+			Cell::Null => TextRepr::Null,
+			Cell::Boolean(x) => TextRepr::Boolean(x),
+			Cell::Integer(x) => TextRepr::Integer(x),
+			Cell::Real(x) => TextRepr::Float(x),
+			Cell::Text(x) => TextRepr::String(x),
+			Cell::Blob(x) => TextRepr::Array(x.into_iter().map(|x| TextRepr::Integer(x as i64)).collect()),
+			x => return Err(x.invalid_type("any"))
+		})
+	}
+}
+
+
+/// A row of a query result, or a single cell of one
+#[derive(Debug, Default)]
+pub struct Row {
+	cells: VecDeque<Cell>,
+	columns: Option<Vec<(String, Cell)>>
+}
+
+
+impl Row {
+	pub fn new(columns: Vec<(String, Cell)>) -> Self {
+		Self { cells: VecDeque::new(), columns: Some(columns) }
+	}
+
+	/// The columns of the row, along with their values
+	pub fn columns(&self) -> &[(String, Cell)] {
+		self.columns.as_deref().unwrap_or_default()
+	}
+
+	fn from_cell(cell: Cell) -> Self {
+		Self { cells: VecDeque::from([cell]), columns: None }
+	}
+
+	fn next(&mut self) -> Result<Cell, DeserializationError> {
+		self.cells.pop_front().ok_or(DeserializationError::EOF)
+	}
+
+	/// The next cell of a sequence, which is the next column of a row
+	fn next_item(&mut self) -> Result<Cell, DeserializationError> {
+		match &mut self.columns {
+			Some(x) if !x.is_empty() => Ok(x.remove(0).1),
+			_ => self.next()
+		}
+	}
+
+	fn key_deserialize<T, F>(&mut self, key: &str, f: F) -> Result<T, DeserializationError>
+		where
+			F: FnOnce(&mut Row) -> Result<T, DeserializationError>
+	{
+		let columns = self.columns.as_mut().ok_or_else(|| DeserializationError::missing_field(key))?;
+		let cell = match columns.iter().position(|x| x.0 == key) {
+			Some(i) => columns.remove(i).1,
+			None => return Err(DeserializationError::missing_field(key))
+		};
+		if cell == Cell::Null {
+			return Err(DeserializationError::missing_field(key))
+		}
+		f(&mut Row::from_cell(cell)).map_err(|e| e.nest().set_field(key))
+	}
+}
+
+
+impl PrimitiveSerializer for Row {
+	fn serialize_bool(&mut self, boolean: bool) {
+		self.cells.push_back(Cell::Boolean(boolean));
+	}
+
+	fn deserialize_bool(&mut self) -> Result<bool, DeserializationError> {
+		match self.next()? {
+			Cell::Boolean(x) => Ok(x),
+			Cell::Integer(x) => Ok(x != 0),
+			Cell::Text(x) => match x.as_str() {
+				"1" | "true" | "TRUE" => Ok(true),
+				"0" | "false" | "FALSE" => Ok(false),
+				_ => Err(DeserializationError::new_kind(DeserializationErrorKind::NoMatch { actual: x }))
+			},
+			x => Err(x.invalid_type("boolean"))
+		}
+	}
+
+	fn serialize_num<T: NumberType>(&mut self, num: T) {
+		self.cells.push_back(match num.to_number() {
+			Number::Unsigned(x) => i64::try_from(x).map(Cell::Integer).unwrap_or_else(|_| Cell::Text(x.to_string())),
+			Number::Signed(x) => Cell::Integer(x),
+			Number::F32(x) => Cell::Real(x as f64),
+			Number::F64(x) => Cell::Real(x)
+		});
+	}
+
+	fn deserialize_num<T: NumberType>(&mut self) -> Result<T, DeserializationError> {
+		let number = match self.next()? {
+			Cell::Integer(x) if x >= 0 => Number::Unsigned(x as u64),
+			Cell::Integer(x) => Number::Signed(x),
+			Cell::Real(x) => Number::F64(x),
+			Cell::Text(x) => match (x.parse(), x.parse()) {
+				(Ok(x), _) => Number::Signed(x),
+				(_, Ok(x)) => Number::F64(x),
+				_ => return Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: format!("{} is not a number", x) }))
+			},
+			x => return Err(x.invalid_type("number"))
+		};
+		T::from_number(number).ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: "number does not fit its type".into() }))
+	}
+
+	fn serialize_string<T: Into<String>>(&mut self, string: T) {
+		self.cells.push_back(Cell::Text(string.into()));
+	}
+
+	fn deserialize_string(&mut self) -> Result<String, DeserializationError> {
+		match self.next()? {
+			Cell::Text(x) => Ok(x),
+			Cell::Blob(x) => String::from_utf8(x).map_err(DeserializationErrorKind::from).no_field(),
+			x => Err(x.invalid_type("text"))
+		}
+	}
+
+	fn serialize_bytes<T: Into<VecDeque<u8>>>(&mut self, bytes: T) {
+		self.cells.push_back(Cell::Blob(bytes.into().into()));
+	}
+
+	fn deserialize_bytes<T: FromIterator<u8>>(&mut self) -> Result<T, DeserializationError> {
+		match self.next()? {
+			Cell::Blob(x) => Ok(x.into_iter().collect()),
+			Cell::Text(x) => Ok(x.into_bytes().into_iter().collect()),
+			x => Err(x.invalid_type("blob"))
+		}
+	}
+}
+
+
+impl Serializer for Row {
+	/// Rows are flat, so an item is only kept if it serializes a single cell
+	fn serialize<P, T: Serialize<P>>(&mut self, item: T) {
+		let mut row = Row::default();
+		item.serialize(&mut row);
+		if row.columns.is_none() && row.cells.len() == 1 {
+			self.cells.append(&mut row.cells);
+		}
+	}
+
+	fn serialize_key<P, T: Serialize<P>, K: Borrow<str>>(&mut self, key: K, item: T) {
+		let mut row = Row::default();
+		item.serialize(&mut row);
+		let cell = row.cells.pop_front().unwrap_or(Cell::Null);
+		self.columns.get_or_insert_with(Vec::new).push((key.borrow().into(), cell));
+	}
+
+	fn deserialize<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError> {
+		let cell = self.next_item()?;
+		T::deserialize(&mut Row::from_cell(cell))
+	}
+
+	fn deserialize_key_internal<P, T: Deserialize<P>>(&mut self, key: &str) -> Result<T, DeserializationError> {
+		self.key_deserialize(key, |x| T::deserialize(x))
+	}
+
+	fn deserialize_seed<P, S: DeserializeSeed<P>>(&mut self, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		let cell = self.next_item()?;
+		seed.deserialize(&mut Row::from_cell(cell))
+	}
+
+	fn deserialize_key_seed<P, S: DeserializeSeed<P>, K: Borrow<str>>(&mut self, key: K, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		self.key_deserialize(key.borrow(), |x| seed.deserialize(x))
+	}
+
+	fn serialize_option<P, T: Serialize<P>>(&mut self, item: Option<T>) {
+		match item {
+			Some(x) => x.serialize(self),
+			None => self.cells.push_back(Cell::Null)
+		}
+	}
+
+	fn deserialize_option<P, T: Deserialize<P>>(&mut self) -> Result<Option<T>, DeserializationError> {
+		if self.cells.front() == Some(&Cell::Null) {
+			self.cells.pop_front();
+			return Ok(None)
+		}
+		T::deserialize(self).map(Some)
+	}
+
+	fn try_get_key<K: FromStr>(&mut self) -> Option<K> {
+		self.columns.as_ref()?.first()?.0.parse().ok()
+	}
+
+	fn is_human_readable(&self) -> bool {
+		false
+	}
+
+	fn remaining_keys(&self) -> Vec<String> {
+		self.columns().iter().map(|x| x.0.clone()).collect()
+	}
+
+	#[cfg(feature = "text")]
+	fn deserialize_text(&mut self) -> Result<TextRepr, DeserializationError> {
+		match self.columns.take() {
+			Some(columns) => {
+				let mut out = Table::default();
+				for (key, cell) in columns {
+					let value = cell.into_repr().map_err(|e| e.nest().set_field(&key))?;
+					out.insert(key.into(), value);
+				}
+				Ok(TextRepr::Table(out))
+			}
+			None => self.next()?.into_repr()
+		}
+	}
+}
+
+
+#[cfg(feature = "rusqlite")]
+impl TryFrom<&rusqlite::Row<'_>> for Row {
+	type Error = DeserializationError;
+
+	fn try_from(row: &rusqlite::Row<'_>) -> Result<Self, Self::Error> {
+		use rusqlite::types::ValueRef;
+
+		let statement = row.as_ref();
+		let mut columns = Vec::with_capacity(statement.column_count());
+		for i in 0..statement.column_count() {
+			let name = statement.column_name(i).map_err(DeserializationError::custom)?;
+			let cell = match row.get_ref(i).map_err(|e| DeserializationError::custom(e).set_field(name))? {
+				ValueRef::Null => Cell::Null,
+				ValueRef::Integer(x) => Cell::Integer(x),
+				ValueRef::Real(x) => Cell::Real(x),
+				ValueRef::Text(x) => Cell::Text(String::from_utf8(x.to_vec()).map_err(DeserializationErrorKind::from).set_field(name)?),
+				ValueRef::Blob(x) => Cell::Blob(x.to_vec())
+			};
+			columns.push((name.to_string(), cell));
+		}
+		Ok(Self::new(columns))
+	}
+}
+
+
+#[cfg(feature = "postgres")]
+impl TryFrom<&tokio_postgres::Row> for Row {
+	type Error = DeserializationError;
+
+	fn try_from(row: &tokio_postgres::Row) -> Result<Self, Self::Error> {
+		use tokio_postgres::types::Type;
+
+		/// Any value that is not NULL, to tell NULL apart in columns of unsupported types
+		struct Present;
+
+		impl<'a> tokio_postgres::types::FromSql<'a> for Present {
+			fn from_sql(_: &Type, _: &'a [u8]) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+				Ok(Present)
+			}
+
+			fn accepts(_: &Type) -> bool {
+				true
+			}
+		}
+
+		fn get<'a, T: tokio_postgres::types::FromSql<'a>>(row: &'a tokio_postgres::Row, i: usize) -> Result<Option<T>, DeserializationError> {
+			row.try_get(i).map_err(|e| DeserializationError::custom(e).set_field(row.columns()[i].name()))
+		}
+
+		let mut columns = Vec::with_capacity(row.len());
+		for (i, column) in row.columns().iter().enumerate() {
+			let kind = column.type_();
+			let cell = if *kind == Type::BOOL {
+				get(row, i)?.map(Cell::Boolean)
+			} else if *kind == Type::CHAR {
+				get::<i8>(row, i)?.map(|x| Cell::Integer(x as i64))
+			} else if *kind == Type::INT2 {
+				get::<i16>(row, i)?.map(|x| Cell::Integer(x as i64))
+			} else if *kind == Type::INT4 {
+				get::<i32>(row, i)?.map(|x| Cell::Integer(x as i64))
+			} else if *kind == Type::INT8 {
+				get(row, i)?.map(Cell::Integer)
+			} else if *kind == Type::OID {
+				get::<u32>(row, i)?.map(|x| Cell::Integer(x as i64))
+			} else if *kind == Type::FLOAT4 {
+				get::<f32>(row, i)?.map(|x| Cell::Real(x as f64))
+			} else if *kind == Type::FLOAT8 {
+				get(row, i)?.map(Cell::Real)
+			} else if *kind == Type::BYTEA {
+				get(row, i)?.map(Cell::Blob)
+			} else if <String as tokio_postgres::types::FromSql>::accepts(kind) {
+				get(row, i)?.map(Cell::Text)
+			} else {
+				get::<Present>(row, i)?.map(|_| Cell::Unsupported(kind.name().into()))
+			};
+			columns.push((column.name().to_string(), cell.unwrap_or(Cell::Null)));
+		}
+		Ok(Self::new(columns))
+	}
+}
+
+
+/// Deserialize any value from a row of an SQLite query
+#[cfg(feature = "rusqlite")]
+pub fn from_sqlite_row<P, T: Deserialize<P>>(row: &rusqlite::Row<'_>) -> Result<T, DeserializationError> {
+	T::deserialize(&mut Row::try_from(row)?)
+}
+
+
+/// Deserialize any value from a row of a Postgres query
+#[cfg(feature = "postgres")]
+pub fn from_postgres_row<P, T: Deserialize<P>>(row: &tokio_postgres::Row) -> Result<T, DeserializationError> {
+	T::deserialize(&mut Row::try_from(row)?)
+}
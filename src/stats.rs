@@ -0,0 +1,161 @@
+//! Size and depth profiling. A StatsSerializer writes nothing, and instead adds up how large each field
+//! would be in the binary format, how many sequence elements it holds, and how deeply it nests,
+//! so the fields that bloat a payload can be found. The fields of the elements of a sequence are added together
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+#[cfg(feature = "text")]
+use crate::text::TextRepr;
+use crate::{DeserializationError, Deserialize, DeserializeSeed, NumberType, PrimitiveSerializer, Serialize, Serializer};
+
+
+/// The size of a value and its fields
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldStats {
+	/// The number of bytes the value takes up in the binary format, including the keys of its fields
+	pub bytes: usize,
+	/// The number of sequence elements in the value, not counting those of its fields
+	pub elements: usize,
+	/// The number of tables and sequences nested inside each other in the value
+	pub max_depth: usize,
+	/// The stats of each keyed field, in the order they were first serialized
+	pub fields: Vec<(String, FieldStats)>
+}
+
+
+impl FieldStats {
+	/// The stats of the field at the given dotted path
+	pub fn get(&self, path: &str) -> Option<&FieldStats> {
+		let mut current = self;
+		for key in path.split('.') {
+			current = &current.fields.iter().find(|x| x.0 == key)?.1;
+		}
+		Some(current)
+	}
+
+	fn add_fields(&mut self, fields: Vec<(String, FieldStats)>) {
+		for (key, stats) in fields {
+			match self.fields.iter_mut().find(|x| x.0 == key) {
+				Some((_, x)) => x.add(stats),
+				None => self.fields.push((key, stats))
+			}
+		}
+	}
+
+	fn add(&mut self, other: Self) {
+		self.bytes += other.bytes;
+		self.elements += other.elements;
+		self.max_depth = self.max_depth.max(other.max_depth);
+		self.add_fields(other.fields);
+	}
+}
+
+
+/// Collects the FieldStats of a value. Every deserialize method returns an error
+#[derive(Debug, Default)]
+pub struct StatsSerializer(FieldStats);
+
+
+impl StatsSerializer {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The stats of the given value
+	pub fn stats<P, T: Serialize<P>>(item: T) -> FieldStats {
+		let mut out = Self::new();
+		item.serialize(&mut out);
+		out.0
+	}
+
+	fn unsupported<T>() -> Result<T, DeserializationError> {
+		Err(DeserializationError::invalid_format("a StatsSerializer cannot be deserialized from"))
+	}
+}
+
+
+impl PrimitiveSerializer for StatsSerializer {
+	fn serialize_bool(&mut self, _boolean: bool) {
+		self.0.bytes += 1;
+	}
+
+	fn deserialize_bool(&mut self) -> Result<bool, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn serialize_num<T: NumberType>(&mut self, _num: T) {
+		self.0.bytes += std::mem::size_of::<T>();
+	}
+
+	fn deserialize_num<T: NumberType>(&mut self) -> Result<T, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn serialize_string<T: Into<String>>(&mut self, string: T) {
+		self.0.bytes += 4 + string.into().len();
+	}
+
+	fn deserialize_string(&mut self) -> Result<String, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn serialize_bytes<T: Into<VecDeque<u8>>>(&mut self, bytes: T) {
+		self.0.bytes += 4 + bytes.into().len();
+	}
+
+	fn deserialize_bytes<T: FromIterator<u8>>(&mut self) -> Result<T, DeserializationError> {
+		Self::unsupported()
+	}
+}
+
+
+impl Serializer for StatsSerializer {
+	fn serialize<P, T: Serialize<P>>(&mut self, item: T) {
+		let mut stats = Self::stats::<P, T>(item);
+		stats.elements = 1;
+		self.0.add(stats);
+	}
+
+	fn serialize_iter<P, T: Serialize<P>, I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		let mut sequence = Self::new();
+		for item in iter {
+			sequence.serialize::<P, T>(item);
+		}
+		sequence.0.max_depth += 1;
+		self.0.add(sequence.0);
+	}
+
+	fn serialize_key<P, T: Serialize<P>, K: std::borrow::Borrow<str>>(&mut self, key: K, item: T) {
+		let key = key.borrow();
+		let mut stats = Self::stats::<P, T>(item);
+		stats.bytes += key.len();
+		self.0.bytes += stats.bytes;
+		self.0.max_depth = self.0.max_depth.max(stats.max_depth + 1);
+		self.0.add_fields(vec![(key.into(), stats)]);
+	}
+
+	fn deserialize<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn deserialize_key_internal<P, T: Deserialize<P>>(&mut self, _key: &str) -> Result<T, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn deserialize_seed<P, S: DeserializeSeed<P>>(&mut self, _seed: &mut S) -> Result<S::Value, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn deserialize_key_seed<P, S: DeserializeSeed<P>, K: std::borrow::Borrow<str>>(&mut self, _key: K, _seed: &mut S) -> Result<S::Value, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn try_get_key<K: FromStr>(&mut self) -> Option<K> {
+		None
+	}
+
+	#[cfg(feature = "text")]
+	fn deserialize_text(&mut self) -> Result<TextRepr, DeserializationError> {
+		Self::unsupported()
+	}
+}
@@ -0,0 +1,92 @@
+//! A typed key value layer over sled trees. Values are serialized through BinSerialize and BinDeserialize,
+//! and keys are serialized to bin with their natural profile, so primitives can be used as keys.
+//!
+//! Unsigned integer keys are big endian in bin, so a tree of them iterates in numeric order
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use crate::bin::{BinDeserialize, BinSerialize};
+use crate::{DeserializationError, Deserialize, Serialize};
+
+
+/// Why a value could not be stored or loaded
+#[derive(Debug)]
+pub enum StoreError {
+	Sled(sled::Error),
+	Deserialization(DeserializationError)
+}
+
+
+impl Display for StoreError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			StoreError::Sled(e) => write!(f, "failed to access the tree: {}", e),
+			StoreError::Deserialization(e) => write!(f, "failed to deserialize a stored value: {}", e)
+		}
+	}
+}
+
+
+impl Error for StoreError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			StoreError::Sled(e) => Some(e),
+			StoreError::Deserialization(e) => Some(e)
+		}
+	}
+}
+
+
+impl From<sled::Error> for StoreError {
+	fn from(value: sled::Error) -> Self {
+		Self::Sled(value)
+	}
+}
+
+
+impl From<DeserializationError> for StoreError {
+	fn from(value: DeserializationError) -> Self {
+		Self::Deserialization(value)
+	}
+}
+
+
+pub trait TreeExt {
+	/// Serialize the key and value, and insert them into the tree
+	fn put_ser<P, K: Serialize, V: BinSerialize<P>>(&self, key: K, value: V) -> Result<(), StoreError>;
+	/// Get the value at the serialized key, if there is one
+	fn get_de<P, K: Serialize, V: BinDeserialize<P>>(&self, key: K) -> Result<Option<V>, StoreError>;
+	/// Remove the value at the serialized key, returning it if there was one
+	fn remove_de<P, K: Serialize, V: BinDeserialize<P>>(&self, key: K) -> Result<Option<V>, StoreError>;
+	/// Iterate over every key and value in the tree, in the order of their serialized keys
+	fn iter_de<P, K: Deserialize, V: BinDeserialize<P>>(&self) -> impl Iterator<Item=Result<(K, V), StoreError>>;
+}
+
+
+impl TreeExt for sled::Tree {
+	fn put_ser<P, K: Serialize, V: BinSerialize<P>>(&self, key: K, value: V) -> Result<(), StoreError> {
+		self.insert(crate::bin::to_vec(key), value.serialize_bin())?;
+		Ok(())
+	}
+
+	fn get_de<P, K: Serialize, V: BinDeserialize<P>>(&self, key: K) -> Result<Option<V>, StoreError> {
+		match self.get(crate::bin::to_vec(key))? {
+			Some(x) => Ok(Some(V::deserialize_bin(x.to_vec())?)),
+			None => Ok(None)
+		}
+	}
+
+	fn remove_de<P, K: Serialize, V: BinDeserialize<P>>(&self, key: K) -> Result<Option<V>, StoreError> {
+		match self.remove(crate::bin::to_vec(key))? {
+			Some(x) => Ok(Some(V::deserialize_bin(x.to_vec())?)),
+			None => Ok(None)
+		}
+	}
+
+	fn iter_de<P, K: Deserialize, V: BinDeserialize<P>>(&self) -> impl Iterator<Item=Result<(K, V), StoreError>> {
+		self.iter().map(|x| {
+			let (key, value) = x?;
+			Ok((crate::bin::from_slice(&key)?, V::deserialize_bin(value.to_vec())?))
+		})
+	}
+}
@@ -0,0 +1,573 @@
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use super::*;
+
+
+/// A range of bytes inside the string buffer of a TextArena
+#[derive(Debug, Clone, Copy, Default)]
+struct Span {
+	start: usize,
+	end: usize
+}
+
+
+/// The first and last child of a table or array
+#[derive(Debug, Clone, Copy, Default)]
+struct List {
+	first: Option<usize>,
+	last: Option<usize>
+}
+
+
+#[derive(Debug, Clone, Copy)]
+enum Node {
+	Empty,
+	Null,
+	String(Span),
+	Integer(i64),
+	Float(f64),
+	Boolean(bool),
+	Table(List),
+	Array(List),
+}
+
+
+impl Node {
+	/// The name of the kind of value this is, as used in InvalidType errors
+	fn type_name(&self) -> &'static str {
+		match self {
+			Node::Empty => "nothing",
+			Node::Null => "null",
+			Node::String(_) => "string",
+			Node::Integer(_) => "integer",
+			Node::Float(_) => "float",
+			Node::Boolean(_) => "boolean",
+			Node::Table(_) => "table",
+			Node::Array(_) => "array"
+		}
+	}
+}
+
+
+/// An entry of a table or array. Keys of array entries are empty
+#[derive(Debug)]
+struct Child {
+	key: Span,
+	node: usize,
+	next: Option<usize>,
+	/// Set once the entry has been deserialized
+	taken: bool
+}
+
+
+/// Storage for the nodes, entries and strings of TextDocs.
+///
+/// All strings share one buffer and all nodes share one Vec,
+/// so a whole document is parsed and dropped with only a handful of allocations
+#[derive(Debug, Default)]
+pub struct TextArena {
+	strings: String,
+	nodes: Vec<Node>,
+	children: Vec<Child>,
+}
+
+
+/// A document stored inside a TextArena.
+/// Behaves the same as a TextRepr when serializing and deserializing
+pub struct TextDoc<'arena> {
+	arena: &'arena mut TextArena,
+	node: usize
+}
+
+
+impl TextArena {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Create an arena that can hold the given number of nodes and bytes of strings without reallocating
+	pub fn with_capacity(nodes: usize, string_bytes: usize) -> Self {
+		Self {
+			strings: String::with_capacity(string_bytes),
+			nodes: Vec::with_capacity(nodes),
+			children: Vec::with_capacity(nodes),
+		}
+	}
+
+	/// Remove every document from the arena, keeping the allocated memory
+	pub fn clear(&mut self) {
+		self.strings.clear();
+		self.nodes.clear();
+		self.children.clear();
+	}
+
+	/// Create a new empty document to serialize into
+	pub fn new_doc(&mut self) -> TextDoc<'_> {
+		let node = self.alloc_node(Node::Empty);
+		TextDoc { arena: self, node }
+	}
+
+	fn alloc_node(&mut self, node: Node) -> usize {
+		self.nodes.push(node);
+		self.nodes.len() - 1
+	}
+
+	fn alloc_str(&mut self, string: &str) -> Span {
+		let start = self.strings.len();
+		self.strings.push_str(string);
+		Span { start, end: self.strings.len() }
+	}
+
+	fn get_str(&self, span: Span) -> &str {
+		&self.strings[span.start..span.end]
+	}
+
+	/// Append a child to the table or array at the given node
+	fn push_child(&mut self, parent: usize, key: Span, node: usize) {
+		let idx = self.children.len();
+		self.children.push(Child { key, node, next: None, taken: false });
+		let list = match &mut self.nodes[parent] {
+			Node::Table(list) | Node::Array(list) => list,
+			_ => unreachable!()
+		};
+		match list.last.replace(idx) {
+			None => list.first = Some(idx),
+			Some(last) => self.children[last].next = Some(idx)
+		}
+	}
+
+	/// Iterate over the indices of the children that have not been deserialized yet
+	fn live_children(&self, list: List) -> impl Iterator<Item=usize> + '_ {
+		let mut next = list.first;
+		std::iter::from_fn(move || {
+			while let Some(idx) = next {
+				next = self.children[idx].next;
+				if !self.children[idx].taken {
+					return Some(idx)
+				}
+			}
+			None
+		})
+	}
+
+	fn is_empty(&self, node: usize) -> bool {
+		match self.nodes[node] {
+			Node::Empty => true,
+			Node::Table(list) | Node::Array(list) => self.live_children(list).next().is_none(),
+			_ => false
+		}
+	}
+
+	fn to_repr(&self, node: usize) -> TextRepr {
+		match self.nodes[node] {
+			Node::Empty => TextRepr::Empty,
+			Node::Null => TextRepr::Null,
+			Node::String(x) => TextRepr::String(self.get_str(x).to_string()),
+			Node::Integer(x) => TextRepr::Integer(x),
+			Node::Float(x) => TextRepr::Float(x),
+			Node::Boolean(x) => TextRepr::Boolean(x),
+			Node::Table(list) => TextRepr::Table(
+				self.live_children(list)
+					.map(|x| (self.get_str(self.children[x].key).into(), self.to_repr(self.children[x].node)))
+					.collect()
+			),
+			Node::Array(list) => TextRepr::Array(
+				self.live_children(list)
+					.map(|x| self.to_repr(self.children[x].node))
+					.collect()
+			)
+		}
+	}
+
+	/// Parse a JSON document into the arena.
+	///
+	/// Strings are copied into the shared string buffer, and containers are tracked with an explicit stack
+	/// so that deeply nested documents cannot overflow the call stack
+	pub fn parse_json(&mut self, data: &str) -> Result<TextDoc<'_>, DeserializationError> {
+		let root = self.alloc_node(Node::Empty);
+		// The containers that are still open
+		let mut stack: Vec<usize> = Vec::new();
+		// The key of the next value in the innermost table
+		let mut key: Option<Span> = None;
+		let mut started = false;
+		let bytes = data.as_bytes();
+		let mut i = 0;
+
+		while i < bytes.len() {
+			let c = bytes[i];
+			match c {
+				b' ' | b'\n' | b'\t' | b'\r' | b',' => {
+					i += 1;
+					continue
+				}
+				b'}' | b']' => {
+					let unbalanced = || DeserializationError::invalid_format_code(ErrorCode::E012_UnbalancedBraces, format!("Unbalanced braces: {}", c as char));
+					let top = stack.pop().ok_or_else(unbalanced)?;
+					match (self.nodes[top], c) {
+						(Node::Table(_), b'}') | (Node::Array(_), b']') => {}
+						_ => return Err(unbalanced())
+					}
+					if let Some(key) = key.take() {
+						return Err(DeserializationError::invalid_format_code(ErrorCode::E013_MissingValue, "missing value").set_field(self.get_str(key)))
+					}
+					i += 1;
+					continue
+				}
+				_ => {}
+			}
+
+			match stack.last() {
+				Some(&top) => if let (Node::Table(_), None) = (self.nodes[top], key) {
+					let (name, end) = read_key(data, i)?;
+					key = Some(self.alloc_str(name));
+					i = end;
+					continue
+				}
+				None => if started {
+					return Err(DeserializationError::invalid_format_code(ErrorCode::E018_TrailingData, "unexpected data after the end of the document"))
+				}
+			}
+
+			let node = match c {
+				b'{' => {
+					i += 1;
+					Node::Table(List::default())
+				}
+				b'[' => {
+					i += 1;
+					Node::Array(List::default())
+				}
+				b'"' => {
+					let end = data[(i + 1)..].find('"')
+						.ok_or_else(|| DeserializationError::invalid_format_code(ErrorCode::E015_UnterminatedString, "String is missing terminating apostrophe"))?
+						+ i + 1;
+					let span = self.alloc_str(&data[(i + 1)..end]);
+					i = end + 1;
+					Node::String(span)
+				}
+				_ => {
+					let end = data[i..].find([',', '}', ']', ' ', '\n', '\t', '\r'])
+						.map(|x| x + i)
+						.unwrap_or(data.len());
+					let token = &data[i..end];
+					i = end;
+					parse_scalar(token)?
+				}
+			};
+
+			let id = match stack.last() {
+				Some(&top) => {
+					let id = self.alloc_node(node);
+					let key = key.take().unwrap_or_default();
+					self.push_child(top, key, id);
+					id
+				}
+				None => {
+					started = true;
+					self.nodes[root] = node;
+					root
+				}
+			};
+			if let Node::Table(_) | Node::Array(_) = node {
+				stack.push(id);
+			}
+		}
+
+		if !stack.is_empty() {
+			return Err(DeserializationError::invalid_format_code(ErrorCode::E011_MissingClosingBrace, "missing closing brace"))
+		}
+		if !started {
+			return Err(DeserializationError::EOF)
+		}
+
+		Ok(TextDoc { arena: self, node: root })
+	}
+}
+
+
+/// Read a key (quoted or not) and the colon after it, returning the key and the index after the colon
+fn read_key(data: &str, start: usize) -> Result<(&str, usize), DeserializationError> {
+	let colon = data[start..].find(':')
+		.ok_or_else(|| DeserializationError::invalid_format_code(ErrorCode::E013_MissingValue, "missing value").set_field(data[start..].trim()))?
+		+ start;
+	let key = data[start..colon].trim();
+	let key = key.strip_prefix('"').and_then(|x| x.strip_suffix('"')).unwrap_or(key);
+	if key.is_empty() {
+		return Err(DeserializationError::invalid_format_code(ErrorCode::E014_MissingKey, "missing key"))
+	}
+	Ok((key, colon + 1))
+}
+
+
+fn parse_scalar(token: &str) -> Result<Node, DeserializationError> {
+	if token == "null" {
+		return Ok(Node::Null)
+	}
+	if let Ok(x) = token.parse() {
+		return Ok(Node::Boolean(x))
+	}
+	if let Ok(x) = token.parse() {
+		return Ok(Node::Integer(x))
+	}
+	if let Ok(x) = token.parse() {
+		return Ok(Node::Float(x))
+	}
+	Err(DeserializationError::new_kind(DeserializationErrorKind::NoMatch { actual: token.to_string() }))
+}
+
+
+impl<'arena> TextDoc<'arena> {
+	pub fn is_empty(&self) -> bool {
+		self.arena.is_empty(self.node)
+	}
+
+	/// Copy the parts of the document that have not been deserialized into a TextRepr
+	pub fn to_repr(&self) -> TextRepr {
+		self.arena.to_repr(self.node)
+	}
+
+	fn child(&mut self, node: usize) -> TextDoc<'_> {
+		TextDoc { arena: self.arena, node }
+	}
+
+	/// Get the node of the next value, and the array entry it belongs to, if any
+	fn pull_value(&mut self) -> Result<(usize, Option<usize>), DeserializationErrorKind> {
+		match self.arena.nodes[self.node] {
+			Node::Array(list) => {
+				let idx = self.arena.live_children(list).next().ok_or(DeserializationErrorKind::UnexpectedEOF)?;
+				self.arena.children[idx].taken = true;
+				Ok((self.arena.children[idx].node, Some(idx)))
+			}
+			Node::Empty => Err(DeserializationErrorKind::UnexpectedEOF),
+			_ => Ok((self.node, None))
+		}
+	}
+
+	/// Take the next value out of the document
+	fn pull_scalar(&mut self) -> Result<Node, DeserializationError> {
+		let (node, _) = self.pull_value().no_field()?;
+		Ok(std::mem::replace(&mut self.arena.nodes[node], Node::Empty))
+	}
+
+	fn push_value(&mut self, other: usize) {
+		match self.arena.nodes[self.node] {
+			Node::Empty => self.arena.nodes[self.node] = self.arena.nodes[other],
+			Node::Array(_) => self.arena.push_child(self.node, Span::default(), other),
+			Node::Table(_) => panic!("Tried to push a TextDoc onto a table TextDoc!"),
+			value => {
+				let first = self.arena.alloc_node(value);
+				self.arena.nodes[self.node] = Node::Array(List::default());
+				self.arena.push_child(self.node, Span::default(), first);
+				self.arena.push_child(self.node, Span::default(), other);
+			}
+		}
+	}
+
+	fn push_entry(&mut self, key: &str, other: usize) {
+		match self.arena.nodes[self.node] {
+			Node::Empty => self.arena.nodes[self.node] = Node::Table(List::default()),
+			Node::Table(_) => {}
+			_ => panic!("Tried to insert a TextDoc onto a non-empty and non-table TextDoc!")
+		}
+		let key = self.arena.alloc_str(key);
+		self.arena.push_child(self.node, key, other);
+	}
+
+	fn serialize_owned<P, T: Serialize<P>>(&mut self, item: T) -> usize {
+		let node = self.arena.alloc_node(Node::Empty);
+		item.serialize(&mut self.child(node));
+		node
+	}
+}
+
+
+impl<'arena> Debug for TextDoc<'arena> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "TextDoc({:?})", self.to_repr())
+	}
+}
+
+
+impl<'arena> PrimitiveSerializer for TextDoc<'arena> {
+	fn serialize_bool(&mut self, boolean: bool) {
+		let node = self.arena.alloc_node(Node::Boolean(boolean));
+		self.push_value(node);
+	}
+
+	fn deserialize_bool(&mut self) -> Result<bool, DeserializationError> {
+		match self.pull_scalar()? {
+			Node::Boolean(x) => Ok(x),
+			x => Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "boolean", actual: x.type_name() }))
+		}
+	}
+
+	fn serialize_num<T: NumberType>(&mut self, num: T) {
+		let node = match num.to_text() {
+			TextRepr::Integer(x) => Node::Integer(x),
+			TextRepr::Float(x) => Node::Float(x),
+			TextRepr::String(x) => Node::String(self.arena.alloc_str(&x)),
+			_ => unreachable!()
+		};
+		let node = self.arena.alloc_node(node);
+		self.push_value(node);
+	}
+
+	fn deserialize_num<T: NumberType>(&mut self) -> Result<T, DeserializationError> {
+		match self.pull_scalar()? {
+			Node::Integer(x) => int_to_number(x),
+			Node::Float(x) => T::from_f64(x).ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "integer", actual: "float" })),
+			Node::String(x) => big_int_to_number(self.arena.get_str(x))
+				.unwrap_or_else(|| Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "number", actual: "string" }))),
+			x => Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "number", actual: x.type_name() }))
+		}
+	}
+
+	fn serialize_string<T: Into<String>>(&mut self, string: T) {
+		let span = self.arena.alloc_str(&string.into());
+		let node = self.arena.alloc_node(Node::String(span));
+		self.push_value(node);
+	}
+
+	fn deserialize_string(&mut self) -> Result<String, DeserializationError> {
+		match self.pull_scalar()? {
+			Node::String(x) => Ok(self.arena.get_str(x).to_string()),
+			x => Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "string", actual: x.type_name() }))
+		}
+	}
+
+	fn serialize_bytes<T: Into<VecDeque<u8>>>(&mut self, bytes: T) {
+		let node = self.arena.alloc_node(Node::Array(List::default()));
+		for byte in bytes.into() {
+			let child = self.arena.alloc_node(Node::Integer(byte as i64));
+			self.arena.push_child(node, Span::default(), child);
+		}
+		self.push_value(node);
+	}
+
+	fn deserialize_bytes<T: FromIterator<u8>>(&mut self) -> Result<T, DeserializationError> {
+		let list = match self.pull_scalar()? {
+			Node::Array(list) => list,
+			x => return Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "array", actual: x.type_name() }))
+		};
+		let arena = &*self.arena;
+		arena.live_children(list)
+			.map(|x| match arena.nodes[arena.children[x].node] {
+				Node::Integer(x) => Ok(x as u8),
+				x => Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "byte", actual: x.type_name() }))
+			})
+			.collect()
+	}
+}
+
+
+impl<'arena> Serializer for TextDoc<'arena> {
+	fn serialize<P, T: Serialize<P>>(&mut self, item: T) {
+		let node = self.serialize_owned(item);
+		self.push_value(node);
+	}
+
+	/// Always writes an array, so that empty and single item sequences keep their shape
+	fn serialize_iter<P, T: Serialize<P>, I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		let node = self.arena.alloc_node(Node::Array(List::default()));
+		for item in iter {
+			let child = self.serialize_owned(item);
+			self.arena.push_child(node, Span::default(), child);
+		}
+		self.push_value(node);
+	}
+
+	fn serialize_tuple<F: FnOnce(&mut Self)>(&mut self, f: F) {
+		let node = self.arena.alloc_node(Node::Array(List::default()));
+		// Point this doc at the array while the elements are pushed onto it
+		let parent = std::mem::replace(&mut self.node, node);
+		f(self);
+		self.node = parent;
+		self.push_value(node);
+	}
+
+	fn serialize_key<P, T: Serialize<P>, K: Borrow<str>>(&mut self, key: K, item: T) {
+		let key = key.borrow();
+		observe::field(key, |measure| {
+			let node = self.serialize_owned(item);
+			let bytes = if measure { self.arena.to_repr(node).to_string().len() } else { 0 };
+			self.push_entry(key, node);
+			bytes
+		});
+	}
+
+	fn deserialize<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError> {
+		self.deserialize_seed::<P, _>(&mut PhantomData::<T>)
+	}
+
+	fn deserialize_key_internal<P, T: Deserialize<P>>(&mut self, key: &str) -> Result<T, DeserializationError> {
+		self.deserialize_key_seed::<P, _, _>(key, &mut PhantomData::<T>)
+	}
+
+	fn deserialize_seed<P, S: DeserializeSeed<P>>(&mut self, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		let (node, entry) = self.pull_value().no_field()?;
+		let result = seed.deserialize(&mut self.child(node));
+		if let Some(entry) = entry {
+			self.arena.children[entry].taken = self.arena.is_empty(node);
+		}
+		result
+	}
+
+	fn deserialize_key_seed<P, S: DeserializeSeed<P>, K: Borrow<str>>(&mut self, key: K, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		let key = key.borrow();
+		let list = match self.arena.nodes[self.node] {
+			Node::Table(list) => list,
+			_ => return Err(DeserializationError::new(key, DeserializationErrorKind::InvalidType { expected: "table", actual: "non-table" }))
+		};
+		let entry = self.arena.live_children(list)
+			.find(|x| self.arena.get_str(self.arena.children[*x].key) == key)
+			.ok_or_else(|| DeserializationError::missing_field(key))?;
+		let node = self.arena.children[entry].node;
+		let result = warning::nest(key, || seed.deserialize(&mut self.child(node))).map_err(|e| { e.nest().set_field(key) });
+		self.arena.children[entry].taken = self.arena.is_empty(node);
+		result
+	}
+
+	fn serialize_option<P, T: Serialize<P>>(&mut self, item: Option<T>) {
+		match item {
+			Some(x) => x.serialize(self),
+			None => {
+				let node = self.arena.alloc_node(Node::Null);
+				self.push_value(node);
+			}
+		}
+	}
+
+	fn deserialize_option<P, T: Deserialize<P>>(&mut self) -> Result<Option<T>, DeserializationError> {
+		if let Node::Null = self.arena.nodes[self.node] {
+			self.arena.nodes[self.node] = Node::Empty;
+			return Ok(None)
+		}
+		T::deserialize(self).map(Some)
+	}
+
+	fn try_get_key<K: FromStr>(&mut self) -> Option<K> {
+		match self.arena.nodes[self.node] {
+			Node::Table(list) => self.arena.live_children(list)
+				.next()
+				.and_then(|x| K::from_str(self.arena.get_str(self.arena.children[x].key)).ok()),
+			_ => None
+		}
+	}
+
+	fn remaining_keys(&self) -> Vec<String> {
+		match self.arena.nodes[self.node] {
+			Node::Table(list) => self.arena.live_children(list)
+				.map(|x| self.arena.get_str(self.arena.children[x].key).into())
+				.collect(),
+			_ => Vec::new()
+		}
+	}
+
+	fn deserialize_text(&mut self) -> Result<TextRepr, DeserializationError> {
+		let out = self.to_repr();
+		self.arena.nodes[self.node] = Node::Empty;
+		Ok(out)
+	}
+}
@@ -0,0 +1,63 @@
+//! JSON bodies for reqwest, through JSONSerialize and JSONDeserialize instead of serde
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+
+use super::json::{JSONDeserialize, JSONSerialize};
+use super::*;
+
+
+/// Why the body of a response could not be read
+#[derive(Debug)]
+pub enum ResponseError {
+	Http(reqwest::Error),
+	Deserialization(DeserializationError)
+}
+
+
+impl Display for ResponseError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ResponseError::Http(e) => write!(f, "failed to read the response body: {}", e),
+			ResponseError::Deserialization(e) => write!(f, "failed to deserialize the response body: {}", e)
+		}
+	}
+}
+
+
+impl Error for ResponseError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			ResponseError::Http(e) => Some(e),
+			ResponseError::Deserialization(e) => Some(e)
+		}
+	}
+}
+
+
+pub trait RequestBuilderExt {
+	/// Set the body to the given value as JSON, with a content type of application/json
+	fn simple_json<P, T: JSONSerialize<P>>(self, value: T) -> Self;
+}
+
+
+impl RequestBuilderExt for reqwest::RequestBuilder {
+	fn simple_json<P, T: JSONSerialize<P>>(self, value: T) -> Self {
+		self.header(reqwest::header::CONTENT_TYPE, "application/json")
+			.body(value.serialize_json_compact())
+	}
+}
+
+
+pub trait ResponseExt {
+	/// Read the body as JSON and deserialize it
+	fn simple_json<T: JSONDeserialize<P>, P>(self) -> impl Future<Output=Result<T, ResponseError>> + Send;
+}
+
+
+impl ResponseExt for reqwest::Response {
+	async fn simple_json<T: JSONDeserialize<P>, P>(self) -> Result<T, ResponseError> {
+		let body = self.text().await.map_err(ResponseError::Http)?;
+		T::deserialize_json(body).map_err(ResponseError::Deserialization)
+	}
+}
@@ -0,0 +1,204 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::fs::read_to_string;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::*;
+
+
+/// An error produced while loading a layered configuration
+#[derive(Debug)]
+pub enum LoadError {
+	/// A configuration file could not be read
+	Io {
+		path: PathBuf,
+		error: io::Error
+	},
+	/// A configuration file could not be parsed
+	Parse {
+		path: PathBuf,
+		error: DeserializationError
+	},
+	/// The merged document could not be deserialized
+	Deserialize {
+		/// The path of the field that failed, outermost first
+		path: Vec<String>,
+		/// The name of the last layer that set the failing field, or its closest parent
+		source: Option<String>,
+		error: DeserializationError
+	}
+}
+
+
+impl Display for LoadError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			LoadError::Io { path, error } => write!(f, "could not read {}: {}", path.display(), error),
+			LoadError::Parse { path, error } => write!(f, "could not parse {}: {}", path.display(), error),
+			LoadError::Deserialize { source: Some(source), error, .. } => write!(f, "{} (set by {})", error, source),
+			LoadError::Deserialize { source: None, error, .. } => write!(f, "{}", error)
+		}
+	}
+}
+
+
+impl Error for LoadError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			LoadError::Io { error, .. } => Some(error),
+			LoadError::Parse { error, .. } | LoadError::Deserialize { error, .. } => Some(error)
+		}
+	}
+}
+
+
+/// Loads a configuration from several layers, such as defaults, files and overrides.
+/// Layers are added from lowest to highest priority, and are deep merged before deserializing
+#[derive(Debug, Default)]
+pub struct ConfigLoader {
+	layers: Vec<(String, TextRepr)>
+}
+
+
+impl ConfigLoader {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add a layer with the given name, which is reported if one of its fields fails to deserialize
+	pub fn add_layer<N: ToString>(&mut self, name: N, repr: TextRepr) -> &mut Self {
+		self.layers.push((name.to_string(), repr));
+		self
+	}
+
+	pub fn add_defaults(&mut self, repr: TextRepr) -> &mut Self {
+		self.add_layer("defaults", repr)
+	}
+
+	pub fn add_overrides(&mut self, repr: TextRepr) -> &mut Self {
+		self.add_layer("overrides", repr)
+	}
+
+	/// Add a file as a layer. The format is picked from the file extension
+	/// (json, toml or mlist), and is detected from the contents otherwise.
+	/// Include directives in toml and mlist files are resolved
+	pub fn add_file<T: AsRef<Path>>(&mut self, path: T) -> Result<&mut Self, LoadError> {
+		let path = path.as_ref();
+		let repr = match path.extension().and_then(|x| x.to_str()) {
+			Some("toml") => TextRepr::from_toml_file(path),
+			Some("mlist") => TextRepr::from_mlist_file(path),
+			extension => {
+				let data = read_to_string(path).map_err(|error| LoadError::Io { path: path.into(), error })?;
+				if extension == Some("json") {
+					TextRepr::from_json(data)
+				} else {
+					data.parse()
+				}
+			}
+		}.map_err(|error| LoadError::Parse { path: path.into(), error })?;
+		Ok(self.add_layer(path.display(), repr))
+	}
+
+	/// Add every environment variable starting with prefix as its own layer.
+	/// The rest of the name is split on double underscores and lowercased into a path,
+	/// so with the prefix `APP_`, `APP_SERVER__PORT=8080` sets `server.port` to 8080
+	pub fn add_env(&mut self, prefix: &str) -> &mut Self {
+		self.add_env_from(prefix, std::env::vars())
+	}
+
+	/// Same as add_env, but reads the variables from the given iterator instead of the environment
+	pub fn add_env_from<I: IntoIterator<Item=(String, String)>>(&mut self, prefix: &str, vars: I) -> &mut Self {
+		let mut vars: Vec<_> = vars.into_iter()
+			.filter(|(name, _)| name.len() > prefix.len() && name.starts_with(prefix))
+			.collect();
+		// Keeps the precedence between overlapping variables stable
+		vars.sort();
+
+		for (name, value) in vars {
+			let mut path: Vec<TableKey> = name[prefix.len()..]
+				.split("__")
+				.map(|x| x.to_lowercase().into())
+				.collect();
+			if path.iter().any(|x| x.is_empty()) {
+				continue
+			}
+			path.reverse();
+
+			let value = TextRepr::from_str_value(value.clone()).unwrap_or(TextRepr::String(value));
+			let mut repr = TextRepr::new();
+			repr.push_entry_path(path, value);
+			self.add_layer(name, repr);
+		}
+		self
+	}
+
+	/// Merge all layers into a single document, along with the name of the
+	/// last layer that set each path in it
+	fn merge(self) -> (TextRepr, HashMap<Vec<String>, String>) {
+		let mut out = TextRepr::new();
+		let mut sources: HashMap<Vec<String>, String> = HashMap::new();
+
+		for (name, repr) in self.layers {
+			repr.walk(|path, value| {
+				if path.is_empty() {
+					return
+				}
+				let path: Vec<String> = path.iter().map(ToString::to_string).collect();
+				if !matches!(value, TextRepr::Table(_) | TextRepr::Empty) {
+					sources.retain(|x, _| !x.starts_with(&path) || x.len() == path.len());
+				}
+				sources.insert(path, name.clone());
+			});
+			out.merge(repr);
+		}
+
+		(out, sources)
+	}
+
+	/// Deep merge every layer, then deserialize the result
+	pub fn load<P, T: Deserialize<P>>(self) -> Result<T, LoadError> {
+		let (mut repr, sources) = self.merge();
+
+		T::deserialize(&mut repr).map_err(|error| {
+			let mut path = Vec::new();
+			let mut current = &error;
+			loop {
+				if let Some(x) = &current.field {
+					path.push(x.clone());
+				}
+				match &current.kind {
+					DeserializationErrorKind::Nested(x) => current = x,
+					_ => break
+				}
+			}
+			let source = (0..=path.len()).rev().find_map(|i| sources.get(&path[..i]).cloned());
+			LoadError::Deserialize { path, source, error }
+		})
+	}
+}
+
+
+/// Write the default value of a type as TOML, with every field present,
+/// so it can be shipped as an example configuration
+pub fn template<T: Default + Serialize<ReadableProfile>>() -> String {
+	let mut out = TextRepr::new();
+	T::default().serialize(&mut out);
+	out.to_toml()
+}
+
+
+/// Same as template, but writes the documentation of each field as comments
+pub fn documented_template<T: Default + Serialize<ReadableProfile> + FieldDocs>() -> String {
+	let mut out = TextRepr::new();
+	T::default().serialize(&mut out);
+	out.to_toml_commented(T::FIELD_DOCS)
+}
+
+
+/// Same as documented_template, but writes JSON, with the documentation in `_comments` tables
+pub fn documented_json_template<T: Default + Serialize<ReadableProfile> + FieldDocs>() -> String {
+	let mut out = TextRepr::new();
+	T::default().serialize(&mut out);
+	out.to_json_commented(T::FIELD_DOCS)
+}
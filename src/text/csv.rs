@@ -0,0 +1,315 @@
+//! CSV, for sequences of tables such as a Vec of structs.
+//!
+//! The header row holds the keys of the tables. Nested tables and arrays are flattened into dotted keys such as
+//! `server.port` and `tags.0`, while empty ones are written as `{}` and `[]`. Quoted cells are always read as
+//! strings, and other cells are read as booleans or numbers where they can be, so strings that would be read as
+//! something else are quoted when writing. Empty cells are left out of their row, so they deserialize as missing fields
+use std::collections::HashMap;
+use std::mem::take;
+
+use super::*;
+
+
+fn error<T: Display>(line: usize, reason: T) -> DeserializationError {
+	DeserializationError::invalid_format(format!("line {}: {}", line, reason))
+}
+
+
+/// Add the cells of a value to a row, under the column of the key it is at
+fn flatten(value: TextRepr, column: String, row: &mut Vec<(String, TextRepr)>) {
+	let join = |key: &str| if column.is_empty() { key.to_string() } else { format!("{}.{}", column, key) };
+	match value {
+		TextRepr::Empty | TextRepr::Null => {}
+		TextRepr::Table(x) if !x.is_empty() => for (key, value) in x {
+			flatten(value, join(&key), row);
+		}
+		TextRepr::Array(x) if !x.is_empty() => for (i, value) in x.into_iter().enumerate() {
+			flatten(value, join(&i.to_string()), row);
+		}
+		value => row.push((column, value))
+	}
+}
+
+
+/// Read a cell that was not quoted
+fn parse_cell(cell: &str) -> Option<TextRepr> {
+	Some(match cell {
+		"" => return None,
+		"true" => TextRepr::Boolean(true),
+		"false" => TextRepr::Boolean(false),
+		"[]" => TextRepr::Array(VecDeque::new()),
+		"{}" => TextRepr::Table(Table::default()),
+		"inf" | "-inf" | "NaN" => TextRepr::Float(cell.parse().unwrap()),
+		_ => {
+			if let Ok(x) = cell.parse() {
+				return Some(TextRepr::Integer(x))
+			}
+			// Rust also parses words such as infinity as floats, which are left as strings
+			if cell.trim_start_matches(['+', '-']).starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+				if let Ok(x) = cell.parse() {
+					return Some(TextRepr::Float(x))
+				}
+			}
+			TextRepr::String(cell.into())
+		}
+	})
+}
+
+
+fn write_cell(out: &mut String, cell: &str, quote: bool) {
+	if !quote && !cell.contains([',', '"', '\n', '\r']) {
+		out.push_str(cell);
+		return
+	}
+	out.push('"');
+	out.push_str(&cell.replace('"', "\"\""));
+	out.push('"');
+}
+
+
+fn write_value(out: &mut String, value: TextRepr) {
+	match value {
+		// Quoted unless it would be read back as the same string without them
+		TextRepr::String(x) => {
+			let quote = !matches!(parse_cell(&x), Some(TextRepr::String(_)));
+			write_cell(out, &x, quote);
+		}
+		TextRepr::Integer(x) => out.push_str(&x.to_string()),
+		TextRepr::Float(x) => out.push_str(&x.to_string()),
+		TextRepr::Boolean(x) => out.push_str(&x.to_string()),
+		TextRepr::Table(_) => out.push_str("{}"),
+		TextRepr::Array(_) => out.push_str("[]"),
+		TextRepr::Empty | TextRepr::Null => {}
+	}
+}
+
+
+/// The cells of a line of CSV, each with whether it was quoted
+type Record = Vec<(String, bool)>;
+
+
+/// Split CSV into records, each with the line it starts on
+fn records(data: &str) -> Result<Vec<(usize, Record)>, DeserializationError> {
+	let mut out = Vec::new();
+	let mut record = Vec::new();
+	let mut cell = String::new();
+	let mut quoted = false;
+	let mut line = 1;
+	let mut start = 1;
+	let mut chars = data.chars().peekable();
+
+	while let Some(c) = chars.next() {
+		match c {
+			'"' if cell.is_empty() && !quoted => {
+				quoted = true;
+				loop {
+					match chars.next() {
+						Some('"') if chars.peek() == Some(&'"') => {
+							chars.next();
+							cell.push('"');
+						}
+						Some('"') => break,
+						Some(c) => {
+							if c == '\n' {
+								line += 1;
+							}
+							cell.push(c);
+						}
+						None => return Err(error(start, "quoted cell is never closed"))
+					}
+				}
+				if !matches!(chars.peek(), None | Some(',' | '\n' | '\r')) {
+					return Err(error(line, "unexpected characters after a quoted cell"))
+				}
+			}
+			',' => record.push((take(&mut cell), take(&mut quoted))),
+			'\r' if chars.peek() == Some(&'\n') => {}
+			'\n' => {
+				record.push((take(&mut cell), take(&mut quoted)));
+				out.push((start, take(&mut record)));
+				line += 1;
+				start = line;
+			}
+			c => cell.push(c)
+		}
+	}
+	if !record.is_empty() || !cell.is_empty() || quoted {
+		record.push((cell, quoted));
+		out.push((start, record));
+	}
+
+	// Blank lines hold a single empty cell
+	out.retain(|(_, record)| !matches!(record.as_slice(), [(x, false)] if x.is_empty()));
+	Ok(out)
+}
+
+
+/// Insert a value at the path of keys into a row, returning false if it conflicts with a value that is already there
+fn insert(table: &mut Table, path: &[TableKey], value: TextRepr) -> bool {
+	match path {
+		[] => false,
+		[key] => table.insert(key.clone(), value).is_none(),
+		[key, rest @ ..] => match table.entry(key.clone()).or_insert_with(|| TextRepr::Table(Table::default())) {
+			TextRepr::Table(x) => insert(x, rest, value),
+			_ => false
+		}
+	}
+}
+
+
+/// Turn tables whose keys are the indices from 0 into arrays, reversing how arrays are flattened into columns
+fn restore_arrays(value: TextRepr) -> TextRepr {
+	match value {
+		TextRepr::Table(mut x) => {
+			if !x.is_empty() && (0..x.len()).all(|i| x.contains_key(i.to_string().as_str())) {
+				TextRepr::Array((0..x.len()).map(|i| restore_arrays(remove_entry(&mut x, &i.to_string()).unwrap())).collect())
+			} else {
+				TextRepr::Table(x.into_iter().map(|(key, value)| (key, restore_arrays(value))).collect())
+			}
+		}
+		x => x
+	}
+}
+
+
+impl TextRepr {
+	pub fn is_valid_csv<T: ToString>(data: T) -> bool {
+		Self::from_csv(data.to_string()).is_ok()
+	}
+
+	/// Write an array of tables as CSV, with a column for each key in the order they first appear.
+	/// Any other value is written as a single row
+	pub fn to_csv(self) -> String {
+		let rows: Vec<_> = match self {
+			TextRepr::Array(x) => x.into_iter().collect(),
+			TextRepr::Empty => Vec::new(),
+			x => vec![x]
+		};
+
+		let mut columns = Vec::new();
+		let mut indices = HashMap::new();
+		let rows: Vec<_> = rows.into_iter()
+			.map(|row| {
+				let mut cells = Vec::new();
+				flatten(row, String::new(), &mut cells);
+				cells.into_iter()
+					.map(|(column, value)| {
+						let index = *indices.entry(column).or_insert_with_key(|column| {
+							columns.push(column.clone());
+							columns.len() - 1
+						});
+						(index, value)
+					})
+					.collect::<Vec<_>>()
+			})
+			.collect();
+
+		let mut out = String::new();
+		for (i, column) in columns.iter().enumerate() {
+			if i > 0 {
+				out.push(',');
+			}
+			write_cell(&mut out, column, false);
+		}
+		out.push('\n');
+		for row in rows {
+			let mut cells = vec![TextRepr::Empty; columns.len()];
+			for (index, value) in row {
+				cells[index] = value;
+			}
+			for (i, value) in cells.into_iter().enumerate() {
+				if i > 0 {
+					out.push(',');
+				}
+				write_value(&mut out, value);
+			}
+			out.push('\n');
+		}
+		out
+	}
+
+	/// Parse CSV with a header row into an array with a table for each of the other rows
+	pub fn from_csv(data: String) -> Result<Self, DeserializationError> {
+		let mut records = records(&data)?.into_iter();
+		let mut interner = KeyInterner::new();
+		let header: Vec<Vec<TableKey>> = match records.next() {
+			Some((_, header)) => header.into_iter()
+				.map(|(column, _)| column.split('.').map(|x| interner.intern(x)).collect())
+				.collect(),
+			None => return Ok(Self::Array(VecDeque::new()))
+		};
+
+		let mut out = VecDeque::new();
+		for (line, record) in records {
+			if record.len() > header.len() {
+				return Err(error(line, format!("row has {} cells, but the header has {}", record.len(), header.len())))
+			}
+			let mut row = Table::default();
+			for (path, (cell, quoted)) in header.iter().zip(record) {
+				let value = if quoted {
+					TextRepr::String(cell)
+				} else {
+					match parse_cell(&cell) {
+						Some(x) => x,
+						None => continue
+					}
+				};
+				if !insert(&mut row, path, value) {
+					return Err(error(line, format!("column {} conflicts with another column", path.join("."))))
+				}
+			}
+			out.push_back(TextRepr::Table(row.into_iter().map(|(key, value)| (key, restore_arrays(value))).collect()));
+		}
+		Ok(Self::Array(out))
+	}
+}
+
+
+/// Serialize rows as CSV, without implementing CSVSerialize for them
+pub fn to_string<P, T: Serialize<P>, I: IntoIterator<Item=T>>(rows: I) -> String {
+	let mut out = TextRepr::Array(VecDeque::new());
+	for row in rows {
+		let mut repr = TextRepr::new();
+		row.serialize(&mut repr);
+		out.push_value(repr);
+	}
+	out.to_csv()
+}
+
+
+/// Deserialize the rows of CSV, without implementing CSVDeserialize for them.
+/// Errors name the row that could not be deserialized, counting from 1 after the header
+pub fn from_str<P, T: Deserialize<P>>(data: &str) -> Result<Vec<T>, DeserializationError> {
+	let rows = match TextRepr::from_csv(data.into())? {
+		TextRepr::Array(x) => x,
+		_ => unreachable!()
+	};
+	rows.into_iter()
+		.enumerate()
+		.map(|(i, mut row)| T::deserialize(&mut row).map_err(|e| e.nest().set_field(format!("row {}", i + 1))))
+		.collect()
+}
+
+
+pub trait CSVSerialize<P = NaturalProfile> {
+	fn serialize_csv(self) -> String;
+}
+
+
+pub trait CSVDeserialize<P = NaturalProfile>: Sized {
+	fn deserialize_csv(data: String) -> Result<Self, DeserializationError>;
+}
+
+
+impl<P, V: Serialize<P>> CSVSerialize<P> for Vec<V> {
+	fn serialize_csv(self) -> String {
+		to_string(self)
+	}
+}
+
+
+impl<P, V: Deserialize<P>> CSVDeserialize<P> for Vec<V> {
+	fn deserialize_csv(data: String) -> Result<Self, DeserializationError> {
+		from_str(&data)
+	}
+}
@@ -0,0 +1,142 @@
+//! Snapshots and deltas of large state. A Snapshot holds the last state that was sent in full or as a delta,
+//! and only the parts that changed since then are put in the next Delta, which the receiving Snapshot applies
+//! to its own copy. Tables are compared entry by entry, while any other value is replaced as a whole
+use super::*;
+
+
+/// A value that changed, and the path of keys leading to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+	pub path: Vec<String>,
+	pub value: TextRepr
+}
+
+
+/// The changes from one TextRepr to another
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Delta {
+	/// The values that were added or changed
+	pub changed: Vec<Change>,
+	/// The paths of the table entries that were removed
+	pub removed: Vec<Vec<String>>
+}
+
+
+impl_key_serde!(Change, NaturalProfile, path, value);
+impl_key_serde!(Delta, NaturalProfile, changed, removed);
+
+
+impl Delta {
+	pub fn is_empty(&self) -> bool {
+		self.changed.is_empty() && self.removed.is_empty()
+	}
+}
+
+
+fn diff_inner(old: &TextRepr, new: &TextRepr, path: &mut Vec<String>, delta: &mut Delta) {
+	match (old, new) {
+		(TextRepr::Table(old), TextRepr::Table(new)) => {
+			for (key, value) in new {
+				path.push(key.to_string());
+				match old.get(key) {
+					Some(x) => diff_inner(x, value, path, delta),
+					None => delta.changed.push(Change { path: path.clone(), value: value.clone() })
+				}
+				path.pop();
+			}
+			for key in old.keys().filter(|x| !new.contains_key(*x)) {
+				path.push(key.to_string());
+				delta.removed.push(path.clone());
+				path.pop();
+			}
+		}
+		(old, new) => if old != new {
+			delta.changed.push(Change { path: path.clone(), value: new.clone() });
+		}
+	}
+}
+
+
+fn remove_path(repr: &mut TextRepr, path: &[String]) {
+	if let TextRepr::Table(x) = repr {
+		match path {
+			[key] => { remove_entry(x, key); }
+			[key, rest @ ..] => if let Some(x) = x.get_mut(key.as_str()) {
+				remove_path(x, rest);
+			}
+			[] => {}
+		}
+	}
+}
+
+
+impl TextRepr {
+	/// The changes that turn this TextRepr into the new one
+	pub fn diff(&self, new: &Self) -> Delta {
+		let mut delta = Delta::default();
+		diff_inner(self, new, &mut Vec::new(), &mut delta);
+		delta
+	}
+
+	/// Apply the changes of a delta made by diff
+	pub fn apply_delta(&mut self, delta: Delta) {
+		for path in delta.removed {
+			remove_path(self, &path);
+		}
+		for change in delta.changed {
+			if change.path.is_empty() {
+				*self = change.value;
+				continue
+			}
+			let mut current = &mut *self;
+			for key in change.path {
+				if !matches!(current, TextRepr::Table(_)) {
+					*current = TextRepr::Table(Table::default());
+				}
+				current = match current {
+					TextRepr::Table(x) => x.entry(key.into()).or_default(),
+					_ => unreachable!()
+				};
+			}
+			*current = change.value;
+		}
+	}
+}
+
+
+/// The last state sent or received, which deltas are made against or applied to
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+	base: TextRepr
+}
+
+
+impl Snapshot {
+	/// Start from the full state of item, which should be sent to the receiver with `base`
+	pub fn new<P, T: Serialize<P>>(item: T) -> Self {
+		Self { base: serialize_owned!(item) }
+	}
+
+	/// Start from a base received in full
+	pub fn from_base(base: TextRepr) -> Self {
+		Self { base }
+	}
+
+	pub fn base(&self) -> &TextRepr {
+		&self.base
+	}
+
+	/// The changes since the last state, which then becomes the state of item
+	pub fn delta<P, T: Serialize<P>>(&mut self, item: T) -> Delta {
+		let new = serialize_owned!(item);
+		let delta = self.base.diff(&new);
+		self.base = new;
+		delta
+	}
+
+	/// Apply a delta received from the sender, and deserialize the updated state
+	pub fn apply<P, T: Deserialize<P>>(&mut self, delta: Delta) -> Result<T, DeserializationError> {
+		self.base.apply_delta(delta);
+		T::deserialize(&mut self.base.clone())
+	}
+}
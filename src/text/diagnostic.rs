@@ -0,0 +1,103 @@
+use std::error::Error;
+use std::iter::once;
+
+use miette::{Diagnostic, LabeledSpan, NamedSource, SourceCode, SourceSpan};
+
+use super::*;
+
+
+/// A parse error along with the document it came from, which miette can render
+/// with a label pointing at the offending line
+#[derive(Debug)]
+pub struct SourceError {
+	source_code: Box<NamedSource<String>>,
+	span: SourceSpan,
+	pub error: DeserializationError
+}
+
+
+impl Display for SourceError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.error)
+	}
+}
+
+
+impl Error for SourceError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		Some(&self.error)
+	}
+}
+
+
+impl Diagnostic for SourceError {
+	fn source_code(&self) -> Option<&dyn SourceCode> {
+		Some(self.source_code.as_ref())
+	}
+
+	fn labels(&self) -> Option<Box<dyn Iterator<Item=LabeledSpan> + '_>> {
+		Some(Box::new(once(LabeledSpan::new_with_span(Some(self.error.kind.to_string()), self.span))))
+	}
+}
+
+
+/// The span of each line, without its line break
+fn line_spans(data: &str) -> Vec<(usize, usize)> {
+	let mut start = 0;
+	data.split_inclusive('\n')
+		.map(|line| {
+			let span = (start, line.trim_end_matches(['\n', '\r']).len());
+			start += line.len();
+			span
+		})
+		.collect()
+}
+
+
+/// Find the span to point at for an error in data.
+/// Line based formats are reparsed line by line until the first line that fails,
+/// while JSON points at the key of the failing field if it can be found
+fn locate(data: &str, format: TextFormat, error: &DeserializationError) -> SourceSpan {
+	let lines = line_spans(data);
+	let parse = match format {
+		TextFormat::Json => None,
+		TextFormat::Toml => Some(TextRepr::from_toml as fn(String) -> Result<TextRepr, DeserializationError>),
+		TextFormat::MList => Some(TextRepr::from_mlist as fn(String) -> Result<TextRepr, DeserializationError>)
+	};
+	if let Some(parse) = parse {
+		for (i, &(start, len)) in lines.iter().enumerate() {
+			let end = lines.get(i + 1).map_or(data.len(), |x| x.0);
+			if parse(data[..end].to_string()).is_err() {
+				return (start, len).into()
+			}
+		}
+	}
+	if let Some(field) = &error.field {
+		let field = field.trim_matches('"');
+		if let Some(offset) = data.find(field).filter(|_| !field.is_empty()) {
+			return (offset, field.len()).into()
+		}
+	}
+	match lines.last() {
+		Some(&(start, len)) => (start, len).into(),
+		None => (0, 0).into()
+	}
+}
+
+
+impl TextRepr {
+	/// Parse data in the given format. On failure, the error keeps the data and the location
+	/// of the problem so it can be rendered as a miette diagnostic
+	pub fn parse_with_source<N: AsRef<str>>(name: N, data: String, format: TextFormat) -> Result<Self, SourceError> {
+		let result = match format {
+			TextFormat::Json => Self::from_json(data.clone()),
+			TextFormat::Toml => Self::from_toml(data.clone()),
+			TextFormat::MList => Self::from_mlist(data.clone())
+		};
+		result.map_err(|error| SourceError {
+			span: locate(&data, format, &error),
+			source_code: Box::new(NamedSource::new(name, data)),
+			error
+		})
+	}
+}
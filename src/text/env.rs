@@ -0,0 +1,271 @@
+//! Dotenv files of `KEY=value` lines, as read by docker compose and most dotenv libraries.
+//!
+//! Only flat tables can be written, such as a struct of strings, numbers and booleans. Keys are written in upper case
+//! and read in lower case, so `PORT=80` is read into a field named `port`. Lines may start with `export`, and comments
+//! start with `#`. Single quoted values are read as they are and double quoted values may hold escapes such as `\n`,
+//! while both may span lines. Quoted values are always read as strings, and other values are read as booleans or
+//! numbers where they can be, so strings that would be read as something else are quoted when writing.
+//! Values that are empty and not quoted are left out, so they deserialize as missing fields.
+//! Variables such as `${HOME}` are not expanded
+use std::fmt::Write;
+
+use super::*;
+
+
+/// Read a value that was not quoted
+fn parse_value(value: &str) -> Option<TextRepr> {
+	Some(match value {
+		"" => return None,
+		"true" => TextRepr::Boolean(true),
+		"false" => TextRepr::Boolean(false),
+		"inf" | "-inf" | "NaN" => TextRepr::Float(value.parse().unwrap()),
+		_ => {
+			if let Ok(x) = value.parse() {
+				return Some(TextRepr::Integer(x))
+			}
+			// Rust also parses words such as infinity as floats, which are left as strings
+			if value.trim_start_matches(['+', '-']).starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+				if let Ok(x) = value.parse() {
+					return Some(TextRepr::Float(x))
+				}
+			}
+			TextRepr::String(value.into())
+		}
+	})
+}
+
+
+fn write_string(out: &mut String, string: &str) {
+	let plain = matches!(parse_value(string), Some(TextRepr::String(_)))
+		&& string.trim() == string
+		&& !string.contains(['#', '"', '\'', '\\', '$', '\n', '\r']);
+	if plain {
+		out.push_str(string);
+		return
+	}
+	out.push('"');
+	for c in string.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'$' => out.push_str("\\$"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			c => out.push(c)
+		}
+	}
+	out.push('"');
+}
+
+
+struct Parser {
+	chars: Vec<char>,
+	pos: usize,
+	interner: KeyInterner
+}
+
+
+impl Parser {
+	fn error<T: Display>(&self, reason: T) -> DeserializationError {
+		let line = self.chars[..self.pos.min(self.chars.len())].iter().filter(|x| **x == '\n').count() + 1;
+		DeserializationError::invalid_format(format!("line {}: {}", line, reason))
+	}
+
+	fn peek(&self) -> Option<char> {
+		self.chars.get(self.pos).copied()
+	}
+
+	/// Skip spaces and tabs, but not the end of the line
+	fn skip_spaces(&mut self) {
+		while self.peek().is_some_and(|c| c == ' ' || c == '\t') {
+			self.pos += 1;
+		}
+	}
+
+	/// Skip to the end of the line, leaving the newline
+	fn skip_line(&mut self) {
+		while self.peek().is_some_and(|c| c != '\n') {
+			self.pos += 1;
+		}
+	}
+
+	fn name(&mut self) -> String {
+		let mut out = String::new();
+		while let Some(c) = self.peek().filter(|c| c.is_alphanumeric() || matches!(c, '_' | '.')) {
+			out.push(c);
+			self.pos += 1;
+		}
+		out
+	}
+
+	fn quoted(&mut self, quote: char) -> Result<String, DeserializationError> {
+		let start = self.pos;
+		self.pos += 1;
+		let mut out = String::new();
+		loop {
+			let Some(c) = self.peek() else {
+				// Point at the line the value starts on
+				self.pos = start;
+				return Err(self.error("value is never closed"))
+			};
+			self.pos += 1;
+			match c {
+				c if c == quote => return Ok(out),
+				'\\' if quote == '"' => {
+					let Some(escaped) = self.peek() else {
+						self.pos = start;
+						return Err(self.error("value is never closed"))
+					};
+					self.pos += 1;
+					match escaped {
+						'n' => out.push('\n'),
+						'r' => out.push('\r'),
+						't' => out.push('\t'),
+						'"' | '\\' | '$' => out.push(escaped),
+						// Other backslashes are kept as they are, as dotenv libraries do
+						x => {
+							out.push('\\');
+							out.push(x);
+						}
+					}
+				}
+				c => out.push(c)
+			}
+		}
+	}
+
+	/// Read the rest of the line as a value that is not quoted,
+	/// ending it at a `#` that follows whitespace
+	fn unquoted(&mut self) -> String {
+		let mut out = String::new();
+		while let Some(c) = self.peek().filter(|c| *c != '\n') {
+			if c == '#' && out.ends_with(char::is_whitespace) {
+				self.skip_line();
+				break
+			}
+			out.push(c);
+			self.pos += 1;
+		}
+		out.trim_end().into()
+	}
+
+	/// Read the next variable, along with its value unless it was empty and not quoted
+	fn entry(&mut self) -> Result<Option<(String, Option<TextRepr>)>, DeserializationError> {
+		loop {
+			match self.peek() {
+				Some('#') => self.skip_line(),
+				Some(c) if c.is_whitespace() => self.pos += 1,
+				Some(_) => break,
+				None => return Ok(None)
+			}
+		}
+
+		let mut name = self.name();
+		if name == "export" && self.peek().is_some_and(|c| c == ' ' || c == '\t') {
+			self.skip_spaces();
+			// A variable named export
+			if self.peek() != Some('=') {
+				name = self.name();
+			}
+		}
+		if name.is_empty() {
+			return Err(match self.peek() {
+				Some(c) => self.error(format!("expected a variable name, found {:?}", c)),
+				None => self.error("expected a variable name, found the end of the file")
+			})
+		}
+
+		self.skip_spaces();
+		if self.peek() != Some('=') {
+			return Err(self.error(format!("expected = after {}", name)))
+		}
+		self.pos += 1;
+		self.skip_spaces();
+
+		let value = match self.peek() {
+			Some(quote @ ('"' | '\'')) => {
+				let value = self.quoted(quote)?;
+				self.skip_spaces();
+				match self.peek() {
+					Some('#') => self.skip_line(),
+					Some('\r' | '\n') | None => {}
+					Some(c) => return Err(self.error(format!("unexpected character {:?} after the value of {}", c, name)))
+				}
+				Some(TextRepr::String(value))
+			}
+			_ => parse_value(&self.unquoted())
+		};
+		Ok(Some((name, value)))
+	}
+}
+
+
+impl TextRepr {
+	pub fn is_valid_env<T: ToString>(data: T) -> bool {
+		Self::from_env(data.to_string()).is_ok()
+	}
+
+	/// Write a flat table as a dotenv file.
+	///
+	/// Panics if the table holds a nested table or array, or if this is not a table
+	pub fn to_env(self) -> String {
+		let mut out = String::new();
+		let table = match self {
+			Self::Table(x) => x,
+			Self::Empty | Self::Null => return out,
+			_ => panic!("Tried to write a value without keys as an env file! Only keyed values can be written as env files.")
+		};
+		for (key, value) in table {
+			let start = out.len();
+			write!(out, "{}=", key.to_uppercase()).unwrap();
+			match value {
+				Self::Empty | Self::Null => {
+					out.truncate(start);
+					continue
+				}
+				Self::String(x) => write_string(&mut out, &x),
+				Self::Integer(x) => write!(out, "{}", x).unwrap(),
+				// Debug formatting keeps the decimal point, so whole floats are not read back as integers
+				Self::Float(x) => write!(out, "{:?}", x).unwrap(),
+				Self::Boolean(x) => write!(out, "{}", x).unwrap(),
+				Self::Table(_) | Self::Array(_) => panic!("Tried to write the nested value at {} as an env variable! Only flat values can be written as env files.", key)
+			}
+			out.push('\n');
+		}
+		out
+	}
+
+	/// Parse a dotenv file into a table. When a variable is set more than once, the last value is kept
+	pub fn from_env(data: String) -> Result<Self, DeserializationError> {
+		let mut parser = Parser { chars: data.chars().collect(), pos: 0, interner: KeyInterner::new() };
+		let mut table = Table::default();
+		while let Some((name, value)) = parser.entry()? {
+			let name = name.to_lowercase();
+			match value {
+				Some(value) => {
+					table.insert(parser.interner.intern(&name), value);
+				}
+				None => {
+					remove_entry(&mut table, &name);
+				}
+			}
+		}
+		Ok(Self::Table(table))
+	}
+}
+
+
+/// Serialize a flat keyed value, such as a struct of strings and numbers, as a dotenv file.
+///
+/// Panics if a field holds a nested value
+pub fn to_string<P, T: Serialize<P>>(value: T) -> String {
+	let mut out = TextRepr::new();
+	value.serialize(&mut out);
+	out.to_env()
+}
+
+
+/// Deserialize a flat keyed value from a dotenv file
+pub fn from_str<P, T: Deserialize<P>>(data: &str) -> Result<T, DeserializationError> {
+	T::deserialize(&mut TextRepr::from_env(data.into())?)
+}
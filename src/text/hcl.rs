@@ -0,0 +1,652 @@
+//! The HashiCorp Configuration Language, the block based syntax of Terraform and similar tools.
+//!
+//! Attributes such as `port = 80` become keys of their table, and blocks become nested tables under their type
+//! and labels, so `server "web" { port = 80 }` reads the same as `server { web { port = 80 } }`.
+//! Blocks of the same type without labels are collected into an array of tables, and are written back that way.
+//! Expressions other than literals, lists and objects are not evaluated, so references such as `var.region` are
+//! read as strings, as are templates such as `"${var.name}"`. Tables are written as blocks and arrays of tables
+//! as repeated blocks, after the attributes of their parent
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+use std::str::FromStr;
+
+use super::*;
+
+
+pub mod hcl_prelude {
+	pub use crate::{impl_hcl, impl_hcl_deser, impl_hcl_ser};
+
+	pub use super::{HCLDeserialize, HCLSerialize, text::TextRepr};
+}
+
+
+fn is_ident(key: &str) -> bool {
+	key.starts_with(|c: char| c.is_alphabetic() || c == '_') && key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+
+fn is_block(value: &TextRepr) -> bool {
+	match value {
+		TextRepr::Table(_) => true,
+		TextRepr::Array(x) => !x.is_empty() && x.iter().all(|x| matches!(x, TextRepr::Table(_))),
+		_ => false
+	}
+}
+
+
+fn push_indent(out: &mut String, indent: usize) {
+	out.extend(std::iter::repeat_n("  ", indent));
+}
+
+
+fn write_key(out: &mut String, key: &str) {
+	if is_ident(key) {
+		out.push_str(key);
+	} else {
+		write_string(out, key);
+	}
+}
+
+
+fn write_string(out: &mut String, string: &str) {
+	out.push('"');
+	let mut chars = string.chars().peekable();
+	while let Some(c) = chars.next() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			// Doubled so that other tools do not read it as the start of a template
+			'$' | '%' if chars.peek() == Some(&'{') => {
+				out.push(c);
+				out.push(c);
+			}
+			c => out.push(c)
+		}
+	}
+	out.push('"');
+}
+
+
+fn write_expr(value: TextRepr, out: &mut String) {
+	match value {
+		TextRepr::Empty | TextRepr::Null => out.push_str("null"),
+		TextRepr::String(x) => write_string(out, &x),
+		TextRepr::Integer(x) => write!(out, "{}", x).unwrap(),
+		// Debug formatting keeps the decimal point, so whole floats are not read back as integers
+		TextRepr::Float(x) => write!(out, "{:?}", x).unwrap(),
+		TextRepr::Boolean(x) => write!(out, "{}", x).unwrap(),
+		TextRepr::Array(x) => {
+			out.push('[');
+			for (i, value) in x.into_iter().enumerate() {
+				if i > 0 {
+					out.push_str(", ");
+				}
+				write_expr(value, out);
+			}
+			out.push(']');
+		}
+		TextRepr::Table(x) if x.is_empty() => out.push_str("{}"),
+		TextRepr::Table(x) => {
+			out.push_str("{ ");
+			for (i, (key, value)) in x.into_iter().filter(|(_, x)| !matches!(x, TextRepr::Empty)).enumerate() {
+				if i > 0 {
+					out.push_str(", ");
+				}
+				write_key(out, &key);
+				out.push_str(" = ");
+				write_expr(value, out);
+			}
+			out.push_str(" }");
+		}
+	}
+}
+
+
+fn write_block(key: &str, body: Table, out: &mut String, indent: usize) {
+	push_indent(out, indent);
+	write_key(out, key);
+	if body.is_empty() {
+		out.push_str(" {}\n");
+		return
+	}
+	out.push_str(" {\n");
+	write_body(body, out, indent + 1);
+	push_indent(out, indent);
+	out.push_str("}\n");
+}
+
+
+fn write_body(table: Table, out: &mut String, indent: usize) {
+	let (blocks, attributes): (Vec<_>, Vec<_>) = table.into_iter()
+		.filter(|(_, x)| !matches!(x, TextRepr::Empty))
+		.partition(|(_, x)| is_block(x));
+
+	for (key, value) in attributes {
+		push_indent(out, indent);
+		write_key(out, &key);
+		out.push_str(" = ");
+		write_expr(value, out);
+		out.push('\n');
+	}
+	for (key, value) in blocks {
+		match value {
+			TextRepr::Table(x) => write_block(&key, x, out, indent),
+			TextRepr::Array(x) => for value in x {
+				if let TextRepr::Table(x) = value {
+					write_block(&key, x, out, indent);
+				}
+			}
+			_ => unreachable!()
+		}
+	}
+}
+
+
+/// A list or object that is still open while parsing an expression
+enum Open {
+	List(VecDeque<TextRepr>),
+	/// An object, and the key of the value being parsed
+	Object(Table, TableKey)
+}
+
+
+struct Parser {
+	chars: Vec<char>,
+	pos: usize,
+	interner: KeyInterner
+}
+
+
+impl Parser {
+	fn error<T: Display>(&self, reason: T) -> DeserializationError {
+		let line = self.chars[..self.pos.min(self.chars.len())].iter().filter(|x| **x == '\n').count() + 1;
+		DeserializationError::invalid_format(format!("line {}: {}", line, reason))
+	}
+
+	fn peek(&self) -> Option<char> {
+		self.chars.get(self.pos).copied()
+	}
+
+	fn peek_at(&self, offset: usize) -> Option<char> {
+		self.chars.get(self.pos + offset).copied()
+	}
+
+	/// Skip whitespace and comments
+	fn skip(&mut self) -> Result<(), DeserializationError> {
+		loop {
+			match (self.peek(), self.peek_at(1)) {
+				(Some(c), _) if c.is_whitespace() => self.pos += 1,
+				(Some('#'), _) | (Some('/'), Some('/')) => while self.peek().is_some_and(|c| c != '\n') {
+					self.pos += 1;
+				}
+				(Some('/'), Some('*')) => {
+					self.pos += 2;
+					while !(self.peek() == Some('*') && self.peek_at(1) == Some('/')) {
+						if self.peek().is_none() {
+							return Err(self.error("block comment is never closed"))
+						}
+						self.pos += 1;
+					}
+					self.pos += 2;
+				}
+				_ => return Ok(())
+			}
+		}
+	}
+
+	fn found(&self) -> String {
+		match self.peek() {
+			Some(c) => format!("{:?}", c),
+			None => "the end of the document".into()
+		}
+	}
+
+	fn ident(&mut self) -> Option<String> {
+		if !self.peek().is_some_and(|c| c.is_alphabetic() || c == '_') {
+			return None
+		}
+		let mut out = String::new();
+		while let Some(c) = self.peek().filter(|c| c.is_alphanumeric() || *c == '_' || *c == '-') {
+			out.push(c);
+			self.pos += 1;
+		}
+		Some(out)
+	}
+
+	fn string(&mut self) -> Result<String, DeserializationError> {
+		let start = self.pos;
+		self.pos += 1;
+		let mut out = String::new();
+		loop {
+			let Some(c) = self.peek() else {
+				self.pos = start;
+				return Err(self.error("string is never closed"))
+			};
+			self.pos += 1;
+			match c {
+				'"' => return Ok(out),
+				'\n' => return Err(self.error("string is never closed before the end of the line")),
+				'$' | '%' if self.peek() == Some(c) && self.peek_at(1) == Some('{') => {
+					self.pos += 1;
+					out.push(c);
+				}
+				'\\' => {
+					let escaped = self.peek().ok_or_else(|| self.error("string is never closed"))?;
+					self.pos += 1;
+					out.push(match escaped {
+						'n' => '\n',
+						'r' => '\r',
+						't' => '\t',
+						'"' | '\\' => escaped,
+						'u' | 'U' => {
+							let len = if escaped == 'u' { 4 } else { 8 };
+							let hex: String = self.chars.get(self.pos..self.pos + len).unwrap_or_default().iter().collect();
+							self.pos += len;
+							u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+								.ok_or_else(|| self.error(format!("invalid unicode escape \\{}{}", escaped, hex)))?
+						}
+						x => return Err(self.error(format!("unknown escape \\{}", x)))
+					});
+				}
+				c => out.push(c)
+			}
+		}
+	}
+
+	/// Read a heredoc after its <<, keeping the newline before its closing marker.
+	/// With <<- the indentation shared by its lines is removed
+	fn heredoc(&mut self) -> Result<String, DeserializationError> {
+		let indented = self.peek() == Some('-');
+		if indented {
+			self.pos += 1;
+		}
+		let marker = self.ident().ok_or_else(|| self.error("expected the marker of a heredoc"))?;
+		while self.peek().is_some_and(|c| c != '\n') {
+			if !self.peek().is_some_and(char::is_whitespace) {
+				return Err(self.error("expected a new line after the marker of a heredoc"))
+			}
+			self.pos += 1;
+		}
+		self.pos += 1;
+
+		let mut lines = Vec::new();
+		loop {
+			if self.pos >= self.chars.len() {
+				return Err(self.error(format!("heredoc is never closed with {}", marker)))
+			}
+			let mut line = String::new();
+			while let Some(c) = self.peek().filter(|c| *c != '\n') {
+				line.push(c);
+				self.pos += 1;
+			}
+			if line.trim() == marker {
+				break
+			}
+			self.pos += 1;
+			lines.push(line);
+		}
+
+		if indented {
+			let indent = lines.iter()
+				.filter(|x| !x.trim().is_empty())
+				.map(|x| x.len() - x.trim_start().len())
+				.min()
+				.unwrap_or(0);
+			for line in &mut lines {
+				*line = line.get(indent..).unwrap_or_default().to_string();
+			}
+		}
+		Ok(lines.into_iter().map(|x| x + "\n").collect())
+	}
+
+	fn number(&mut self) -> Result<TextRepr, DeserializationError> {
+		let start = self.pos;
+		if self.peek() == Some('-') {
+			self.pos += 1;
+		}
+		while let Some(c) = self.peek() {
+			let exponent_sign = matches!(c, '+' | '-') && matches!(self.chars.get(self.pos - 1), Some('e' | 'E'));
+			if !(c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E') || exponent_sign) {
+				break
+			}
+			self.pos += 1;
+		}
+		let number: String = self.chars[start..self.pos].iter().collect();
+		if let Ok(x) = number.parse() {
+			return Ok(TextRepr::Integer(x))
+		}
+		number.parse().map(TextRepr::Float).map_err(|_| self.error(format!("{} is not a number", number)))
+	}
+
+	/// Read a key of an object or an attribute, which is an identifier or a string
+	fn key(&mut self) -> Result<String, DeserializationError> {
+		if self.peek() == Some('"') {
+			return self.string()
+		}
+		self.ident().ok_or_else(|| self.error(format!("expected a key, found {}", self.found())))
+	}
+
+	/// Read the key of an object and the = or : after it
+	fn object_key(&mut self) -> Result<TableKey, DeserializationError> {
+		let key = self.key()?;
+		self.skip()?;
+		if !matches!(self.peek(), Some('=' | ':')) {
+			return Err(self.error(format!("expected = or : after key {}, found {}", key, self.found())))
+		}
+		self.pos += 1;
+		Ok(self.interner.intern(&key))
+	}
+
+	/// Read a value that is not a list or an object
+	fn scalar(&mut self) -> Result<TextRepr, DeserializationError> {
+		match self.peek() {
+			Some('"') => self.string().map(TextRepr::String),
+			Some('<') if self.peek_at(1) == Some('<') => {
+				self.pos += 2;
+				self.heredoc().map(TextRepr::String)
+			}
+			Some(c) if c.is_ascii_digit() || c == '-' => self.number(),
+			Some(c) if c.is_alphabetic() || c == '_' => {
+				let mut ident = self.ident().unwrap_or_default();
+				Ok(match ident.as_str() {
+					"true" => TextRepr::Boolean(true),
+					"false" => TextRepr::Boolean(false),
+					"null" => TextRepr::Null,
+					_ => {
+						// A reference such as var.region, kept as written
+						while self.peek() == Some('.') {
+							self.pos += 1;
+							ident.push('.');
+							ident.push_str(&self.ident().ok_or_else(|| self.error(format!("expected an attribute name after {}", ident)))?);
+						}
+						TextRepr::String(ident)
+					}
+				})
+			}
+			_ => Err(self.error(format!("expected a value, found {}", self.found())))
+		}
+	}
+
+	/// Read an expression with an explicit stack of open lists and objects instead of recursion,
+	/// so deeply nested documents cannot overflow the stack. Depth is how deeply the expression is already nested
+	fn expr(&mut self, depth: usize) -> Result<TextRepr, DeserializationError> {
+		let mut stack = Vec::new();
+		loop {
+			self.skip()?;
+			let mut value = match self.peek() {
+				Some(c @ ('[' | '{')) => {
+					self.pos += 1;
+					self.skip()?;
+					if self.peek() == Some(if c == '[' { ']' } else { '}' }) {
+						self.pos += 1;
+						if c == '[' { TextRepr::Array(VecDeque::new()) } else { TextRepr::Table(Table::default()) }
+					} else {
+						if depth + stack.len() >= MAX_DEPTH {
+							return Err(too_deep())
+						}
+						stack.push(if c == '[' { Open::List(VecDeque::new()) } else { Open::Object(Table::default(), self.object_key()?) });
+						continue
+					}
+				}
+				_ => self.scalar()?
+			};
+
+			// Add the value to the innermost open container, closing every container that ends after it
+			loop {
+				let closed = match stack.last_mut() {
+					None => return Ok(value),
+					Some(Open::List(items)) => {
+						items.push_back(value);
+						self.skip()?;
+						match self.peek() {
+							Some(',') => {
+								self.pos += 1;
+								self.skip()?;
+								self.peek() == Some(']')
+							}
+							Some(']') => true,
+							_ => return Err(self.error(format!("expected , or ] in a list, found {}", self.found())))
+						}
+					}
+					Some(Open::Object(table, key)) => {
+						if table.insert(key.clone(), value).is_some() {
+							return Err(self.error(format!("key {} is defined twice", key)))
+						}
+						self.skip()?;
+						if self.peek() == Some(',') {
+							self.pos += 1;
+							self.skip()?;
+						}
+						if self.peek() != Some('}') {
+							*key = self.object_key()?;
+						}
+						self.peek() == Some('}')
+					}
+				};
+				if !closed {
+					break
+				}
+				self.pos += 1;
+				value = match stack.pop() {
+					Some(Open::List(x)) => TextRepr::Array(x),
+					Some(Open::Object(x, _)) => TextRepr::Table(x),
+					None => unreachable!()
+				};
+			}
+		}
+	}
+
+	/// Read the attributes and blocks of a document, with an explicit stack of open blocks
+	fn body(&mut self) -> Result<Table, DeserializationError> {
+		let mut table = Table::default();
+		let mut stack: Vec<(Table, String, Vec<String>)> = Vec::new();
+		loop {
+			self.skip()?;
+			match self.peek() {
+				None if stack.is_empty() => return Ok(table),
+				None => return Err(self.error("block is never closed")),
+				Some('}') if !stack.is_empty() => {
+					self.pos += 1;
+					let (parent, key, labels) = stack.pop().unwrap();
+					let body = std::mem::replace(&mut table, parent);
+					self.insert_block(&mut table, key, labels, body)?;
+					continue
+				}
+				_ => {}
+			}
+
+			let key = self.key()?;
+			self.skip()?;
+			if self.peek() == Some('=') && self.peek_at(1) != Some('=') {
+				self.pos += 1;
+				let value = self.expr(stack.len() + 1)?;
+				if table.insert(self.interner.intern(&key), value).is_some() {
+					return Err(self.error(format!("attribute {} is defined twice", key)))
+				}
+				continue
+			}
+
+			let mut labels = Vec::new();
+			while self.peek() != Some('{') {
+				labels.push(self.key().map_err(|_| self.error(format!("expected = or a block after {}, found {}", key, self.found())))?);
+				self.skip()?;
+			}
+			self.pos += 1;
+			if stack.len() + 1 >= MAX_DEPTH {
+				return Err(too_deep())
+			}
+			stack.push((std::mem::take(&mut table), key, labels));
+		}
+	}
+
+	fn insert_block(&mut self, table: &mut Table, key: String, labels: Vec<String>, body: Table) -> Result<(), DeserializationError> {
+		let key = self.interner.intern(&key);
+		let Some((last, labels)) = labels.split_last() else {
+			match table.get_mut(&key) {
+				None => {
+					table.insert(key, TextRepr::Table(body));
+				}
+				Some(TextRepr::Array(x)) => x.push_back(TextRepr::Table(body)),
+				Some(x @ TextRepr::Table(_)) => {
+					let first = std::mem::replace(x, TextRepr::Empty);
+					*x = TextRepr::Array(VecDeque::from([first, TextRepr::Table(body)]));
+				}
+				Some(_) => return Err(self.error(format!("block {} conflicts with an attribute of the same name", key)))
+			}
+			return Ok(())
+		};
+
+		let mut table = table;
+		for label in std::iter::once(&*key).chain(labels.iter().map(String::as_str)) {
+			let label = self.interner.intern(label);
+			table = match table.entry(label.clone()).or_insert_with(|| TextRepr::Table(Table::default())) {
+				TextRepr::Table(x) => x,
+				_ => return Err(self.error(format!("block {} conflicts with another value", label)))
+			};
+		}
+		let last = self.interner.intern(last);
+		if table.insert(last.clone(), TextRepr::Table(body)).is_some() {
+			return Err(self.error(format!("block {} {} is defined twice", key, last)))
+		}
+		Ok(())
+	}
+}
+
+
+impl TextRepr {
+	pub fn is_valid_hcl<T: ToString>(data: T) -> bool {
+		Self::from_hcl(data.to_string()).is_ok()
+	}
+
+	/// Write a table as the body of an HCL document. Any other value is written as the attribute `value`
+	pub fn to_hcl(self) -> String {
+		let table = match self {
+			TextRepr::Table(x) => x,
+			TextRepr::Empty => Table::default(),
+			x => [(TableKey::from("value"), x)].into_iter().collect()
+		};
+		let mut out = String::new();
+		write_body(table, &mut out, 0);
+		out
+	}
+
+	pub fn from_hcl(data: String) -> Result<Self, DeserializationError> {
+		let mut parser = Parser { chars: data.chars().collect(), pos: 0, interner: KeyInterner::new() };
+		parser.body().map(Self::Table)
+	}
+}
+
+
+/// Serialize any value as HCL, without implementing HCLSerialize for it
+pub fn to_string<P, T: Serialize<P>>(value: T) -> String {
+	let mut out = TextRepr::new();
+	value.serialize(&mut out);
+	out.to_hcl()
+}
+
+
+/// Deserialize any value from HCL, without implementing HCLDeserialize for it
+pub fn from_str<P, T: Deserialize<P>>(data: &str) -> Result<T, DeserializationError> {
+	T::deserialize(&mut TextRepr::from_hcl(data.into())?)
+}
+
+
+pub trait HCLSerialize<P = NaturalProfile> {
+	fn serialize_hcl(self) -> String;
+}
+
+
+pub trait HCLDeserialize<P = NaturalProfile>: Sized {
+	fn deserialize_hcl(data: String) -> Result<Self, DeserializationError>;
+}
+
+
+/// A marker trait for types that can be serialized and deserialized into HCL with the same profile,
+/// without a marshall. Is automatically implemented on all appropriate types
+pub trait HCLSerde<P = NaturalProfile>: HCLSerialize<P> + HCLDeserialize<P> {}
+
+impl<P, T: HCLSerialize<P> + HCLDeserialize<P>> HCLSerde<P> for T {}
+
+
+pub trait MarshalledHCLSerialize<Marshall, P = NaturalProfile> {
+	fn serialize_hcl(self, marshall: &Marshall) -> String;
+}
+
+
+pub trait MarshalledHCLDeserialize<'a, Marshall, P = NaturalProfile>: Sized {
+	fn deserialize_hcl(data: String, marshall: &'a Marshall) -> Result<Self, DeserializationError>;
+}
+
+
+/// A marker trait for types that can be serialized and deserialized into HCL with the same profile,
+/// and the same type of marshall. Is automatically implemented on all appropriate types
+pub trait MarshalledHCLSerde<'a, Marshall, P = NaturalProfile>: MarshalledHCLSerialize<Marshall, P> + MarshalledHCLDeserialize<'a, Marshall, P> {}
+
+impl<'a, P, Marshall, T: MarshalledHCLSerialize<Marshall, P> + MarshalledHCLDeserialize<'a, Marshall, P>> MarshalledHCLSerde<'a, Marshall, P> for T {}
+
+#[macro_export]
+macro_rules! impl_hcl {
+    ($name: ty, $profile: ty) => {
+		impl_hcl_ser!($name, $profile);
+		impl_hcl_deser!($name, $profile);
+	};
+    ($name: ty, $profile: ty, $marshall: ty) => {
+		impl_hcl_ser!($name, $profile, $marshall);
+		impl_hcl_deser!($name, $profile, $marshall);
+	};
+}
+
+#[macro_export]
+macro_rules! impl_hcl_ser {
+    ($name: ty, $profile: ty) => {
+		impl HCLSerialize<$profile> for $name {
+			fn serialize_hcl(self) -> String {
+				$crate::hcl::to_string::<$profile, _>(self)
+			}
+		}
+	};
+    ($name: ty, $profile: ty, $marshall: ty) => {
+		impl MarshalledHCLSerialize<$marshall, $profile> for $name {
+			fn serialize_hcl(self, marshall: &$marshall) -> String {
+				let mut out = TextRepr::new();
+				MarshalledSerialize::<$profile>::serialize(self, &mut out, marshall);
+				out.to_hcl()
+			}
+		}
+	};
+}
+
+#[macro_export]
+macro_rules! impl_hcl_deser {
+    ($name: ty, $profile: ty) => {
+		impl HCLDeserialize<$profile> for $name {
+			fn deserialize_hcl(data: String) -> Result<Self, DeserializationError> {
+				$crate::hcl::from_str::<$profile, _>(&data)
+			}
+		}
+	};
+    ($name: ty, $profile: ty, $marshall: ty) => {
+		impl MarshalledHCLDeserialize<$marshall, $profile> for $name {
+			fn deserialize_hcl(data: String, marshall: &$marshall) -> Result<Self, DeserializationError> {
+				MarshalledDeserialize::<$profile>::deserialize(&mut TextRepr::from_hcl(data)?, marshall)
+			}
+		}
+	};
+}
+
+
+impl<P, K: Borrow<str> + Eq + std::hash::Hash, V: Serialize<P>, S: std::hash::BuildHasher> HCLSerialize<P> for HashMap<K, V, S> {
+	fn serialize_hcl(self) -> String {
+		TextRepr::to_hcl(serialize_owned!(self))
+	}
+}
+
+
+impl<E: Debug, P, K: Eq + std::hash::Hash + FromStr<Err=E>, V: Deserialize<P>, S: std::hash::BuildHasher + Default> HCLDeserialize<P> for HashMap<K, V, S> {
+	fn deserialize_hcl(data: String) -> Result<Self, DeserializationError> {
+		Self::deserialize::<TextRepr>(&mut TextRepr::from_hcl(data)?)
+	}
+}
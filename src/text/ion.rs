@@ -0,0 +1,879 @@
+//! Amazon Ion, in both its text and binary forms.
+//!
+//! Ion values are mapped onto TextRepr: null of any type is Null, ints are Integer, floats and decimals are Float,
+//! strings, symbols, clobs and timestamps are String, lists and s-expressions are Array, structs are Table,
+//! and blobs are an Array of their bytes. Annotations are skipped. A stream with several top level values
+//! is read as an Array of them
+use std::fmt::Write;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use super::*;
+
+
+/// The symbols every Ion stream starts with, so local symbols start at 10
+const SYSTEM_SYMBOLS: [&str; 10] = ["", "$ion", "$ion_1_0", "$ion_symbol_table", "name", "version", "imports", "symbols", "max_id", "$ion_shared_symbol_table"];
+const SYMBOL_TABLE: usize = 3;
+const SYMBOLS: usize = 7;
+const VERSION_MARKER: [u8; 4] = [0xE0, 0x01, 0x00, 0xEA];
+
+
+fn write_string(out: &mut String, string: &str, quote: char) {
+	out.push(quote);
+	for c in string.chars() {
+		match c {
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			c if c == quote => {
+				out.push('\\');
+				out.push(c);
+			}
+			c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+			c => out.push(c)
+		}
+	}
+	out.push(quote);
+}
+
+
+fn is_identifier(symbol: &str) -> bool {
+	let mut chars = symbol.chars();
+	matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$')
+		&& chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+		&& !matches!(symbol, "null" | "true" | "false" | "nan")
+}
+
+
+fn write_text(out: &mut String, repr: &TextRepr) {
+	match repr {
+		TextRepr::Empty | TextRepr::Null => out.push_str("null"),
+		TextRepr::String(x) => write_string(out, x, '"'),
+		TextRepr::Integer(x) => write!(out, "{}", x).unwrap(),
+		TextRepr::Float(x) if x.is_nan() => out.push_str("nan"),
+		TextRepr::Float(x) if x.is_infinite() => out.push_str(if *x > 0.0 { "+inf" } else { "-inf" }),
+		TextRepr::Float(x) => write!(out, "{:e}", x).unwrap(),
+		TextRepr::Boolean(x) => write!(out, "{}", x).unwrap(),
+		TextRepr::Table(x) => {
+			out.push('{');
+			for (i, (key, value)) in x.iter().enumerate() {
+				if i > 0 {
+					out.push_str(", ");
+				}
+				if is_identifier(key) {
+					out.push_str(key);
+				} else {
+					write_string(out, key, '\'');
+				}
+				out.push_str(": ");
+				write_text(out, value);
+			}
+			out.push('}');
+		}
+		TextRepr::Array(x) => {
+			out.push('[');
+			for (i, value) in x.iter().enumerate() {
+				if i > 0 {
+					out.push_str(", ");
+				}
+				write_text(out, value);
+			}
+			out.push(']');
+		}
+	}
+}
+
+
+fn is_delimiter(c: char) -> bool {
+	c.is_whitespace() || matches!(c, ',' | ':' | '[' | ']' | '{' | '}' | '(' | ')' | '"' | '\'')
+}
+
+
+fn is_operator(c: char) -> bool {
+	"!#%&*+-./;<=>?@^`|~".contains(c)
+}
+
+
+/// A list, s-expression or struct whose values are being read
+enum Open {
+	List {
+		close: char,
+		sexp: bool,
+		items: VecDeque<TextRepr>
+	},
+	Struct {
+		table: Table,
+		/// The key of the field whose value is being read
+		key: Option<String>
+	}
+}
+
+
+/// A parser of Ion text
+struct TextParser<'a> {
+	chars: Peekable<Chars<'a>>
+}
+
+
+impl TextParser<'_> {
+	fn error<T: ToString>(reason: T) -> DeserializationError {
+		DeserializationError::invalid_format(reason)
+	}
+
+	/// Skip whitespace and comments, returning the next char without consuming it
+	fn peek(&mut self) -> Option<char> {
+		loop {
+			let c = *self.chars.peek()?;
+			if c.is_whitespace() {
+				self.chars.next();
+				continue
+			}
+			if c != '/' {
+				return Some(c)
+			}
+			let mut lookahead = self.chars.clone();
+			lookahead.next();
+			match lookahead.next() {
+				Some('/') => while !matches!(self.chars.next(), Some('\n') | None) {},
+				Some('*') => {
+					self.chars.next();
+					self.chars.next();
+					let mut last = ' ';
+					for c in self.chars.by_ref() {
+						if last == '*' && c == '/' {
+							break
+						}
+						last = c;
+					}
+				}
+				_ => return Some(c)
+			}
+		}
+	}
+
+	fn expect(&mut self, expected: char) -> Result<(), DeserializationError> {
+		match self.peek() {
+			Some(c) if c == expected => {
+				self.chars.next();
+				Ok(())
+			}
+			Some(c) => Err(Self::error(format!("expected '{}' but found '{}'", expected, c))),
+			None => Err(DeserializationError::EOF)
+		}
+	}
+
+	fn token(&mut self) -> String {
+		let mut out = String::new();
+		while let Some(c) = self.chars.peek() {
+			// Colons only delimit a token outside the time of a timestamp
+			let time = *c == ':' && out.contains('T') && out.starts_with(|c: char| c.is_ascii_digit());
+			if (is_delimiter(*c) && !time) || (*c == '/' && !out.is_empty()) {
+				break
+			}
+			out.push(*c);
+			self.chars.next();
+		}
+		out
+	}
+
+	fn escape(&mut self) -> Result<char, DeserializationError> {
+		let c = self.chars.next().ok_or(DeserializationError::EOF)?;
+		let digits = match c {
+			'x' => 2,
+			'u' => 4,
+			'U' => 8,
+			'n' => return Ok('\n'),
+			'r' => return Ok('\r'),
+			't' => return Ok('\t'),
+			'0' => return Ok('\0'),
+			'a' => return Ok('\x07'),
+			'b' => return Ok('\x08'),
+			'f' => return Ok('\x0C'),
+			'v' => return Ok('\x0B'),
+			c => return Ok(c)
+		};
+		let hex: String = self.chars.by_ref().take(digits).collect();
+		u32::from_str_radix(&hex, 16).ok()
+			.and_then(char::from_u32)
+			.ok_or_else(|| Self::error(format!("invalid escape \\{}{}", c, hex)))
+	}
+
+	/// Read a quoted string or symbol, after its opening quote
+	fn quoted(&mut self, quote: char) -> Result<String, DeserializationError> {
+		let mut out = String::new();
+		loop {
+			match self.chars.next().ok_or(DeserializationError::EOF)? {
+				'\\' => out.push(self.escape()?),
+				c if c == quote => return Ok(out),
+				c => out.push(c)
+			}
+		}
+	}
+
+	/// Read a string, joining any long strings that follow each other
+	fn string(&mut self) -> Result<String, DeserializationError> {
+		if self.chars.next() == Some('"') {
+			return self.quoted('"')
+		}
+		// The first quote of the long string is already consumed
+		self.chars.next();
+		self.chars.next();
+		let mut out = String::new();
+		loop {
+			match self.chars.next().ok_or(DeserializationError::EOF)? {
+				'\\' => out.push(self.escape()?),
+				'\'' if self.chars.clone().take(2).eq(['\'', '\'']) => {
+					self.chars.next();
+					self.chars.next();
+					if !self.long_string_follows() {
+						return Ok(out)
+					}
+					self.chars.nth(2);
+				}
+				c => out.push(c)
+			}
+		}
+	}
+
+	fn long_string_follows(&mut self) -> bool {
+		if self.peek() != Some('\'') {
+			return false
+		}
+		let mut lookahead = self.chars.clone();
+		lookahead.next() == Some('\'') && lookahead.next() == Some('\'') && lookahead.next() == Some('\'')
+	}
+
+	/// Read a symbol, which is quoted, an identifier, or an operator inside an s-expression
+	fn symbol(&mut self, operators: bool) -> Result<String, DeserializationError> {
+		match self.peek().ok_or(DeserializationError::EOF)? {
+			'\'' => {
+				self.chars.next();
+				self.quoted('\'')
+			}
+			c if operators && is_operator(c) => {
+				let mut out = String::new();
+				while let Some(c) = self.chars.peek().filter(|c| is_operator(**c)) {
+					out.push(*c);
+					self.chars.next();
+				}
+				Ok(out)
+			}
+			_ => {
+				let token = self.token();
+				if token.is_empty() {
+					return Err(Self::error("expected a symbol"))
+				}
+				Ok(token)
+			}
+		}
+	}
+
+	/// Whether the next char is a sign that starts a number, rather than an operator
+	fn signs_number(&mut self) -> bool {
+		let mut lookahead = self.chars.clone();
+		matches!(lookahead.next(), Some('+' | '-')) && matches!(lookahead.next(), Some(c) if c.is_ascii_digit() || c == 'i')
+	}
+
+	fn number(token: &str) -> Result<TextRepr, DeserializationError> {
+		let invalid = || Self::error(format!("invalid number {}", token));
+		let digits = token.replace('_', "");
+		let (negative, unsigned) = match digits.strip_prefix('-') {
+			Some(x) => (true, x),
+			None => (false, digits.as_str())
+		};
+		let radix = match unsigned.get(..2) {
+			Some("0x" | "0X") => Some(16),
+			Some("0b" | "0B") => Some(2),
+			_ => None
+		};
+		if let Some(radix) = radix {
+			let value = i64::from_str_radix(&unsigned[2..], radix).map_err(|_| invalid())?;
+			return Ok(TextRepr::Integer(if negative { -value } else { value }))
+		}
+		if digits.contains(['e', 'E']) || digits.contains(['d', 'D']) || digits.contains('.') {
+			return digits.replace(['d', 'D'], "e").parse().map(TextRepr::Float).map_err(|_| invalid())
+		}
+		digits.parse().map(TextRepr::Integer).map_err(|_| invalid())
+	}
+
+	fn scalar(&mut self, c: char, operators: bool) -> Result<TextRepr, DeserializationError> {
+		Ok(match c {
+			'"' => TextRepr::String(self.string()?),
+			'\'' => {
+				if self.long_string_follows() {
+					TextRepr::String(self.string()?)
+				} else {
+					TextRepr::String(self.symbol(operators)?)
+				}
+			}
+			c if operators && is_operator(c) && !self.signs_number() => TextRepr::String(self.symbol(true)?),
+			_ => {
+				let token = self.token();
+				match token.as_str() {
+					"" => return Err(Self::error(format!("unexpected '{}'", c))),
+					"true" => TextRepr::Boolean(true),
+					"false" => TextRepr::Boolean(false),
+					"nan" => TextRepr::Float(f64::NAN),
+					"+inf" => TextRepr::Float(f64::INFINITY),
+					"-inf" => TextRepr::Float(f64::NEG_INFINITY),
+					"null" => TextRepr::Null,
+					x if x.starts_with("null.") => TextRepr::Null,
+					x if x.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+') => {
+						// Timestamps start with a year followed by a '-' or a 'T'
+						if x.len() > 4 && x[..4].chars().all(|c| c.is_ascii_digit()) && matches!(x.as_bytes()[4], b'-' | b'T') {
+							TextRepr::String(x.into())
+						} else {
+							Self::number(x)?
+						}
+					}
+					x => TextRepr::String(x.into())
+				}
+			}
+		})
+	}
+
+	/// Read a blob or a clob, after the first of its opening braces
+	fn lob(&mut self) -> Result<TextRepr, DeserializationError> {
+		self.chars.next();
+		let out = if self.peek() == Some('"') || self.long_string_follows() {
+			TextRepr::String(self.string()?)
+		} else {
+			let mut data = String::new();
+			while let Some(c) = self.peek().filter(|c| *c != '}') {
+				data.push(c);
+				self.chars.next();
+			}
+			TextRepr::Array(crate::base64::decode(&data)?.into_iter().map(|x| TextRepr::Integer(x as i64)).collect())
+		};
+		self.expect('}')?;
+		self.expect('}')?;
+		Ok(out)
+	}
+
+	/// Read a value with an explicit stack of open lists and structs instead of recursion,
+	/// so deeply nested documents cannot overflow the stack
+	fn value(&mut self) -> Result<TextRepr, DeserializationError> {
+		let mut stack = Vec::new();
+		self.nested_value(&mut stack).map_err(|e| {
+			// Errors inside a field are nested under every field that was open
+			stack.iter().rev().fold(e, |e, open| match open {
+				Open::Struct { key: Some(key), .. } => e.nest().set_field(key),
+				_ => e
+			})
+		})
+	}
+
+	fn nested_value(&mut self, stack: &mut Vec<Open>) -> Result<TextRepr, DeserializationError> {
+		// Whether an annotation was just read, so a value must follow it
+		let mut annotated = false;
+		loop {
+			let value = match stack.last_mut() {
+				Some(Open::List { close, items, .. }) if !annotated && self.peek().ok_or(DeserializationError::EOF)? == *close => {
+					self.chars.next();
+					let items = std::mem::take(items);
+					stack.pop();
+					TextRepr::Array(items)
+				}
+				Some(Open::Struct { table, key: key @ None }) => match self.peek().ok_or(DeserializationError::EOF)? {
+					'}' => {
+						self.chars.next();
+						let table = std::mem::take(table);
+						stack.pop();
+						TextRepr::Table(table)
+					}
+					c => {
+						let name = if c == '"' || (c == '\'' && self.long_string_follows()) {
+							self.string()?
+						} else {
+							self.symbol(false)?
+						};
+						self.expect(':')?;
+						*key = Some(name);
+						continue
+					}
+				}
+				last => {
+					let operators = matches!(last, Some(Open::List { sexp: true, .. }));
+					let c = self.peek().ok_or(DeserializationError::EOF)?;
+					match c {
+						'[' | '(' => {
+							if stack.len() >= MAX_DEPTH {
+								return Err(too_deep())
+							}
+							self.chars.next();
+							stack.push(Open::List { close: if c == '[' { ']' } else { ')' }, sexp: c == '(', items: VecDeque::new() });
+							annotated = false;
+							continue
+						}
+						'{' => {
+							self.chars.next();
+							// {{ starts a blob or a clob
+							if self.chars.peek() == Some(&'{') {
+								self.lob()?
+							} else {
+								if stack.len() >= MAX_DEPTH {
+									return Err(too_deep())
+								}
+								stack.push(Open::Struct { table: Table::default(), key: None });
+								annotated = false;
+								continue
+							}
+						}
+						c => self.scalar(c, operators)?
+					}
+				}
+			};
+			// A symbol followed by '::' is an annotation of the value after it
+			if self.chars.clone().take(2).collect::<String>() == "::" {
+				self.chars.next();
+				self.chars.next();
+				annotated = true;
+				continue
+			}
+			annotated = false;
+			match stack.last_mut() {
+				None => return Ok(value),
+				Some(Open::List { close, sexp, items }) => {
+					items.push_back(value);
+					if !*sexp && self.peek() != Some(*close) {
+						self.expect(',')?;
+					}
+				}
+				Some(Open::Struct { table, key }) => {
+					table.insert(key.take().unwrap().into(), value);
+					if self.peek() != Some('}') {
+						self.expect(',')?;
+					}
+				}
+			}
+		}
+	}
+
+	fn stream(&mut self) -> Result<TextRepr, DeserializationError> {
+		let mut values = VecDeque::new();
+		while self.peek().is_some() {
+			match self.value()? {
+				// The version marker is a symbol of its own, and not a value
+				TextRepr::String(x) if x == "$ion_1_0" => {}
+				x => values.push_back(x)
+			}
+		}
+		Ok(match values.len() {
+			0 => TextRepr::Empty,
+			1 => values.pop_front().unwrap(),
+			_ => TextRepr::Array(values)
+		})
+	}
+}
+
+
+fn push_var_uint(out: &mut Vec<u8>, value: usize) {
+	let mut groups = vec![(value & 0x7F) as u8 | 0x80];
+	let mut rest = value >> 7;
+	while rest > 0 {
+		groups.push((rest & 0x7F) as u8);
+		rest >>= 7;
+	}
+	out.extend(groups.into_iter().rev());
+}
+
+
+fn push_header(out: &mut Vec<u8>, kind: u8, len: usize) {
+	if len < 14 {
+		out.push(kind << 4 | len as u8);
+	} else {
+		out.push(kind << 4 | 14);
+		push_var_uint(out, len);
+	}
+}
+
+
+fn uint_bytes(value: u64) -> Vec<u8> {
+	value.to_be_bytes().into_iter().skip_while(|x| *x == 0).collect()
+}
+
+
+/// Collect the keys of every table, which are written as symbols
+fn collect_symbols(repr: &TextRepr, symbols: &mut Vec<String>, ids: &mut HashMap<String, usize>) {
+	match repr {
+		TextRepr::Table(x) => for (key, value) in x {
+			if !ids.contains_key(&**key) {
+				ids.insert(key.to_string(), SYSTEM_SYMBOLS.len() + symbols.len());
+				symbols.push(key.to_string());
+			}
+			collect_symbols(value, symbols, ids);
+		}
+		TextRepr::Array(x) => for value in x {
+			collect_symbols(value, symbols, ids);
+		}
+		_ => {}
+	}
+}
+
+
+fn write_binary(out: &mut Vec<u8>, repr: TextRepr, ids: &HashMap<String, usize>) {
+	match repr {
+		TextRepr::Empty | TextRepr::Null => out.push(0x0F),
+		TextRepr::Boolean(x) => out.push(0x10 | x as u8),
+		TextRepr::Integer(x) => {
+			let magnitude = uint_bytes(x.unsigned_abs());
+			push_header(out, if x < 0 { 3 } else { 2 }, magnitude.len());
+			out.extend(magnitude);
+		}
+		TextRepr::Float(x) => {
+			out.push(0x48);
+			out.extend(x.to_be_bytes());
+		}
+		TextRepr::String(x) => {
+			push_header(out, 8, x.len());
+			out.extend(x.into_bytes());
+		}
+		TextRepr::Array(x) => {
+			let mut content = Vec::new();
+			for value in x {
+				write_binary(&mut content, value, ids);
+			}
+			push_header(out, 0xB, content.len());
+			out.extend(content);
+		}
+		TextRepr::Table(x) => {
+			let mut content = Vec::new();
+			for (key, value) in x {
+				push_var_uint(&mut content, ids[&*key]);
+				write_binary(&mut content, value, ids);
+			}
+			push_header(out, 0xD, content.len());
+			out.extend(content);
+		}
+	}
+}
+
+
+/// A list, s-expression, struct or annotation of binary Ion whose content is being read.
+/// Each keeps the bytes that follow it, to be read once it is closed
+enum OpenBinary<'a> {
+	List {
+		rest: &'a [u8],
+		items: VecDeque<TextRepr>
+	},
+	Struct {
+		rest: &'a [u8],
+		table: Table,
+		/// The key of the field whose value is being read
+		key: Option<String>
+	},
+	Annotation {
+		rest: &'a [u8],
+		/// The first annotation, which marks symbol tables
+		first: usize
+	}
+}
+
+
+/// A reader of binary Ion, which keeps the symbol table of the stream
+struct BinaryReader<'a> {
+	bytes: &'a [u8],
+	symbols: Vec<String>
+}
+
+
+impl<'a> BinaryReader<'a> {
+	fn take(&mut self, len: usize) -> Result<&'a [u8], DeserializationError> {
+		if len > self.bytes.len() {
+			return Err(DeserializationError::EOF)
+		}
+		let (out, rest) = self.bytes.split_at(len);
+		self.bytes = rest;
+		Ok(out)
+	}
+
+	fn var_uint(&mut self) -> Result<usize, DeserializationError> {
+		let mut out = 0usize;
+		loop {
+			let byte = self.take(1)?[0];
+			out = out.checked_mul(128).ok_or_else(|| DeserializationError::invalid_format("VarUInt is too large"))? | (byte & 0x7F) as usize;
+			if byte & 0x80 != 0 {
+				return Ok(out)
+			}
+		}
+	}
+
+	fn symbol(&self, id: usize) -> Result<String, DeserializationError> {
+		self.symbols.get(id).cloned().ok_or_else(|| DeserializationError::invalid_format(format!("unknown symbol ${}", id)))
+	}
+
+	fn uint(bytes: &[u8]) -> Result<u64, DeserializationError> {
+		if bytes.len() > 8 {
+			return Err(DeserializationError::invalid_format("integer is too large"))
+		}
+		Ok(bytes.iter().fold(0u64, |acc, x| acc << 8 | *x as u64))
+	}
+
+	fn scalar(&self, kind: u8, content: &[u8]) -> Result<TextRepr, DeserializationError> {
+		Ok(match kind {
+			2 => TextRepr::Integer(i64::try_from(Self::uint(content)?).map_err(|_| DeserializationError::invalid_format("integer is too large"))?),
+			3 => TextRepr::Integer(-i64::try_from(Self::uint(content)?).map_err(|_| DeserializationError::invalid_format("integer is too large"))?),
+			4 => TextRepr::Float(match content.len() {
+				0 => 0.0,
+				4 => f32::from_be_bytes(content.try_into().unwrap()) as f64,
+				8 => f64::from_be_bytes(content.try_into().unwrap()),
+				_ => return Err(DeserializationError::invalid_format("floats must be 0, 4 or 8 bytes long"))
+			}),
+			5 => TextRepr::Float(Self::decimal(content)?),
+			6 => return Err(DeserializationError::invalid_format("binary timestamps are not supported")),
+			7 => TextRepr::String(self.symbol(Self::uint(content)? as usize)?),
+			8 | 9 => TextRepr::String(String::from_utf8(content.to_vec()).map_err(DeserializationErrorKind::from).no_field()?),
+			0xA => TextRepr::Array(content.iter().map(|x| TextRepr::Integer(*x as i64)).collect()),
+			x => return Err(DeserializationError::invalid_format(format!("unsupported type {}", x)))
+		})
+	}
+
+	/// Read the next value with an explicit stack of open containers instead of recursion,
+	/// returning None for padding and symbol tables
+	fn value(&mut self) -> Result<Option<TextRepr>, DeserializationError> {
+		let mut stack = Vec::new();
+		self.nested_value(&mut stack).map_err(|e| {
+			// Errors inside a field are nested under every field that was open
+			stack.iter().rev().fold(e, |e, open| match open {
+				OpenBinary::Struct { key: Some(key), .. } => e.nest().set_field(key),
+				_ => e
+			})
+		})
+	}
+
+	fn nested_value(&mut self, stack: &mut Vec<OpenBinary<'a>>) -> Result<Option<TextRepr>, DeserializationError> {
+		loop {
+			let mut value = match stack.last_mut() {
+				Some(OpenBinary::List { rest, items }) if self.bytes.is_empty() => {
+					self.bytes = rest;
+					let items = std::mem::take(items);
+					stack.pop();
+					Some(TextRepr::Array(items))
+				}
+				Some(OpenBinary::Struct { rest, table, key: None }) if self.bytes.is_empty() => {
+					self.bytes = rest;
+					let table = std::mem::take(table);
+					stack.pop();
+					Some(TextRepr::Table(table))
+				}
+				Some(OpenBinary::Struct { key: key @ None, .. }) => {
+					let id = self.var_uint()?;
+					*key = Some(self.symbol(id)?);
+					continue
+				}
+				_ => {
+					let descriptor = self.take(1)?[0];
+					let kind = descriptor >> 4;
+					let len = match descriptor & 0xF {
+						15 => 0,
+						14 if kind != 1 => self.var_uint()?,
+						1 if kind == 0xD => self.var_uint()?,
+						x => x as usize
+					};
+					match kind {
+						_ if descriptor & 0xF == 15 => Some(TextRepr::Null),
+						0 => {
+							self.take(len)?;
+							None
+						}
+						1 => Some(TextRepr::Boolean(len == 1)),
+						0xE if len == 0 => return Err(DeserializationError::invalid_format("annotations must wrap a value")),
+						0xB..=0xE => {
+							if stack.len() >= MAX_DEPTH {
+								return Err(too_deep())
+							}
+							let content = self.take(len)?;
+							let rest = std::mem::replace(&mut self.bytes, content);
+							stack.push(match kind {
+								0xD => OpenBinary::Struct { rest, table: Table::default(), key: None },
+								0xE => {
+									let annotations_len = self.var_uint()?;
+									let annotations = self.take(annotations_len)?;
+									let first = BinaryReader { bytes: annotations, symbols: Vec::new() }.var_uint()?;
+									OpenBinary::Annotation { rest, first }
+								}
+								_ => OpenBinary::List { rest, items: VecDeque::new() }
+							});
+							continue
+						}
+						x => {
+							let content = self.take(len)?;
+							Some(self.scalar(x, content)?)
+						}
+					}
+				}
+			};
+			loop {
+				match stack.last_mut() {
+					None => return Ok(value),
+					Some(OpenBinary::List { items, .. }) => items.extend(value),
+					Some(OpenBinary::Struct { table, key, .. }) => {
+						let key = key.take().unwrap();
+						if let Some(x) = value {
+							table.insert(key.into(), x);
+						}
+					}
+					Some(OpenBinary::Annotation { rest, first }) => {
+						// Annotations wrap a single value, and anything after it is skipped
+						self.bytes = rest;
+						if *first == SYMBOL_TABLE {
+							self.load_symbol_table(value);
+							value = None;
+						}
+						stack.pop();
+						continue
+					}
+				}
+				break
+			}
+		}
+	}
+
+	fn decimal(content: &[u8]) -> Result<f64, DeserializationError> {
+		if content.is_empty() {
+			return Ok(0.0)
+		}
+		let mut reader = BinaryReader { bytes: content, symbols: Vec::new() };
+		let mut exponent = 0i64;
+		let mut first = true;
+		let mut negative = false;
+		loop {
+			let byte = reader.take(1)?[0];
+			let bits = if first {
+				negative = byte & 0x40 != 0;
+				byte & 0x3F
+			} else {
+				byte & 0x7F
+			};
+			first = false;
+			exponent = exponent.saturating_mul(128) | bits as i64;
+			if byte & 0x80 != 0 {
+				break
+			}
+		}
+		if negative {
+			exponent = -exponent;
+		}
+		let coefficient = match reader.bytes.split_first() {
+			None => 0.0,
+			Some((first, rest)) => {
+				let magnitude = Self::uint(&[&[first & 0x7F], rest].concat())? as f64;
+				if first & 0x80 != 0 { -magnitude } else { magnitude }
+			}
+		};
+		Ok(coefficient * 10f64.powi(exponent.clamp(-400, 400) as i32))
+	}
+
+	/// Replace the local symbols with those of a symbol table, appending to them if it imports $ion_symbol_table
+	fn load_symbol_table(&mut self, table: Option<TextRepr>) {
+		let table = match table {
+			Some(TextRepr::Table(x)) => x,
+			_ => return
+		};
+		let append = matches!(table.get(SYSTEM_SYMBOLS[6]), Some(TextRepr::String(x)) if x == SYSTEM_SYMBOLS[SYMBOL_TABLE]);
+		if !append {
+			self.symbols.truncate(SYSTEM_SYMBOLS.len());
+		}
+		if let Some(TextRepr::Array(x)) = table.get(SYSTEM_SYMBOLS[SYMBOLS]) {
+			for symbol in x {
+				self.symbols.push(match symbol {
+					TextRepr::String(x) => x.clone(),
+					_ => String::new()
+				});
+			}
+		}
+	}
+
+	fn stream(&mut self) -> Result<TextRepr, DeserializationError> {
+		let mut values = VecDeque::new();
+		while !self.bytes.is_empty() {
+			if self.bytes.starts_with(&VERSION_MARKER) {
+				self.bytes = &self.bytes[VERSION_MARKER.len()..];
+				self.symbols.truncate(SYSTEM_SYMBOLS.len());
+				continue
+			}
+			if let Some(x) = self.value()? {
+				values.push_back(x);
+			}
+		}
+		Ok(match values.len() {
+			0 => TextRepr::Empty,
+			1 => values.pop_front().unwrap(),
+			_ => TextRepr::Array(values)
+		})
+	}
+}
+
+
+impl TextRepr {
+	/// Write Ion text on a single line
+	pub fn to_ion(&self) -> String {
+		let mut out = String::new();
+		write_text(&mut out, self);
+		out
+	}
+
+	pub fn from_ion(data: &str) -> Result<Self, DeserializationError> {
+		TextParser { chars: data.chars().peekable() }.stream()
+	}
+
+	/// Write binary Ion, with a local symbol table holding the keys of every table
+	pub fn to_ion_binary(self) -> Vec<u8> {
+		let mut symbols = Vec::new();
+		let mut ids = HashMap::new();
+		collect_symbols(&self, &mut symbols, &mut ids);
+
+		let mut out = VERSION_MARKER.to_vec();
+		if !symbols.is_empty() {
+			let mut list = Vec::new();
+			for symbol in symbols {
+				write_binary(&mut list, TextRepr::String(symbol), &ids);
+			}
+			let mut table = Vec::new();
+			push_var_uint(&mut table, SYMBOLS);
+			push_header(&mut table, 0xB, list.len());
+			table.extend(list);
+			let mut wrapped = Vec::new();
+			push_var_uint(&mut wrapped, 1);
+			push_var_uint(&mut wrapped, SYMBOL_TABLE);
+			push_header(&mut wrapped, 0xD, table.len());
+			wrapped.extend(table);
+			push_header(&mut out, 0xE, wrapped.len());
+			out.extend(wrapped);
+		}
+		write_binary(&mut out, self, &ids);
+		out
+	}
+
+	pub fn from_ion_binary(data: &[u8]) -> Result<Self, DeserializationError> {
+		if !data.starts_with(&VERSION_MARKER) {
+			return Err(DeserializationError::invalid_format("missing Ion version marker"))
+		}
+		BinaryReader { bytes: data, symbols: SYSTEM_SYMBOLS.iter().map(|x| x.to_string()).collect() }.stream()
+	}
+}
+
+
+/// Serialize any value as Ion text
+pub fn to_string<P, T: Serialize<P>>(value: T) -> String {
+	serialize_owned!(value).to_ion()
+}
+
+
+/// Deserialize any value from Ion text
+pub fn from_str<P, T: Deserialize<P>>(data: &str) -> Result<T, DeserializationError> {
+	T::deserialize(&mut TextRepr::from_ion(data)?)
+}
+
+
+/// Serialize any value as binary Ion
+pub fn to_vec<P, T: Serialize<P>>(value: T) -> Vec<u8> {
+	serialize_owned!(value).to_ion_binary()
+}
+
+
+/// Deserialize any value from binary Ion
+pub fn from_slice<P, T: Deserialize<P>>(data: &[u8]) -> Result<T, DeserializationError> {
+	T::deserialize(&mut TextRepr::from_ion_binary(data)?)
+}
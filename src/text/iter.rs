@@ -0,0 +1,160 @@
+#[cfg(not(feature = "preserve_order"))]
+use std::collections::hash_map as table_iter;
+use std::collections::vec_deque;
+
+#[cfg(feature = "preserve_order")]
+use indexmap::map as table_iter;
+use std::fmt::Display;
+
+use super::*;
+
+
+/// One step of the path to a value inside a TextRepr
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathSegment<'a> {
+	/// The key of a table entry
+	Key(&'a str),
+	/// The index of an array item
+	Index(usize)
+}
+
+
+impl<'a> Display for PathSegment<'a> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			PathSegment::Key(x) => write!(f, "{}", x),
+			PathSegment::Index(x) => write!(f, "{}", x)
+		}
+	}
+}
+
+
+/// Iterator over the entries of a table
+pub struct Entries<'a>(Option<table_iter::Iter<'a, TableKey, TextRepr>>);
+
+
+impl<'a> Iterator for Entries<'a> {
+	type Item = (&'a str, &'a TextRepr);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.0.as_mut()?.next().map(|(key, value)| (&**key, value))
+	}
+}
+
+
+/// Iterator over the values of a table or the items of an array
+pub enum Values<'a> {
+	Table(table_iter::Values<'a, TableKey, TextRepr>),
+	Array(vec_deque::Iter<'a, TextRepr>),
+	None
+}
+
+
+impl<'a> Iterator for Values<'a> {
+	type Item = &'a TextRepr;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		match self {
+			Values::Table(x) => x.next(),
+			Values::Array(x) => x.next(),
+			Values::None => None
+		}
+	}
+}
+
+
+/// Mutable iterator over the values of a table or the items of an array
+pub enum ValuesMut<'a> {
+	Table(table_iter::ValuesMut<'a, TableKey, TextRepr>),
+	Array(vec_deque::IterMut<'a, TextRepr>),
+	None
+}
+
+
+impl<'a> Iterator for ValuesMut<'a> {
+	type Item = &'a mut TextRepr;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		match self {
+			ValuesMut::Table(x) => x.next(),
+			ValuesMut::Array(x) => x.next(),
+			ValuesMut::None => None
+		}
+	}
+}
+
+
+impl TextRepr {
+	/// Iterate over the entries of a table. Yields nothing if this is not a table
+	pub fn entries(&self) -> Entries<'_> {
+		match self {
+			TextRepr::Table(x) => Entries(Some(x.iter())),
+			_ => Entries(None)
+		}
+	}
+
+	/// Iterate over the values of a table or the items of an array.
+	/// Yields nothing for any other value
+	pub fn values(&self) -> Values<'_> {
+		match self {
+			TextRepr::Table(x) => Values::Table(x.values()),
+			TextRepr::Array(x) => Values::Array(x.iter()),
+			_ => Values::None
+		}
+	}
+
+	/// Mutably iterate over the values of a table or the items of an array.
+	/// Yields nothing for any other value
+	pub fn iter_mut(&mut self) -> ValuesMut<'_> {
+		match self {
+			TextRepr::Table(x) => ValuesMut::Table(x.values_mut()),
+			TextRepr::Array(x) => ValuesMut::Array(x.iter_mut()),
+			_ => ValuesMut::None
+		}
+	}
+
+	/// Visit this value and every value nested inside it, parents before their children.
+	/// The visitor is given the path from this value to the visited value
+	pub fn walk<F: FnMut(&[PathSegment], &TextRepr)>(&self, mut visitor: F) {
+		self.walk_inner(&mut Vec::new(), &mut visitor);
+	}
+
+	fn walk_inner<'a, F: FnMut(&[PathSegment], &TextRepr)>(&'a self, path: &mut Vec<PathSegment<'a>>, visitor: &mut F) {
+		visitor(path, self);
+		match self {
+			TextRepr::Table(x) => for (key, value) in x {
+				path.push(PathSegment::Key(key));
+				value.walk_inner(path, visitor);
+				path.pop();
+			}
+			TextRepr::Array(x) => for (i, value) in x.iter().enumerate() {
+				path.push(PathSegment::Index(i));
+				value.walk_inner(path, visitor);
+				path.pop();
+			}
+			_ => {}
+		}
+	}
+
+	/// Same as walk, but the visitor can modify each value before its children are visited
+	pub fn walk_mut<F: FnMut(&[PathSegment], &mut TextRepr)>(&mut self, mut visitor: F) {
+		self.walk_mut_inner(&mut Vec::new(), &mut visitor);
+	}
+
+	fn walk_mut_inner<'a, F: FnMut(&[PathSegment], &mut TextRepr)>(&'a mut self, path: &mut Vec<PathSegment<'a>>, visitor: &mut F) {
+		visitor(path, self);
+		match self {
+			TextRepr::Table(x) => for (key, value) in x.iter_mut() {
+				path.push(PathSegment::Key(key));
+				value.walk_mut_inner(path, visitor);
+				path.pop();
+			}
+			TextRepr::Array(x) => for (i, value) in x.iter_mut().enumerate() {
+				path.push(PathSegment::Index(i));
+				value.walk_mut_inner(path, visitor);
+				path.pop();
+			}
+			_ => {}
+		}
+	}
+}
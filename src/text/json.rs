@@ -1,6 +1,5 @@
 use std::collections::{HashMap, VecDeque};
-use std::fmt::Write;
-use std::ops::Add;
+use std::fmt::{Display, Write};
 use std::str::FromStr;
 
 use super::*;
@@ -8,57 +7,220 @@ use super::*;
 pub mod json_prelude {
 	pub use crate::{impl_json, impl_json_deser, impl_json_ser};
 
-	pub use super::{JSONDeserialize, JSONSerialize, text::TextRepr};
+	pub use super::{JSONDeserialize, JSONSerialize, JsonFormat, text::TextRepr};
 }
 
 
-fn split_layer(data: String) -> Result<Vec<String>, char> {
-	let mut out = Vec::new();
-	let mut curly_count = 0usize;
-	let mut square_count = 0usize;
-	let mut buffer = String::new();
+/// How TextRepr::to_json_with lays out JSON
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonFormat {
+	/// Written once for each level of nesting before the entries of tables
+	pub indent: String,
+	/// Whether a space is written after colons, and after the commas between array items
+	pub spacing: bool,
+	/// Whether the document ends with a newline
+	pub trailing_newline: bool,
+	/// Whether tables are written on a single line, ignoring indent
+	pub compact: bool
+}
 
-	for c in data.trim().chars() {
-		if c == '{' {
-			curly_count += 1;
-			if curly_count == 1 {
-				continue
-			}
-		} else if c == '}' {
-			if curly_count == 0 {
-				return Err(c)
-			}
-			curly_count -= 1;
-			if curly_count == 0 {
-				continue
-			}
-		} else if c == '[' {
-			square_count += 1;
-			if square_count == 1 {
-				continue
-			}
-		} else if c == ']' {
-			if square_count == 0 {
-				return Err(c)
-			}
-			square_count -= 1;
-			if square_count == 0 {
-				continue
+
+/// Tab indented tables, with arrays on one line
+impl Default for JsonFormat {
+	fn default() -> Self {
+		Self { indent: "\t".into(), spacing: true, trailing_newline: false, compact: false }
+	}
+}
+
+
+impl JsonFormat {
+	/// A single line without any whitespace, for payloads that are not read by people
+	pub fn compact() -> Self {
+		Self { indent: String::new(), spacing: false, trailing_newline: false, compact: true }
+	}
+}
+
+
+/// A container that is still open while parsing
+enum Frame {
+	/// A table, and the key of the value being parsed
+	Table(TextRepr, Option<TableKey>),
+	Array(TextRepr)
+}
+
+
+/// What the parser expects next
+#[derive(Clone, Copy, PartialEq)]
+enum Expect {
+	/// A key, or the end of the table
+	Key,
+	/// A value, or the end of the array
+	Item,
+	Value,
+	/// A comma, or the end of the container
+	Delimiter
+}
+
+
+/// The index of the first delimiter in data that is not inside a string, or the length of data
+fn find_delimiter(data: &str, delimiters: &[u8]) -> usize {
+	let bytes = data.as_bytes();
+	let mut i = 0;
+	while i < bytes.len() {
+		match bytes[i] {
+			b'"' => {
+				i += 1;
+				while i < bytes.len() && bytes[i] != b'"' {
+					if bytes[i] == b'\\' {
+						i += 1;
+					}
+					i += 1;
+				}
 			}
-		} else if c == ',' && !(square_count > 1 || curly_count > 1) {
-			out.push(buffer.trim().into());
-			buffer.clear();
-			continue
+			c if delimiters.contains(&c) => return i,
+			_ => {}
 		}
+		i += 1;
+	}
+	bytes.len()
+}
+
 
-		buffer.push(c);
+/// Write a string or key with quotes around it, escaping quotes, backslashes and control characters
+fn write_string<W: Write>(out: &mut W, string: &str) -> std::fmt::Result {
+	out.write_char('"')?;
+	for c in string.chars() {
+		match c {
+			'"' => out.write_str("\\\"")?,
+			'\\' => out.write_str("\\\\")?,
+			'\n' => out.write_str("\\n")?,
+			'\r' => out.write_str("\\r")?,
+			'\t' => out.write_str("\\t")?,
+			c if c.is_control() => write!(out, "\\u{:04x}", c as u32)?,
+			c => out.write_char(c)?
+		}
 	}
+	out.write_char('"')
+}
 
-	if !buffer.is_empty() {
-		out.push(buffer.trim().into());
+
+/// Whether token is a number, made of an optional sign, digits, an optional fraction and an optional exponent.
+/// Words such as inf and NaN, which Rust would parse as floats, are not numbers in JSON
+fn is_number(token: &str) -> bool {
+	let bytes = token.strip_prefix(['+', '-']).unwrap_or(token).as_bytes();
+	let mut i = 0;
+	let digits = |i: &mut usize| {
+		let start = *i;
+		while bytes.get(*i).is_some_and(u8::is_ascii_digit) {
+			*i += 1;
+		}
+		*i > start
+	};
+	if !digits(&mut i) {
+		return false
 	}
+	if bytes.get(i) == Some(&b'.') {
+		i += 1;
+		if !digits(&mut i) {
+			return false
+		}
+	}
+	if matches!(bytes.get(i), Some(b'e' | b'E')) {
+		i += 1;
+		if matches!(bytes.get(i), Some(b'+' | b'-')) {
+			i += 1;
+		}
+		if !digits(&mut i) {
+			return false
+		}
+	}
+	i == bytes.len()
+}
 
-	Ok(out)
+
+/// Read a quoted string or key, which must make up the whole of segment
+fn parse_string(segment: &str) -> Result<String, DeserializationError> {
+	let invalid = |reason: String| DeserializationError::invalid_format(reason).set_field(segment);
+	let unterminated = || DeserializationError::invalid_format_code(ErrorCode::E015_UnterminatedString, "string is missing its closing quote").set_field(segment);
+	// Reads the 4 hex digits of a \u escape
+	let code_unit = |chars: &mut std::str::Chars| {
+		let hex = chars.as_str().get(..4).filter(|x| x.chars().all(|c| c.is_ascii_hexdigit()));
+		let hex = hex.ok_or_else(|| invalid(format!("invalid escape \\u{}", chars.as_str().chars().take(4).collect::<String>())))?;
+		*chars = chars.as_str()[4..].chars();
+		Ok(u16::from_str_radix(hex, 16).unwrap())
+	};
+	let mut out = String::with_capacity(segment.len());
+	let mut chars = segment[1..].chars();
+	loop {
+		match chars.next().ok_or_else(unterminated)? {
+			'"' if chars.as_str().is_empty() => return Ok(out),
+			'"' => return Err(DeserializationError::invalid_format_code(ErrorCode::E018_TrailingData, format!("unexpected data after string: {}", chars.as_str())).set_field(segment)),
+			'\\' => match chars.next().ok_or_else(unterminated)? {
+				'"' => out.push('"'),
+				'\\' => out.push('\\'),
+				'/' => out.push('/'),
+				'b' => out.push('\u{8}'),
+				'f' => out.push('\u{c}'),
+				'n' => out.push('\n'),
+				'r' => out.push('\r'),
+				't' => out.push('\t'),
+				'u' => {
+					let mut units = vec![code_unit(&mut chars)?];
+					// Characters outside the basic multilingual plane are escaped as a surrogate pair
+					if (0xD800..0xDC00).contains(&units[0]) {
+						if let Some(rest) = chars.as_str().strip_prefix("\\u") {
+							chars = rest.chars();
+							units.push(code_unit(&mut chars)?);
+						}
+					}
+					out.push_str(&String::from_utf16(&units).map_err(|_| invalid("invalid unicode escape".into()))?);
+				}
+				x => return Err(invalid(format!("invalid escape \\{}", x)))
+			},
+			c => out.push(c)
+		}
+	}
+}
+
+
+/// Writes compact, single line JSON
+impl Display for TextRepr {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			// Nothing was serialized, such as for an empty map
+			TextRepr::Empty => f.write_str("{}"),
+			TextRepr::Null => f.write_str("null"),
+			TextRepr::String(x) => write_string(f, x),
+			TextRepr::Integer(x) => write!(f, "{}", x),
+			// JSON has no infinity or NaN
+			TextRepr::Float(x) if !x.is_finite() => f.write_str("null"),
+			// -0 would be read back as the integer 0
+			TextRepr::Float(x) if *x == 0.0 && x.is_sign_negative() => f.write_str("-0.0"),
+			TextRepr::Float(x) => write!(f, "{}", x),
+			TextRepr::Boolean(x) => write!(f, "{}", x),
+			TextRepr::Table(x) => {
+				f.write_str("{")?;
+				for (i, (key, value)) in x.iter().enumerate() {
+					if i > 0 {
+						f.write_str(", ")?;
+					}
+					write_string(f, key)?;
+					write!(f, ": {}", value)?;
+				}
+				f.write_str("}")
+			}
+			TextRepr::Array(x) => {
+				f.write_str("[")?;
+				for (i, value) in x.iter().enumerate() {
+					if i > 0 {
+						f.write_str(", ")?;
+					}
+					write!(f, "{}", value)?;
+				}
+				f.write_str("]")
+			}
+		}
+	}
 }
 
 
@@ -66,97 +228,284 @@ impl TextRepr {
 	pub fn is_valid_json<T: ToString>(data: T) -> bool {
 		Self::from_json(data.to_string()).is_ok()
 	}
+	/// Write standard JSON, with quoted keys and no trailing commas, in the default JsonFormat
 	pub fn to_json(self) -> String {
+		self.to_json_with(&JsonFormat::default())
+	}
+	/// Same as to_json, but laid out with the given format
+	pub fn to_json_with(self, format: &JsonFormat) -> String {
+		let mut out = String::new();
+		self.write_json(&mut out, format, 0);
+		if format.trailing_newline {
+			out.push('\n');
+		}
+		out
+	}
+	/// Same as to_json, but as JSON has no comment syntax, the comments at dotted key paths are written into a
+	/// `_comments` table next to the keys they describe. Deserializing skips it like any other unknown field
+	pub fn to_json_commented(self, comments: &[(&str, &str)]) -> String {
+		self.with_comments(comments, "").to_json()
+	}
+	/// Add a `_comments` table to this table and each nested table, holding the comments of their keys
+	fn with_comments(self, comments: &[(&str, &str)], prefix: &str) -> Self {
+		let Self::Table(map) = self else {
+			return self
+		};
+		let mut notes = Table::default();
+		let mut out = Table::default();
+		for (key, value) in map {
+			let path = if prefix.is_empty() { key.to_string() } else { format!("{}.{}", prefix, key) };
+			if let Some((_, comment)) = comments.iter().find(|(x, _)| *x == path).filter(|(_, x)| !x.trim().is_empty()) {
+				let lines: Vec<_> = comment.trim().lines().map(str::trim).collect();
+				notes.insert(key.clone(), Self::String(lines.join("\n")));
+			}
+			out.insert(key, value.with_comments(comments, &path));
+		}
+		if !notes.is_empty() {
+			out.insert("_comments".into(), Self::Table(notes));
+		}
+		Self::Table(out)
+	}
+	/// Write the JSON of older versions, whose keys are not quoted and whose tables end with a trailing comma.
+	/// Only this crate can read it back
+	pub fn to_legacy_json(self) -> String {
+		let mut out = String::new();
+		self.write_legacy_json(&mut out);
+		out
+	}
+	/// Write into a single buffer, so nested values are not copied into their parents at each depth
+	fn write_json(self, out: &mut String, format: &JsonFormat, depth: usize) {
+		let colon = if format.spacing { ": " } else { ":" };
 		match self {
-			TextRepr::Empty => String::new(),
-			TextRepr::String(x) => format!("\"{}\"", x),
-			TextRepr::Integer(x) => x.to_string(),
-			TextRepr::Float(x) => x.to_string(),
-			TextRepr::Boolean(x) => x.to_string(),
+			TextRepr::Table(x) if !x.is_empty() => {
+				out.push('{');
+				for (i, (key, value)) in x.into_iter().enumerate() {
+					if i > 0 {
+						out.push(',');
+					}
+					if !format.compact {
+						out.push('\n');
+						out.extend(std::iter::repeat_n(format.indent.as_str(), depth + 1));
+					}
+					write_string(out, &key).unwrap();
+					out.push_str(colon);
+					value.write_json(out, format, depth + 1);
+				}
+				if !format.compact {
+					out.push('\n');
+					out.extend(std::iter::repeat_n(format.indent.as_str(), depth));
+				}
+				out.push('}');
+			}
+			TextRepr::Array(x) => {
+				out.push('[');
+				for (i, value) in x.into_iter().enumerate() {
+					if i > 0 {
+						out.push_str(if format.spacing { ", " } else { "," });
+					}
+					value.write_json(out, format, depth);
+				}
+				out.push(']');
+			}
+			x => x.write_legacy_json(out)
+		}
+	}
+	fn write_legacy_json(self, out: &mut String) {
+		match self {
+			// Nothing was serialized, such as for an empty map
+			TextRepr::Empty => out.push_str("{}"),
+			TextRepr::Null => out.push_str("null"),
+			TextRepr::String(x) => write_string(out, &x).unwrap(),
+			TextRepr::Integer(x) => write!(out, "{}", x).unwrap(),
+			TextRepr::Float(x) if !x.is_finite() => out.push_str("null"),
+			// -0 would be read back as the integer 0
+			TextRepr::Float(x) if x == 0.0 && x.is_sign_negative() => out.push_str("-0.0"),
+			TextRepr::Float(x) => write!(out, "{}", x).unwrap(),
+			TextRepr::Boolean(x) => write!(out, "{}", x).unwrap(),
+			TextRepr::Table(x) if x.is_empty() => out.push_str("{}"),
 			TextRepr::Table(x) => {
-				let mut out = String::from("{\n");
-
+				out.push_str("{\n");
 				for (key, value) in x {
-					writeln!(out, "\t{}: {},", key, value.to_json()).expect("Unexpected error while writing to json string. Please report this to the developer");
+					write!(out, "\t{}: ", key).unwrap();
+					value.write_legacy_json(out);
+					out.push_str(",\n");
 				}
-
-				out.add("}")
+				out.push('}');
+			}
+			TextRepr::Array(x) => {
+				out.push('[');
+				for (i, value) in x.into_iter().enumerate() {
+					if i > 0 {
+						out.push_str(", ");
+					}
+					value.write_legacy_json(out);
+				}
+				out.push(']');
 			}
-			TextRepr::Array(x) => format!(
-				"{:?}",
-				x.into_iter().map(Self::to_json).collect::<Vec<_>>()
-			)
 		}
 	}
+	/// Parse JSON with the default ParseOptions, so a repeated key replaces its earlier value
 	pub fn from_json(data: String) -> Result<Self, DeserializationError> {
-		let mut out = Self::new();
-		let data = data.trim().to_string();
-		let chars: VecDeque<_> = data.char_indices().collect();
-
-		let start_char = match chars.front() {
-			Some(c) => c.1,
-			None => return Err(DeserializationError::EOF)
-		};
+		Self::from_json_with(data, &ParseOptions::default())
+	}
+	/// Parse JSON with an explicit stack of open containers instead of recursion,
+	/// so deeply nested documents cannot overflow the stack. Documents nested deeper than MAX_DEPTH are rejected
+	pub fn from_json_with(data: String, options: &ParseOptions) -> Result<Self, DeserializationError> {
+		let mut interner = KeyInterner::new();
+		let data = data.trim();
+		if data.is_empty() {
+			return Err(DeserializationError::EOF)
+		}
 
-		if start_char == '{' {
-			unsafe {
-				if chars.back().unwrap_unchecked().1 != '}' {
-					return Err(DeserializationError::invalid_format("missing closing brace"))
+		let mut stack: Vec<Frame> = Vec::new();
+		let mut out = None;
+		let mut expect = Expect::Value;
+		let mut i = 0;
+
+		loop {
+			i += data[i..].len() - data[i..].trim_start().len();
+			let Some(c) = data[i..].chars().next() else {
+				return match stack.pop() {
+					None => out.ok_or(DeserializationError::EOF),
+					Some(Frame::Table(..)) => Err(DeserializationError::invalid_format_code(ErrorCode::E011_MissingClosingBrace, "missing closing brace")),
+					Some(Frame::Array(_)) => Err(DeserializationError::invalid_format_code(ErrorCode::E011_MissingClosingBrace, "missing closing bracket"))
 				}
-			}
-
-			let segments = split_layer(data).map_err(|c| { DeserializationError::invalid_format(format!("Unbalanced braces: {c}")) })?;
-
-			for segment in segments {
-				if segment.is_empty() {
+			};
+
+			let depth = stack.len();
+			let value = match (expect, stack.last_mut()) {
+				(Expect::Delimiter, None) if c == '}' || c == ']' => return Err(DeserializationError::invalid_format_code(ErrorCode::E012_UnbalancedBraces, format!("Unbalanced braces: {c}"))),
+				(Expect::Delimiter, None) => return Err(DeserializationError::invalid_format_code(ErrorCode::E018_TrailingData, "unexpected data after the end of the document")),
+				(Expect::Delimiter, Some(frame)) => {
+					i += 1;
+					match (c, frame) {
+						(',', Frame::Table(..)) => {
+							expect = Expect::Key;
+							continue
+						}
+						(',', Frame::Array(_)) => {
+							expect = Expect::Item;
+							continue
+						}
+						('}', Frame::Table(..)) | (']', Frame::Array(_)) => {}
+						('}' | ']', _) => return Err(DeserializationError::invalid_format_code(ErrorCode::E012_UnbalancedBraces, format!("Unbalanced braces: {c}"))),
+						_ => return Err(DeserializationError::invalid_format(format!("expected a comma, found {c}")))
+					}
+					match stack.pop().unwrap() {
+						Frame::Table(x, _) | Frame::Array(x) => x
+					}
+				}
+				(Expect::Key, _) if c == ',' => {
+					i += 1;
+					continue
+				}
+				(Expect::Key | Expect::Item, _) if c == '}' || c == ']' => {
+					i += 1;
+					match (c, stack.pop().unwrap()) {
+						('}', Frame::Table(x, _)) | (']', Frame::Array(x)) => x,
+						_ => return Err(DeserializationError::invalid_format_code(ErrorCode::E012_UnbalancedBraces, format!("Unbalanced braces: {c}")))
+					}
+				}
+				(Expect::Key, Some(Frame::Table(_, key))) => {
+					let len = find_delimiter(&data[i..], b":,}");
+					let segment = data[i..(i + len)].trim();
+					if !data[(i + len)..].starts_with(':') {
+						return Err(DeserializationError::invalid_format_code(ErrorCode::E013_MissingValue, "missing value").set_field(segment))
+					}
+					// Keys may be quoted as in standard JSON, or bare as in the legacy form
+					if segment.starts_with('"') {
+						*key = Some(interner.intern(&parse_string(segment)?));
+					} else if segment.is_empty() {
+						return Err(DeserializationError::invalid_format_code(ErrorCode::E014_MissingKey, "missing key"))
+					} else {
+						*key = Some(interner.intern(segment));
+					}
+					i += len + 1;
+					expect = Expect::Value;
 					continue
 				}
-				let idx = match segment.find(':') {
-					None => return Err(DeserializationError::invalid_format("missing value").set_field(segment)),
-					Some(x) => x
-				};
-				let (key, value) = segment.split_at(idx);
+				(Expect::Item, _) if c == ',' => return Err(DeserializationError::invalid_format_code(ErrorCode::E013_MissingValue, "missing array value")),
+				(_, frame) => {
+					match c {
+						'{' | '[' if depth == MAX_DEPTH => return Err(too_deep()),
+						'{' => {
+							i += 1;
+							stack.push(Frame::Table(Self::Table(Table::default()), None));
+							expect = Expect::Key;
+							continue
+						}
+						'[' => {
+							i += 1;
+							stack.push(Frame::Array(Self::Array(VecDeque::new())));
+							expect = Expect::Item;
+							continue
+						}
+						'}' | ']' | ',' => return Err(match frame {
+							Some(Frame::Table(_, Some(key))) => DeserializationError::invalid_format_code(ErrorCode::E013_MissingValue, "missing value").set_field(key),
+							_ if c == ',' => DeserializationError::invalid_format_code(ErrorCode::E013_MissingValue, "missing value"),
+							_ => DeserializationError::invalid_format_code(ErrorCode::E012_UnbalancedBraces, format!("Unbalanced braces: {c}"))
+						}),
+						_ => {}
+					}
+					let len = find_delimiter(&data[i..], b",}]");
+					let value = match data[i..(i + len)].trim_end() {
+						"null" => Self::Null,
+						x if x.starts_with('"') => Self::String(parse_string(x)?),
+						x @ ("true" | "false") => Self::from_str_value(x.into())?,
+						x if is_number(x) => match Self::from_str_value(x.into())? {
+							Self::Float(f) if !f.is_finite() => return Err(DeserializationError::invalid_format(format!("{} is out of the range of a float", x))),
+							value => value
+						},
+						x => return Err(DeserializationError::invalid_format(format!("{} is not a string, number or boolean", x)))
+					};
+					i += len;
+					value
+				}
+			};
 
-				let key = key.trim();
+			expect = Expect::Delimiter;
+			match stack.last_mut() {
+				None => out = Some(value),
+				Some(Frame::Table(x, key)) => x.push_parsed_entry(vec![key.take().unwrap()], value, options.duplicate_keys)?,
+				Some(Frame::Array(x)) => x.push_value(value)
+			}
+		}
+	}
+}
 
-				if key.is_empty() {
-					return Err(DeserializationError::invalid_format("missing key"))
-				}
 
-				let value = match value.get(1..) {
-					None => "",
-					Some(x) => x.trim()
-				};
+/// Serialize any value as JSON, without implementing JSONSerialize for it
+pub fn to_string<P, T: Serialize<P>>(value: T) -> String {
+	let mut out = TextRepr::new();
+	value.serialize(&mut out);
+	out.to_json()
+}
 
-				if value.is_empty() {
-					return Err(DeserializationError::invalid_format("missing value").set_field(key))
-				}
 
-				out.push_entry(key.into(), Self::from_json(value.into())?);
-			}
-		} else if start_char == '[' {
-			let segments = split_layer(data).map_err(|c| { DeserializationError::invalid_format(format!("Unbalanced braces: {c}")) })?;
+/// Same as to_string, but laid out with the given format
+pub fn to_string_with<P, T: Serialize<P>>(value: T, format: &JsonFormat) -> String {
+	let mut out = TextRepr::new();
+	value.serialize(&mut out);
+	out.to_json_with(format)
+}
 
-			for segment in segments {
-				let segment = segment.trim().to_string();
 
-				if segment.is_empty() {
-					return Err(DeserializationError::invalid_format("missing array value"))
-				}
+/// Deserialize any value from JSON, without implementing JSONDeserialize for it
+pub fn from_str<P, T: Deserialize<P>>(data: &str) -> Result<T, DeserializationError> {
+	T::deserialize(&mut TextRepr::from_json(data.into())?)
+}
 
-				out.push_value(Self::from_json(segment)?);
-			}
-		} else {
-			return Self::from_str_value(data)
-		}
 
-		Ok(out)
-	}
+/// Same as from_str, but parsed with the given options
+pub fn from_str_with<P, T: Deserialize<P>>(data: &str, options: &ParseOptions) -> Result<T, DeserializationError> {
+	T::deserialize(&mut TextRepr::from_json_with(data.into(), options)?)
 }
 
 
 pub trait JSONSerialize<P = NaturalProfile> {
 	fn serialize_json(self) -> String;
+	/// Serialize as JSON on a single line without any whitespace, as in JsonFormat::compact
+	fn serialize_json_compact(self) -> String;
 }
 
 
@@ -199,9 +548,10 @@ macro_rules! impl_json_ser {
     ($name: ty, $profile: ty) => {
 		impl JSONSerialize<$profile> for $name {
 			fn serialize_json(self) -> String {
-				let mut out = TextRepr::new();
-				Serialize::<$profile>::serialize(self, &mut out);
-				out.to_json()
+				$crate::json::to_string::<$profile, _>(self)
+			}
+			fn serialize_json_compact(self) -> String {
+				$crate::json::to_string_with::<$profile, _>(self, &$crate::json::JsonFormat::compact())
 			}
 		}
 	};
@@ -235,7 +585,7 @@ macro_rules! impl_json_deser {
     ($name: ty, $profile: ty) => {
 		impl JSONDeserialize<$profile> for $name {
 			fn deserialize_json(data: String) -> Result<Self, DeserializationError> {
-				Deserialize::<$profile>::deserialize(&mut TextRepr::from_json(data)?)
+				$crate::json::from_str::<$profile, _>(&data)
 			}
 		}
 	};
@@ -249,14 +599,34 @@ macro_rules! impl_json_deser {
 }
 
 
-impl<P, K: Borrow<str> + Eq + std::hash::Hash, V: Serialize<P>> JSONSerialize<P> for HashMap<K, V> {
+impl<P, K: Borrow<str> + Eq + std::hash::Hash, V: Serialize<P>, S: std::hash::BuildHasher> JSONSerialize<P> for HashMap<K, V, S> {
 	fn serialize_json(self) -> String {
 		TextRepr::to_json(serialize_owned!(self))
 	}
+	fn serialize_json_compact(self) -> String {
+		TextRepr::to_json_with(serialize_owned!(self), &JsonFormat::compact())
+	}
+}
+
+
+impl<E: Debug, P, K: Eq + std::hash::Hash + FromStr<Err=E>, V: Deserialize<P>, S: std::hash::BuildHasher + Default> JSONDeserialize<P> for HashMap<K, V, S> {
+	fn deserialize_json(data: String) -> Result<Self, DeserializationError> {
+		Self::deserialize::<TextRepr>(&mut TextRepr::from_json(data)?)
+	}
+}
+
+
+impl<P, V: Serialize<P>> JSONSerialize<P> for Vec<V> {
+	fn serialize_json(self) -> String {
+		TextRepr::to_json(serialize_owned!(self))
+	}
+	fn serialize_json_compact(self) -> String {
+		TextRepr::to_json_with(serialize_owned!(self), &JsonFormat::compact())
+	}
 }
 
 
-impl<E: Debug, P, K: Eq + std::hash::Hash + FromStr<Err=E>, V: Deserialize<P>> JSONDeserialize<P> for HashMap<K, V> {
+impl<P, V: Deserialize<P>> JSONDeserialize<P> for Vec<V> {
 	fn deserialize_json(data: String) -> Result<Self, DeserializationError> {
 		Self::deserialize::<TextRepr>(&mut TextRepr::from_json(data)?)
 	}
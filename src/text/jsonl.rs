@@ -0,0 +1,108 @@
+//! JSON Lines, also known as NDJSON, which holds one compact JSON document on each line.
+//!
+//! Each line is written and read on its own, so large logs can be streamed with [to_writer] and [from_reader]
+//! without holding every document in memory. Blank lines are skipped when reading
+use std::io::{self, BufRead, Write};
+use std::marker::PhantomData;
+
+use super::*;
+
+
+fn read_line<P, T: Deserialize<P>>(line: &str, number: usize) -> Result<T, DeserializationError> {
+	TextRepr::from_json(line.into())
+		.and_then(|mut x| T::deserialize(&mut x))
+		.map_err(|e| e.nest().set_field(format!("line {}", number)))
+}
+
+
+/// Serialize each item as a line of JSON, without implementing JSONLinesSerialize for them
+pub fn to_string<P, T: Serialize<P>, I: IntoIterator<Item=T>>(items: I) -> String {
+	let mut out = String::new();
+	for item in items {
+		let mut repr = TextRepr::new();
+		item.serialize(&mut repr);
+		out.push_str(&repr.to_string());
+		out.push('\n');
+	}
+	out
+}
+
+
+/// Serialize each item as a line of JSON into the writer, one at a time
+pub fn to_writer<P, T: Serialize<P>, I: IntoIterator<Item=T>, W: Write>(mut writer: W, items: I) -> io::Result<()> {
+	for item in items {
+		let mut repr = TextRepr::new();
+		item.serialize(&mut repr);
+		writeln!(writer, "{}", repr)?;
+	}
+	Ok(())
+}
+
+
+/// Deserialize every line of JSON, without implementing JSONLinesDeserialize for them.
+/// Errors name the line that could not be deserialized, counting from 1
+pub fn from_str<P, T: Deserialize<P>>(data: &str) -> Result<Vec<T>, DeserializationError> {
+	data.lines()
+		.enumerate()
+		.filter(|(_, line)| !line.trim().is_empty())
+		.map(|(i, line)| read_line(line, i + 1))
+		.collect()
+}
+
+
+/// Lazily deserialize each line of JSON from the reader
+pub fn from_reader<P, T: Deserialize<P>, R: BufRead>(reader: R) -> JSONLines<R, T, P> {
+	JSONLines { reader, line: String::new(), number: 0, _phantom: PhantomData }
+}
+
+
+/// An iterator that reads and deserializes one line of JSON at a time.
+/// A line that fails to be read or deserialized yields an error, after which the following lines can still be read
+pub struct JSONLines<R, T, P = NaturalProfile> {
+	reader: R,
+	line: String,
+	number: usize,
+	_phantom: PhantomData<fn() -> (T, P)>
+}
+
+
+impl<R: BufRead, T: Deserialize<P>, P> Iterator for JSONLines<R, T, P> {
+	type Item = Result<T, DeserializationError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			self.line.clear();
+			self.number += 1;
+			match self.reader.read_line(&mut self.line) {
+				Ok(0) => return None,
+				Ok(_) if self.line.trim().is_empty() => continue,
+				Ok(_) => return Some(read_line(&self.line, self.number)),
+				Err(e) => return Some(Err(DeserializationError::custom(e).set_field(format!("line {}", self.number))))
+			}
+		}
+	}
+}
+
+
+pub trait JSONLinesSerialize<P = NaturalProfile> {
+	fn serialize_json_lines(self) -> String;
+}
+
+
+pub trait JSONLinesDeserialize<P = NaturalProfile>: Sized {
+	fn deserialize_json_lines(data: String) -> Result<Self, DeserializationError>;
+}
+
+
+impl<P, V: Serialize<P>, I: IntoIterator<Item=V>> JSONLinesSerialize<P> for I {
+	fn serialize_json_lines(self) -> String {
+		to_string(self)
+	}
+}
+
+
+impl<P, V: Deserialize<P>> JSONLinesDeserialize<P> for Vec<V> {
+	fn deserialize_json_lines(data: String) -> Result<Self, DeserializationError> {
+		from_str(&data)
+	}
+}
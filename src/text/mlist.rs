@@ -1,5 +1,6 @@
 use std::collections::{HashMap, VecDeque};
 use std::fmt::Write;
+use std::mem::take;
 use std::str::FromStr;
 use crate::toml::{AVG_TOML_LINE_LENGTH, map_entries_recursive};
 
@@ -19,7 +20,7 @@ impl TextRepr {
 	}
 	pub fn to_mlist(self) -> String {
 		match self {
-			TextRepr::Empty => String::new(),
+			TextRepr::Empty | TextRepr::Null => String::new(),
 			TextRepr::String(x) => format!("\"{}\"", x),
 			TextRepr::Integer(x) => x.to_string(),
 			TextRepr::Float(x) => x.to_string(),
@@ -37,15 +38,15 @@ impl TextRepr {
 					if path.is_empty() {
 						field_name = String::new();
 					} else {
-						field_name = path.remove(0);
+						field_name = path.remove(0).to_string();
 
 						for segment in path {
 							field_name += ".";
-							field_name += segment.as_str();
+							field_name += &segment;
 						}
 					}
 					for (name, value) in values {
-						writeln!(out, "[{}]\n{}", field_name.clone() + name.as_str(), value.to_mlist()).expect("Error writing map to mlist string. Please report this to the developer.");
+						writeln!(out, "[{}]\n{}", field_name.clone() + &name, value.to_mlist()).expect("Error writing map to mlist string. Please report this to the developer.");
 					}
 					out += "\n";
 				}
@@ -69,7 +70,7 @@ impl TextRepr {
 				});
 				let mut out = String::new();
 
-				for v in x {
+				for v in x.into_iter().filter(|x| !matches!(x, TextRepr::Null)) {
 					writeln!(out, "{}", v.to_mlist()).expect("Error writing map to mlist string. Please report this to the developer.");
 				}
 
@@ -78,19 +79,24 @@ impl TextRepr {
 		}
 	}
 
+	/// Parse an mlist file, resolving the paths listed under any `[include]` field
+	pub fn from_mlist_file<T: AsRef<Path>>(path: T) -> Result<Self, DeserializationError> {
+		Self::from_file_with_includes(path.as_ref(), Self::from_mlist, &mut Vec::new())
+	}
+
 	pub fn from_mlist(data: String) -> Result<Self, DeserializationError> {
 		let mut out = Self::new();
 		let mut data: VecDeque<char> = data.chars().collect();
 		let mut outer_path = Vec::new();
 		let mut values = Vec::new();
+		let mut interner = KeyInterner::new();
 
 		while let Some(start_char) = first_symbol(&mut data) {
 			if start_char == '[' {
 				if !values.is_empty() {
-					let mut new_path = outer_path.clone();
+					let mut new_path = take(&mut outer_path);
 					new_path.reverse();
-					out.push_entry_path(new_path, Self::Array(values.into()));
-					values = Vec::new();
+					out.push_parsed_entry(new_path, Self::Array(take(&mut values).into()), DuplicateKeys::LastWins)?;
 				}
 
 				outer_path.clear();
@@ -101,7 +107,7 @@ impl TextRepr {
 						break
 					}
 					if c == '.' {
-						outer_path.push(segment.clone());
+						outer_path.push(interner.intern(&segment));
 						segment.clear();
 						continue
 					}
@@ -109,9 +115,9 @@ impl TextRepr {
 				}
 				if segment.is_empty() {
 					// TODO Make clearer
-					return Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidFormat { reason: "Outer field name is either empty or terminates incorrectly".into() }))
+					return Err(DeserializationError::invalid_format_code(ErrorCode::E016_InvalidTableName, "Outer field name is either empty or terminates incorrectly"))
 				}
-				outer_path.push(segment);
+				outer_path.push(interner.intern(&segment));
 				continue
 			}
 
@@ -123,13 +129,16 @@ impl TextRepr {
 				value.push(c);
 			}
 			value = value.trim().to_string();
+			if outer_path.is_empty() {
+				return Err(DeserializationError::invalid_format_code(ErrorCode::E016_InvalidTableName, format!("{} does not come after a field name", value)))
+			}
 			values.push(Self::from_str_value(value)?);
 		}
 
 		if !values.is_empty() {
-			let mut new_path = outer_path.clone();
+			let mut new_path = outer_path;
 			new_path.reverse();
-			out.push_entry_path(new_path, Self::Array(values.into()));
+			out.push_parsed_entry(new_path, Self::Array(values.into()), DuplicateKeys::LastWins)?;
 		}
 
 		Ok(out)
@@ -137,6 +146,20 @@ impl TextRepr {
 }
 
 
+/// Serialize any value as MList, without implementing MListSerialize for it
+pub fn to_string<P, T: Serialize<P>>(value: T) -> String {
+	let mut out = TextRepr::new();
+	value.serialize(&mut out);
+	out.to_mlist()
+}
+
+
+/// Deserialize any value from MList, without implementing MListDeserialize for it
+pub fn from_str<P, T: Deserialize<P>>(data: &str) -> Result<T, DeserializationError> {
+	T::deserialize(&mut TextRepr::from_mlist(data.into())?)
+}
+
+
 pub trait MListSerialize<P = NaturalProfile> {
 	fn serialize_mlist(self) -> String;
 }
@@ -187,9 +210,7 @@ macro_rules! impl_mlist_ser {
     ($name: ty, $profile: ty) => {
 		impl MListSerialize<$profile> for $name {
 			fn serialize_mlist(self) -> String {
-				let mut out = TextRepr::new();
-				Serialize::<$profile>::serialize(self, &mut out);
-				out.to_mlist()
+				$crate::mlist::to_string::<$profile, _>(self)
 			}
 		}
 	};
@@ -223,7 +244,7 @@ macro_rules! impl_mlist_deser {
     ($name: ty, $profile: ty) => {
 		impl MListDeserialize<$profile> for $name {
 			fn deserialize_mlist(data: String) -> Result<Self, DeserializationError> {
-				Deserialize::<$profile>::deserialize(&mut TextRepr::from_mlist(data)?)
+				$crate::mlist::from_str::<$profile, _>(&data)
 			}
 		}
 	};
@@ -237,14 +258,14 @@ macro_rules! impl_mlist_deser {
 }
 
 
-impl<P, K: Borrow<str> + Eq + std::hash::Hash, V: Serialize<P>> MListSerialize<P> for HashMap<K, V> {
+impl<P, K: Borrow<str> + Eq + std::hash::Hash, V: Serialize<P>, S: std::hash::BuildHasher> MListSerialize<P> for HashMap<K, V, S> {
 	fn serialize_mlist(self) -> String {
 		TextRepr::to_mlist(serialize_owned!(self))
 	}
 }
 
 
-impl<E: Debug, P, K: Eq + std::hash::Hash + FromStr<Err=E>, V: Deserialize<P>> MListDeserialize<P> for HashMap<K, V> {
+impl<E: Debug, P, K: Eq + std::hash::Hash + FromStr<Err=E>, V: Deserialize<P>, S: std::hash::BuildHasher + Default> MListDeserialize<P> for HashMap<K, V, S> {
 	fn deserialize_mlist(data: String) -> Result<Self, DeserializationError> {
 		Self::deserialize::<TextRepr>(&mut TextRepr::from_mlist(data)?)
 	}
@@ -1,18 +1,57 @@
-use std::collections::{HashMap, VecDeque};
+#[cfg(not(feature = "fxhash"))]
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet, VecDeque};
+#[cfg(not(feature = "preserve_order"))]
+use std::collections::hash_map::Entry;
+use std::fs::read_to_string;
 use std::hint;
 use std::mem::replace;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[cfg(feature = "preserve_order")]
+use indexmap::map::Entry;
 
 pub use json::json_prelude;
 pub use toml::toml_prelude;
 pub use mlist::mlist_prelude;
+pub use yaml::yaml_prelude;
+pub use ron::ron_prelude;
+pub use hcl::hcl_prelude;
 
 use super::*;
 
 pub mod toml;
 pub mod json;
+pub mod jsonl;
 pub mod mlist;
+pub mod yaml;
+pub mod ron;
+pub mod hcl;
+pub mod plist;
+pub mod sexpr;
+pub mod arena;
+pub mod config;
+pub mod csv;
+pub mod env;
+pub mod urlencoded;
+pub mod delta;
+pub mod iter;
+pub mod tracked;
 #[cfg(feature = "regex")]
 mod regex;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "tracing")]
+pub mod trace;
+#[cfg(feature = "miette")]
+pub mod diagnostic;
+#[cfg(feature = "ion")]
+pub mod ion;
+#[cfg(feature = "web")]
+pub mod web;
+#[cfg(feature = "reqwest")]
+pub mod client;
 
 
 macro_rules! serialize_owned {
@@ -26,18 +65,109 @@ macro_rules! serialize_owned {
 use serialize_owned;
 
 
-#[derive(Debug)]
+/// The key of a table entry. Equal keys in a parsed document share the same allocation
+pub type TableKey = Arc<str>;
+
+/// The hasher used by tables.
+/// With the fxhash feature, the faster but non-randomized FxHash is used, as keys are mostly short field names
+#[cfg(feature = "fxhash")]
+pub type TableHasher = rustc_hash::FxBuildHasher;
+#[cfg(not(feature = "fxhash"))]
+pub type TableHasher = RandomState;
+
+/// The map backing TextRepr::Table.
+/// With the preserve_order feature, tables keep their keys in the order they were inserted,
+/// so documents are written with their keys in the same order that they were parsed or serialized in
+#[cfg(not(feature = "preserve_order"))]
+pub type Table = HashMap<TableKey, TextRepr, TableHasher>;
+#[cfg(feature = "preserve_order")]
+pub type Table = indexmap::IndexMap<TableKey, TextRepr, TableHasher>;
+
+
+/// Remove an entry from a table, keeping the remaining keys in order
+pub(crate) fn remove_entry(table: &mut Table, key: &str) -> Option<TextRepr> {
+	#[cfg(feature = "preserve_order")]
+	return table.shift_remove(key);
+	#[cfg(not(feature = "preserve_order"))]
+	return table.remove(key);
+}
+
+/// What the JSON and TOML parsers do when a table holds the same key more than once
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKeys {
+	/// Fail with a DuplicateKey error
+	Error,
+	/// Keep the first value, ignoring later ones
+	FirstWins,
+	/// Keep the last value, replacing earlier ones
+	#[default]
+	LastWins
+}
+
+
+/// Options for TextRepr::from_json_with and TextRepr::from_toml_with
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+	pub duplicate_keys: DuplicateKeys
+}
+
+
+/// The deepest nesting of tables and arrays that the parsers accept.
+/// Dropping, printing and deserializing a document recurses once per level, so deeper documents could overflow the stack
+pub const MAX_DEPTH: usize = 1024;
+
+fn too_deep() -> DeserializationError {
+	DeserializationError::invalid_format(format!("document is nested deeper than {} levels", MAX_DEPTH))
+}
+
+
+/// An untyped document of a text format.
+/// Equality compares floats as IEEE values, so a NaN is never equal to anything
+#[derive(Debug, Default, Clone, PartialEq)]
 pub enum TextRepr {
+	#[default]
 	Empty,
+	/// An explicitly missing value, such as a None. JSON writes it as null, while TOML and MList leave the key out
+	Null,
 	String(String),
 	Integer(i64),
 	Float(f64),
 	Boolean(bool),
-	Table(HashMap<String, Self>),
+	Table(Table),
 	Array(VecDeque<TextRepr>),
 }
 
 
+/// The text formats a TextRepr can be written as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextFormat {
+	Json,
+	Toml,
+	MList
+}
+
+
+/// Hands out a single shared allocation for each distinct table key
+#[derive(Debug, Default)]
+pub struct KeyInterner(HashSet<TableKey>);
+
+
+impl KeyInterner {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn intern(&mut self, key: &str) -> TableKey {
+		if let Some(x) = self.0.get(key) {
+			return x.clone()
+		}
+		let key: TableKey = key.into();
+		self.0.insert(key.clone());
+		key
+	}
+}
+
+
 fn first_symbol(data: &mut VecDeque<char>) -> Option<char> {
 	while let Some(c) = data.pop_front() {
 		match c {
@@ -54,6 +184,15 @@ impl TextRepr {
 		Self::Empty
 	}
 
+	/// Write this TextRepr in the given format, without consuming it
+	pub fn format(&self, fmt: TextFormat) -> String {
+		match fmt {
+			TextFormat::Json => self.clone().to_json(),
+			TextFormat::Toml => self.clone().to_toml(),
+			TextFormat::MList => self.clone().to_mlist()
+		}
+	}
+
 	pub fn is_empty(&self) -> bool {
 		match self {
 			Self::Empty => true,
@@ -63,17 +202,32 @@ impl TextRepr {
 		}
 	}
 
+	/// The name of the kind of value this is, as used in InvalidType errors
+	pub fn type_name(&self) -> &'static str {
+		match self {
+			TextRepr::Empty => "nothing",
+			TextRepr::Null => "null",
+			TextRepr::String(_) => "string",
+			TextRepr::Integer(_) => "integer",
+			TextRepr::Float(_) => "float",
+			TextRepr::Boolean(_) => "boolean",
+			TextRepr::Table(_) => "table",
+			TextRepr::Array(_) => "array"
+		}
+	}
+
 	pub fn pull_value(&mut self) -> Result<Self, DeserializationErrorKind> {
 		match self {
 			TextRepr::Array(x) => x.pop_front().ok_or(DeserializationErrorKind::UnexpectedEOF),
+			TextRepr::Empty => Err(DeserializationErrorKind::UnexpectedEOF),
 			// TextRepr::Table(_) => Err(DeserializationErrorKind::InvalidType { expected: "non-table", actual: "table" }),
 			_ => Ok(replace(self, Self::Empty))
 		}
 	}
 
-	pub fn pull_entry<T: Borrow<String>>(&mut self, key: T) -> Result<Self, DeserializationErrorKind> {
+	pub fn pull_entry<T: Borrow<str>>(&mut self, key: T) -> Result<Self, DeserializationErrorKind> {
 		match self {
-			TextRepr::Table(x) => x.remove(key.borrow()).ok_or(DeserializationErrorKind::MissingField),
+			TextRepr::Table(x) => remove_entry(x, key.borrow()).ok_or(DeserializationErrorKind::MissingField),
 			_ => Err(DeserializationErrorKind::InvalidType { expected: "table", actual: "non-table" })
 		}
 	}
@@ -87,8 +241,8 @@ impl TextRepr {
 				let value = replace(self, Self::Array(VecDeque::new()));
 				match self {
 					Self::Array(arr) => {
-						arr.push_front(value);
-						arr.push_front(other);
+						arr.push_back(value);
+						arr.push_back(other);
 					}
 					_ => unreachable!()
 				}
@@ -96,10 +250,42 @@ impl TextRepr {
 		};
 	}
 
-	fn push_entry(&mut self, key: String, other: Self) {
+	/// Deep merge other on top of this TextRepr.
+	/// Tables are merged entry by entry, while any other value in other replaces the value here.
+	/// An empty other leaves this TextRepr untouched
+	pub fn merge(&mut self, other: Self) {
+		match (self, other) {
+			(_, TextRepr::Empty) => {}
+			(TextRepr::Table(x), TextRepr::Table(other)) => for (key, value) in other {
+				match x.entry(key) {
+					Entry::Occupied(mut x) => x.get_mut().merge(value),
+					Entry::Vacant(x) => { x.insert(value); }
+				}
+			}
+			(this, other) => *this = other
+		}
+	}
+
+	/// Make equal keys throughout this TextRepr share the same allocation
+	pub fn intern_keys(&mut self, interner: &mut KeyInterner) {
+		match self {
+			TextRepr::Table(x) => {
+				*x = std::mem::take(x).into_iter()
+					.map(|(key, mut value)| {
+						value.intern_keys(interner);
+						(interner.intern(&key), value)
+					})
+					.collect();
+			}
+			TextRepr::Array(x) => x.iter_mut().for_each(|x| x.intern_keys(interner)),
+			_ => {}
+		}
+	}
+
+	fn push_entry(&mut self, key: TableKey, other: Self) {
 		match self {
 			TextRepr::Empty => {
-				let mut table = HashMap::new();
+				let mut table = Table::default();
 				table.insert(key, other);
 				*self = Self::Table(table);
 			}
@@ -108,26 +294,91 @@ impl TextRepr {
 		}
 	}
 
-	fn push_entry_path(&mut self, mut path: Vec<String>, other: Self) {
+	/// Insert other at the path, whose first key is last. The tables along the path are created as needed
+	fn push_entry_path(&mut self, mut path: Vec<TableKey>, other: Self) {
 		assert!(!path.is_empty());
-		if path.len() == 1 {
-			self.push_entry(path.pop().unwrap(), other);
-			return
+		let mut current = self;
+		while path.len() > 1 {
+			if let TextRepr::Empty = current {
+				*current = Self::Table(Table::default());
+			}
+			current = match current {
+				TextRepr::Table(x) => x.entry(path.pop().unwrap()).or_insert_with(|| TextRepr::Table(Table::default())),
+				_ => panic!("Tried to insert a TextRepr onto a non-empty and non-table TextRepr!")
+			};
 		}
-		match self {
-			TextRepr::Empty => {
-				*self = Self::Table(HashMap::new());
-				self.push_entry_path(path, other);
+		current.push_entry(path.pop().unwrap(), other);
+	}
+
+	/// Same as push_entry_path, but a key that is already set is handled according to duplicates.
+	/// Used by parsers, where a repeated key comes from the document itself
+	fn push_parsed_entry(&mut self, path: Vec<TableKey>, other: Self, duplicates: DuplicateKeys) -> Result<(), DeserializationError> {
+		// A key along the path that already holds something other than a table cannot hold keys of its own
+		let mut current = Some(&*self);
+		for i in (1..path.len()).rev() {
+			current = match current {
+				Some(TextRepr::Table(x)) => x.get(&path[i]),
+				_ => None
+			};
+			if let Some(value) = current.filter(|x| !matches!(x, TextRepr::Table(_) | TextRepr::Empty)) {
+				let key = path[i..].iter().rev().map(|x| &**x).collect::<Vec<_>>().join(".");
+				return Err(match duplicates {
+					DuplicateKeys::Error => DeserializationError::new_kind(DeserializationErrorKind::DuplicateKey { key }),
+					_ => DeserializationError::invalid_format(format!("{} is already set to a value of type {}, so it cannot be a table", key, value.type_name()))
+				})
 			}
-			TextRepr::Table(x) => {
-				let field_name = path.pop().unwrap();
-				if !x.contains_key(&field_name) {
-					x.insert(field_name.clone(), TextRepr::Table(HashMap::new()));
+		}
+		let existing = path.iter().rev().try_fold(&*self, |current, key| match current {
+			TextRepr::Table(x) => x.get(key),
+			_ => None
+		});
+		if existing.is_some() {
+			match duplicates {
+				DuplicateKeys::Error => {
+					let key = path.iter().rev().map(|x| &**x).collect::<Vec<_>>().join(".");
+					return Err(DeserializationError::new_kind(DeserializationErrorKind::DuplicateKey { key }))
 				}
-				x.get_mut(&field_name).unwrap().push_entry_path(path, other);
+				DuplicateKeys::FirstWins => return Ok(()),
+				DuplicateKeys::LastWins => {}
 			}
-			_ => panic!("Tried to insert a TextRepr onto a non-empty and non-table TextRepr!")
 		}
+		self.push_entry_path(path, other);
+		Ok(())
+	}
+
+	/// Read and parse a file, resolving its include directive.
+	/// The include entry holds a path, or an array of paths, relative to the including file.
+	/// Included files are merged in order, then the including file is merged on top of them
+	fn from_file_with_includes(path: &Path, parse: fn(String) -> Result<Self, DeserializationError>, stack: &mut Vec<PathBuf>) -> Result<Self, DeserializationError> {
+		let path = path.canonicalize().map_err(|e| DeserializationError::invalid_format(e).set_field(path.display()))?;
+		if stack.contains(&path) {
+			let chain: Vec<_> = stack.iter().chain([&path]).map(|x| x.display().to_string()).collect();
+			return Err(DeserializationError::invalid_format_code(ErrorCode::E017_IncludeCycle, format!("include cycle: {}", chain.join(" -> "))))
+		}
+		let data = read_to_string(&path).map_err(|e| DeserializationError::invalid_format(e).set_field(path.display()))?;
+		let mut repr = parse(data).map_err(|e| e.nest().set_field(path.display()))?;
+
+		let includes = match repr.pull_entry("include") {
+			Ok(TextRepr::String(x)) => vec![x],
+			Ok(TextRepr::Array(x)) => x.into_iter()
+				.map(|x| match x {
+					TextRepr::String(x) => Ok(x),
+					_ => Err(DeserializationError::invalid_format("include paths must be strings").set_field(path.display()))
+				})
+				.collect::<Result<_, _>>()?,
+			Ok(_) => return Err(DeserializationError::invalid_format("include must be a path or an array of paths").set_field(path.display())),
+			Err(_) => Vec::new()
+		};
+
+		let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+		stack.push(path);
+		let mut out = TextRepr::new();
+		for include in includes {
+			out.merge(Self::from_file_with_includes(&dir.join(include), parse, stack)?);
+		}
+		stack.pop();
+		out.merge(repr);
+		Ok(out)
 	}
 
 	fn from_str_value(mut data: String) -> Result<Self, DeserializationError> {
@@ -137,13 +388,13 @@ impl TextRepr {
 
 		if data.starts_with('"') {
 			if !data.ends_with('"') {
-				return Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidFormat { reason: "String is missing terminating apostrophe".into() }))
+				return Err(DeserializationError::invalid_format_code(ErrorCode::E015_UnterminatedString, "String is missing terminating apostrophe"))
 			}
 
 			return Ok(TextRepr::String(data.drain(1..(data.len() - 1)).collect()))
 		}
 		if data.ends_with('"') {
-			return Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidFormat { reason: "String is missing starting apostrophe".into() }))
+			return Err(DeserializationError::invalid_format_code(ErrorCode::E015_UnterminatedString, "String is missing starting apostrophe"))
 		}
 
 		macro_rules! try_or_skip {
@@ -158,11 +409,75 @@ impl TextRepr {
 		try_or_skip!(Boolean);
 		try_or_skip!(Integer);
 		try_or_skip!(Float);
-		Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "todo!", actual: "todo!" }))
+		Err(DeserializationError::invalid_format(format!("{} is not a string, number or boolean", data)))
 	}
 }
 
 
+/// Which of JSON, TOML and MList the data most looks like, so the error of that format can be reported
+fn likely_format(data: &str) -> TextFormat {
+	let data = data.trim_start();
+	let json = match data.chars().next() {
+		Some('{' | '"') => true,
+		// A TOML or MList table header starts with a key instead of a value
+		Some('[') => !data[1..].trim_start().starts_with(|c: char| c.is_alphanumeric() || c == '_'),
+		_ => false
+	};
+	if json {
+		TextFormat::Json
+	} else if data.contains('=') {
+		TextFormat::Toml
+	} else {
+		TextFormat::MList
+	}
+}
+
+
+/// Tries to parse the string as JSON, then TOML, then MList.
+/// If none of them can parse it, the error of the format it most looks like is returned
+impl FromStr for TextRepr {
+	type Err = DeserializationError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let json_err = match Self::from_json(s.into()) {
+			Ok(x) => return Ok(x),
+			Err(e) => e
+		};
+		let toml_err = match Self::from_toml(s.into()) {
+			Ok(x) => return Ok(x),
+			Err(e) => e
+		};
+		let mlist_err = match Self::from_mlist(s.into()) {
+			Ok(x) => return Ok(x),
+			Err(e) => e
+		};
+		Err(match likely_format(s) {
+			TextFormat::Json => json_err,
+			TextFormat::Toml => toml_err,
+			TextFormat::MList => mlist_err
+		})
+	}
+}
+
+
+fn out_of_range<T>(value: impl Display) -> DeserializationError {
+	DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: format!("{} is out of the range of {}", value, std::any::type_name::<T>()) })
+}
+
+
+/// Read an integer as T, failing if it does not fit
+pub(crate) fn int_to_number<T: NumberType>(int: i64) -> Result<T, DeserializationError> {
+	T::from_i64(int).ok_or_else(|| out_of_range::<T>(int))
+}
+
+
+/// Read a string as T if it holds an integer above i64::MAX, which NumberType::to_text writes as a string
+pub(crate) fn big_int_to_number<T: NumberType>(string: &str) -> Option<Result<T, DeserializationError>> {
+	let int = string.parse::<u64>().ok().filter(|x| *x > i64::MAX as u64)?;
+	Some(T::from_number(Number::Unsigned(int)).ok_or_else(|| out_of_range::<T>(int)))
+}
+
+
 impl PrimitiveSerializer for TextRepr {
 	fn serialize_bool(&mut self, boolean: bool) {
 		self.push_value(TextRepr::Boolean(boolean));
@@ -171,7 +486,7 @@ impl PrimitiveSerializer for TextRepr {
 	fn deserialize_bool(&mut self) -> Result<bool, DeserializationError> {
 		match self.pull_value().no_field()? {
 			TextRepr::Boolean(x) => Ok(x),
-			_ => Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "number", actual: "todo!" }))
+			x => Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "boolean", actual: x.type_name() }))
 		}
 	}
 
@@ -181,9 +496,11 @@ impl PrimitiveSerializer for TextRepr {
 
 	fn deserialize_num<T: NumberType>(&mut self) -> Result<T, DeserializationError> {
 		match self.pull_value().no_field()? {
-			TextRepr::Integer(x) => T::from_i64(x).ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "unsigned int", actual: "signed int" })),
+			TextRepr::Integer(x) => int_to_number(x),
 			TextRepr::Float(x) => T::from_f64(x).ok_or_else(|| DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "integer", actual: "float" })),
-			_ => Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "number", actual: "todo!" }))
+			TextRepr::String(x) => big_int_to_number(&x)
+				.unwrap_or_else(|| Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "number", actual: "string" }))),
+			x => Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "number", actual: x.type_name() }))
 		}
 	}
 
@@ -194,7 +511,7 @@ impl PrimitiveSerializer for TextRepr {
 	fn deserialize_string(&mut self) -> Result<String, DeserializationError> {
 		match self.pull_value().no_field()? {
 			TextRepr::String(x) => Ok(x),
-			_ => Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "string", actual: "todo!" }))
+			x => Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "string", actual: x.type_name() }))
 		}
 	}
 
@@ -216,13 +533,13 @@ impl PrimitiveSerializer for TextRepr {
 					for value in values {
 						match value {
 							Self::Integer(x) => out.push(x as u8),
-							_ => return Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "byte", actual: "todo!" }))
+							x => return Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "byte", actual: x.type_name() }))
 						}
 					}
 
 					Ok(out.into_iter().collect())
 				},
-				_ => Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "array", actual: "todo!" }))
+				x => Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "array", actual: x.type_name() }))
 			}
 		}
 	}
@@ -233,33 +550,99 @@ impl Serializer for TextRepr {
 		self.push_value(serialize_owned!(item));
 	}
 
+	/// Always writes an array, so that empty and single item sequences keep their shape
+	fn serialize_iter<P, T: Serialize<P>, I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		self.push_value(Self::Array(iter.into_iter().map(|x| serialize_owned!(x)).collect()));
+	}
+
+	fn serialize_tuple<F: FnOnce(&mut Self)>(&mut self, f: F) {
+		let mut out = Self::Array(VecDeque::new());
+		f(&mut out);
+		self.push_value(out);
+	}
+
 	fn serialize_key<P, T: Serialize<P>, K: Borrow<str>>(&mut self, key: K, item: T) {
-		self.push_entry(key.borrow().into(), serialize_owned!(item));
+		let key = key.borrow();
+		observe::field(key, |measure| {
+			let value = serialize_owned!(item);
+			let bytes = if measure { value.to_string().len() } else { 0 };
+			self.push_entry(key.into(), value);
+			bytes
+		});
 	}
 
 	fn deserialize<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError> {
+		self.deserialize_seed::<P, _>(&mut PhantomData::<T>)
+	}
+
+	fn deserialize_key_internal<P, T: Deserialize<P>>(&mut self, key: &str) -> Result<T, DeserializationError> {
+		self.deserialize_key_seed::<P, _, _>(key, &mut PhantomData::<T>)
+	}
+
+	fn deserialize_seed<P, S: DeserializeSeed<P>>(&mut self, seed: &mut S) -> Result<S::Value, DeserializationError> {
 		let mut value = self.pull_value().no_field()?;
-		let result = T::deserialize(&mut value);
+		let result = seed.deserialize(&mut value);
 		if !value.is_empty() {
 			self.push_value(value);
 		}
 		result
 	}
 
-	fn deserialize_key_internal<P, T: Deserialize<P>>(&mut self, key: &str) -> Result<T, DeserializationError> {
-		let key = key.to_string();
-		let mut value = self.pull_entry(key.clone()).set_field(key.clone())?;
-		let result = T::deserialize(&mut value).map_err(|e| { e.nest().set_field(key.clone()) });
+	fn deserialize_key_seed<P, S: DeserializeSeed<P>, K: Borrow<str>>(&mut self, key: K, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		let key = key.borrow().to_string();
+		let mut value = self.pull_entry(key.as_str()).set_field(&key)?;
+		let result = warning::nest(&key, || seed.deserialize(&mut value)).map_err(|e| { e.nest().set_field(&key) });
 		if !value.is_empty() {
-			self.push_entry(key, value);
+			self.push_entry(key.into(), value);
 		}
 		result
 	}
 
 	fn try_get_key<K: FromStr>(&mut self) -> Option<K> {
 		match self {
-			Self::Table(x) => x.keys().next().map(|x| K::from_str(x.as_str()).ok()).flatten(),
+			Self::Table(x) => x.keys().next().and_then(|x| K::from_str(x).ok()),
 			_ => None
 		}
 	}
+
+	fn remaining_keys(&self) -> Vec<String> {
+		self.entries().map(|(key, _)| key.into()).collect()
+	}
+
+	fn serialize_text(&mut self, text: TextRepr) {
+		self.push_value(text);
+	}
+
+	fn serialize_option<P, T: Serialize<P>>(&mut self, item: Option<T>) {
+		match item {
+			Some(x) => x.serialize(self),
+			None => self.push_value(Self::Null)
+		}
+	}
+
+	fn deserialize_option<P, T: Deserialize<P>>(&mut self) -> Result<Option<T>, DeserializationError> {
+		if let Self::Null = self {
+			*self = Self::Empty;
+			return Ok(None)
+		}
+		T::deserialize(self).map(Some)
+	}
+
+	fn deserialize_text(&mut self) -> Result<TextRepr, DeserializationError> {
+		Ok(replace(self, Self::Empty))
+	}
+}
+
+
+impl Serialize for TextRepr {
+	fn serialize<T: Serializer>(self, data: &mut T) {
+		data.serialize_text(self);
+	}
+}
+
+
+impl Deserialize for TextRepr {
+	fn deserialize<T: Serializer>(data: &mut T) -> Result<Self, DeserializationError> {
+		data.deserialize_text()
+	}
 }
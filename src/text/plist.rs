@@ -0,0 +1,331 @@
+//! Apple's XML property lists, as read and written by macOS and iOS tooling.
+//!
+//! Tables become `<dict>`, arrays `<array>`, strings `<string>`, integers `<integer>`, floats `<real>` and
+//! booleans `<true/>` and `<false/>`. Property lists have no null, so null values are left out of their dict or array.
+//! When reading, `<date>` is read as the string it holds and `<data>` as an array of its decoded bytes
+use std::fmt::Write;
+
+use super::*;
+
+
+const HEADER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">
+<plist version=\"1.0\">
+";
+
+
+fn push_indent(out: &mut String, indent: usize) {
+	out.extend(std::iter::repeat_n('\t', indent));
+}
+
+
+fn escape(text: &str) -> String {
+	text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+
+fn write_plist(value: TextRepr, out: &mut String, indent: usize) {
+	let skip = |x: &TextRepr| matches!(x, TextRepr::Empty | TextRepr::Null);
+	if skip(&value) {
+		return
+	}
+	push_indent(out, indent);
+	match value {
+		TextRepr::Empty | TextRepr::Null => unreachable!(),
+		TextRepr::String(x) => write!(out, "<string>{}</string>", escape(&x)).unwrap(),
+		TextRepr::Integer(x) => write!(out, "<integer>{}</integer>", x).unwrap(),
+		TextRepr::Float(x) if x.is_nan() => out.push_str("<real>nan</real>"),
+		TextRepr::Float(x) if x.is_infinite() => out.push_str(if x > 0.0 { "<real>+infinity</real>" } else { "<real>-infinity</real>" }),
+		TextRepr::Float(x) => write!(out, "<real>{:?}</real>", x).unwrap(),
+		TextRepr::Boolean(x) => out.push_str(if x { "<true/>" } else { "<false/>" }),
+		TextRepr::Table(x) if x.values().all(skip) => out.push_str("<dict/>"),
+		TextRepr::Table(x) => {
+			out.push_str("<dict>\n");
+			for (key, value) in x.into_iter().filter(|(_, x)| !skip(x)) {
+				push_indent(out, indent + 1);
+				writeln!(out, "<key>{}</key>", escape(&key)).unwrap();
+				write_plist(value, out, indent + 1);
+			}
+			push_indent(out, indent);
+			out.push_str("</dict>");
+		}
+		TextRepr::Array(x) if x.iter().all(skip) => out.push_str("<array/>"),
+		TextRepr::Array(x) => {
+			out.push_str("<array>\n");
+			for value in x {
+				write_plist(value, out, indent + 1);
+			}
+			push_indent(out, indent);
+			out.push_str("</array>");
+		}
+	}
+	out.push('\n');
+}
+
+
+/// A dict or array that is still open while parsing
+enum Frame {
+	/// A dict, and the key of the value being parsed once its <key> has been read
+	Dict(Table, Option<TableKey>),
+	Array(VecDeque<TextRepr>)
+}
+
+
+/// An opening or closing tag
+struct Tag {
+	name: String,
+	closing: bool,
+	/// Whether the tag closes itself, as in <true/>
+	empty: bool
+}
+
+
+struct Parser {
+	chars: Vec<char>,
+	pos: usize,
+	interner: KeyInterner
+}
+
+
+impl Parser {
+	fn error<T: Display>(&self, reason: T) -> DeserializationError {
+		let line = self.chars[..self.pos.min(self.chars.len())].iter().filter(|x| **x == '\n').count() + 1;
+		DeserializationError::invalid_format(format!("line {}: {}", line, reason))
+	}
+
+	fn starts_with(&self, prefix: &str) -> bool {
+		prefix.chars().enumerate().all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+	}
+
+	/// Move past the given terminator, returning the characters before it
+	fn read_until(&mut self, end: &str, what: &str) -> Result<String, DeserializationError> {
+		let start = self.pos;
+		while !self.starts_with(end) {
+			if self.pos >= self.chars.len() {
+				self.pos = start;
+				return Err(self.error(format!("{} is never closed", what)))
+			}
+			self.pos += 1;
+		}
+		let out = self.chars[start..self.pos].iter().collect();
+		self.pos += end.chars().count();
+		Ok(out)
+	}
+
+	/// Skip whitespace, comments, the XML declaration and the doctype
+	fn skip(&mut self) -> Result<(), DeserializationError> {
+		loop {
+			if self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+				self.pos += 1;
+			} else if self.starts_with("<!--") {
+				self.read_until("-->", "comment")?;
+			} else if self.starts_with("<?") {
+				self.read_until("?>", "declaration")?;
+			} else if self.starts_with("<!DOCTYPE") {
+				self.read_until(">", "doctype")?;
+			} else {
+				return Ok(())
+			}
+		}
+	}
+
+	fn tag(&mut self) -> Result<Tag, DeserializationError> {
+		self.skip()?;
+		if !self.starts_with("<") {
+			return Err(match self.chars.get(self.pos) {
+				Some(_) => self.error("expected an element, found text"),
+				None => self.error("expected an element, found the end of the document")
+			})
+		}
+		self.pos += 1;
+		let inner = self.read_until(">", "tag")?;
+		let closing = inner.starts_with('/');
+		let empty = inner.ends_with('/');
+		let inner = inner.trim_start_matches('/').trim_end_matches('/');
+		// Attributes, such as the version of the plist, are ignored
+		let name = inner.split_whitespace().next().unwrap_or_default().to_string();
+		Ok(Tag { name, closing, empty })
+	}
+
+	/// Read the text of an element up to its closing tag, decoding entities and CDATA sections
+	fn text(&mut self, name: &str) -> Result<String, DeserializationError> {
+		let mut out = String::new();
+		loop {
+			if self.starts_with("<![CDATA[") {
+				self.pos += 9;
+				out.push_str(&self.read_until("]]>", "CDATA section")?);
+			} else if self.starts_with("<!--") {
+				self.read_until("-->", "comment")?;
+			} else if self.starts_with("</") {
+				let tag = self.tag()?;
+				if tag.name != name {
+					return Err(self.error(format!("expected </{}>, found </{}>", name, tag.name)))
+				}
+				return Ok(out)
+			} else if self.starts_with("&") {
+				self.pos += 1;
+				let entity = self.read_until(";", "entity")?;
+				out.push(match entity.as_str() {
+					"amp" => '&',
+					"lt" => '<',
+					"gt" => '>',
+					"quot" => '"',
+					"apos" => '\'',
+					_ => entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X"))
+						.map(|x| u32::from_str_radix(x, 16).ok())
+						.or_else(|| entity.strip_prefix('#').map(|x| x.parse().ok()))
+						.flatten()
+						.and_then(char::from_u32)
+						.ok_or_else(|| self.error(format!("unknown entity &{};", entity)))?
+				});
+			} else if let Some(c) = self.chars.get(self.pos) {
+				out.push(*c);
+				self.pos += 1;
+			} else {
+				return Err(self.error(format!("<{}> is never closed", name)))
+			}
+		}
+	}
+
+	/// Read the text of an element that may also be written as an empty tag
+	fn content(&mut self, tag: &Tag) -> Result<String, DeserializationError> {
+		if tag.empty {
+			return Ok(String::new())
+		}
+		self.text(&tag.name)
+	}
+
+	fn real(&self, text: &str) -> Result<f64, DeserializationError> {
+		match text.to_ascii_lowercase().as_str() {
+			"nan" => Ok(f64::NAN),
+			"inf" | "+inf" | "infinity" | "+infinity" => Ok(f64::INFINITY),
+			"-inf" | "-infinity" => Ok(f64::NEG_INFINITY),
+			_ => text.parse().map_err(|_| self.error(format!("{} is not a real", text)))
+		}
+	}
+
+	/// Read a value with an explicit stack of open dicts and arrays instead of recursion,
+	/// so deeply nested documents cannot overflow the stack
+	fn value(&mut self) -> Result<TextRepr, DeserializationError> {
+		let mut stack: Vec<Frame> = Vec::new();
+		loop {
+			let tag = self.tag()?;
+
+			// A dict alternates between keys and values
+			if let Some(Frame::Dict(_, key @ None)) = stack.last_mut() {
+				if !tag.closing && tag.name == "key" {
+					let text = if tag.empty { String::new() } else { self.text("key")? };
+					*key = Some(self.interner.intern(&text));
+					continue
+				}
+				if !(tag.closing && tag.name == "dict") {
+					return Err(self.error(format!("expected <key> or </dict>, found <{}>", tag.name)))
+				}
+			}
+
+			let value = if tag.closing {
+				match (stack.pop(), tag.name.as_str()) {
+					(Some(Frame::Dict(x, None)), "dict") => TextRepr::Table(x),
+					(Some(Frame::Array(x)), "array") => TextRepr::Array(x),
+					(Some(Frame::Dict(_, Some(key))), _) => return Err(self.error(format!("key {} has no value", key))),
+					_ => return Err(self.error(format!("unexpected </{}>", tag.name)))
+				}
+			} else {
+				match tag.name.as_str() {
+					"dict" | "array" if !tag.empty => {
+						if stack.len() >= MAX_DEPTH {
+							return Err(too_deep())
+						}
+						stack.push(if tag.name == "dict" { Frame::Dict(Table::default(), None) } else { Frame::Array(VecDeque::new()) });
+						continue
+					}
+					"dict" => TextRepr::Table(Table::default()),
+					"array" => TextRepr::Array(VecDeque::new()),
+					"true" | "false" => {
+						self.content(&tag)?;
+						TextRepr::Boolean(tag.name == "true")
+					}
+					"string" | "date" => TextRepr::String(self.content(&tag)?),
+					"integer" => {
+						let text = self.content(&tag)?;
+						let text = text.trim();
+						TextRepr::Integer(text.parse().map_err(|_| self.error(format!("{} is not an integer", text)))?)
+					}
+					"real" => {
+						let text = self.content(&tag)?;
+						TextRepr::Float(self.real(text.trim())?)
+					}
+					"data" => {
+						let text: String = self.content(&tag)?.chars().filter(|c| !c.is_whitespace()).collect();
+						TextRepr::Array(crate::base64::decode(&text)?.into_iter().map(|x| TextRepr::Integer(x as i64)).collect())
+					}
+					name => return Err(self.error(format!("unknown element <{}>", name)))
+				}
+			};
+
+			match stack.last_mut() {
+				None => return Ok(value),
+				Some(Frame::Dict(table, key)) => {
+					table.insert(key.take().unwrap(), value);
+				}
+				Some(Frame::Array(items)) => items.push_back(value)
+			}
+		}
+	}
+}
+
+
+impl TextRepr {
+	pub fn is_valid_plist<T: ToString>(data: T) -> bool {
+		Self::from_plist(data.to_string()).is_ok()
+	}
+
+	pub fn to_plist(self) -> String {
+		let mut out = HEADER.to_string();
+		write_plist(self, &mut out, 0);
+		out.push_str("</plist>\n");
+		out
+	}
+
+	/// Parse a property list, with or without the plist element around its value
+	pub fn from_plist(data: String) -> Result<Self, DeserializationError> {
+		let mut parser = Parser { chars: data.chars().collect(), pos: 0, interner: KeyInterner::new() };
+		parser.skip()?;
+		let start = parser.pos;
+		let tag = parser.tag()?;
+		if tag.name != "plist" || tag.closing {
+			parser.pos = start;
+			return parser.value()
+		}
+		if tag.empty {
+			return Ok(Self::Empty)
+		}
+		parser.skip()?;
+		if parser.starts_with("</") {
+			parser.text("plist")?;
+			return Ok(Self::Empty)
+		}
+		let out = parser.value()?;
+		parser.skip()?;
+		parser.text("plist")?;
+		parser.skip()?;
+		if parser.pos < parser.chars.len() {
+			return Err(parser.error("unexpected content after </plist>"))
+		}
+		Ok(out)
+	}
+}
+
+
+/// Serialize any value as an XML property list
+pub fn to_string<P, T: Serialize<P>>(value: T) -> String {
+	let mut out = TextRepr::new();
+	value.serialize(&mut out);
+	out.to_plist()
+}
+
+
+/// Deserialize any value from an XML property list
+pub fn from_str<P, T: Deserialize<P>>(data: &str) -> Result<T, DeserializationError> {
+	T::deserialize(&mut TextRepr::from_plist(data.into())?)
+}
@@ -0,0 +1,567 @@
+//! Rusty Object Notation, which looks like Rust's own syntax for structs, enums and tuples.
+//!
+//! Tables are written as structs such as `(port: 80)`, or as maps such as `{"a key": 1}` when their keys are not
+//! identifiers. A table with a single capitalized key is written as the enum variant it came from, as in `Number(22)`.
+//! Since a TextRepr does not keep the names of types, struct names are dropped when parsing, so `Config(port: 80)`
+//! reads the same as `(port: 80)`, while named tuples such as `Number(22)` are read as enum variants
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+use std::str::FromStr;
+
+use super::*;
+
+
+pub mod ron_prelude {
+	pub use crate::{impl_ron, impl_ron_deser, impl_ron_ser};
+
+	pub use super::{RONDeserialize, RONSerialize, text::TextRepr};
+}
+
+
+fn is_ident(key: &str) -> bool {
+	key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+
+/// Whether a key can be written as the name of an enum variant
+fn is_variant(key: &str) -> bool {
+	is_ident(key) && key.starts_with(|c: char| c.is_ascii_uppercase()) && key != "Some" && key != "None"
+}
+
+
+fn push_indent(out: &mut String, indent: usize) {
+	out.extend(std::iter::repeat_n('\t', indent));
+}
+
+
+fn write_ron(value: TextRepr, out: &mut String, indent: usize) {
+	match value {
+		TextRepr::Empty => {}
+		TextRepr::Null => out.push_str("None"),
+		// Debug formatting escapes strings the same way Rust string literals are written
+		TextRepr::String(x) => write!(out, "{:?}", x).unwrap(),
+		TextRepr::Integer(x) => write!(out, "{}", x).unwrap(),
+		TextRepr::Float(x) => write!(out, "{:?}", x).unwrap(),
+		TextRepr::Boolean(x) => write!(out, "{}", x).unwrap(),
+		TextRepr::Table(x) if x.len() == 1 && x.keys().all(|x| is_variant(x)) => {
+			let (key, value) = x.into_iter().next().unwrap();
+			out.push_str(&key);
+			out.push('(');
+			write_ron(value, out, indent);
+			out.push(')');
+		}
+		TextRepr::Table(x) if x.is_empty() => out.push_str("{}"),
+		TextRepr::Table(x) => {
+			let is_struct = x.keys().all(|x| is_ident(x));
+			out.push_str(if is_struct { "(\n" } else { "{\n" });
+			for (key, value) in x {
+				if matches!(value, TextRepr::Empty) {
+					continue
+				}
+				push_indent(out, indent + 1);
+				if is_struct {
+					out.push_str(&key);
+				} else {
+					write!(out, "{:?}", &*key).unwrap();
+				}
+				out.push_str(": ");
+				write_ron(value, out, indent + 1);
+				out.push_str(",\n");
+			}
+			push_indent(out, indent);
+			out.push(if is_struct { ')' } else { '}' });
+		}
+		// Arrays of tables are written with an item on each line
+		TextRepr::Array(x) if x.iter().any(|x| matches!(x, TextRepr::Table(x) if !x.is_empty())) => {
+			out.push_str("[\n");
+			for value in x {
+				if matches!(value, TextRepr::Empty) {
+					continue
+				}
+				push_indent(out, indent + 1);
+				write_ron(value, out, indent + 1);
+				out.push_str(",\n");
+			}
+			push_indent(out, indent);
+			out.push(']');
+		}
+		TextRepr::Array(x) => {
+			out.push('[');
+			for (i, value) in x.into_iter().filter(|x| !matches!(x, TextRepr::Empty)).enumerate() {
+				if i > 0 {
+					out.push_str(", ");
+				}
+				write_ron(value, out, indent);
+			}
+			out.push(']');
+		}
+	}
+}
+
+
+struct Parser {
+	chars: Vec<char>,
+	pos: usize,
+	interner: KeyInterner
+}
+
+
+impl Parser {
+	fn error<T: Display>(&self, reason: T) -> DeserializationError {
+		let line = self.chars[..self.pos.min(self.chars.len())].iter().filter(|x| **x == '\n').count() + 1;
+		DeserializationError::invalid_format(format!("line {}: {}", line, reason))
+	}
+
+	fn peek(&self) -> Option<char> {
+		self.chars.get(self.pos).copied()
+	}
+
+	/// Skip whitespace and comments
+	fn skip(&mut self) -> Result<(), DeserializationError> {
+		loop {
+			match (self.peek(), self.chars.get(self.pos + 1)) {
+				(Some(c), _) if c.is_whitespace() => self.pos += 1,
+				(Some('/'), Some('/')) => while self.peek().is_some_and(|c| c != '\n') {
+					self.pos += 1;
+				}
+				(Some('/'), Some('*')) => {
+					self.pos += 2;
+					while !(self.peek() == Some('*') && self.chars.get(self.pos + 1) == Some(&'/')) {
+						if self.peek().is_none() {
+							return Err(self.error("block comment is never closed"))
+						}
+						self.pos += 1;
+					}
+					self.pos += 2;
+				}
+				_ => return Ok(())
+			}
+		}
+	}
+
+	/// Skip past the next character if it is c
+	fn eat(&mut self, c: char) -> Result<bool, DeserializationError> {
+		self.skip()?;
+		if self.peek() == Some(c) {
+			self.pos += 1;
+			return Ok(true)
+		}
+		Ok(false)
+	}
+
+	fn expect(&mut self, c: char) -> Result<(), DeserializationError> {
+		if self.eat(c)? {
+			return Ok(())
+		}
+		Err(match self.peek() {
+			Some(x) => self.error(format!("expected {}, found {}", c, x)),
+			None => self.error(format!("expected {}, found the end of the document", c))
+		})
+	}
+
+	fn ident(&mut self) -> Option<String> {
+		if !self.peek().is_some_and(|c| c.is_alphabetic() || c == '_') {
+			return None
+		}
+		let mut out = String::new();
+		while let Some(c) = self.peek().filter(|c| c.is_alphanumeric() || *c == '_') {
+			out.push(c);
+			self.pos += 1;
+		}
+		Some(out)
+	}
+
+	fn value(&mut self, depth: usize) -> Result<TextRepr, DeserializationError> {
+		if depth >= MAX_DEPTH {
+			return Err(too_deep())
+		}
+		self.skip()?;
+		match self.peek() {
+			None => Err(self.error("expected a value, found the end of the document")),
+			Some('"') => self.string().map(TextRepr::String),
+			Some('r') if matches!(self.chars.get(self.pos + 1), Some('#' | '"')) => self.raw_string().map(TextRepr::String),
+			Some('\'') => self.char_literal().map(|x| TextRepr::String(x.into())),
+			Some('[') => {
+				self.pos += 1;
+				self.items(']', depth).map(TextRepr::Array)
+			}
+			Some('{') => self.map(depth),
+			Some('(') => {
+				self.pos += 1;
+				self.parens(None, depth)
+			}
+			Some(c) if c.is_ascii_digit() || matches!(c, '+' | '-' | '.') => self.number(),
+			Some(c) if c.is_alphabetic() || c == '_' => {
+				let ident = self.ident().unwrap_or_default();
+				match ident.as_str() {
+					"true" => Ok(TextRepr::Boolean(true)),
+					"false" => Ok(TextRepr::Boolean(false)),
+					"None" => Ok(TextRepr::Null),
+					"inf" => Ok(TextRepr::Float(f64::INFINITY)),
+					"NaN" => Ok(TextRepr::Float(f64::NAN)),
+					"Some" => {
+						self.expect('(')?;
+						let value = self.value(depth + 1)?;
+						self.eat(',')?;
+						self.expect(')')?;
+						Ok(value)
+					}
+					_ if self.eat('(')? => self.parens(Some(ident), depth),
+					// A unit enum variant
+					_ => Ok(TextRepr::String(ident))
+				}
+			}
+			Some(c) => Err(self.error(format!("unexpected character {:?}", c)))
+		}
+	}
+
+	/// Parse values up to the closing character, separated by commas with an optional trailing comma
+	fn items(&mut self, close: char, depth: usize) -> Result<VecDeque<TextRepr>, DeserializationError> {
+		let mut out = VecDeque::new();
+		while !self.eat(close)? {
+			out.push_back(self.value(depth + 1)?);
+			if !self.eat(',')? {
+				self.expect(close)?;
+				break
+			}
+		}
+		Ok(out)
+	}
+
+	fn map(&mut self, depth: usize) -> Result<TextRepr, DeserializationError> {
+		self.pos += 1;
+		let mut table = Table::default();
+		while !self.eat('}')? {
+			let key = match self.value(depth + 1)? {
+				TextRepr::String(x) => x,
+				TextRepr::Integer(x) => x.to_string(),
+				TextRepr::Float(x) => x.to_string(),
+				TextRepr::Boolean(x) => x.to_string(),
+				_ => return Err(self.error("map keys must be strings, numbers or booleans"))
+			};
+			self.expect(':')?;
+			let value = self.value(depth + 1)?;
+			table.insert(self.interner.intern(&key), value);
+			if !self.eat(',')? {
+				self.expect('}')?;
+				break
+			}
+		}
+		Ok(TextRepr::Table(table))
+	}
+
+	/// Parse what follows an opening bracket, which is either the fields of a struct or the items of a tuple.
+	/// A struct with a name is only a table of its fields, while a tuple with a name is an enum variant
+	fn parens(&mut self, name: Option<String>, depth: usize) -> Result<TextRepr, DeserializationError> {
+		self.skip()?;
+		let start = self.pos;
+		let is_struct = self.ident().is_some() && self.eat(':')?;
+		self.pos = start;
+
+		if is_struct {
+			let mut table = Table::default();
+			while !self.eat(')')? {
+				let key = self.ident().ok_or_else(|| self.error("expected a field name"))?;
+				self.expect(':')?;
+				let value = self.value(depth + 1)?;
+				table.insert(self.interner.intern(&key), value);
+				if !self.eat(',')? {
+					self.expect(')')?;
+					break
+				}
+			}
+			return Ok(TextRepr::Table(table))
+		}
+
+		let mut items = self.items(')', depth)?;
+		Ok(match name {
+			None => TextRepr::Array(items),
+			Some(name) => {
+				let value = match items.len() {
+					1 => items.pop_front().unwrap(),
+					_ => TextRepr::Array(items)
+				};
+				let mut table = Table::default();
+				table.insert(self.interner.intern(&name), value);
+				TextRepr::Table(table)
+			}
+		})
+	}
+
+	fn number(&mut self) -> Result<TextRepr, DeserializationError> {
+		let start = self.pos;
+		while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.' | '_')) {
+			self.pos += 1;
+		}
+		let text: String = self.chars[start..self.pos].iter().filter(|x| **x != '_').collect();
+		let (sign, digits) = match text.strip_prefix('-') {
+			Some(x) => (-1, x),
+			None => (1, text.trim_start_matches('+'))
+		};
+		let radix = match digits.get(..2) {
+			Some("0x") => 16,
+			Some("0o") => 8,
+			Some("0b") => 2,
+			_ => 10
+		};
+		if radix != 10 {
+			return i64::from_str_radix(&digits[2..], radix)
+				.map(|x| TextRepr::Integer(sign * x))
+				.map_err(|_| self.error(format!("{} is not a valid number", text)))
+		}
+		if let Ok(x) = text.parse() {
+			return Ok(TextRepr::Integer(x))
+		}
+		text.parse()
+			.map(TextRepr::Float)
+			.map_err(|_| self.error(format!("{} is not a valid number", text)))
+	}
+
+	fn escape(&mut self) -> Result<char, DeserializationError> {
+		let c = self.peek().ok_or_else(|| self.error("string is never closed"))?;
+		self.pos += 1;
+		let hex = |this: &mut Self, len: usize| {
+			let digits: String = this.chars.iter().skip(this.pos).take(len).collect();
+			this.pos += len;
+			u32::from_str_radix(&digits, 16).ok()
+				.and_then(char::from_u32)
+				.ok_or_else(|| this.error(format!("invalid escape {}", digits)))
+		};
+		Ok(match c {
+			'n' => '\n',
+			't' => '\t',
+			'r' => '\r',
+			'0' => '\0',
+			'\\' | '"' | '\'' => c,
+			'x' => hex(self, 2)?,
+			'u' => {
+				self.expect('{')?;
+				let len = self.chars.iter().skip(self.pos).take_while(|x| **x != '}').count();
+				let c = hex(self, len)?;
+				self.expect('}')?;
+				c
+			}
+			c => return Err(self.error(format!("unknown escape \\{}", c)))
+		})
+	}
+
+	fn string(&mut self) -> Result<String, DeserializationError> {
+		self.pos += 1;
+		let mut out = String::new();
+		loop {
+			match self.peek() {
+				None => return Err(self.error("string is never closed")),
+				Some('"') => {
+					self.pos += 1;
+					return Ok(out)
+				}
+				Some('\\') => {
+					self.pos += 1;
+					out.push(self.escape()?);
+				}
+				Some(c) => {
+					out.push(c);
+					self.pos += 1;
+				}
+			}
+		}
+	}
+
+	/// Parse a string such as `r#"say "hi""#`, which ends at a quote followed by as many #s as it started with
+	fn raw_string(&mut self) -> Result<String, DeserializationError> {
+		self.pos += 1;
+		let hashes = self.chars[self.pos..].iter().take_while(|x| **x == '#').count();
+		self.pos += hashes;
+		self.expect('"')?;
+		let start = self.pos;
+		while self.pos < self.chars.len() {
+			if self.chars[self.pos] == '"' && self.chars[self.pos + 1..].iter().take(hashes).filter(|x| **x == '#').count() == hashes {
+				let out = self.chars[start..self.pos].iter().collect();
+				self.pos += 1 + hashes;
+				return Ok(out)
+			}
+			self.pos += 1;
+		}
+		Err(self.error("string is never closed"))
+	}
+
+	fn char_literal(&mut self) -> Result<char, DeserializationError> {
+		self.pos += 1;
+		let c = match self.peek() {
+			Some('\\') => {
+				self.pos += 1;
+				self.escape()?
+			}
+			Some(c) => {
+				self.pos += 1;
+				c
+			}
+			None => return Err(self.error("character is never closed"))
+		};
+		if self.peek() != Some('\'') {
+			return Err(self.error("character is never closed"))
+		}
+		self.pos += 1;
+		Ok(c)
+	}
+}
+
+
+impl TextRepr {
+	pub fn is_valid_ron<T: ToString>(data: T) -> bool {
+		Self::from_ron(data.to_string()).is_ok()
+	}
+
+	pub fn to_ron(self) -> String {
+		let mut out = String::new();
+		write_ron(self, &mut out, 0);
+		out
+	}
+
+	pub fn from_ron(data: String) -> Result<Self, DeserializationError> {
+		let mut parser = Parser { chars: data.chars().collect(), pos: 0, interner: KeyInterner::new() };
+		// Extensions such as #![enable(implicit_some)] are ignored
+		while parser.eat('#')? {
+			while parser.peek().is_some_and(|c| c != ']') {
+				parser.pos += 1;
+			}
+			parser.expect(']')?;
+		}
+		parser.skip()?;
+		if parser.peek().is_none() {
+			return Ok(Self::Empty)
+		}
+		let out = parser.value(0)?;
+		parser.skip()?;
+		match parser.peek() {
+			Some(c) => Err(parser.error(format!("unexpected character {:?} after the value", c))),
+			None => Ok(out)
+		}
+	}
+}
+
+
+/// Serialize any value as RON, without implementing RONSerialize for it
+pub fn to_string<P, T: Serialize<P>>(value: T) -> String {
+	let mut out = TextRepr::new();
+	value.serialize(&mut out);
+	out.to_ron()
+}
+
+
+/// Deserialize any value from RON, without implementing RONDeserialize for it
+pub fn from_str<P, T: Deserialize<P>>(data: &str) -> Result<T, DeserializationError> {
+	T::deserialize(&mut TextRepr::from_ron(data.into())?)
+}
+
+
+pub trait RONSerialize<P = NaturalProfile> {
+	fn serialize_ron(self) -> String;
+}
+
+
+pub trait RONDeserialize<P = NaturalProfile>: Sized {
+	fn deserialize_ron(data: String) -> Result<Self, DeserializationError>;
+}
+
+
+/// A marker trait for types that can be serialized and deserialized into RON with the same profile,
+/// without a marshall. Is automatically implemented on all appropriate types
+pub trait RONSerde<P = NaturalProfile>: RONSerialize<P> + RONDeserialize<P> {}
+
+impl<P, T: RONSerialize<P> + RONDeserialize<P>> RONSerde<P> for T {}
+
+
+pub trait MarshalledRONSerialize<Marshall, P = NaturalProfile> {
+	fn serialize_ron(self, marshall: &Marshall) -> String;
+}
+
+
+pub trait MarshalledRONDeserialize<'a, Marshall, P = NaturalProfile>: Sized {
+	fn deserialize_ron(data: String, marshall: &'a Marshall) -> Result<Self, DeserializationError>;
+}
+
+
+/// A marker trait for types that can be serialized and deserialized into RON with the same profile,
+/// and the same type of marshall. Is automatically implemented on all appropriate types
+pub trait MarshalledRONSerde<'a, Marshall, P = NaturalProfile>: MarshalledRONSerialize<Marshall, P> + MarshalledRONDeserialize<'a, Marshall, P> {}
+
+impl<'a, P, Marshall, T: MarshalledRONSerialize<Marshall, P> + MarshalledRONDeserialize<'a, Marshall, P>> MarshalledRONSerde<'a, Marshall, P> for T {}
+
+#[macro_export]
+macro_rules! impl_ron {
+    ($name: ty, $profile: ty) => {
+		impl_ron_ser!($name, $profile);
+		impl_ron_deser!($name, $profile);
+	};
+    ($name: ty, $profile: ty, $marshall: ty) => {
+		impl_ron_ser!($name, $profile, $marshall);
+		impl_ron_deser!($name, $profile, $marshall);
+	};
+}
+
+#[macro_export]
+macro_rules! impl_ron_ser {
+    ($name: ty, $profile: ty) => {
+		impl RONSerialize<$profile> for $name {
+			fn serialize_ron(self) -> String {
+				$crate::ron::to_string::<$profile, _>(self)
+			}
+		}
+	};
+    ($name: ty, $profile: ty, $marshall: ty) => {
+		impl MarshalledRONSerialize<$marshall, $profile> for $name {
+			fn serialize_ron(self, marshall: &$marshall) -> String {
+				let mut out = TextRepr::new();
+				MarshalledSerialize::<$profile>::serialize(self, &mut out, marshall);
+				out.to_ron()
+			}
+		}
+	};
+}
+
+#[macro_export]
+macro_rules! impl_ron_deser {
+    ($name: ty, $profile: ty) => {
+		impl RONDeserialize<$profile> for $name {
+			fn deserialize_ron(data: String) -> Result<Self, DeserializationError> {
+				$crate::ron::from_str::<$profile, _>(&data)
+			}
+		}
+	};
+    ($name: ty, $profile: ty, $marshall: ty) => {
+		impl MarshalledRONDeserialize<$marshall, $profile> for $name {
+			fn deserialize_ron(data: String, marshall: &$marshall) -> Result<Self, DeserializationError> {
+				MarshalledDeserialize::<$profile>::deserialize(&mut TextRepr::from_ron(data)?, marshall)
+			}
+		}
+	};
+}
+
+
+impl<P, K: Borrow<str> + Eq + std::hash::Hash, V: Serialize<P>, S: std::hash::BuildHasher> RONSerialize<P> for HashMap<K, V, S> {
+	fn serialize_ron(self) -> String {
+		TextRepr::to_ron(serialize_owned!(self))
+	}
+}
+
+
+impl<E: Debug, P, K: Eq + std::hash::Hash + FromStr<Err=E>, V: Deserialize<P>, S: std::hash::BuildHasher + Default> RONDeserialize<P> for HashMap<K, V, S> {
+	fn deserialize_ron(data: String) -> Result<Self, DeserializationError> {
+		Self::deserialize::<TextRepr>(&mut TextRepr::from_ron(data)?)
+	}
+}
+
+
+impl<P, V: Serialize<P>> RONSerialize<P> for Vec<V> {
+	fn serialize_ron(self) -> String {
+		TextRepr::to_ron(serialize_owned!(self))
+	}
+}
+
+
+impl<P, V: Deserialize<P>> RONDeserialize<P> for Vec<V> {
+	fn deserialize_ron(data: String) -> Result<Self, DeserializationError> {
+		Self::deserialize::<TextRepr>(&mut TextRepr::from_ron(data)?)
+	}
+}
@@ -0,0 +1,293 @@
+//! S-expressions, the parenthesized lists of Lisp and of the build tools and DSLs that borrow its syntax.
+//!
+//! Arrays are written as lists such as `(1 2 3)`, and tables as property lists of keywords and values such as
+//! `(:port 80 :host "a")`, with keys that are not plain symbols quoted as in `:"a key"`. Null is `nil`, booleans are
+//! `#t` and `#f`, and infinities and NaN are written as Scheme writes them, such as `+inf.0`. A list that does not
+//! start with a keyword is read as an array, so the empty table is written as `()` and read back as an empty array.
+//! Bare symbols other than these are read as strings, and comments start with `;`
+use std::fmt::Write;
+
+use super::*;
+
+
+/// Whether a key can be written as a keyword without quoting it
+fn is_symbol(key: &str) -> bool {
+	!key.is_empty()
+		&& !key.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | '#'))
+		&& key.chars().all(|c| c.is_alphanumeric() || "_-!?*<>=/.".contains(c))
+}
+
+
+fn write_string(out: &mut String, string: &str) {
+	out.push('"');
+	for c in string.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			c => out.push(c)
+		}
+	}
+	out.push('"');
+}
+
+
+fn write_sexpr(value: TextRepr, out: &mut String) {
+	match value {
+		TextRepr::Empty => {}
+		TextRepr::Null => out.push_str("nil"),
+		TextRepr::String(x) => write_string(out, &x),
+		TextRepr::Integer(x) => write!(out, "{}", x).unwrap(),
+		TextRepr::Float(x) if x.is_nan() => out.push_str("+nan.0"),
+		TextRepr::Float(x) if x.is_infinite() => out.push_str(if x > 0.0 { "+inf.0" } else { "-inf.0" }),
+		// Debug formatting keeps the decimal point, so whole floats are not read back as integers
+		TextRepr::Float(x) => write!(out, "{:?}", x).unwrap(),
+		TextRepr::Boolean(x) => out.push_str(if x { "#t" } else { "#f" }),
+		TextRepr::Table(x) => {
+			out.push('(');
+			for (i, (key, value)) in x.into_iter().filter(|(_, x)| !matches!(x, TextRepr::Empty)).enumerate() {
+				if i > 0 {
+					out.push(' ');
+				}
+				out.push(':');
+				if is_symbol(&key) {
+					out.push_str(&key);
+				} else {
+					write_string(out, &key);
+				}
+				out.push(' ');
+				write_sexpr(value, out);
+			}
+			out.push(')');
+		}
+		TextRepr::Array(x) => {
+			out.push('(');
+			for (i, value) in x.into_iter().enumerate() {
+				if i > 0 {
+					out.push(' ');
+				}
+				write_sexpr(value, out);
+			}
+			out.push(')');
+		}
+	}
+}
+
+
+/// A keyword or a value, as found in a list
+enum Item {
+	Keyword(String),
+	Value(TextRepr)
+}
+
+
+struct Parser {
+	chars: Vec<char>,
+	pos: usize,
+	interner: KeyInterner
+}
+
+
+impl Parser {
+	fn error<T: Display>(&self, reason: T) -> DeserializationError {
+		let line = self.chars[..self.pos.min(self.chars.len())].iter().filter(|x| **x == '\n').count() + 1;
+		DeserializationError::invalid_format(format!("line {}: {}", line, reason))
+	}
+
+	fn peek(&self) -> Option<char> {
+		self.chars.get(self.pos).copied()
+	}
+
+	/// Skip whitespace and comments
+	fn skip(&mut self) {
+		while let Some(c) = self.peek() {
+			if c == ';' {
+				while self.peek().is_some_and(|c| c != '\n') {
+					self.pos += 1;
+				}
+			} else if c.is_whitespace() {
+				self.pos += 1;
+			} else {
+				return
+			}
+		}
+	}
+
+	fn string(&mut self) -> Result<String, DeserializationError> {
+		let start = self.pos;
+		self.pos += 1;
+		let mut out = String::new();
+		loop {
+			let Some(c) = self.peek() else {
+				// Point at the line the string starts on
+				self.pos = start;
+				return Err(self.error("string is never closed"))
+			};
+			self.pos += 1;
+			match c {
+				'"' => return Ok(out),
+				'\\' => {
+					let Some(escaped) = self.peek() else {
+						self.pos = start;
+						return Err(self.error("string is never closed"))
+					};
+					self.pos += 1;
+					out.push(match escaped {
+						'n' => '\n',
+						'r' => '\r',
+						't' => '\t',
+						'"' | '\\' => escaped,
+						x => return Err(self.error(format!("unknown escape \\{}", x)))
+					});
+				}
+				c => out.push(c)
+			}
+		}
+	}
+
+	/// Read the characters of an atom, up to whitespace, a parenthesis, a quote or a comment
+	fn atom(&mut self) -> String {
+		let mut out = String::new();
+		while let Some(c) = self.peek().filter(|c| !c.is_whitespace() && !matches!(c, '(' | ')' | '"' | ';')) {
+			out.push(c);
+			self.pos += 1;
+		}
+		out
+	}
+
+	/// Read a string, keyword or atom
+	fn item(&mut self) -> Result<Item, DeserializationError> {
+		Ok(Item::Value(match self.peek() {
+			Some('"') => TextRepr::String(self.string()?),
+			Some(':') => {
+				self.pos += 1;
+				return Ok(Item::Keyword(match self.peek() {
+					Some('"') => self.string()?,
+					_ => self.atom()
+				}))
+			}
+			_ => {
+				let atom = self.atom();
+				match atom.as_str() {
+					"nil" => TextRepr::Null,
+					"#t" | "true" => TextRepr::Boolean(true),
+					"#f" | "false" => TextRepr::Boolean(false),
+					"+inf.0" => TextRepr::Float(f64::INFINITY),
+					"-inf.0" => TextRepr::Float(f64::NEG_INFINITY),
+					"+nan.0" | "-nan.0" => TextRepr::Float(f64::NAN),
+					_ if atom.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '+' | '-' | '.')) => {
+						if let Ok(x) = atom.parse() {
+							TextRepr::Integer(x)
+						} else if let Ok(x) = atom.parse() {
+							TextRepr::Float(x)
+						} else {
+							TextRepr::String(atom)
+						}
+					}
+					_ => TextRepr::String(atom)
+				}
+			}
+		}))
+	}
+
+	/// Read a value with an explicit stack of open lists instead of recursion,
+	/// so deeply nested documents cannot overflow the stack
+	fn value(&mut self) -> Result<TextRepr, DeserializationError> {
+		let mut stack: Vec<Vec<Item>> = Vec::new();
+		loop {
+			self.skip();
+			let item = match self.peek() {
+				None if stack.is_empty() => return Err(self.error("expected a value, found the end of the document")),
+				None => return Err(self.error("list is never closed")),
+				Some('(') => {
+					if stack.len() >= MAX_DEPTH {
+						return Err(too_deep())
+					}
+					self.pos += 1;
+					stack.push(Vec::new());
+					continue
+				}
+				Some(')') => {
+					self.pos += 1;
+					let items = stack.pop().ok_or_else(|| self.error("unexpected )"))?;
+					Item::Value(self.collect(items)?)
+				}
+				Some(_) => self.item()?
+			};
+			match (stack.last_mut(), item) {
+				(Some(list), item) => list.push(item),
+				(None, Item::Value(x)) => return Ok(x),
+				(None, Item::Keyword(x)) => return Err(self.error(format!("keyword :{} outside of a list", x)))
+			}
+		}
+	}
+
+	/// Turn the items of a list into a table if it starts with a keyword, and into an array otherwise
+	fn collect(&mut self, items: Vec<Item>) -> Result<TextRepr, DeserializationError> {
+		if !matches!(items.first(), Some(Item::Keyword(_))) {
+			return items.into_iter()
+				.map(|x| match x {
+					Item::Value(x) => Ok(x),
+					Item::Keyword(x) => Err(self.error(format!("keyword :{} in a list that is not a property list", x)))
+				})
+				.collect::<Result<_, _>>()
+				.map(TextRepr::Array)
+		}
+
+		let mut table = Table::default();
+		let mut items = items.into_iter();
+		while let Some(key) = items.next() {
+			let Item::Keyword(key) = key else {
+				return Err(self.error("expected a keyword in a property list, found a value"))
+			};
+			let Some(Item::Value(value)) = items.next() else {
+				return Err(self.error(format!("keyword :{} has no value", key)))
+			};
+			table.insert(self.interner.intern(&key), value);
+		}
+		Ok(TextRepr::Table(table))
+	}
+}
+
+
+impl TextRepr {
+	pub fn is_valid_sexpr<T: ToString>(data: T) -> bool {
+		Self::from_sexpr(data.to_string()).is_ok()
+	}
+
+	pub fn to_sexpr(self) -> String {
+		let mut out = String::new();
+		write_sexpr(self, &mut out);
+		out
+	}
+
+	pub fn from_sexpr(data: String) -> Result<Self, DeserializationError> {
+		let mut parser = Parser { chars: data.chars().collect(), pos: 0, interner: KeyInterner::new() };
+		parser.skip();
+		if parser.peek().is_none() {
+			return Ok(Self::Empty)
+		}
+		let out = parser.value()?;
+		parser.skip();
+		match parser.peek() {
+			Some(c) => Err(parser.error(format!("unexpected character {:?} after the value", c))),
+			None => Ok(out)
+		}
+	}
+}
+
+
+/// Serialize any value as an s-expression
+pub fn to_string<P, T: Serialize<P>>(value: T) -> String {
+	let mut out = TextRepr::new();
+	value.serialize(&mut out);
+	out.to_sexpr()
+}
+
+
+/// Deserialize any value from an s-expression
+pub fn from_str<P, T: Deserialize<P>>(data: &str) -> Result<T, DeserializationError> {
+	T::deserialize(&mut TextRepr::from_sexpr(data.into())?)
+}
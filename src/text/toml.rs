@@ -1,5 +1,6 @@
 use std::collections::{HashMap, VecDeque};
 use std::fmt::Write;
+use std::mem::take;
 use std::str::FromStr;
 
 use super::*;
@@ -14,7 +15,7 @@ pub mod toml_prelude {
 }
 
 
-pub(crate) fn map_entries_recursive(map: HashMap<String, TextRepr>, root: Vec<String>, entries: &mut HashMap<Vec<String>, HashMap<String, TextRepr>>) {
+pub(crate) fn map_entries_recursive(map: Table, root: Vec<TableKey>, entries: &mut HashMap<Vec<TableKey>, Table>) {
 	for (key, value) in map {
 		match value {
 			TextRepr::Table(x) => {
@@ -22,11 +23,13 @@ pub(crate) fn map_entries_recursive(map: HashMap<String, TextRepr>, root: Vec<St
 				new_root.push(key);
 				map_entries_recursive(x, new_root, entries);
 			}
+			// A missing value is written by leaving its key out
+			TextRepr::Null => {}
 			value => {
 				match entries.get_mut(&root) {
 					None => {
 						entries.insert(root.clone(), {
-							let mut map = HashMap::new();
+							let mut map = Table::default();
 							map.insert(key, value);
 							map
 						});
@@ -39,6 +42,56 @@ pub(crate) fn map_entries_recursive(map: HashMap<String, TextRepr>, root: Vec<St
 }
 
 
+fn write_comment(out: &mut String, comments: &[(&str, &str)], path: &str) {
+	if let Some((_, comment)) = comments.iter().find(|(x, _)| *x == path) {
+		for line in comment.trim().lines() {
+			let line = line.trim();
+			if line.is_empty() {
+				out.push_str("#\n");
+			} else {
+				writeln!(out, "# {}", line).expect("Error writing comment to toml string. Please report this to the developer.");
+			}
+		}
+	}
+}
+
+
+fn table_to_toml(map: Table, comments: &[(&str, &str)]) -> String {
+	let line_count = map.len();
+	let mut entries = HashMap::new();
+	map_entries_recursive(map, Vec::new(), &mut entries);
+	let mut entries: Vec<_> = entries.into_iter().collect();
+	entries.sort_by(|x, y| { x.0.len().cmp(&y.0.len()) });
+
+	let mut out = String::with_capacity(AVG_TOML_LINE_LENGTH * line_count);
+	for (mut path, values) in entries {
+		let mut field_name = String::new();
+		if !path.is_empty() {
+			field_name = path.remove(0).to_string();
+
+			for segment in path {
+				field_name += ".";
+				field_name += &segment;
+			}
+
+			write_comment(&mut out, comments, &field_name);
+			writeln!(out, "[{}]", field_name).expect("Error writing map to toml string. Please report this to the developer.");
+		}
+		for (name, value) in values {
+			if field_name.is_empty() {
+				write_comment(&mut out, comments, &name);
+			} else {
+				write_comment(&mut out, comments, &format!("{field_name}.{name}"));
+			}
+			writeln!(out, "{} = {}", name, value.to_toml()).expect("Error writing map to toml string. Please report this to the developer.");
+		}
+		out += "\n";
+	}
+	out.shrink_to_fit();
+	out
+}
+
+
 pub(crate) fn delimit_comma_split(data: &str) -> Vec<String> {
 	let mut in_string = false;
 	let mut item = String::new();
@@ -48,8 +101,7 @@ pub(crate) fn delimit_comma_split(data: &str) -> Vec<String> {
 		if c == '"' {
 			in_string = !in_string;
 		} else if !in_string && c == ',' {
-			out.push(item.clone());
-			item.clear();
+			out.push(take(&mut item));
 			continue
 		}
 		item.push(c);
@@ -69,39 +121,14 @@ impl TextRepr {
 	}
 	pub fn to_toml(self) -> String {
 		match self {
-			TextRepr::Empty => String::new(),
+			TextRepr::Empty | TextRepr::Null => String::new(),
 			TextRepr::String(x) => format!("\"{}\"", x),
 			TextRepr::Integer(x) => x.to_string(),
 			TextRepr::Float(x) => x.to_string(),
 			TextRepr::Boolean(x) => x.to_string(),
-			TextRepr::Table(map) => {
-				let line_count = map.len();
-				let mut entries = HashMap::new();
-				map_entries_recursive(map, Vec::new(), &mut entries);
-				let mut entries: Vec<_> = entries.into_iter().collect();
-				entries.sort_by(|x, y| { x.0.len().cmp(&y.0.len()) });
-
-				let mut out = String::with_capacity(AVG_TOML_LINE_LENGTH * line_count);
-				for (mut path, values) in entries {
-					if !path.is_empty() {
-						let mut field_name = path.remove(0);
-
-						for segment in path {
-							field_name += ".";
-							field_name += segment.as_str();
-						}
-
-						writeln!(out, "[{}]", field_name).expect("Error writing map to toml string. Please report this to the developer.");
-					}
-					for (name, value) in values {
-						writeln!(out, "{} = {}", name, value.to_toml()).expect("Error writing map to toml string. Please report this to the developer.");
-					}
-					out += "\n";
-				}
-				out.shrink_to_fit();
-				out
-			}
-			TextRepr::Array(mut x) => {
+			TextRepr::Table(map) => table_to_toml(map, &[]),
+			TextRepr::Array(x) => {
+				let mut x: VecDeque<_> = x.into_iter().filter(|x| !matches!(x, TextRepr::Null)).collect();
 				debug_assert!(!{
 					fn contains_table(arr: &VecDeque<TextRepr>) -> bool {
 						for item in arr {
@@ -134,12 +161,35 @@ impl TextRepr {
 		}
 	}
 
+	/// Same as to_toml, but writes comments as `#` lines above the tables and keys at their dotted paths
+	pub fn to_toml_commented(self, comments: &[(&str, &str)]) -> String {
+		match self {
+			TextRepr::Table(map) => table_to_toml(map, comments),
+			x => x.to_toml()
+		}
+	}
+
+	/// Parse a TOML file, resolving any `include = "other.toml"` or `include = ["a.toml", "b.toml"]` directive
+	pub fn from_toml_file<T: AsRef<Path>>(path: T) -> Result<Self, DeserializationError> {
+		Self::from_file_with_includes(path.as_ref(), Self::from_toml, &mut Vec::new())
+	}
+
+	/// Parse TOML with the default ParseOptions, so a repeated key replaces its earlier value
 	pub fn from_toml(data: String) -> Result<Self, DeserializationError> {
+		Self::from_toml_with(data, &ParseOptions::default())
+	}
+
+	pub fn from_toml_with(data: String, options: &ParseOptions) -> Result<Self, DeserializationError> {
 		let mut out = Self::new();
 		let mut data: VecDeque<char> = data.chars().collect();
 		let mut outer_path = Vec::new();
+		let mut interner = KeyInterner::new();
 
 		while let Some(start_char) = first_symbol(&mut data) {
+			if start_char == '#' {
+				while data.pop_front().is_some_and(|c| c != '\n') {}
+				continue
+			}
 			if start_char == '[' {
 				outer_path.clear();
 				let mut segment = String::new();
@@ -149,7 +199,7 @@ impl TextRepr {
 						break
 					}
 					if c == '.' {
-						outer_path.push(segment.clone());
+						outer_path.push(interner.intern(&segment));
 						segment.clear();
 						continue
 					}
@@ -157,20 +207,23 @@ impl TextRepr {
 				}
 				if segment.is_empty() {
 					// TODO Make clearer
-					return Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidFormat { reason: "Outer field name is either empty or terminates incorrectly".into() }))
+					return Err(DeserializationError::invalid_format_code(ErrorCode::E016_InvalidTableName, "Outer field name is either empty or terminates incorrectly"))
+				}
+				outer_path.push(interner.intern(&segment));
+				if outer_path.len() >= MAX_DEPTH {
+					return Err(too_deep())
 				}
-				outer_path.push(segment);
 				continue
 			}
 			let mut key = String::from(start_char);
 			loop {
-				let c = data.pop_front().ok_or(DeserializationErrorKind::UnexpectedEOF).set_field(key.clone())?;
+				let c = data.pop_front().ok_or(DeserializationErrorKind::UnexpectedEOF).set_field(&key)?;
 				if c == '=' {
 					break
 				}
 				key.push(c);
 			}
-			key = key.trim().to_string();
+			let key = interner.intern(key.trim());
 
 			let mut value = String::new();
 			while let Some(c) = data.pop_front() {
@@ -190,9 +243,9 @@ impl TextRepr {
 				for item in delimit_comma_split(value.get(1..(value.len() - 1)).unwrap()) {
 					arr.push_back(Self::from_str_value(item.trim().to_string())?);
 				}
-				out.push_entry_path(new_path, Self::Array(arr))
+				out.push_parsed_entry(new_path, Self::Array(arr), options.duplicate_keys)?;
 			} else {
-				out.push_entry_path(new_path, Self::from_str_value(value)?);
+				out.push_parsed_entry(new_path, Self::from_str_value(value)?, options.duplicate_keys)?;
 			}
 		}
 
@@ -201,6 +254,26 @@ impl TextRepr {
 }
 
 
+/// Serialize any value as TOML, without implementing TOMLSerialize for it
+pub fn to_string<P, T: Serialize<P>>(value: T) -> String {
+	let mut out = TextRepr::new();
+	value.serialize(&mut out);
+	out.to_toml()
+}
+
+
+/// Deserialize any value from TOML, without implementing TOMLDeserialize for it
+pub fn from_str<P, T: Deserialize<P>>(data: &str) -> Result<T, DeserializationError> {
+	T::deserialize(&mut TextRepr::from_toml(data.into())?)
+}
+
+
+/// Same as from_str, but parsed with the given options
+pub fn from_str_with<P, T: Deserialize<P>>(data: &str, options: &ParseOptions) -> Result<T, DeserializationError> {
+	T::deserialize(&mut TextRepr::from_toml_with(data.into(), options)?)
+}
+
+
 pub trait TOMLSerialize<P = NaturalProfile> {
 	fn serialize_toml(self) -> String;
 }
@@ -251,9 +324,7 @@ macro_rules! impl_toml_ser {
     ($name: ty, $profile: ty) => {
 		impl TOMLSerialize<$profile> for $name {
 			fn serialize_toml(self) -> String {
-				let mut out = TextRepr::new();
-				Serialize::<$profile>::serialize(self, &mut out);
-				out.to_toml()
+				$crate::toml::to_string::<$profile, _>(self)
 			}
 		}
 	};
@@ -287,7 +358,7 @@ macro_rules! impl_toml_deser {
     ($name: ty, $profile: ty) => {
 		impl TOMLDeserialize<$profile> for $name {
 			fn deserialize_toml(data: String) -> Result<Self, DeserializationError> {
-				Deserialize::<$profile>::deserialize(&mut TextRepr::from_toml(data)?)
+				$crate::toml::from_str::<$profile, _>(&data)
 			}
 		}
 	};
@@ -301,14 +372,14 @@ macro_rules! impl_toml_deser {
 }
 
 
-impl<P, K: Borrow<str> + Eq + std::hash::Hash, V: Serialize<P>> TOMLSerialize<P> for HashMap<K, V> {
+impl<P, K: Borrow<str> + Eq + std::hash::Hash, V: Serialize<P>, S: std::hash::BuildHasher> TOMLSerialize<P> for HashMap<K, V, S> {
 	fn serialize_toml(self) -> String {
 		TextRepr::to_toml(serialize_owned!(self))
 	}
 }
 
 
-impl<E: Debug, P, K: Eq + std::hash::Hash + FromStr<Err=E>, V: Deserialize<P>> TOMLDeserialize<P> for HashMap<K, V> {
+impl<E: Debug, P, K: Eq + std::hash::Hash + FromStr<Err=E>, V: Deserialize<P>, S: std::hash::BuildHasher + Default> TOMLDeserialize<P> for HashMap<K, V, S> {
 	fn deserialize_toml(data: String) -> Result<Self, DeserializationError> {
 		Self::deserialize::<TextRepr>(&mut TextRepr::from_toml(data)?)
 	}
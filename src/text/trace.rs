@@ -0,0 +1,44 @@
+use ::tracing::field::display;
+use ::tracing::Span;
+
+use super::*;
+
+
+/// Flatten the keyed fields of an item into dotted paths and their values.
+/// Arrays are kept whole, as they have no field names of their own
+pub fn flatten_fields<P, T: Serialize<P>>(item: T) -> Vec<(String, TextRepr)> {
+	fn flatten(prefix: &str, repr: TextRepr, out: &mut Vec<(String, TextRepr)>) {
+		match repr {
+			TextRepr::Table(x) => for (key, value) in x {
+				if prefix.is_empty() {
+					flatten(&key, value, out);
+				} else {
+					flatten(&format!("{prefix}.{key}"), value, out);
+				}
+			}
+			TextRepr::Empty | TextRepr::Null => {}
+			value => out.push((prefix.to_string(), value))
+		}
+	}
+
+	let mut out = Vec::new();
+	flatten("", serialize_owned!(item), &mut out);
+	out
+}
+
+
+/// Record the keyed fields of an item onto a span.
+/// Nested fields are recorded with dotted names, such as `server.port`, and only fields
+/// declared on the span (for example with `tracing::field::Empty`) are kept
+pub fn record_fields<P, T: Serialize<P>>(span: &Span, item: T) {
+	for (name, value) in flatten_fields(item) {
+		let name = name.as_str();
+		match value {
+			TextRepr::String(x) => { span.record(name, x.as_str()); }
+			TextRepr::Integer(x) => { span.record(name, x); }
+			TextRepr::Float(x) => { span.record(name, x); }
+			TextRepr::Boolean(x) => { span.record(name, x); }
+			value => { span.record(name, display(value)); }
+		}
+	}
+}
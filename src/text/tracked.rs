@@ -0,0 +1,103 @@
+//! Dirty field tracking. A `Tracked<T>` remembers the fields of T as they were last saved,
+//! so that saving it again with `serialize_dirty` only writes the keys whose values changed since
+use std::collections::BTreeSet;
+use std::ops::{Deref, DerefMut};
+
+use super::*;
+
+
+/// A value that remembers which of its fields changed since it was last saved
+#[derive(Debug, Clone)]
+pub struct Tracked<T> {
+	value: T,
+	saved: Table,
+	marked: BTreeSet<String>
+}
+
+
+impl<T> Tracked<T> {
+	/// Track a value that has never been saved, so all of its fields are dirty
+	pub fn new(value: T) -> Self {
+		Self { value, saved: Table::default(), marked: BTreeSet::new() }
+	}
+
+	/// Track a value that is already saved, so none of its fields are dirty
+	pub fn saved<P>(value: T) -> Self where T: Serialize<P> + Clone {
+		let mut out = Self::new(value);
+		out.saved = out.current::<P>();
+		out
+	}
+
+	/// Mark a field as dirty even if its value looks unchanged
+	pub fn mark<K: Into<String>>(&mut self, field: K) {
+		self.marked.insert(field.into());
+	}
+
+	pub fn into_inner(self) -> T {
+		self.value
+	}
+
+	fn current<P>(&self) -> Table where T: Serialize<P> + Clone {
+		let mut out = TextRepr::new();
+		Serialize::<P>::serialize(self.value.clone(), &mut out);
+		match out {
+			TextRepr::Table(x) => x,
+			_ => Table::default()
+		}
+	}
+
+	fn dirty_entries<P>(&self) -> Vec<(TableKey, TextRepr)> where T: Serialize<P> + Clone {
+		let mut out: Vec<_> = self.current::<P>()
+			.into_iter()
+			.filter(|(key, value)| self.marked.contains(&**key) || self.saved.get(key) != Some(value))
+			.collect();
+		out.sort_by(|x, y| x.0.cmp(&y.0));
+		out
+	}
+
+	/// The names of the fields that changed since the last save, in sorted order
+	pub fn dirty_fields<P>(&self) -> Vec<String> where T: Serialize<P> + Clone {
+		self.dirty_entries::<P>().into_iter().map(|(key, _)| key.to_string()).collect()
+	}
+
+	/// Write only the fields that changed since the last save, and consider them saved
+	pub fn serialize_dirty<P, S: Serializer>(&mut self, data: &mut S) where T: Serialize<P> + Clone {
+		for (key, value) in self.dirty_entries::<P>() {
+			data.serialize_key(&*key, value.clone());
+			self.saved.insert(key, value);
+		}
+		self.marked.clear();
+	}
+}
+
+
+impl<T> Deref for Tracked<T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		&self.value
+	}
+}
+
+
+impl<T> DerefMut for Tracked<T> {
+	fn deref_mut(&mut self) -> &mut T {
+		&mut self.value
+	}
+}
+
+
+/// Writes the whole value, without changing what is considered saved
+impl<P, T: Serialize<P>> Serialize<P> for Tracked<T> {
+	fn serialize<S: Serializer>(self, data: &mut S) {
+		self.value.serialize(data);
+	}
+}
+
+
+/// A deserialized value is considered saved
+impl<P, T: Deserialize<P> + Serialize<P> + Clone> Deserialize<P> for Tracked<T> {
+	fn deserialize<S: Serializer>(data: &mut S) -> Result<Self, DeserializationError> {
+		T::deserialize(data).map(Self::saved::<P>)
+	}
+}
@@ -0,0 +1,318 @@
+//! The `application/x-www-form-urlencoded` format of HTML forms and query strings, such as `a=1&b=hello+world`.
+//!
+//! Only flat values can be written, so each field is a string, number, boolean, None or a sequence of these.
+//! Sequences are written by repeating their key, and None and empty sequences are left out,
+//! so fields that can be empty should be Options. Every value is read as a string and then parsed into the type
+//! of its field, with booleans also accepting `on`, which is what a checked checkbox sends
+use std::borrow::Borrow;
+use std::str::FromStr;
+
+use super::*;
+
+
+/// A value being written or read, before it is flattened into pairs or after pairs are grouped by their key
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+	Null,
+	Text(String),
+	List(Vec<Value>),
+	Map(Vec<(String, Value)>)
+}
+
+
+impl Value {
+	fn type_name(&self) -> &'static str {
+		match self {
+			Value::Null => "null",
+			Value::Text(_) => "string",
+			Value::List(_) => "sequence",
+			Value::Map(_) => "map"
+		}
+	}
+
+	fn invalid_type(&self, expected: &'static str) -> DeserializationError {
+		DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected, actual: self.type_name() })
+	}
+
+	fn into_text(self) -> TextRepr {
+		match self {
+			Value::Null => TextRepr::Null,
+			Value::Text(x) => TextRepr::String(x),
+			Value::List(x) => TextRepr::Array(x.into_iter().map(Value::into_text).collect()),
+			Value::Map(x) => TextRepr::Table(x.into_iter().map(|(key, value)| (key.into(), value.into_text())).collect())
+		}
+	}
+}
+
+
+fn invalid_value<T: Display>(text: &str, expected: T) -> DeserializationError {
+	DeserializationError::new_kind(DeserializationErrorKind::InvalidValue { reason: format!("{:?} is not {}", text, expected) })
+}
+
+
+/// Percent encode a key or value, writing spaces as +
+fn encode(out: &mut String, text: &str) {
+	for byte in text.bytes() {
+		match byte {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => out.push(byte as char),
+			b' ' => out.push('+'),
+			_ => out.push_str(&format!("%{:02X}", byte))
+		}
+	}
+}
+
+
+/// Reverse the percent encoding of a key or value
+fn decode(text: &str) -> Result<String, DeserializationError> {
+	let mut out = Vec::with_capacity(text.len());
+	let mut bytes = text.bytes();
+	while let Some(byte) = bytes.next() {
+		match byte {
+			b'+' => out.push(b' '),
+			b'%' => {
+				let hex = [bytes.next(), bytes.next()];
+				let byte = match hex {
+					[Some(a), Some(b)] => std::str::from_utf8(&[a, b]).ok().and_then(|x| u8::from_str_radix(x, 16).ok()),
+					_ => None
+				};
+				out.push(byte.ok_or_else(|| DeserializationError::invalid_format(format!("invalid percent encoding in {:?}", text)))?);
+			}
+			byte => out.push(byte)
+		}
+	}
+	String::from_utf8(out).map_err(|e| DeserializationError::new_kind(DeserializationErrorKind::from(e)))
+}
+
+
+fn write_pair(out: &mut String, key: &str, value: Value) {
+	match value {
+		Value::Null => {}
+		Value::Text(x) => {
+			if !out.is_empty() {
+				out.push('&');
+			}
+			encode(out, key);
+			out.push('=');
+			encode(out, &x);
+		}
+		Value::List(x) => for value in x {
+			write_pair(out, key, value);
+		}
+		Value::Map(_) => panic!("Tried to urlencode the nested value at {}! Only flat values can be urlencoded.", key)
+	}
+}
+
+
+/// A value being written or read.
+/// Values that are not keyed are kept in order, and keyed values are kept in a map
+#[derive(Debug, Default)]
+struct Document {
+	values: VecDeque<Value>,
+	map: Option<Vec<(String, Value)>>
+}
+
+
+impl Document {
+	fn from_value(value: Value) -> Self {
+		match value {
+			Value::List(x) => Self { values: x.into(), map: None },
+			Value::Map(x) => Self { values: VecDeque::new(), map: Some(x) },
+			Value::Null => Self::default(),
+			x => Self { values: VecDeque::from([x]), map: None }
+		}
+	}
+
+	fn into_value(mut self) -> Value {
+		if let Some(x) = self.map {
+			return Value::Map(x)
+		}
+		match self.values.len() {
+			0 => Value::Null,
+			1 => self.values.pop_front().unwrap(),
+			_ => Value::List(self.values.into())
+		}
+	}
+
+	fn next_text(&mut self) -> Result<String, DeserializationError> {
+		match self.values.pop_front().ok_or(DeserializationError::EOF)? {
+			Value::Text(x) => Ok(x),
+			x => Err(x.invalid_type("string"))
+		}
+	}
+
+	fn key_deserialize<T, F>(&mut self, key: &str, f: F) -> Result<T, DeserializationError>
+		where
+			F: FnOnce(&mut Document) -> Result<T, DeserializationError>
+	{
+		let map = self.map.as_mut().ok_or_else(|| DeserializationError::missing_field(key))?;
+		let idx = map.iter().position(|x| x.0 == key).ok_or_else(|| DeserializationError::missing_field(key))?;
+		let value = map.remove(idx).1;
+		f(&mut Document::from_value(value)).map_err(|e| e.nest().set_field(key))
+	}
+}
+
+
+impl PrimitiveSerializer for Document {
+	fn serialize_bool(&mut self, boolean: bool) {
+		self.values.push_back(Value::Text(boolean.to_string()));
+	}
+
+	fn deserialize_bool(&mut self) -> Result<bool, DeserializationError> {
+		let text = self.next_text()?;
+		match text.as_str() {
+			"true" | "on" => Ok(true),
+			"false" => Ok(false),
+			_ => Err(invalid_value(&text, "a boolean"))
+		}
+	}
+
+	fn serialize_num<T: NumberType>(&mut self, num: T) {
+		self.values.push_back(Value::Text(match num.to_number() {
+			Number::Unsigned(x) => x.to_string(),
+			Number::Signed(x) => x.to_string(),
+			Number::F32(x) => x.to_string(),
+			Number::F64(x) => x.to_string()
+		}));
+	}
+
+	fn deserialize_num<T: NumberType>(&mut self) -> Result<T, DeserializationError> {
+		let text = self.next_text()?;
+		let number = if let Ok(x) = text.parse() {
+			Number::Unsigned(x)
+		} else if let Ok(x) = text.parse() {
+			Number::Signed(x)
+		} else {
+			Number::F64(text.parse().map_err(|_| invalid_value(&text, "a number"))?)
+		};
+		T::from_number(number).ok_or_else(|| invalid_value(&text, "a number that fits its type"))
+	}
+
+	fn serialize_string<T: Into<String>>(&mut self, string: T) {
+		self.values.push_back(Value::Text(string.into()));
+	}
+
+	fn deserialize_string(&mut self) -> Result<String, DeserializationError> {
+		self.next_text()
+	}
+
+	/// Bytes are written as a sequence of numbers
+	fn serialize_bytes<T: Into<VecDeque<u8>>>(&mut self, bytes: T) {
+		self.values.push_back(Value::List(bytes.into().into_iter().map(|x| Value::Text(x.to_string())).collect()));
+	}
+
+	fn deserialize_bytes<T: FromIterator<u8>>(&mut self) -> Result<T, DeserializationError> {
+		let mut out = Vec::with_capacity(self.values.len());
+		while !self.values.is_empty() {
+			out.push(self.deserialize_num()?);
+		}
+		Ok(out.into_iter().collect())
+	}
+}
+
+
+impl Serializer for Document {
+	fn serialize<P, T: Serialize<P>>(&mut self, item: T) {
+		let mut document = Document::default();
+		item.serialize(&mut document);
+		self.values.push_back(document.into_value());
+	}
+
+	fn serialize_iter<P, T: Serialize<P>, I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		let mut document = Document::default();
+		for item in iter {
+			document.serialize(item);
+		}
+		self.values.push_back(Value::List(document.values.into()));
+	}
+
+	fn serialize_key<P, T: Serialize<P>, K: Borrow<str>>(&mut self, key: K, item: T) {
+		let mut document = Document::default();
+		item.serialize(&mut document);
+		self.map.get_or_insert_with(Vec::new).push((key.borrow().into(), document.into_value()));
+	}
+
+	fn deserialize<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError> {
+		let value = self.values.pop_front().ok_or(DeserializationError::EOF)?;
+		T::deserialize(&mut Document::from_value(value))
+	}
+
+	fn deserialize_key_internal<P, T: Deserialize<P>>(&mut self, key: &str) -> Result<T, DeserializationError> {
+		self.key_deserialize(key, |x| T::deserialize(x))
+	}
+
+	fn deserialize_seed<P, S: DeserializeSeed<P>>(&mut self, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		let value = self.values.pop_front().ok_or(DeserializationError::EOF)?;
+		seed.deserialize(&mut Document::from_value(value))
+	}
+
+	fn deserialize_key_seed<P, S: DeserializeSeed<P>, K: Borrow<str>>(&mut self, key: K, seed: &mut S) -> Result<S::Value, DeserializationError> {
+		self.key_deserialize(key.borrow(), |x| seed.deserialize(x))
+	}
+
+	fn serialize_option<P, T: Serialize<P>>(&mut self, item: Option<T>) {
+		match item {
+			Some(x) => x.serialize(self),
+			None => self.values.push_back(Value::Null)
+		}
+	}
+
+	fn deserialize_option<P, T: Deserialize<P>>(&mut self) -> Result<Option<T>, DeserializationError> {
+		if self.values.is_empty() && self.map.is_none() {
+			return Ok(None)
+		}
+		T::deserialize(self).map(Some)
+	}
+
+	fn try_get_key<K: FromStr>(&mut self) -> Option<K> {
+		self.map.as_ref()?.first().and_then(|x| K::from_str(&x.0).ok())
+	}
+
+	fn remaining_keys(&self) -> Vec<String> {
+		self.map.iter().flatten().map(|x| x.0.clone()).collect()
+	}
+
+	fn deserialize_text(&mut self) -> Result<TextRepr, DeserializationError> {
+		if let Some(x) = self.map.take() {
+			return Ok(Value::Map(x).into_text())
+		}
+		self.values.pop_front().map(Value::into_text).ok_or(DeserializationError::EOF)
+	}
+}
+
+
+/// Serialize a flat keyed value, such as a struct of strings and numbers, as a urlencoded form body.
+///
+/// Panics if a field holds a nested keyed value
+pub fn to_string<P, T: Serialize<P>>(value: T) -> String {
+	let mut document = Document::default();
+	value.serialize(&mut document);
+	let mut out = String::new();
+	match document.into_value() {
+		Value::Map(x) => for (key, value) in x {
+			write_pair(&mut out, &key, value);
+		}
+		Value::Null => {}
+		_ => panic!("Tried to urlencode a value without keys! Only keyed values can be urlencoded.")
+	}
+	out
+}
+
+
+/// Deserialize a flat keyed value from a urlencoded form body or query string, without its leading `?`.
+/// Keys that appear more than once are read as a sequence
+pub fn from_str<P, T: Deserialize<P>>(data: &str) -> Result<T, DeserializationError> {
+	let mut map: Vec<(String, Value)> = Vec::new();
+	for pair in data.split('&').filter(|x| !x.is_empty()) {
+		let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+		let (key, value) = (decode(key)?, Value::Text(decode(value)?));
+		match map.iter_mut().find(|x| x.0 == key) {
+			Some((_, Value::List(x))) => x.push(value),
+			Some((_, existing)) => {
+				let first = std::mem::replace(existing, Value::Null);
+				*existing = Value::List(vec![first, value]);
+			}
+			None => map.push((key, value))
+		}
+	}
+	T::deserialize(&mut Document::from_value(Value::Map(map)))
+}
@@ -0,0 +1,80 @@
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+
+use super::*;
+
+
+impl TextRepr {
+	/// Convert into a plain JS value. Tables become objects and arrays become arrays
+	pub fn to_js_value(self) -> JsValue {
+		match self {
+			TextRepr::Empty => JsValue::UNDEFINED,
+			TextRepr::Null => JsValue::NULL,
+			TextRepr::String(x) => JsValue::from_str(&x),
+			TextRepr::Integer(x) => JsValue::from_f64(x as f64),
+			TextRepr::Float(x) => JsValue::from_f64(x),
+			TextRepr::Boolean(x) => JsValue::from_bool(x),
+			TextRepr::Table(x) => {
+				let out = Object::new();
+				for (key, value) in x {
+					Reflect::set(&out, &JsValue::from_str(&key), &value.to_js_value()).expect("Error setting a property on a new object. Please report this to the developer.");
+				}
+				out.into()
+			}
+			TextRepr::Array(x) => x.into_iter().map(Self::to_js_value).collect::<Array>().into()
+		}
+	}
+
+	/// Convert from a plain JS value. Whole numbers become integers
+	pub fn from_js_value(value: &JsValue) -> Result<Self, DeserializationError> {
+		if value.is_null() {
+			return Ok(TextRepr::Null)
+		}
+		if value.is_undefined() {
+			return Ok(TextRepr::Empty)
+		}
+		if let Some(x) = value.as_bool() {
+			return Ok(TextRepr::Boolean(x))
+		}
+		if let Some(x) = value.as_f64() {
+			if x.fract() == 0.0 && x >= i64::MIN as f64 && x <= i64::MAX as f64 {
+				return Ok(TextRepr::Integer(x as i64))
+			}
+			return Ok(TextRepr::Float(x))
+		}
+		if let Some(x) = value.as_string() {
+			return Ok(TextRepr::String(x))
+		}
+		if Array::is_array(value) {
+			return value.unchecked_ref::<Array>()
+				.iter()
+				.map(|x| Self::from_js_value(&x))
+				.collect::<Result<VecDeque<_>, _>>()
+				.map(TextRepr::Array)
+		}
+		if let Some(object) = value.dyn_ref::<Object>() {
+			let mut interner = KeyInterner::new();
+			let mut out = Table::default();
+			for entry in Object::entries(object).iter() {
+				let entry: Array = entry.unchecked_into();
+				let key = entry.get(0).as_string().ok_or_else(|| DeserializationError::invalid_format("object key is not a string"))?;
+				let value = Self::from_js_value(&entry.get(1)).map_err(|e| e.nest().set_field(&key))?;
+				out.insert(interner.intern(&key), value);
+			}
+			return Ok(TextRepr::Table(out))
+		}
+		Err(DeserializationError::new_kind(DeserializationErrorKind::InvalidType { expected: "plain js value", actual: "other js value" }))
+	}
+}
+
+
+/// Serialize an item straight into a JS value
+pub fn to_js_value<P, T: Serialize<P>>(item: T) -> JsValue {
+	serialize_owned!(item).to_js_value()
+}
+
+
+/// Deserialize an item straight from a JS value
+pub fn from_js_value<P, T: Deserialize<P>>(value: &JsValue) -> Result<T, DeserializationError> {
+	T::deserialize(&mut TextRepr::from_js_value(value)?)
+}
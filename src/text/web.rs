@@ -0,0 +1,195 @@
+//! Request body extractors and response wrappers for web frameworks.
+//!
+//! [Json] and [Toml] deserialize request bodies with JSONDeserialize and TOMLDeserialize,
+//! and serialize responses with JSONSerialize and TOMLSerialize, setting the content type to match.
+//! The `axum` feature implements FromRequest and IntoResponse for them, and the `actix` feature
+//! implements FromRequest and Responder. A request with the wrong content type is rejected with 415,
+//! a body that cannot be read with 400, and a body that cannot be deserialized with 422
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+
+use super::json::{JSONDeserialize, JSONSerialize};
+use super::toml::{TOMLDeserialize, TOMLSerialize};
+use super::*;
+
+
+/// Why a request body could not be extracted
+#[derive(Debug)]
+pub enum Rejection {
+	/// The request does not have the content type of the format
+	ContentType { expected: &'static str },
+	/// The body could not be read, or is not UTF-8
+	Body(String),
+	/// The body could not be deserialized
+	Deserialization(DeserializationError)
+}
+
+
+impl Rejection {
+	/// The HTTP status code to respond with
+	pub fn status(&self) -> u16 {
+		match self {
+			Rejection::ContentType { .. } => 415,
+			Rejection::Body(_) => 400,
+			Rejection::Deserialization(_) => 422
+		}
+	}
+}
+
+
+impl Display for Rejection {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Rejection::ContentType { expected } => write!(f, "expected a request with content type {}", expected),
+			Rejection::Body(e) => write!(f, "failed to read the request body: {}", e),
+			Rejection::Deserialization(e) => write!(f, "failed to deserialize the request body: {}", e)
+		}
+	}
+}
+
+
+impl Error for Rejection {}
+
+
+/// Whether a content type is the given essence, or a structured syntax suffix of it such as application/ld+json
+fn content_type_matches(content_type: Option<&str>, essence: &str) -> bool {
+	let Some(content_type) = content_type else { return false };
+	let content_type = content_type.split(';').next().unwrap_or_default().trim();
+	let suffix = essence.rsplit('/').next().unwrap_or_default();
+	content_type.eq_ignore_ascii_case(essence)
+		|| (content_type.len() > suffix.len() + 1 && content_type.to_ascii_lowercase().ends_with(&format!("+{}", suffix)))
+}
+
+
+macro_rules! web_format {
+	($(#[$doc: meta])* $name: ident, $ser: ident, $ser_fn: ident, $deser: ident, $deser_fn: ident, $content_type: literal) => {
+		$(#[$doc])*
+		pub struct $name<T, P = NaturalProfile>(pub T, pub PhantomData<P>);
+
+		impl<T, P> $name<T, P> {
+			pub const CONTENT_TYPE: &'static str = $content_type;
+
+			pub fn new(value: T) -> Self {
+				Self(value, PhantomData)
+			}
+
+			pub fn into_inner(self) -> T {
+				self.0
+			}
+
+			/// Deserialize a request body, given its content type
+			pub fn from_body(content_type: Option<&str>, body: &[u8]) -> Result<Self, Rejection> where T: $deser<P> {
+				if !content_type_matches(content_type, $content_type) {
+					return Err(Rejection::ContentType { expected: $content_type })
+				}
+				let body = String::from_utf8(body.to_vec()).map_err(|e| Rejection::Body(e.to_string()))?;
+				T::$deser_fn(body).map(Self::new).map_err(Rejection::Deserialization)
+			}
+
+			/// Serialize the value into a response body
+			pub fn to_body(self) -> String where T: $ser<P> {
+				self.0.$ser_fn()
+			}
+		}
+
+		// Derives would require the profile to implement the traits as well
+		impl<T: std::fmt::Debug, P> std::fmt::Debug for $name<T, P> {
+			fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+				f.debug_tuple(stringify!($name)).field(&self.0).finish()
+			}
+		}
+
+		impl<T: Clone, P> Clone for $name<T, P> {
+			fn clone(&self) -> Self {
+				Self::new(self.0.clone())
+			}
+		}
+
+		impl<T: Default, P> Default for $name<T, P> {
+			fn default() -> Self {
+				Self::new(T::default())
+			}
+		}
+
+		impl<T, P> From<T> for $name<T, P> {
+			fn from(value: T) -> Self {
+				Self::new(value)
+			}
+		}
+
+		#[cfg(feature = "axum")]
+		impl<T, P, S> axum::extract::FromRequest<S> for $name<T, P>
+			where
+				T: $deser<P> + Send,
+				P: Send,
+				S: Send + Sync
+		{
+			type Rejection = Rejection;
+
+			async fn from_request(req: axum::extract::Request, state: &S) -> Result<Self, Self::Rejection> {
+				let content_type = req.headers().get(axum::http::header::CONTENT_TYPE).and_then(|x| x.to_str().ok()).map(String::from);
+				let body = <axum::body::Bytes as axum::extract::FromRequest<S>>::from_request(req, state).await.map_err(|e| Rejection::Body(e.body_text()))?;
+				Self::from_body(content_type.as_deref(), &body)
+			}
+		}
+
+		#[cfg(feature = "axum")]
+		impl<T: $ser<P>, P> axum::response::IntoResponse for $name<T, P> {
+			fn into_response(self) -> axum::response::Response {
+				([(axum::http::header::CONTENT_TYPE, $content_type)], self.to_body()).into_response()
+			}
+		}
+
+		#[cfg(feature = "actix")]
+		impl<T: $deser<P> + 'static, P: 'static> actix_web::FromRequest for $name<T, P> {
+			type Error = Rejection;
+			type Future = std::pin::Pin<Box<dyn std::future::Future<Output=Result<Self, Self::Error>>>>;
+
+			fn from_request(req: &actix_web::HttpRequest, payload: &mut actix_web::dev::Payload) -> Self::Future {
+				let content_type = req.headers().get(actix_web::http::header::CONTENT_TYPE).and_then(|x| x.to_str().ok()).map(String::from);
+				let body = <actix_web::web::Bytes as actix_web::FromRequest>::from_request(req, payload);
+				Box::pin(async move {
+					let body = body.await.map_err(|e| Rejection::Body(e.to_string()))?;
+					Self::from_body(content_type.as_deref(), &body)
+				})
+			}
+		}
+
+		#[cfg(feature = "actix")]
+		impl<T: $ser<P>, P> actix_web::Responder for $name<T, P> {
+			type Body = actix_web::body::BoxBody;
+
+			fn respond_to(self, _req: &actix_web::HttpRequest) -> actix_web::HttpResponse {
+				actix_web::HttpResponse::Ok().content_type($content_type).body(self.to_body())
+			}
+		}
+	};
+}
+
+
+web_format!(
+	/// A JSON request or response body
+	Json, JSONSerialize, serialize_json_compact, JSONDeserialize, deserialize_json, "application/json"
+);
+web_format!(
+	/// A TOML request or response body
+	Toml, TOMLSerialize, serialize_toml, TOMLDeserialize, deserialize_toml, "application/toml"
+);
+
+
+#[cfg(feature = "axum")]
+impl axum::response::IntoResponse for Rejection {
+	fn into_response(self) -> axum::response::Response {
+		let status = axum::http::StatusCode::from_u16(self.status()).unwrap();
+		(status, self.to_string()).into_response()
+	}
+}
+
+
+#[cfg(feature = "actix")]
+impl actix_web::ResponseError for Rejection {
+	fn status_code(&self) -> actix_web::http::StatusCode {
+		actix_web::http::StatusCode::from_u16(self.status()).unwrap()
+	}
+}
@@ -0,0 +1,723 @@
+//! YAML, limited to the subset that configuration files are usually written in.
+//!
+//! Mappings and sequences are nested by indentation, or written on one line in flow style such as `[1, 2]`
+//! and `{a: 1}`. Scalars may be plain, single or double quoted, or `|` and `>` block scalars.
+//! Anchors, aliases, tags and documents with more than one `---` section are rejected
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+use std::str::FromStr;
+
+use super::*;
+
+
+pub mod yaml_prelude {
+	pub use crate::{impl_yaml, impl_yaml_deser, impl_yaml_ser};
+
+	pub use super::{text::TextRepr, YAMLDeserialize, YAMLSerialize};
+}
+
+
+/// A line of a YAML document
+#[derive(Clone, Copy)]
+struct Line<'a> {
+	/// The line number, starting from 1
+	number: usize,
+	/// The number of spaces the line starts with
+	indent: usize,
+	/// The content of the line, without indentation, comments or trailing whitespace
+	text: &'a str,
+	/// The whole line, which block scalars are read from
+	raw: &'a str
+}
+
+
+fn error<T: Display>(number: usize, reason: T) -> DeserializationError {
+	DeserializationError::invalid_format(format!("line {}: {}", number, reason))
+}
+
+
+/// The index of the first character in text that is not inside quotes and matches pred
+fn find_unquoted(text: &str, mut pred: impl FnMut(usize, char) -> bool) -> Option<usize> {
+	let mut quote = None;
+	let mut escaped = false;
+	let mut prev = ' ';
+	for (i, c) in text.char_indices() {
+		match quote {
+			Some('"') if escaped => escaped = false,
+			Some('"') if c == '\\' => escaped = true,
+			Some(x) if x == c => quote = None,
+			Some(_) => {}
+			// Quotes only start a string at the start of a scalar, so apostrophes in plain strings are left alone
+			None if matches!(c, '"' | '\'') && (prev.is_whitespace() || matches!(prev, '[' | '{' | ',' | ':')) => quote = Some(c),
+			None if pred(i, c) => return Some(i),
+			None => {}
+		}
+		prev = c;
+	}
+	None
+}
+
+
+fn strip_comment(line: &str) -> &str {
+	match find_unquoted(line, |i, c| c == '#' && (i == 0 || line[..i].ends_with([' ', '\t']))) {
+		Some(i) => &line[..i],
+		None => line
+	}
+}
+
+
+/// The index of the colon that ends the key at the start of text, if text is a mapping entry
+fn key_end(text: &str) -> Option<usize> {
+	if text.starts_with(['[', '{']) {
+		return None
+	}
+	find_unquoted(text, |i, c| c == ':' && (i + 1 == text.len() || text[i + 1..].starts_with([' ', '\t'])))
+}
+
+
+/// Whether text is an entry of a block sequence
+fn is_entry(text: &str) -> bool {
+	text == "-" || text.starts_with("- ")
+}
+
+
+/// Read the quoted string at the start of text, returning it and the length of text it took up
+fn read_quoted(text: &str) -> Result<(String, usize), String> {
+	let mut chars = text.char_indices();
+	let quote = match chars.next() {
+		Some((_, x)) => x,
+		None => return Err("expected a string".into())
+	};
+	let mut out = String::new();
+	while let Some((i, c)) = chars.next() {
+		match c {
+			'\'' if quote == '\'' && text[i + 1..].starts_with('\'') => {
+				chars.next();
+				out.push('\'');
+			}
+			c if c == quote => return Ok((out, i + 1)),
+			'\\' if quote == '"' => {
+				let (_, escape) = chars.next().ok_or("unterminated string")?;
+				match escape {
+					'n' => out.push('\n'),
+					't' => out.push('\t'),
+					'r' => out.push('\r'),
+					'0' => out.push('\0'),
+					'"' | '\\' | '/' | ' ' => out.push(escape),
+					'x' | 'u' | 'U' => {
+						let len = match escape {
+							'x' => 2,
+							'u' => 4,
+							_ => 8
+						};
+						let hex = text.get(i + 2..i + 2 + len).unwrap_or_default();
+						let c = u32::from_str_radix(hex, 16).ok()
+							.and_then(char::from_u32)
+							.ok_or_else(|| format!("invalid escape \\{}{}", escape, hex))?;
+						out.push(c);
+						for _ in 0..len {
+							chars.next();
+						}
+					}
+					x => return Err(format!("unknown escape \\{}", x))
+				}
+			}
+			c => out.push(c)
+		}
+	}
+	Err("unterminated string".into())
+}
+
+
+/// Parse a scalar that is not quoted
+fn parse_plain(text: &str) -> TextRepr {
+	match text {
+		"~" | "null" | "Null" | "NULL" => TextRepr::Null,
+		"true" | "True" | "TRUE" => TextRepr::Boolean(true),
+		"false" | "False" | "FALSE" => TextRepr::Boolean(false),
+		".inf" | ".Inf" | ".INF" | "+.inf" => TextRepr::Float(f64::INFINITY),
+		"-.inf" | "-.Inf" | "-.INF" => TextRepr::Float(f64::NEG_INFINITY),
+		".nan" | ".NaN" | ".NAN" => TextRepr::Float(f64::NAN),
+		_ => {
+			if let Ok(x) = text.parse() {
+				return TextRepr::Integer(x)
+			}
+			// Rust also parses words such as inf and NaN as floats, which are plain strings in YAML
+			if text.trim_start_matches(['+', '-']).starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+				if let Ok(x) = text.parse() {
+					return TextRepr::Float(x)
+				}
+			}
+			TextRepr::String(text.into())
+		}
+	}
+}
+
+
+/// Fold the lines of a `>` block scalar, turning single line breaks into spaces
+fn fold(lines: &[&str]) -> String {
+	let mut out = String::new();
+	for (i, line) in lines.iter().enumerate() {
+		if line.is_empty() {
+			out.push('\n');
+		} else {
+			if i > 0 && !lines[i - 1].is_empty() {
+				out.push(' ');
+			}
+			out.push_str(line);
+		}
+	}
+	out
+}
+
+
+/// Parses a flow collection such as `[1, {a: 2}]`, which must fit on one line
+struct Flow<'a, 'b> {
+	text: &'a str,
+	pos: usize,
+	number: usize,
+	interner: &'b mut KeyInterner
+}
+
+
+impl<'a> Flow<'a, '_> {
+	fn skip_whitespace(&mut self) {
+		self.pos = self.text.len() - self.text[self.pos..].trim_start().len();
+	}
+
+	fn eat(&mut self, c: char) -> bool {
+		self.skip_whitespace();
+		if self.text[self.pos..].starts_with(c) {
+			self.pos += c.len_utf8();
+			true
+		} else {
+			false
+		}
+	}
+
+	fn quoted(&mut self) -> Result<String, DeserializationError> {
+		let (string, len) = read_quoted(&self.text[self.pos..]).map_err(|e| error(self.number, e))?;
+		self.pos += len;
+		Ok(string)
+	}
+
+	/// Read a scalar that is not quoted, which ends at any of the given characters
+	fn plain(&mut self, ends: &[char]) -> &'a str {
+		let rest = &self.text[self.pos..];
+		let len = rest.find(ends).unwrap_or(rest.len());
+		self.pos += len;
+		rest[..len].trim()
+	}
+
+	fn value(&mut self, depth: usize) -> Result<TextRepr, DeserializationError> {
+		if depth >= MAX_DEPTH {
+			return Err(too_deep())
+		}
+		if self.eat('[') {
+			let mut arr = VecDeque::new();
+			while !self.eat(']') {
+				arr.push_back(self.value(depth + 1)?);
+				if !self.eat(',') && !self.text[self.pos..].starts_with(']') {
+					return Err(error(self.number, "expected , or ] in a flow sequence"))
+				}
+			}
+			return Ok(TextRepr::Array(arr))
+		}
+		if self.eat('{') {
+			let mut table = Table::default();
+			while !self.eat('}') {
+				let key = if self.text[self.pos..].starts_with(['"', '\'']) {
+					self.quoted()?
+				} else {
+					self.plain(&[':', ',', '}']).to_string()
+				};
+				if key.is_empty() {
+					return Err(error(self.number, "expected a key in a flow mapping"))
+				}
+				let value = if self.eat(':') { self.value(depth + 1)? } else { TextRepr::Null };
+				table.insert(self.interner.intern(&key), value);
+				if !self.eat(',') && !self.text[self.pos..].starts_with('}') {
+					return Err(error(self.number, "expected , or } in a flow mapping"))
+				}
+			}
+			return Ok(TextRepr::Table(table))
+		}
+		self.skip_whitespace();
+		match self.text[self.pos..].chars().next() {
+			None => Err(error(self.number, "unterminated flow collection")),
+			Some('"' | '\'') => self.quoted().map(TextRepr::String),
+			_ => match self.plain(&[',', ']', '}']) {
+				"" => Err(error(self.number, "expected a value")),
+				x => Ok(parse_plain(x))
+			}
+		}
+	}
+}
+
+
+/// Parses block style YAML line by line, recursing once per level of nesting
+struct Parser<'a> {
+	lines: Vec<Line<'a>>,
+	pos: usize,
+	interner: KeyInterner
+}
+
+
+impl<'a> Parser<'a> {
+	fn new(data: &'a str) -> Self {
+		let mut lines: Vec<_> = data.lines()
+			.enumerate()
+			.map(|(i, raw)| Line {
+				number: i + 1,
+				indent: raw.len() - raw.trim_start_matches(' ').len(),
+				text: strip_comment(raw).trim(),
+				raw
+			})
+			.collect();
+
+		// Skip any directives and the marker at the start of the document, and stop at the marker that ends it
+		for line in lines.iter_mut() {
+			if line.text.starts_with('%') || line.text == "---" {
+				line.text = "";
+			} else if !line.text.is_empty() {
+				break
+			}
+		}
+		if let Some(end) = lines.iter().position(|x| x.text == "...") {
+			lines.truncate(end);
+		}
+
+		Self { lines, pos: 0, interner: KeyInterner::new() }
+	}
+
+	/// The next line with content, without consuming it
+	fn peek(&mut self) -> Option<Line<'a>> {
+		while let Some(line) = self.lines.get(self.pos) {
+			if !line.text.is_empty() {
+				return Some(*line)
+			}
+			self.pos += 1;
+		}
+		None
+	}
+
+	/// Parse the node starting at the next line, which is indented by indent
+	fn node(&mut self, indent: usize, depth: usize) -> Result<TextRepr, DeserializationError> {
+		if depth >= MAX_DEPTH {
+			return Err(too_deep())
+		}
+		let line = match self.peek() {
+			Some(x) => x,
+			None => return Ok(TextRepr::Null)
+		};
+		if is_entry(line.text) {
+			self.sequence(indent, depth)
+		} else if key_end(line.text).is_some() {
+			self.mapping(indent, depth)
+		} else {
+			self.pos += 1;
+			self.inline(line.text, line.number, depth)
+		}
+	}
+
+	/// Parse the value of a key or sequence entry that was left empty, which is nested on the lines below it
+	fn child(&mut self, indent: usize, depth: usize) -> Result<TextRepr, DeserializationError> {
+		match self.peek() {
+			Some(line) if line.indent > indent => self.node(line.indent, depth + 1),
+			_ => Ok(TextRepr::Null)
+		}
+	}
+
+	fn sequence(&mut self, indent: usize, depth: usize) -> Result<TextRepr, DeserializationError> {
+		let mut arr = VecDeque::new();
+		while let Some(line) = self.peek() {
+			if line.indent < indent {
+				break
+			}
+			if line.indent > indent {
+				return Err(error(line.number, "unexpected indentation"))
+			}
+			if !is_entry(line.text) {
+				break
+			}
+			let rest = line.text[1..].trim_start();
+			if rest.is_empty() {
+				self.pos += 1;
+				arr.push_back(self.child(indent, depth)?);
+			} else if rest.starts_with(['|', '>']) {
+				self.pos += 1;
+				arr.push_back(self.block_scalar(indent, rest, line.number)?);
+			} else {
+				// The rest of the line is parsed as if it started a new line, indented to where it starts
+				let indent = indent + line.text.len() - rest.len();
+				self.lines[self.pos] = Line { indent, text: rest, ..line };
+				arr.push_back(self.node(indent, depth + 1)?);
+			}
+		}
+		Ok(TextRepr::Array(arr))
+	}
+
+	fn mapping(&mut self, indent: usize, depth: usize) -> Result<TextRepr, DeserializationError> {
+		let mut table = Table::default();
+		while let Some(line) = self.peek() {
+			if line.indent < indent {
+				break
+			}
+			if line.indent > indent {
+				return Err(error(line.number, "unexpected indentation"))
+			}
+			let end = match key_end(line.text) {
+				Some(x) if !is_entry(line.text) => x,
+				_ => return Err(error(line.number, "expected a key"))
+			};
+			let key = self.key(&line.text[..end], line.number)?;
+			let rest = line.text[end + 1..].trim();
+			self.pos += 1;
+
+			let value = if rest.is_empty() {
+				match self.peek() {
+					// A sequence may be nested at the same indentation as the key it belongs to
+					Some(next) if next.indent == indent && is_entry(next.text) => self.sequence(indent, depth + 1)?,
+					_ => self.child(indent, depth)?
+				}
+			} else if rest.starts_with(['|', '>']) {
+				self.block_scalar(indent, rest, line.number)?
+			} else {
+				self.inline(rest, line.number, depth)?
+			};
+			table.insert(key, value);
+		}
+		Ok(TextRepr::Table(table))
+	}
+
+	fn key(&mut self, text: &str, number: usize) -> Result<TableKey, DeserializationError> {
+		let text = text.trim();
+		if text.starts_with(['"', '\'']) {
+			return match read_quoted(text) {
+				Ok((key, len)) if len == text.len() => Ok(self.interner.intern(&key)),
+				Ok(_) => Err(error(number, "unexpected characters after a quoted key")),
+				Err(e) => Err(error(number, e))
+			}
+		}
+		Ok(self.interner.intern(text))
+	}
+
+	/// Parse a value that is written on the rest of a line
+	fn inline(&mut self, text: &str, number: usize, depth: usize) -> Result<TextRepr, DeserializationError> {
+		match text.chars().next() {
+			Some('[' | '{') => {
+				let mut flow = Flow { text, pos: 0, number, interner: &mut self.interner };
+				let value = flow.value(depth)?;
+				flow.skip_whitespace();
+				if flow.pos < text.len() {
+					return Err(error(number, "unexpected characters after a flow collection"))
+				}
+				Ok(value)
+			}
+			Some('"' | '\'') => match read_quoted(text) {
+				Ok((string, len)) if text[len..].trim().is_empty() => Ok(TextRepr::String(string)),
+				Ok(_) => Err(error(number, "unexpected characters after a quoted string")),
+				Err(e) => Err(error(number, e))
+			},
+			Some('&' | '*' | '!') => Err(error(number, "anchors, aliases and tags are not supported")),
+			_ => Ok(parse_plain(text))
+		}
+	}
+
+	/// Read the lines of a `|` or `>` block scalar, which are indented further than parent
+	fn block_scalar(&mut self, parent: usize, header: &str, number: usize) -> Result<TextRepr, DeserializationError> {
+		let chomp = header[1..].trim();
+		if !matches!(chomp, "" | "-" | "+") {
+			return Err(error(number, "block scalars only support the - and + indicators"))
+		}
+
+		let mut lines = Vec::new();
+		let mut content_indent = None;
+		while let Some(&line) = self.lines.get(self.pos) {
+			if line.raw.trim().is_empty() {
+				lines.push("");
+			} else {
+				if line.indent <= parent {
+					break
+				}
+				let indent = *content_indent.get_or_insert(line.indent);
+				if line.indent < indent {
+					return Err(error(line.number, "block scalar line is indented less than its first line"))
+				}
+				lines.push(&line.raw[indent..]);
+			}
+			self.pos += 1;
+		}
+
+		let trailing = lines.iter().rev().take_while(|x| x.is_empty()).count();
+		lines.truncate(lines.len() - trailing);
+		let mut out = if header.starts_with('|') { lines.join("\n") } else { fold(&lines) };
+		match chomp {
+			"-" => {}
+			"+" => out.push_str(&"\n".repeat(trailing + 1)),
+			_ => if !lines.is_empty() {
+				out.push('\n');
+			}
+		}
+		Ok(TextRepr::String(out))
+	}
+}
+
+
+/// Whether a string can be written without quotes and still be read back as the same string
+fn is_plain(string: &str) -> bool {
+	!string.is_empty()
+		&& string.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | ' '))
+		&& !string.starts_with(['-', ' '])
+		&& !string.ends_with(' ')
+		&& matches!(parse_plain(string), TextRepr::String(_))
+}
+
+
+fn write_string(out: &mut String, string: &str) {
+	if is_plain(string) {
+		out.push_str(string);
+		return
+	}
+	out.push('"');
+	for c in string.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\t' => out.push_str("\\t"),
+			'\r' => out.push_str("\\r"),
+			c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+			c => out.push(c)
+		}
+	}
+	out.push('"');
+}
+
+
+/// Whether the value is written as a block over the following lines instead of on the rest of a line
+fn is_block(value: &TextRepr) -> bool {
+	match value {
+		TextRepr::Table(x) => !x.is_empty(),
+		TextRepr::Array(x) => !x.is_empty(),
+		_ => false
+	}
+}
+
+
+/// Write a value that fits on the rest of a line
+fn write_inline(value: &TextRepr, out: &mut String) {
+	match value {
+		TextRepr::Empty | TextRepr::Null => out.push_str("null"),
+		TextRepr::String(x) => write_string(out, x),
+		TextRepr::Integer(x) => write!(out, "{}", x).unwrap(),
+		TextRepr::Float(x) if x.is_nan() => out.push_str(".nan"),
+		TextRepr::Float(x) if x.is_infinite() => out.push_str(if *x > 0.0 { ".inf" } else { "-.inf" }),
+		TextRepr::Float(x) => write!(out, "{}", x).unwrap(),
+		TextRepr::Boolean(x) => write!(out, "{}", x).unwrap(),
+		TextRepr::Table(_) => out.push_str("{}"),
+		TextRepr::Array(_) => out.push_str("[]")
+	}
+}
+
+
+/// Write a table or array with each entry on its own line at the given indentation.
+/// The first line is not indented if it continues the line of a sequence entry
+fn write_block(value: TextRepr, out: &mut String, indent: usize, mut continued: bool) {
+	let mut start_line = |out: &mut String| if !std::mem::take(&mut continued) {
+		out.extend(std::iter::repeat_n(' ', indent));
+	};
+	match value {
+		TextRepr::Table(x) => for (key, value) in x {
+			if matches!(value, TextRepr::Empty) {
+				continue
+			}
+			start_line(out);
+			write_string(out, &key);
+			out.push(':');
+			if is_block(&value) {
+				out.push('\n');
+				write_block(value, out, indent + 2, false);
+			} else {
+				out.push(' ');
+				write_inline(&value, out);
+				out.push('\n');
+			}
+		}
+		TextRepr::Array(x) => for value in x {
+			if matches!(value, TextRepr::Empty) {
+				continue
+			}
+			start_line(out);
+			out.push_str("- ");
+			if is_block(&value) {
+				write_block(value, out, indent + 2, true);
+			} else {
+				write_inline(&value, out);
+				out.push('\n');
+			}
+		}
+		value => {
+			write_inline(&value, out);
+			out.push('\n');
+		}
+	}
+}
+
+
+impl TextRepr {
+	pub fn is_valid_yaml<T: ToString>(data: T) -> bool {
+		Self::from_yaml(data.to_string()).is_ok()
+	}
+
+	/// Write block style YAML, quoting strings only where they would otherwise be read back as something else
+	pub fn to_yaml(self) -> String {
+		let mut out = String::new();
+		match self {
+			TextRepr::Empty => {}
+			x if is_block(&x) => write_block(x, &mut out, 0, false),
+			x => write_inline(&x, &mut out)
+		}
+		out
+	}
+
+	pub fn from_yaml(data: String) -> Result<Self, DeserializationError> {
+		let mut parser = Parser::new(&data);
+		let indent = match parser.peek() {
+			Some(line) => line.indent,
+			None => return Ok(Self::Empty)
+		};
+		let out = parser.node(indent, 0)?;
+		match parser.peek() {
+			Some(line) if line.text == "---" => Err(error(line.number, "documents with more than one section are not supported")),
+			Some(line) => Err(error(line.number, "unexpected indentation")),
+			None => Ok(out)
+		}
+	}
+}
+
+
+/// Serialize any value as YAML, without implementing YAMLSerialize for it
+pub fn to_string<P, T: Serialize<P>>(value: T) -> String {
+	let mut out = TextRepr::new();
+	value.serialize(&mut out);
+	out.to_yaml()
+}
+
+
+/// Deserialize any value from YAML, without implementing YAMLDeserialize for it
+pub fn from_str<P, T: Deserialize<P>>(data: &str) -> Result<T, DeserializationError> {
+	T::deserialize(&mut TextRepr::from_yaml(data.into())?)
+}
+
+
+pub trait YAMLSerialize<P = NaturalProfile> {
+	fn serialize_yaml(self) -> String;
+}
+
+
+pub trait YAMLDeserialize<P = NaturalProfile>: Sized {
+	fn deserialize_yaml(data: String) -> Result<Self, DeserializationError>;
+}
+
+
+/// A marker trait for types that can be serialized and deserialized into YAML with the same profile,
+/// without a marshall. Is automatically implemented on all appropriate types
+pub trait YAMLSerde<P = NaturalProfile>: YAMLSerialize<P> + YAMLDeserialize<P> {}
+
+impl<P, T: YAMLSerialize<P> + YAMLDeserialize<P>> YAMLSerde<P> for T {}
+
+
+pub trait MarshalledYAMLSerialize<Marshall, P = NaturalProfile> {
+	fn serialize_yaml(self, marshall: &Marshall) -> String;
+}
+
+
+pub trait MarshalledYAMLDeserialize<'a, Marshall, P = NaturalProfile>: Sized {
+	fn deserialize_yaml(data: String, marshall: &'a Marshall) -> Result<Self, DeserializationError>;
+}
+
+
+/// A marker trait for types that can be serialized and deserialized into YAML with the same profile,
+/// and the same type of marshall. Is automatically implemented on all appropriate types
+pub trait MarshalledYAMLSerde<'a, Marshall, P = NaturalProfile>: MarshalledYAMLSerialize<Marshall, P> + MarshalledYAMLDeserialize<'a, Marshall, P> {}
+
+impl<'a, P, Marshall, T: MarshalledYAMLSerialize<Marshall, P> + MarshalledYAMLDeserialize<'a, Marshall, P>> MarshalledYAMLSerde<'a, Marshall, P> for T {}
+
+#[macro_export]
+macro_rules! impl_yaml {
+    ($name: ty, $profile: ty) => {
+		impl_yaml_ser!($name, $profile);
+		impl_yaml_deser!($name, $profile);
+	};
+    ($name: ty, $profile: ty, $marshall: ty) => {
+		impl_yaml_ser!($name, $profile, $marshall);
+		impl_yaml_deser!($name, $profile, $marshall);
+	};
+}
+
+#[macro_export]
+macro_rules! impl_yaml_ser {
+    ($name: ty, $profile: ty) => {
+		impl YAMLSerialize<$profile> for $name {
+			fn serialize_yaml(self) -> String {
+				$crate::yaml::to_string::<$profile, _>(self)
+			}
+		}
+	};
+    ($name: ty, $profile: ty, $marshall: ty) => {
+		impl MarshalledYAMLSerialize<$marshall, $profile> for $name {
+			fn serialize_yaml(self, marshall: &$marshall) -> String {
+				let mut out = TextRepr::new();
+				MarshalledSerialize::<$profile>::serialize(self, &mut out, marshall);
+				out.to_yaml()
+			}
+		}
+	};
+}
+
+#[macro_export]
+macro_rules! impl_yaml_deser {
+    ($name: ty, $profile: ty) => {
+		impl YAMLDeserialize<$profile> for $name {
+			fn deserialize_yaml(data: String) -> Result<Self, DeserializationError> {
+				$crate::yaml::from_str::<$profile, _>(&data)
+			}
+		}
+	};
+    ($name: ty, $profile: ty, $marshall: ty) => {
+		impl MarshalledYAMLDeserialize<$marshall, $profile> for $name {
+			fn deserialize_yaml(data: String, marshall: &$marshall) -> Result<Self, DeserializationError> {
+				MarshalledDeserialize::<$profile>::deserialize(&mut TextRepr::from_yaml(data)?, marshall)
+			}
+		}
+	};
+}
+
+
+impl<P, K: Borrow<str> + Eq + std::hash::Hash, V: Serialize<P>, S: std::hash::BuildHasher> YAMLSerialize<P> for HashMap<K, V, S> {
+	fn serialize_yaml(self) -> String {
+		TextRepr::to_yaml(serialize_owned!(self))
+	}
+}
+
+
+impl<E: Debug, P, K: Eq + std::hash::Hash + FromStr<Err=E>, V: Deserialize<P>, S: std::hash::BuildHasher + Default> YAMLDeserialize<P> for HashMap<K, V, S> {
+	fn deserialize_yaml(data: String) -> Result<Self, DeserializationError> {
+		Self::deserialize::<TextRepr>(&mut TextRepr::from_yaml(data)?)
+	}
+}
+
+
+impl<P, V: Serialize<P>> YAMLSerialize<P> for Vec<V> {
+	fn serialize_yaml(self) -> String {
+		TextRepr::to_yaml(serialize_owned!(self))
+	}
+}
+
+
+impl<P, V: Deserialize<P>> YAMLDeserialize<P> for Vec<V> {
+	fn deserialize_yaml(data: String) -> Result<Self, DeserializationError> {
+		Self::deserialize::<TextRepr>(&mut TextRepr::from_yaml(data)?)
+	}
+}
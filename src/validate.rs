@@ -0,0 +1,161 @@
+//! Dry runs of serialization. `validate_serializable` serializes a value into a sink that writes nothing,
+//! and reports the mistakes that would make the text serializers panic as errors instead,
+//! such as a keyed field being serialized after an unkeyed value at the same level
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+#[cfg(feature = "text")]
+use crate::text::TextRepr;
+use crate::{DeserializationError, Deserialize, DeserializeSeed, NumberType, PrimitiveSerializer, Serialize, Serializer};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+	Empty,
+	Value,
+	Array,
+	Table
+}
+
+
+/// A Serializer that only tracks the shape of what it is given
+#[derive(Debug)]
+struct ShapeSink {
+	shape: Shape,
+	error: Option<DeserializationError>
+}
+
+
+impl ShapeSink {
+	fn new() -> Self {
+		Self { shape: Shape::Empty, error: None }
+	}
+
+	fn fail(&mut self, error: DeserializationError) {
+		if self.error.is_none() {
+			self.error = Some(error);
+		}
+	}
+
+	fn push_value(&mut self) {
+		self.shape = match self.shape {
+			Shape::Empty => Shape::Value,
+			Shape::Value | Shape::Array => Shape::Array,
+			Shape::Table => {
+				self.fail(DeserializationError::invalid_format("an unkeyed value was serialized into a table"));
+				Shape::Table
+			}
+		};
+	}
+
+	fn unsupported<T>() -> Result<T, DeserializationError> {
+		Err(DeserializationError::invalid_format("validation does not deserialize"))
+	}
+}
+
+
+impl PrimitiveSerializer for ShapeSink {
+	fn serialize_bool(&mut self, _boolean: bool) {
+		self.push_value();
+	}
+
+	fn deserialize_bool(&mut self) -> Result<bool, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn serialize_num<T: NumberType>(&mut self, _num: T) {
+		self.push_value();
+	}
+
+	fn deserialize_num<T: NumberType>(&mut self) -> Result<T, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn serialize_string<T: Into<String>>(&mut self, _string: T) {
+		self.push_value();
+	}
+
+	fn deserialize_string(&mut self) -> Result<String, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn serialize_bytes<T: Into<VecDeque<u8>>>(&mut self, _bytes: T) {
+		self.push_value();
+	}
+
+	fn deserialize_bytes<T: FromIterator<u8>>(&mut self) -> Result<T, DeserializationError> {
+		Self::unsupported()
+	}
+}
+
+
+impl Serializer for ShapeSink {
+	fn serialize<P, T: Serialize<P>>(&mut self, item: T) {
+		let mut child = Self::new();
+		item.serialize(&mut child);
+		if let Some(e) = child.error {
+			self.fail(e);
+		}
+		self.push_value();
+	}
+
+	fn serialize_iter<P, T: Serialize<P>, I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		let mut child = Self::new();
+		for item in iter {
+			child.serialize::<P, T>(item);
+		}
+		if let Some(e) = child.error {
+			self.fail(e);
+		}
+		self.push_value();
+	}
+
+	fn serialize_key<P, T: Serialize<P>, K: std::borrow::Borrow<str>>(&mut self, key: K, item: T) {
+		let key = key.borrow();
+		let mut child = Self::new();
+		item.serialize(&mut child);
+		if let Some(e) = child.error {
+			self.fail(e.nest().set_field(key));
+		}
+		match self.shape {
+			Shape::Empty | Shape::Table => self.shape = Shape::Table,
+			_ => self.fail(DeserializationError::invalid_format("a keyed value was serialized into a non-table value").set_field(key))
+		}
+	}
+
+	fn deserialize<P, T: Deserialize<P>>(&mut self) -> Result<T, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn deserialize_key_internal<P, T: Deserialize<P>>(&mut self, _key: &str) -> Result<T, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn deserialize_seed<P, S: DeserializeSeed<P>>(&mut self, _seed: &mut S) -> Result<S::Value, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn deserialize_key_seed<P, S: DeserializeSeed<P>, K: std::borrow::Borrow<str>>(&mut self, _key: K, _seed: &mut S) -> Result<S::Value, DeserializationError> {
+		Self::unsupported()
+	}
+
+	fn try_get_key<K: FromStr>(&mut self) -> Option<K> {
+		None
+	}
+
+	#[cfg(feature = "text")]
+	fn deserialize_text(&mut self) -> Result<TextRepr, DeserializationError> {
+		Self::unsupported()
+	}
+}
+
+
+/// Serialize item without writing anything, returning the first mistake that would make a text serializer panic
+pub fn validate_serializable<P, T: Serialize<P>>(item: T) -> Result<(), DeserializationError> {
+	let mut sink = ShapeSink::new();
+	item.serialize(&mut sink);
+	match sink.error {
+		Some(e) => Err(e),
+		None => Ok(())
+	}
+}
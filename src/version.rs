@@ -0,0 +1,86 @@
+//! Versioned data. `Versioned<T>` writes a format version next to the value, and while
+//! the value is read back, fields marked `since` or `until` in the impl_key macros can
+//! check that version to tell whether a missing field is expected for old data
+use std::cell::Cell;
+
+use crate::{DeserializationError, DeserializationErrorKind, Deserialize, Serialize, Serializer};
+use crate::warning::{warn, WarningKind};
+
+
+thread_local! {
+	static DATA_VERSION: Cell<Option<u32>> = const { Cell::new(None) };
+}
+
+
+/// The version of the data currently being deserialized, if it is inside a Versioned
+pub fn data_version() -> Option<u32> {
+	DATA_VERSION.with(Cell::get)
+}
+
+
+/// Run f with the data version set to the given version, restoring the previous version afterwards
+pub fn with_data_version<R, F: FnOnce() -> R>(version: u32, f: F) -> R {
+	let previous = DATA_VERSION.with(|x| x.replace(Some(version)));
+	let out = f();
+	DATA_VERSION.with(|x| x.set(previous));
+	out
+}
+
+
+/// A value stored along with the version of its format
+#[derive(Debug)]
+pub struct Versioned<T> {
+	pub version: u32,
+	pub value: T
+}
+
+
+impl<T> Versioned<T> {
+	pub fn new(version: u32, value: T) -> Self {
+		Self { version, value }
+	}
+}
+
+
+impl<P, T: Serialize<P>> Serialize<P> for Versioned<T> {
+	fn serialize<S: Serializer>(self, data: &mut S) {
+		data.serialize_key("version", self.version);
+		data.serialize_key("data", self.value);
+	}
+}
+
+
+impl<P, T: Deserialize<P>> Deserialize<P> for Versioned<T> {
+	fn deserialize<S: Serializer>(data: &mut S) -> Result<Self, DeserializationError> {
+		let version = data.deserialize_key("version")?;
+		let value = with_data_version(version, || data.deserialize_key("data"))?;
+		Ok(Self { version, value })
+	}
+}
+
+
+fn missing_as_default<V: Default>(result: Result<V, DeserializationError>, expected: bool) -> Result<V, DeserializationError> {
+	match result {
+		Err(e) if !expected && matches!(e.kind, DeserializationErrorKind::MissingField) => Ok(V::default()),
+		result => result
+	}
+}
+
+
+/// Used by the impl_key macros for a field added in the given version.
+/// The field may be missing from older data, in which case it is defaulted
+pub fn since<V: Default>(result: Result<V, DeserializationError>, version: u32) -> Result<V, DeserializationError> {
+	missing_as_default(result, data_version().is_none_or(|x| x >= version))
+}
+
+
+/// Used by the impl_key macros for a field removed in the given version.
+/// The field may be missing from data of that version or newer, in which case it is defaulted,
+/// and if it is present anyway, a deprecated key warning is raised
+pub fn until<V: Default>(result: Result<V, DeserializationError>, version: u32, key: &str) -> Result<V, DeserializationError> {
+	let expected = data_version().is_none_or(|x| x < version);
+	if !expected && result.is_ok() {
+		warn(Some(key), WarningKind::DeprecatedKey { replacement: None });
+	}
+	missing_as_default(result, expected)
+}
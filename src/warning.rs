@@ -0,0 +1,108 @@
+//! Non-fatal issues found while deserializing. Wrap deserialization in `collect_warnings` to
+//! receive them, such as deprecated keys being used, values being coerced, or unknown fields being
+//! ignored. Outside of `collect_warnings`, warnings are dropped
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+
+use crate::Serializer;
+
+
+thread_local! {
+	static WARNINGS: RefCell<Option<Vec<Warning>>> = const { RefCell::new(None) };
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WarningKind {
+	/// The key is deprecated, and may be replaced by another key
+	DeprecatedKey { replacement: Option<String> },
+	/// The value did not fit the expected type, and was converted into a different value
+	Coerced { from: String, to: String },
+	/// The key does not belong to any field, and was ignored
+	UnknownField
+}
+
+
+impl Display for WarningKind {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::DeprecatedKey { replacement: None } => write!(f, "deprecated key"),
+			Self::DeprecatedKey { replacement: Some(x) } => write!(f, "deprecated key, use {} instead", x),
+			Self::Coerced { from, to } => write!(f, "{} was coerced to {}", from, to),
+			Self::UnknownField => write!(f, "unknown field was ignored")
+		}
+	}
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+	/// The dotted path to the field the warning is about, if known
+	pub field: Option<String>,
+	pub kind: WarningKind
+}
+
+
+impl Display for Warning {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match &self.field {
+			None => write!(f, "{}", self.kind),
+			Some(x) => write!(f, "{}: {}", x, self.kind)
+		}
+	}
+}
+
+
+/// Run f, returning its output along with every warning raised while it ran
+pub fn collect_warnings<R, F: FnOnce() -> R>(f: F) -> (R, Vec<Warning>) {
+	let previous = WARNINGS.with(|x| x.replace(Some(Vec::new())));
+	let out = f();
+	let warnings = WARNINGS.with(|x| x.replace(previous)).unwrap_or_default();
+	(out, warnings)
+}
+
+
+/// Whether warnings are currently being collected
+pub fn is_collecting() -> bool {
+	WARNINGS.with(|x| x.borrow().is_some())
+}
+
+
+/// Raise a warning about the given field, or about the value being deserialized if there is no field
+pub fn warn(field: Option<&str>, kind: WarningKind) {
+	WARNINGS.with(|x| if let Some(warnings) = x.borrow_mut().as_mut() {
+		warnings.push(Warning { field: field.map(Into::into), kind });
+	});
+}
+
+
+/// Run f, which deserializes the value at the given key,
+/// prefixing the fields of the warnings it raises with that key
+#[cfg_attr(not(feature = "text"), allow(dead_code))]
+pub(crate) fn nest<R, F: FnOnce() -> R>(key: &str, f: F) -> R {
+	let start = match WARNINGS.with(|x| x.borrow().as_ref().map(Vec::len)) {
+		Some(x) => x,
+		None => return f()
+	};
+	let out = f();
+	WARNINGS.with(|x| if let Some(warnings) = x.borrow_mut().as_mut() {
+		for warning in warnings.iter_mut().skip(start) {
+			warning.field = Some(match warning.field.take() {
+				None => key.into(),
+				Some(x) => format!("{}.{}", key, x)
+			});
+		}
+	});
+	out
+}
+
+
+/// Used by the impl_key macros to raise a warning for every key left unread after the known fields
+#[doc(hidden)]
+pub fn unknown_fields<T: Serializer>(data: &T, known: &[&str]) {
+	for key in data.remaining_keys() {
+		if !known.contains(&key.as_str()) {
+			warn(Some(&key), WarningKind::UnknownField);
+		}
+	}
+}